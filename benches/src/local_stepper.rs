@@ -191,7 +191,12 @@ impl LocalBevyStepper {
     }
 
     pub fn init(&mut self) {
+        // finish building the plugins, like `App::run` would do
+        self.server_app.finish();
+        self.server_app.cleanup();
         self.client_apps.values_mut().for_each(|client_app| {
+            client_app.finish();
+            client_app.cleanup();
             let _ = client_app
                 .world
                 .resource_mut::<ClientConnection>()
@@ -33,7 +33,10 @@ impl ServerPluginGroup {
                     certificate,
                 }
             }
-            Transports::WebSocket => TransportConfig::WebSocketServer { server_addr },
+            Transports::WebSocket => TransportConfig::WebSocketServer {
+                server_addr,
+                certificate: None,
+            },
         };
         let link_conditioner = LinkConditionerConfig {
             incoming_latency: Duration::from_millis(0),
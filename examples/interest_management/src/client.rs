@@ -44,7 +44,10 @@ impl ClientPluginGroup {
                 #[cfg(target_family = "wasm")]
                 certificate_digest,
             },
-            Transports::WebSocket => TransportConfig::WebSocketClient { server_addr },
+            Transports::WebSocket => TransportConfig::WebSocketClient {
+                server_addr,
+                tls: None,
+            },
         };
         let link_conditioner = LinkConditionerConfig {
             incoming_latency: Duration::from_millis(100),
@@ -44,7 +44,10 @@ impl ClientPluginGroup {
                 #[cfg(target_family = "wasm")]
                 certificate_digest,
             },
-            Transports::WebSocket => TransportConfig::WebSocketClient { server_addr },
+            Transports::WebSocket => TransportConfig::WebSocketClient {
+                server_addr,
+                tls: None,
+            },
         };
         let link_conditioner = LinkConditionerConfig {
             incoming_latency: Duration::from_millis(0),
@@ -67,6 +70,7 @@ impl ClientPluginGroup {
                 delay: InterpolationDelay::default().with_send_interval_ratio(2.0),
                 // do not do linear interpolation per component, instead we provide our own interpolation logic
                 custom_interpolation_logic: true,
+                ..default()
             },
             ..default()
         };
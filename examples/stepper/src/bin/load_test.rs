@@ -0,0 +1,315 @@
+//! Load test: runs a server and many headless clients against it over UDP on localhost, and periodically
+//! reports the RTT, packet loss and bandwidth of the clients, and the update time of the server.
+//!
+//! All the clients are updated from the same thread, so that hundreds of clients can run on a single machine.
+//!
+//! Run with: `cargo run --release --bin load_test -- --clients 200 --duration-secs 60`
+#![cfg(not(target_family = "wasm"))]
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bevy::diagnostic::DiagnosticsStore;
+use bevy::prelude::*;
+use bevy::utils::{Duration, HashMap, Instant};
+use bevy::MinimalPlugins;
+use clap::{Parser, ValueEnum};
+use rand::Rng;
+use tracing::{info, warn};
+
+use lightyear::connection::netcode::{generate_key, ClientId, Key, MAX_CLIENTS};
+use lightyear::prelude::client::{Authentication, ClientConnection, InputSystemSet, NetClient};
+use lightyear::prelude::server::{ConnectEvent, DisconnectEvent, InputEvent};
+use lightyear::prelude::*;
+use lightyear::transport::io::IoDiagnosticsPlugin;
+use stepper::protocol::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum InputMode {
+    /// Every client sends a random input every tick
+    Random,
+    /// Every client sends the same sequence of inputs, which only depends on the tick
+    Scripted,
+}
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Number of headless clients to connect to the server
+    #[arg(short, long, default_value_t = 100)]
+    clients: usize,
+
+    /// Duration of the test
+    #[arg(short, long, default_value_t = 30)]
+    duration_secs: u64,
+
+    /// Interval between two reports
+    #[arg(short, long, default_value_t = 5)]
+    report_interval_secs: u64,
+
+    #[arg(short, long, default_value_t = 5000)]
+    port: u16,
+
+    #[arg(short, long, value_enum, default_value_t = InputMode::Random)]
+    inputs: InputMode,
+}
+
+/// Update times since the last report
+#[derive(Default)]
+struct UpdateTimes {
+    count: u32,
+    total: Duration,
+    max: Duration,
+}
+
+impl UpdateTimes {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::default();
+        }
+        self.total / self.count
+    }
+}
+
+/// Entity controlled by each client on the server
+#[derive(Resource, Default)]
+struct Players(HashMap<ClientId, Entity>);
+
+fn handle_connections(
+    mut commands: Commands,
+    mut players: ResMut<Players>,
+    mut connections: EventReader<ConnectEvent>,
+    mut disconnections: EventReader<DisconnectEvent>,
+) {
+    for connection in connections.read() {
+        let entity = commands.spawn((Component1(0.0), Replicate::default())).id();
+        players.0.insert(*connection.context(), entity);
+    }
+    for disconnection in disconnections.read() {
+        if let Some(entity) = players.0.remove(disconnection.context()) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn apply_inputs(
+    players: Res<Players>,
+    mut query: Query<&mut Component1>,
+    mut inputs: EventReader<InputEvent<MyInput>>,
+) {
+    for input in inputs.read() {
+        let Some(MyInput(value)) = input.input() else {
+            continue;
+        };
+        if let Some(mut component) = players
+            .0
+            .get(input.context())
+            .and_then(|entity| query.get_mut(*entity).ok())
+        {
+            component.0 += *value as f32;
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ClientInputMode(InputMode);
+
+fn buffer_inputs(
+    mut connection: ResMut<ClientConnectionManager>,
+    tick_manager: Res<TickManager>,
+    mode: Res<ClientInputMode>,
+) {
+    let tick = tick_manager.tick();
+    let input = match mode.0 {
+        InputMode::Random => MyInput(rand::thread_rng().gen_range(-1..=1)),
+        InputMode::Scripted => MyInput([1, 1, 0, -1, -1, 0][(tick.0 % 6) as usize]),
+    };
+    connection.add_input(input, tick);
+}
+
+fn run_server(
+    server_addr: SocketAddr,
+    protocol_id: u64,
+    private_key: Key,
+    update_times: Arc<Mutex<UpdateTimes>>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut server_app = App::new();
+    server_app.add_plugins(MinimalPlugins);
+    stepper::server::bevy_setup(&mut server_app, server_addr, protocol_id, private_key);
+    server_app.init_resource::<Players>();
+    server_app.add_systems(Update, handle_connections);
+    server_app.add_systems(FixedUpdate, apply_inputs);
+    server_app.finish();
+    server_app.cleanup();
+
+    let frame_duration = Duration::from_millis(10);
+    while !stop.load(Ordering::Relaxed) {
+        let start = Instant::now();
+        server_app.update();
+        let elapsed = start.elapsed();
+        update_times.lock().unwrap().record(elapsed);
+        if let Some(remaining) = frame_duration.checked_sub(elapsed) {
+            std::thread::sleep(remaining);
+        }
+    }
+}
+
+fn main() {
+    tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(tracing::Level::INFO)
+        .init();
+    let cli = Cli::parse();
+    let num_clients = if cli.clients > MAX_CLIENTS {
+        warn!("The server accepts at most {MAX_CLIENTS} clients");
+        MAX_CLIENTS
+    } else {
+        cli.clients
+    };
+
+    let server_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), cli.port);
+    let protocol_id = 0;
+    let private_key = generate_key();
+
+    let server_update_times = Arc::new(Mutex::new(UpdateTimes::default()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let server_thread = {
+        let update_times = server_update_times.clone();
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            run_server(server_addr, protocol_id, private_key, update_times, stop)
+        })
+    };
+
+    // all the clients are updated from this thread
+    let mut client_apps: Vec<(ClientId, App)> = (0..num_clients)
+        .map(|i| {
+            let client_id = i as ClientId + 1;
+            let mut client_app = App::new();
+            client_app.add_plugins(MinimalPlugins);
+            stepper::client::bevy_setup(
+                &mut client_app,
+                Authentication::Manual {
+                    server_addr,
+                    protocol_id,
+                    private_key,
+                    client_id,
+                },
+            );
+            client_app.insert_resource(ClientInputMode(cli.inputs));
+            client_app.add_systems(
+                FixedPreUpdate,
+                buffer_inputs.in_set(InputSystemSet::BufferInputs),
+            );
+            client_app.finish();
+            client_app.cleanup();
+            client_app
+                .world
+                .resource_mut::<ClientConnection>()
+                .connect()
+                .expect("could not connect");
+            (client_id, client_app)
+        })
+        .collect();
+    info!("Started {num_clients} clients");
+
+    let frame_duration = Duration::from_secs_f32(1.0 / 60.0);
+    let report_interval = Duration::from_secs(cli.report_interval_secs);
+    let start = Instant::now();
+    let mut last_report = start;
+    let mut client_frame_times = UpdateTimes::default();
+    while start.elapsed() < Duration::from_secs(cli.duration_secs) {
+        let frame_start = Instant::now();
+        for (_, client_app) in client_apps.iter_mut() {
+            client_app.update();
+        }
+        let elapsed = frame_start.elapsed();
+        client_frame_times.record(elapsed);
+
+        if last_report.elapsed() >= report_interval {
+            report(
+                &client_apps,
+                &client_frame_times,
+                &server_update_times.lock().unwrap(),
+            );
+            client_frame_times = UpdateTimes::default();
+            *server_update_times.lock().unwrap() = UpdateTimes::default();
+            last_report = Instant::now();
+        }
+        if let Some(remaining) = frame_duration.checked_sub(elapsed) {
+            std::thread::sleep(remaining);
+        }
+    }
+
+    stop.store(true, Ordering::Relaxed);
+    server_thread.join().expect("server thread has panicked");
+}
+
+fn report(
+    client_apps: &[(ClientId, App)],
+    client_frame_times: &UpdateTimes,
+    server_update_times: &UpdateTimes,
+) {
+    let mut connected = 0;
+    let mut rtts = vec![];
+    let mut total_loss = 0.0;
+    let mut kb_sent = 0.0;
+    let mut kb_received = 0.0;
+    for (_, client_app) in client_apps {
+        let netclient = client_app.world.resource::<ClientConnection>();
+        if !netclient.is_connected() {
+            continue;
+        }
+        connected += 1;
+        let connection = client_app.world.resource::<ClientConnectionManager>();
+        rtts.push(connection.rtt());
+        total_loss += connection.packet_loss();
+        // the io stats are reset every frame by the io diagnostics, so we use the diagnostics directly
+        // (averaged over the last `IoDiagnosticsPlugin::DIAGNOSTIC_HISTORY_LEN` frames)
+        let diagnostics = client_app.world.resource::<DiagnosticsStore>();
+        let average = |path| {
+            diagnostics
+                .get(path)
+                .and_then(|diagnostic| diagnostic.average())
+                .unwrap_or_default()
+        };
+        kb_sent += average(&IoDiagnosticsPlugin::BYTES_OUT);
+        kb_received += average(&IoDiagnosticsPlugin::BYTES_IN);
+    }
+    rtts.sort();
+    let percentile = |p: f32| {
+        rtts.get(((rtts.len() as f32 * p) as usize).min(rtts.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or_default()
+    };
+    let per_client = |kb_per_sec: f64| {
+        if connected == 0 {
+            return 0.0;
+        }
+        kb_per_sec / connected as f64
+    };
+    info!(
+        "clients connected: {connected}/{} | rtt p50: {:?}, p99: {:?} | packet loss: {:.2}% | \
+        per client: {:.2} KB/s sent, {:.2} KB/s received | \
+        server update: mean {:?}, max {:?} | clients frame: mean {:?}, max {:?}",
+        client_apps.len(),
+        percentile(0.5),
+        percentile(0.99),
+        if connected == 0 {
+            0.0
+        } else {
+            100.0 * total_loss / connected as f32
+        },
+        per_client(kb_sent),
+        per_client(kb_received),
+        server_update_times.mean(),
+        server_update_times.max,
+        client_frame_times.mean(),
+        client_frame_times.max,
+    );
+}
@@ -74,6 +74,7 @@ impl BevyStepper {
             },
             ping: PingConfig::default(),
             packet: Default::default(),
+            replication: Default::default(),
         };
         let plugin_config = server::PluginConfig::new(config, protocol());
         let plugin = server::ServerPlugin::new(plugin_config);
@@ -92,7 +93,8 @@ impl BevyStepper {
         let io = Io::from_config(
             IoConfig::from_transport(TransportConfig::UdpSocket(addr))
                 .with_conditioner(conditioner.clone()),
-        );
+        )
+        .unwrap();
         let config = ClientConfig {
             shared: shared_config.clone(),
             sync: sync_config,
@@ -145,6 +147,12 @@ impl BevyStepper {
     }
 
     pub(crate) fn init(&mut self) {
+        // finish building the plugins, like `App::run` would do
+        self.client_app.finish();
+        self.client_app.cleanup();
+        self.server_app.finish();
+        self.server_app.cleanup();
+
         self.client_mut().connect();
 
         // Advance the world to let the connection process complete
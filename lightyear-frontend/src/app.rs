@@ -0,0 +1,319 @@
+//! The dashboard: connection bar, timelines and tables
+use leptos::*;
+
+use crate::feed::{Feed, FeedStatus};
+use crate::history::{History, HISTORY_LEN};
+use crate::snapshot::{ClientId, InspectorSource};
+
+const DEFAULT_FEED_URL: &str = "ws://127.0.0.1:9102";
+
+const TIMELINE_WIDTH: f32 = 300.0;
+const TIMELINE_HEIGHT: f32 = 60.0;
+
+#[component]
+pub fn App() -> impl IntoView {
+    let (history, set_history) = create_signal(History::default());
+    let (status, set_status) = create_signal(FeedStatus::Disconnected);
+    let (url, set_url) = create_signal(DEFAULT_FEED_URL.to_string());
+    let (selected, set_selected) = create_signal(None::<ClientId>);
+    let feed = store_value(None::<Feed>);
+
+    let connect = move |_| {
+        // close the previous connection first
+        feed.set_value(None);
+        set_history.set(History::default());
+        match Feed::connect(&url.get_untracked(), set_history, set_status) {
+            Ok(new_feed) => feed.set_value(Some(new_feed)),
+            Err(e) => set_status.set(FeedStatus::Error(e)),
+        }
+    };
+
+    // default to the first connection if none is selected (or if the selected client disconnected)
+    let selected_client = create_memo(move |_| {
+        history.with(|history| {
+            let latest = history.latest()?;
+            selected
+                .get()
+                .filter(|client_id| latest.connection(*client_id).is_some())
+                .or_else(|| latest.connections.first().map(|c| c.client_id))
+        })
+    });
+    let header = move || {
+        history.with(|history| {
+            history.latest().map(|latest| {
+                let source = match latest.source {
+                    InspectorSource::Server => "server",
+                    InspectorSource::Client => "client",
+                };
+                format!("{source} | tick {} | {:.1}s", latest.tick, latest.time)
+            })
+        })
+    };
+    let is_client = move || {
+        history.with(|history| {
+            history
+                .latest()
+                .map_or(false, |latest| latest.source == InspectorSource::Client)
+        })
+    };
+
+    view! {
+        <header>
+            <h1>"lightyear inspector"</h1>
+            <input
+                type="text"
+                prop:value=url
+                on:input=move |ev| set_url.set(event_target_value(&ev))
+            />
+            <button on:click=connect>"Connect"</button>
+            <span class="status">{move || status.get().to_string()}</span>
+            <span class="source">{header}</span>
+        </header>
+        <main>
+            <section>
+                <h2>"Connections"</h2>
+                <ConnectionsTable
+                    history=history
+                    selected_client=selected_client
+                    set_selected=set_selected
+                />
+            </section>
+            {move || {
+                selected_client
+                    .get()
+                    .map(|client_id| {
+                        view! {
+                            <section class="timelines">
+                                <h2>{format!("Client {client_id}")}</h2>
+                                <Timeline
+                                    label="RTT"
+                                    unit="ms"
+                                    values=Signal::derive(move || {
+                                        history.with(|h| h.rtt_timeline(client_id))
+                                    })
+                                />
+                                <Timeline
+                                    label="Sent"
+                                    unit="B/s"
+                                    values=Signal::derive(move || {
+                                        history.with(|h| h.bandwidth_timeline(client_id).0)
+                                    })
+                                />
+                                <Timeline
+                                    label="Received"
+                                    unit="B/s"
+                                    values=Signal::derive(move || {
+                                        history.with(|h| h.bandwidth_timeline(client_id).1)
+                                    })
+                                />
+                            </section>
+                            <section>
+                                <h2>"Channels"</h2>
+                                <ChannelsTable history=history client_id=client_id/>
+                            </section>
+                            <section>
+                                <h2>"Replication groups"</h2>
+                                <GroupsTable history=history client_id=client_id/>
+                            </section>
+                        }
+                    })
+            }}
+            <Show when=is_client>
+                <section>
+                    <h2>"Rollbacks"</h2>
+                    <Timeline
+                        label="Rollbacks per snapshot"
+                        unit=""
+                        values=Signal::derive(move || history.with(|h| h.rollback_timeline()))
+                    />
+                    <RollbacksTable history=history/>
+                </section>
+            </Show>
+        </main>
+    }
+}
+
+/// Sparkline of the last [`HISTORY_LEN`] values, scaled to the maximum value
+#[component]
+fn Timeline(label: &'static str, unit: &'static str, values: Signal<Vec<f32>>) -> impl IntoView {
+    let points = move || {
+        values.with(|values| {
+            let max = values.iter().copied().fold(0.0, f32::max).max(f32::EPSILON);
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let x = i as f32 * TIMELINE_WIDTH / (HISTORY_LEN - 1) as f32;
+                    let y = TIMELINE_HEIGHT - value / max * TIMELINE_HEIGHT;
+                    format!("{x:.1},{y:.1}")
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    };
+    let summary = move || {
+        values.with(|values| {
+            let latest = values.last().copied().unwrap_or_default();
+            let max = values.iter().copied().fold(0.0, f32::max);
+            format!("{label}: {latest:.1} {unit} (max {max:.1})")
+        })
+    };
+    view! {
+        <div class="timeline">
+            <div class="timeline-label">{summary}</div>
+            <svg
+                viewBox=format!("0 0 {TIMELINE_WIDTH} {TIMELINE_HEIGHT}")
+                preserveAspectRatio="none"
+            >
+                <polyline points=points fill="none" stroke="currentColor"></polyline>
+            </svg>
+        </div>
+    }
+}
+
+#[component]
+fn ConnectionsTable(
+    history: ReadSignal<History>,
+    selected_client: Memo<Option<ClientId>>,
+    set_selected: WriteSignal<Option<ClientId>>,
+) -> impl IntoView {
+    let rows = move || {
+        history
+            .with(|h| h.connection_rows())
+            .into_iter()
+            .map(|row| {
+                let client_id = row.client_id;
+                view! {
+                    <tr
+                        class:selected=move || selected_client.get() == Some(client_id)
+                        on:click=move |_| set_selected.set(Some(client_id))>
+                        <td>{row.client_id}</td>
+                        <td>{format!("{:.1}", row.rtt_ms)}</td>
+                        <td>{format!("{:.1}", row.jitter_ms)}</td>
+                        <td>{format!("{:.1}%", row.packet_loss * 100.0)}</td>
+                        <td>{format!("{:.0}", row.send_rate)}</td>
+                        <td>{format!("{:.0}", row.receive_rate)}</td>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+    view! {
+        <table>
+            <thead>
+                <tr>
+                    <th>"Client"</th>
+                    <th>"RTT (ms)"</th>
+                    <th>"Jitter (ms)"</th>
+                    <th>"Packet loss"</th>
+                    <th>"Sent (B/s)"</th>
+                    <th>"Received (B/s)"</th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
+
+#[component]
+fn ChannelsTable(history: ReadSignal<History>, client_id: ClientId) -> impl IntoView {
+    let rows = move || {
+        history
+            .with(|h| h.channel_rows(client_id))
+            .into_iter()
+            .map(|row| {
+                view! {
+                    <tr>
+                        <td>{row.name}</td>
+                        <td>{row.messages_sent}</td>
+                        <td>{row.messages_received}</td>
+                        <td>{format!("{:.0}", row.send_rate)}</td>
+                        <td>{format!("{:.0}", row.receive_rate)}</td>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+    view! {
+        <table>
+            <thead>
+                <tr>
+                    <th>"Channel"</th>
+                    <th>"Messages sent"</th>
+                    <th>"Messages received"</th>
+                    <th>"Sent (B/s)"</th>
+                    <th>"Received (B/s)"</th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
+
+#[component]
+fn GroupsTable(history: ReadSignal<History>, client_id: ClientId) -> impl IntoView {
+    let rows = move || {
+        history
+            .with(|h| h.group_rows(client_id))
+            .into_iter()
+            .map(|row| {
+                view! {
+                    <tr>
+                        <td>{row.group_id}</td>
+                        <td>{row.actions_sent}</td>
+                        <td>{row.updates_sent}</td>
+                        <td>{row.bytes_sent}</td>
+                        <td>{format!("{:.0}", row.send_rate)}</td>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+    view! {
+        <table>
+            <thead>
+                <tr>
+                    <th>"Group"</th>
+                    <th>"Actions sent"</th>
+                    <th>"Updates sent"</th>
+                    <th>"Bytes sent"</th>
+                    <th>"Sent (B/s)"</th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
+
+#[component]
+fn RollbacksTable(history: ReadSignal<History>) -> impl IntoView {
+    let rows = move || {
+        history
+            .with(|h| h.recent_rollbacks())
+            .into_iter()
+            .map(|rollback| {
+                view! {
+                    <tr>
+                        <td>{rollback.tick}</td>
+                        <td>{rollback.start_tick}</td>
+                        <td>{rollback.depth()}</td>
+                        <td>{rollback.num_entities}</td>
+                    </tr>
+                }
+            })
+            .collect_view()
+    };
+    view! {
+        <table>
+            <thead>
+                <tr>
+                    <th>"Tick"</th>
+                    <th>"Resimulated from"</th>
+                    <th>"Ticks"</th>
+                    <th>"Entities"</th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+    }
+}
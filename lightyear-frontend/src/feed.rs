@@ -0,0 +1,94 @@
+//! Websocket connection to the inspector feed of a lightyear app
+use std::fmt;
+
+use leptos::{SignalSet, SignalUpdate, WriteSignal};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{CloseEvent, Event, MessageEvent, WebSocket};
+
+use crate::history::History;
+use crate::snapshot::InspectorSnapshot;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeedStatus {
+    Disconnected,
+    Connecting,
+    Connected,
+    Error(String),
+}
+
+impl fmt::Display for FeedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedStatus::Disconnected => write!(f, "disconnected"),
+            FeedStatus::Connecting => write!(f, "connecting..."),
+            FeedStatus::Connected => write!(f, "connected"),
+            FeedStatus::Error(e) => write!(f, "error: {e}"),
+        }
+    }
+}
+
+/// Open connection to the feed. The connection is closed when this is dropped
+pub struct Feed {
+    socket: WebSocket,
+    _on_open: Closure<dyn FnMut(Event)>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_error: Closure<dyn FnMut(Event)>,
+    _on_close: Closure<dyn FnMut(CloseEvent)>,
+}
+
+impl Feed {
+    /// Connect to the feed at `url`, and push the received snapshots to the history
+    pub fn connect(
+        url: &str,
+        history: WriteSignal<History>,
+        status: WriteSignal<FeedStatus>,
+    ) -> Result<Self, String> {
+        let socket = WebSocket::new(url).map_err(|e| format!("{e:?}"))?;
+        status.set(FeedStatus::Connecting);
+
+        let on_open = Closure::<dyn FnMut(Event)>::new(move |_| status.set(FeedStatus::Connected));
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            let Some(text) = event.data().as_string() else {
+                return;
+            };
+            match serde_json::from_str::<InspectorSnapshot>(&text) {
+                Ok(snapshot) => history.update(|history| history.push(snapshot)),
+                Err(e) => status.set(FeedStatus::Error(format!("invalid snapshot: {e}"))),
+            }
+        });
+        let on_error = Closure::<dyn FnMut(Event)>::new(move |_| {
+            status.set(FeedStatus::Error(
+                "could not connect to the feed".to_string(),
+            ))
+        });
+        let on_close = Closure::<dyn FnMut(CloseEvent)>::new(move |event: CloseEvent| {
+            // keep the error message if the connection failed
+            if event.was_clean() {
+                status.set(FeedStatus::Disconnected);
+            }
+        });
+        socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        socket.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        Ok(Self {
+            socket,
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_error: on_error,
+            _on_close: on_close,
+        })
+    }
+}
+
+impl Drop for Feed {
+    fn drop(&mut self) {
+        // the callbacks are dropped with the feed, so they must not be called anymore
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onerror(None);
+        self.socket.set_onclose(None);
+        let _ = self.socket.close();
+    }
+}
@@ -0,0 +1,266 @@
+//! Keeps the recent snapshots, and computes the rates displayed in the tables and timelines
+use std::collections::VecDeque;
+
+use crate::snapshot::{ClientId, InspectorSnapshot, RollbackSnapshot};
+
+/// Number of snapshots displayed in the timelines
+pub const HISTORY_LEN: usize = 240;
+
+/// Maximum number of rollbacks displayed in the rollbacks table
+const MAX_ROLLBACKS: usize = 50;
+
+#[derive(Default, Clone, Debug)]
+pub struct History {
+    snapshots: VecDeque<InspectorSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectionRow {
+    pub client_id: ClientId,
+    pub rtt_ms: f32,
+    pub jitter_ms: f32,
+    pub packet_loss: f32,
+    /// Bytes per second
+    pub send_rate: f32,
+    pub receive_rate: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelRow {
+    pub name: String,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    /// Bytes per second
+    pub send_rate: f32,
+    pub receive_rate: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupRow {
+    pub group_id: u64,
+    pub actions_sent: u64,
+    pub updates_sent: u64,
+    pub bytes_sent: u64,
+    /// Bytes per second
+    pub send_rate: f32,
+}
+
+/// Rate of a cumulative counter between two snapshots.
+/// The counters are reset when a client reconnects, so we ignore decreases.
+fn rate(previous: Option<u64>, current: u64, dt: f32) -> f32 {
+    match previous {
+        Some(previous) if dt > 0.0 => current.saturating_sub(previous) as f32 / dt,
+        _ => 0.0,
+    }
+}
+
+impl History {
+    pub fn push(&mut self, snapshot: InspectorSnapshot) {
+        // the app restarted: the previous snapshots are not comparable anymore
+        if self
+            .latest()
+            .map_or(false, |latest| snapshot.time < latest.time)
+        {
+            self.snapshots.clear();
+        }
+        if self.snapshots.len() == HISTORY_LEN {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub fn latest(&self) -> Option<&InspectorSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// The latest snapshot and the one before it
+    fn latest_pair(&self) -> Option<(Option<&InspectorSnapshot>, &InspectorSnapshot)> {
+        let latest = self.snapshots.back()?;
+        let previous = self
+            .snapshots
+            .len()
+            .checked_sub(2)
+            .and_then(|i| self.snapshots.get(i));
+        Some((previous, latest))
+    }
+
+    pub fn connection_rows(&self) -> Vec<ConnectionRow> {
+        let Some((previous, latest)) = self.latest_pair() else {
+            return vec![];
+        };
+        let dt = previous.map_or(0.0, |p| latest.time - p.time);
+        latest
+            .connections
+            .iter()
+            .map(|connection| {
+                let previous = previous.and_then(|p| p.connection(connection.client_id));
+                ConnectionRow {
+                    client_id: connection.client_id,
+                    rtt_ms: connection.rtt_ms,
+                    jitter_ms: connection.jitter_ms,
+                    packet_loss: connection.packet_loss,
+                    send_rate: rate(
+                        previous.map(|p| p.bytes_sent()),
+                        connection.bytes_sent(),
+                        dt,
+                    ),
+                    receive_rate: rate(
+                        previous.map(|p| p.bytes_received()),
+                        connection.bytes_received(),
+                        dt,
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    pub fn channel_rows(&self, client_id: ClientId) -> Vec<ChannelRow> {
+        let Some((previous, latest)) = self.latest_pair() else {
+            return vec![];
+        };
+        let Some(connection) = latest.connection(client_id) else {
+            return vec![];
+        };
+        let dt = previous.map_or(0.0, |p| latest.time - p.time);
+        let previous = previous.and_then(|p| p.connection(client_id));
+        connection
+            .channels
+            .iter()
+            .map(|channel| {
+                let previous =
+                    previous.and_then(|p| p.channels.iter().find(|c| c.name == channel.name));
+                ChannelRow {
+                    name: channel.name.clone(),
+                    messages_sent: channel.messages_sent,
+                    messages_received: channel.messages_received,
+                    send_rate: rate(previous.map(|p| p.bytes_sent), channel.bytes_sent, dt),
+                    receive_rate: rate(
+                        previous.map(|p| p.bytes_received),
+                        channel.bytes_received,
+                        dt,
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    pub fn group_rows(&self, client_id: ClientId) -> Vec<GroupRow> {
+        let Some((previous, latest)) = self.latest_pair() else {
+            return vec![];
+        };
+        let Some(connection) = latest.connection(client_id) else {
+            return vec![];
+        };
+        let dt = previous.map_or(0.0, |p| latest.time - p.time);
+        let previous = previous.and_then(|p| p.connection(client_id));
+        connection
+            .groups
+            .iter()
+            .map(|group| {
+                let previous =
+                    previous.and_then(|p| p.groups.iter().find(|g| g.group_id == group.group_id));
+                GroupRow {
+                    group_id: group.group_id,
+                    actions_sent: group.actions_sent,
+                    updates_sent: group.updates_sent,
+                    bytes_sent: group.bytes_sent,
+                    send_rate: rate(previous.map(|p| p.bytes_sent), group.bytes_sent, dt),
+                }
+            })
+            .collect()
+    }
+
+    /// RTT of the connection in each snapshot
+    pub fn rtt_timeline(&self, client_id: ClientId) -> Vec<f32> {
+        self.snapshots
+            .iter()
+            .map(|s| s.connection(client_id).map_or(0.0, |c| c.rtt_ms))
+            .collect()
+    }
+
+    /// Bytes per second sent and received on the connection, between each pair of consecutive snapshots
+    pub fn bandwidth_timeline(&self, client_id: ClientId) -> (Vec<f32>, Vec<f32>) {
+        self.snapshots
+            .iter()
+            .zip(self.snapshots.iter().skip(1))
+            .map(|(previous, current)| {
+                let dt = current.time - previous.time;
+                let previous = previous.connection(client_id);
+                current.connection(client_id).map_or((0.0, 0.0), |c| {
+                    (
+                        rate(previous.map(|p| p.bytes_sent()), c.bytes_sent(), dt),
+                        rate(previous.map(|p| p.bytes_received()), c.bytes_received(), dt),
+                    )
+                })
+            })
+            .unzip()
+    }
+
+    /// Number of rollbacks in each snapshot
+    pub fn rollback_timeline(&self) -> Vec<f32> {
+        self.snapshots
+            .iter()
+            .map(|s| s.rollbacks.len() as f32)
+            .collect()
+    }
+
+    /// The most recent rollbacks, most recent first
+    pub fn recent_rollbacks(&self) -> Vec<RollbackSnapshot> {
+        self.snapshots
+            .iter()
+            .rev()
+            .flat_map(|s| s.rollbacks.iter().rev())
+            .take(MAX_ROLLBACKS)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sample snapshot, as serialized by `lightyear::shared::inspector`
+    fn snapshot(time: f32, bytes_sent: u64) -> InspectorSnapshot {
+        serde_json::from_str(&format!(
+            r#"{{
+                "source": "Server",
+                "tick": 10,
+                "time": {time},
+                "connections": [{{
+                    "client_id": 1,
+                    "rtt_ms": 20.0,
+                    "jitter_ms": 2.0,
+                    "packet_loss": 0.0,
+                    "channels": [{{
+                        "name": "EntityUpdatesChannel",
+                        "messages_sent": 5,
+                        "bytes_sent": {bytes_sent},
+                        "messages_received": 0,
+                        "bytes_received": 0
+                    }}],
+                    "groups": [{{"group_id": 3, "actions_sent": 1, "updates_sent": 4, "bytes_sent": {bytes_sent}}}]
+                }}],
+                "rollbacks": []
+            }}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rates() {
+        let mut history = History::default();
+        history.push(snapshot(1.0, 100));
+        assert_eq!(history.connection_rows()[0].send_rate, 0.0);
+
+        history.push(snapshot(1.5, 300));
+        assert_eq!(history.connection_rows()[0].send_rate, 400.0);
+        assert_eq!(history.channel_rows(1)[0].send_rate, 400.0);
+        assert_eq!(history.group_rows(1)[0].send_rate, 400.0);
+        assert_eq!(history.bandwidth_timeline(1).0, vec![400.0]);
+
+        // the app restarted
+        history.push(snapshot(0.5, 10));
+        assert_eq!(history.rtt_timeline(1).len(), 1);
+    }
+}
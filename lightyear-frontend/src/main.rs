@@ -0,0 +1,13 @@
+//! Dashboard that renders the network state published by the inspector feed of a lightyear app.
+//!
+//! Run with `trunk serve`, then connect to the feed of the game (by default `ws://127.0.0.1:9102` for a server
+//! and `ws://127.0.0.1:9103` for a client).
+mod app;
+mod feed;
+mod history;
+mod snapshot;
+
+fn main() {
+    console_error_panic_hook::set_once();
+    leptos::mount_to_body(app::App);
+}
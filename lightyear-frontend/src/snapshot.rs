@@ -0,0 +1,142 @@
+//! Snapshots published by the inspector feed of a lightyear app.
+//!
+//! These mirror the types of `lightyear::shared::inspector`: we don't depend on lightyear here so that the
+//! dashboard doesn't need to compile bevy to wasm. The two must be kept in sync.
+use serde::Deserialize;
+
+pub type ClientId = u64;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InspectorSource {
+    Server,
+    Client,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct InspectorSnapshot {
+    pub source: InspectorSource,
+    pub tick: u16,
+    /// Time elapsed since the app started, in seconds
+    pub time: f32,
+    pub connections: Vec<ConnectionSnapshot>,
+    /// Rollbacks that happened since the previous snapshot
+    pub rollbacks: Vec<RollbackSnapshot>,
+}
+
+impl InspectorSnapshot {
+    pub fn connection(&self, client_id: ClientId) -> Option<&ConnectionSnapshot> {
+        self.connections.iter().find(|c| c.client_id == client_id)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ConnectionSnapshot {
+    pub client_id: ClientId,
+    pub rtt_ms: f32,
+    pub jitter_ms: f32,
+    pub packet_loss: f32,
+    pub channels: Vec<ChannelSnapshot>,
+    pub groups: Vec<GroupSnapshot>,
+}
+
+impl ConnectionSnapshot {
+    pub fn bytes_sent(&self) -> u64 {
+        self.channels.iter().map(|c| c.bytes_sent).sum()
+    }
+
+    pub fn bytes_received(&self) -> u64 {
+        self.channels.iter().map(|c| c.bytes_received).sum()
+    }
+}
+
+/// Counters of a channel, cumulative since the start of the connection
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ChannelSnapshot {
+    pub name: String,
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub messages_received: u64,
+    pub bytes_received: u64,
+}
+
+/// Counters of a replication group, cumulative since the start of the connection
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct GroupSnapshot {
+    pub group_id: u64,
+    pub actions_sent: u64,
+    pub updates_sent: u64,
+    pub bytes_sent: u64,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct RollbackSnapshot {
+    pub tick: u16,
+    pub start_tick: u16,
+    pub num_entities: usize,
+}
+
+impl RollbackSnapshot {
+    /// Number of ticks that were resimulated
+    pub fn depth(&self) -> u16 {
+        self.tick.wrapping_sub(self.start_tick).wrapping_add(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Same layout as the snapshot in the JSON layout test of `lightyear::shared::inspector`
+    #[test]
+    fn test_deserialize_snapshot() {
+        let snapshot: InspectorSnapshot = serde_json::from_str(
+            r#"{
+                "source": "Client",
+                "tick": 10,
+                "time": 1.5,
+                "connections": [{
+                    "client_id": 1,
+                    "rtt_ms": 20.0,
+                    "jitter_ms": 2.0,
+                    "packet_loss": 0.25,
+                    "channels": [
+                        {"name": "Channel1", "messages_sent": 1, "bytes_sent": 2, "messages_received": 3, "bytes_received": 4},
+                        {"name": "Channel2", "messages_sent": 1, "bytes_sent": 10, "messages_received": 1, "bytes_received": 20}
+                    ],
+                    "groups": [{"group_id": 5, "actions_sent": 6, "updates_sent": 7, "bytes_sent": 8}]
+                }],
+                "rollbacks": [{"tick": 10, "start_tick": 8, "num_entities": 3}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(snapshot.source, InspectorSource::Client);
+        assert_eq!(snapshot.tick, 10);
+        assert_eq!(snapshot.time, 1.5);
+        assert!(snapshot.connection(2).is_none());
+        let connection = snapshot.connection(1).unwrap();
+        assert_eq!(connection.packet_loss, 0.25);
+        assert_eq!(connection.bytes_sent(), 12);
+        assert_eq!(connection.bytes_received(), 24);
+        assert_eq!(
+            connection.groups,
+            vec![GroupSnapshot {
+                group_id: 5,
+                actions_sent: 6,
+                updates_sent: 7,
+                bytes_sent: 8,
+            }]
+        );
+        assert_eq!(snapshot.rollbacks[0].num_entities, 3);
+        assert_eq!(snapshot.rollbacks[0].depth(), 3);
+    }
+
+    #[test]
+    fn test_rollback_depth_wraps() {
+        let rollback = RollbackSnapshot {
+            tick: 1,
+            start_tick: u16::MAX - 1,
+            num_entities: 1,
+        };
+        assert_eq!(rollback.depth(), 4);
+    }
+}
@@ -1,6 +1,7 @@
 //! This module contains the [`Channel`] trait
 use bevy::reflect::Reflect;
 use bevy::utils::Duration;
+use serde::{Deserialize, Serialize};
 
 use lightyear_macros::ChannelInternal;
 
@@ -24,6 +25,17 @@ pub struct ChannelContainer {
     pub setting: ChannelSettings,
     pub(crate) receiver: ChannelReceiver,
     pub(crate) sender: ChannelSender,
+    pub(crate) stats: ChannelStats,
+}
+
+/// Number of messages (and their size in bytes) that were sent and received on a channel since the start of the connection
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+pub struct ChannelStats {
+    /// Number of messages buffered for sending (retries of reliable messages are not counted)
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub messages_received: u64,
+    pub bytes_received: u64,
 }
 
 /// A Channel is an abstraction for a way to send messages over the network
@@ -91,6 +103,7 @@ impl ChannelContainer {
             setting: settings_clone,
             receiver,
             sender,
+            stats: ChannelStats::default(),
         }
     }
 }
@@ -152,6 +165,16 @@ impl ChannelMode {
         }
     }
 
+    /// Returns true if the messages of the channel should be sent on the reliable stream of the transport, if there is one
+    pub(crate) fn uses_transport_stream(&self) -> bool {
+        match self {
+            ChannelMode::UnorderedReliable(settings)
+            | ChannelMode::SequencedReliable(settings)
+            | ChannelMode::OrderedReliable(settings) => settings.use_transport_stream,
+            _ => false,
+        }
+    }
+
     /// Returns true if the channel cares about tracking ACKs of messages
     pub(crate) fn is_watching_acks(&self) -> bool {
         match self {
@@ -180,6 +203,15 @@ pub struct ReliableSettings {
     pub rtt_resend_factor: f32,
     /// Minimum duration to wait before resending a packet if it has not been acked
     pub rtt_resend_min_delay: Duration,
+    /// If true, and the transport provides a reliable ordered stream (for example WebTransport),
+    /// the messages of this channel are sent on that stream instead of as datagrams.
+    ///
+    /// The transport is then responsible for the retransmissions, so lightyear does not resend the messages.
+    /// Messages sent on the stream still count against the bandwidth quota.
+    ///
+    /// The setting applies to every connection of the channel; connections whose transport has no stream
+    /// (for example WebTransport wasm clients, UDP) keep sending the messages as datagrams.
+    pub use_transport_stream: bool,
 }
 
 impl Default for ReliableSettings {
@@ -187,6 +219,7 @@ impl Default for ReliableSettings {
         Self {
             rtt_resend_factor: 1.5,
             rtt_resend_min_delay: Duration::default(),
+            use_transport_stream: false,
         }
     }
 }
@@ -286,6 +286,7 @@ mod tests {
         let mut sender = ReliableSender::new(ReliableSettings {
             rtt_resend_factor: 1.5,
             rtt_resend_min_delay: Duration::from_millis(100),
+            use_transport_stream: false,
         });
         sender.current_rtt = Duration::from_millis(100);
         sender.current_time = WrappedTime::new(0);
@@ -31,17 +31,50 @@ impl<T> SyncComponent for T where T: Component + Clone + PartialEq + Message {}
 // NOTE: we use these traits that the Protocol will implement so that we don't implement
 // external traits on external types and break the orphan rule
 
+/// The server states surrounding the current interpolation tick
+pub struct InterpolationSamples<'a, C> {
+    /// the server state received before `start`, if any
+    pub previous: Option<(Tick, &'a C)>,
+    /// the server state we are interpolating from
+    pub start: (Tick, &'a C),
+    /// the server state we are interpolating towards
+    pub end: (Tick, &'a C),
+    /// the server state received after `end`, if any
+    pub next: Option<(Tick, &'a C)>,
+}
+
 /// Function that will interpolated between two values
 pub trait LerpFn<C> {
     fn lerp(start: &C, other: &C, t: f32) -> C;
+
+    /// Interpolate between `samples.start` and `samples.end`, with access to the surrounding server states
+    /// (to compute splines, etc.). By default, only the two closest states are used.
+    fn interpolate(samples: &InterpolationSamples<C>, t: f32) -> C {
+        Self::lerp(samples.start.1, samples.end.1, t)
+    }
+}
+
+/// Function that will extrapolate a value past the last received server state
+pub trait ExtrapolateFn<C> {
+    /// Project the component forward from `last`, using `previous` (the server state received before `last`).
+    /// `t` is the time elapsed since `last`, as a fraction of the interval between `previous` and `last`
+    fn extrapolate(previous: &C, last: &C, t: f32) -> C;
 }
 
 /// Defines how to do interpolation/correction for the component
 pub trait SyncMetadata<C> {
     type Interpolator: LerpFn<C> + 'static;
+    type Extrapolator: ExtrapolateFn<C> + 'static;
     type Corrector: LerpFn<C> + 'static;
 
     fn mode() -> ComponentSyncMode;
+
+    /// Returns the value of the component with the precision used to send it over the network.
+    ///
+    /// By default, the component is not quantized.
+    fn quantize(component: C) -> C {
+        component
+    }
 }
 
 #[derive(Debug, Default, PartialEq)]
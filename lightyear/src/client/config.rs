@@ -10,6 +10,7 @@ use crate::client::sync::SyncConfig;
 use crate::connection::client::NetConfig;
 use crate::shared::config::SharedConfig;
 use crate::shared::ping::manager::PingConfig;
+use crate::shared::replication::receive::DEFAULT_ENTITY_REFERENCE_TIMEOUT_TICKS;
 
 #[derive(Clone)]
 /// Config related to the netcode protocol (abstraction of a connection over raw UDP-like transport)
@@ -76,6 +77,43 @@ impl PacketConfig {
     }
 }
 
+/// Configuration related to the replication messages received from the server
+#[derive(Clone, Debug)]
+pub struct ReplicationConfig {
+    /// Number of ticks that replication messages are held back before being applied.
+    /// This lets updates that arrive out of order because of network jitter be applied in order of tick.
+    /// 0 means that messages are applied as soon as possible.
+    pub playout_delay_ticks: u16,
+    /// Maximum number of ticks that a replicated component is held back while waiting for the entities
+    /// it references to be replicated.
+    /// After that, the references that could not be mapped are set to [`Entity::PLACEHOLDER`](bevy::prelude::Entity::PLACEHOLDER)
+    pub entity_reference_timeout_ticks: u16,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            playout_delay_ticks: 0,
+            entity_reference_timeout_ticks: DEFAULT_ENTITY_REFERENCE_TIMEOUT_TICKS,
+        }
+    }
+}
+
+impl ReplicationConfig {
+    pub fn with_playout_delay_ticks(mut self, playout_delay_ticks: u16) -> Self {
+        self.playout_delay_ticks = playout_delay_ticks;
+        self
+    }
+
+    pub fn with_entity_reference_timeout_ticks(
+        mut self,
+        entity_reference_timeout_ticks: u16,
+    ) -> Self {
+        self.entity_reference_timeout_ticks = entity_reference_timeout_ticks;
+        self
+    }
+}
+
 /// The configuration object that lets you create a `ClientPlugin` with the desired settings.
 ///
 /// Most of the fields are optional and have sensible defaults.
@@ -97,4 +135,5 @@ pub struct ClientConfig {
     pub sync: SyncConfig,
     pub prediction: PredictionConfig,
     pub interpolation: InterpolationConfig,
+    pub replication: ReplicationConfig,
 }
@@ -17,6 +17,7 @@ use crate::packet::message_manager::MessageManager;
 use crate::packet::packet_manager::Payload;
 use crate::prelude::{Channel, ChannelKind, LightyearMapEntities, Message, NetworkTarget};
 use crate::protocol::channel::ChannelRegistry;
+use crate::protocol::message_registry::MessageRegistry;
 use crate::protocol::Protocol;
 use crate::serialize::reader::ReadBuffer;
 use crate::server::message::ServerMessage;
@@ -27,6 +28,7 @@ use crate::shared::replication::receive::ReplicationReceiver;
 use crate::shared::replication::send::ReplicationSender;
 use crate::shared::replication::ReplicationMessage;
 use crate::shared::replication::ReplicationMessageData;
+use crate::shared::tick_event::{NetworkedEvent, TickEventMessage};
 use crate::shared::tick_manager::Tick;
 use crate::shared::tick_manager::TickManager;
 use crate::shared::time_manager::TimeManager;
@@ -63,6 +65,8 @@ pub struct ConnectionManager<P: Protocol> {
     pub(crate) ping_manager: PingManager,
     pub(crate) input_buffer: InputBuffer<P::Input>,
     pub(crate) sync_manager: SyncManager,
+    /// Messages registered at runtime; copied from the app when the plugin finishes building
+    pub(crate) message_registry: MessageRegistry,
     // TODO: maybe don't do any replication until connection is synced?
 }
 
@@ -73,6 +77,8 @@ impl<P: Protocol> ConnectionManager<P> {
         sync_config: SyncConfig,
         ping_config: PingConfig,
         input_delay_ticks: u16,
+        playout_delay_ticks: u16,
+        entity_reference_timeout_ticks: u16,
     ) -> Self {
         // create the message manager and the channels
         let mut message_manager = MessageManager::new(channel_registry, packet_config.into());
@@ -88,7 +94,9 @@ impl<P: Protocol> ConnectionManager<P> {
             message_manager.get_replication_update_send_receiver();
         let replication_sender =
             ReplicationSender::new(update_acks_tracker, replication_update_send_receiver);
-        let replication_receiver = ReplicationReceiver::new();
+        let replication_receiver = ReplicationReceiver::new()
+            .with_playout_delay_ticks(playout_delay_ticks)
+            .with_entity_reference_timeout_ticks(entity_reference_timeout_ticks);
         Self {
             message_manager,
             replication_sender,
@@ -97,6 +105,7 @@ impl<P: Protocol> ConnectionManager<P> {
             input_buffer: InputBuffer::default(),
             sync_manager: SyncManager::new(sync_config, input_delay_ticks),
             events: ConnectionEvents::default(),
+            message_registry: MessageRegistry::default(),
         }
     }
 
@@ -106,6 +115,21 @@ impl<P: Protocol> ConnectionManager<P> {
         self.sync_manager.is_synced()
     }
 
+    /// Latest estimate of the round-trip time to the server
+    pub fn rtt(&self) -> Duration {
+        self.ping_manager.rtt()
+    }
+
+    /// Latest estimate of the jitter of the connection to the server
+    pub fn jitter(&self) -> Duration {
+        self.ping_manager.jitter()
+    }
+
+    /// Fraction of the packets sent to the server that were lost recently
+    pub fn packet_loss(&self) -> f32 {
+        self.message_manager.packet_loss()
+    }
+
     pub(crate) fn received_new_server_tick(&self) -> bool {
         self.sync_manager.duration_since_latest_received_server_tick == Duration::default()
     }
@@ -165,6 +189,36 @@ impl<P: Protocol> ConnectionManager<P> {
         self.buffer_message(message.into(), channel, target)
     }
 
+    /// Send a message registered with [`AppMessageExt::register_message`](crate::prelude::AppMessageExt::register_message) to the server
+    pub fn send_registered_message<C: Channel, M: Message + Serialize>(
+        &mut self,
+        message: M,
+    ) -> Result<()> {
+        self.send_registered_message_to_target::<C, M>(message, NetworkTarget::None)
+    }
+
+    /// Send a registered message to the server, the message should be re-broadcasted according to the `target`
+    pub fn send_registered_message_to_target<C: Channel, M: Message + Serialize>(
+        &mut self,
+        message: M,
+        target: NetworkTarget,
+    ) -> Result<()> {
+        let message = self.message_registry.serialize(&message)?;
+        self.buffer_message(message.into(), ChannelKind::of::<C>(), target)
+    }
+
+    /// Send an event that happened at `tick` to the server, to be emitted as a [`TickEvent`](crate::server::tick_event::TickEvent)
+    /// when the server tick reaches `tick`.
+    ///
+    /// The event type must be added with the [`TickEventPlugin`](crate::client::tick_event::TickEventPlugin).
+    pub fn send_tick_event<C: Channel, E: NetworkedEvent>(
+        &mut self,
+        event: E,
+        tick: Tick,
+    ) -> Result<()> {
+        self.send_registered_message::<C, _>(TickEventMessage { tick, event })
+    }
+
     pub(crate) fn buffer_message(
         &mut self,
         message: P::Message,
@@ -215,10 +269,19 @@ impl<P: Protocol> ConnectionManager<P> {
                 });
                 trace!("Sending replication message: {:?}", message);
                 message.emit_send_logs(&channel_name);
+                let bytes_before = self.message_manager.bytes_sent(&channel);
                 let message_id = self
                     .message_manager
                     .buffer_send_with_priority(message, channel, priority)?
                     .expect("The EntityUpdatesChannel should always return a message_id");
+                self.replication_sender
+                    .group_channels
+                    .entry(group_id)
+                    .or_default()
+                    .record_sent_message(
+                        should_track_ack,
+                        self.message_manager.bytes_sent(&channel) - bytes_before,
+                    );
 
                 // TODO: if should_track_ack OR bandwidth_cap is enabled
                 // keep track of the group associated with the message, so we can handle receiving an ACK for that message_id later
@@ -349,8 +412,11 @@ impl<P: Protocol> ConnectionManager<P> {
         //
         // Check if we have any replication messages we can apply to the World (and emit events)
         if self.sync_manager.is_synced() {
-            for (group, replication_list) in
-                self.replication_receiver.read_messages(tick_manager.tick())
+            // the most recent server tick that we know of is our estimate of the remote tick
+            let remote_tick = self.latest_received_server_tick();
+            for (group, replication_list) in self
+                .replication_receiver
+                .read_messages(tick_manager.tick(), remote_tick)
             {
                 trace!(?group, ?replication_list, "read replication messages");
                 replication_list
@@ -366,6 +432,9 @@ impl<P: Protocol> ConnectionManager<P> {
                         );
                     });
             }
+            // components can reference entities from other replication groups, that we might have just received
+            self.replication_receiver
+                .resolve_pending_components(world, &mut self.events);
         }
 
         // TODO: do i really need this? I could just create events in this function directly?
@@ -388,6 +457,13 @@ impl<P: Protocol> ConnectionManager<P> {
             .map_or(true, |server_tick| tick >= server_tick)
         {
             trace!("new last recv server tick: {:?}", tick);
+            if self
+                .sync_manager
+                .latest_received_server_tick
+                .is_some_and(|server_tick| tick > server_tick)
+            {
+                self.sync_manager.record_server_update_gap();
+            }
             self.sync_manager.latest_received_server_tick = Some(tick);
             // TODO: add 'received_new_server_tick' ?
             // we probably actually physically received the packet some time between our last `receive` and now.
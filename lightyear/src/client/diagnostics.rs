@@ -2,6 +2,10 @@ use bevy::app::{App, Plugin, PostUpdate};
 use bevy::diagnostic::Diagnostics;
 use bevy::prelude::{Real, Res, ResMut, Time};
 
+use crate::client::interpolation::diagnostics::{
+    interpolation_diagnostics_system, InterpolationDiagnosticsPlugin,
+};
+use crate::client::prediction::diagnostics::PredictionDiagnosticsPlugin;
 use crate::connection::client::{ClientConnection, NetClient};
 use crate::prelude::Protocol;
 use crate::transport::io::IoDiagnosticsPlugin;
@@ -30,6 +34,11 @@ fn io_diagnostics_system(
 impl<P: Protocol> Plugin for ClientDiagnosticsPlugin<P> {
     fn build(&self, app: &mut App) {
         app.add_plugins(IoDiagnosticsPlugin);
-        app.add_systems(PostUpdate, io_diagnostics_system);
+        app.add_plugins(PredictionDiagnosticsPlugin);
+        app.add_plugins(InterpolationDiagnosticsPlugin);
+        app.add_systems(
+            PostUpdate,
+            (io_diagnostics_system, interpolation_diagnostics_system),
+        );
     }
 }
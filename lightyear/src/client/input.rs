@@ -241,9 +241,12 @@ fn prepare_input_message<P: Protocol>(
     //  - buffer an input every frame; and require some redundancy (number of tick per frame)
     //  - or buffer an input only when we are sending, and require more redundancy
     // let message_len = 20 as u16;
-    let message = connection
+    let mut message = connection
         .input_buffer
         .create_message(tick_manager.tick(), message_len);
+    // include the interpolation tick so that the server can perform lag compensation
+    let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
+    message.interpolation_tick = Some(interpolation_tick);
     // all inputs are absent
     if !message.is_empty() {
         // TODO: should we provide variants of each user-facing function, so that it pushes the error
@@ -260,7 +263,6 @@ fn prepare_input_message<P: Protocol>(
     //  maybe at interpolation_tick(), since it's before any latest server update we receive?
 
     // delete old input values
-    let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
     connection.input_buffer.pop(interpolation_tick);
     // .pop(current_tick - (message_len + 1));
 }
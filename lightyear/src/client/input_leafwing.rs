@@ -573,6 +573,8 @@ fn prepare_input_message<P: Protocol, A: LeafwingUserAction>(
     // delete old input values
     // anything beyond interpolation tick should be safe to be deleted
     let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
+    // include the interpolation tick so that the server can perform lag compensation
+    message.interpolation_tick = Some(interpolation_tick);
     trace!(
         "popping all inputs since interpolation tick: {:?}",
         interpolation_tick
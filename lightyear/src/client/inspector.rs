@@ -0,0 +1,136 @@
+//! Client side of the network inspector: publish the state of the connection to the server and the rollbacks.
+//!
+//! See [`crate::shared::inspector`] for more details.
+//!
+//! ```rust,ignore
+//! use lightyear::prelude::client::*;
+//!
+//! fn setup(app: &mut App) {
+//!     app.add_plugins(InspectorPlugin::<MyProtocol>::new(InspectorConfig::client()));
+//! }
+//! ```
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    App, Commands, IntoSystemConfigs, Plugin, PostUpdate, PreUpdate, Query, Real, Res, ResMut,
+    Resource, Startup, Time, With,
+};
+
+use crate::client::connection::ConnectionManager;
+use crate::client::prediction::plugin::{is_in_rollback, PredictionSet};
+use crate::client::prediction::{Predicted, Rollback, RollbackState};
+use crate::connection::client::{ClientConnection, NetClient};
+use crate::prelude::MainSet;
+use crate::protocol::Protocol;
+use crate::shared::inspector::feed::InspectorFeed;
+use crate::shared::inspector::{
+    ConnectionSnapshot, InspectorConfig, InspectorSnapshot, InspectorSource, RollbackSnapshot,
+};
+use crate::shared::tick_manager::TickManager;
+
+/// Plugin that publishes the state of the client's connection to the network inspector feed
+pub struct InspectorPlugin<P> {
+    config: InspectorConfig,
+    _marker: PhantomData<P>,
+}
+
+impl<P> InspectorPlugin<P> {
+    pub fn new(config: InspectorConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P> Default for InspectorPlugin<P> {
+    fn default() -> Self {
+        Self::new(InspectorConfig::client())
+    }
+}
+
+/// Rollbacks that happened since the last snapshot
+#[derive(Resource, Default)]
+struct InspectorRollbacks(Vec<RollbackSnapshot>);
+
+impl<P: Protocol> Plugin for InspectorPlugin<P> {
+    fn build(&self, app: &mut App) {
+        let config = self.config.clone();
+        // RESOURCES
+        app.init_resource::<InspectorRollbacks>();
+        // SYSTEMS
+        // the feed uses the IoTaskPool, which is only available once the app is running
+        app.add_systems(Startup, move |mut commands: Commands| {
+            commands.insert_resource(InspectorFeed::start(config.clone()));
+        });
+        // the entities that are rolled back are known after CollectRollbackEntities
+        app.add_systems(
+            PreUpdate,
+            record_rollback
+                .after(PredictionSet::CollectRollbackEntities)
+                .before(PredictionSet::PrepareRollback)
+                .run_if(is_in_rollback),
+        );
+        // publish the snapshot after the packets of the frame were sent, so that they are included in the stats
+        app.add_systems(PostUpdate, publish_snapshot::<P>.after(MainSet::Send));
+    }
+}
+
+fn record_rollback(
+    tick_manager: Res<TickManager>,
+    rollback: Res<Rollback>,
+    predicted: Query<(), With<Predicted>>,
+    feed: Option<Res<InspectorFeed>>,
+    mut rollbacks: ResMut<InspectorRollbacks>,
+) {
+    // don't accumulate rollbacks if no frontend is connected
+    if !feed.is_some_and(|feed| feed.has_frontends()) {
+        return;
+    }
+    let RollbackState::ShouldRollback { current_tick } = rollback.state else {
+        return;
+    };
+    let num_entities = if rollback.partial {
+        rollback.entities.len()
+    } else {
+        predicted.iter().count()
+    };
+    rollbacks.0.push(RollbackSnapshot {
+        tick: tick_manager.tick(),
+        start_tick: current_tick,
+        num_entities,
+    });
+}
+
+fn publish_snapshot<P: Protocol>(
+    time: Res<Time<Real>>,
+    tick_manager: Res<TickManager>,
+    netclient: Res<ClientConnection>,
+    connection_manager: Res<ConnectionManager<P>>,
+    feed: Option<ResMut<InspectorFeed>>,
+    mut rollbacks: ResMut<InspectorRollbacks>,
+) {
+    let Some(mut feed) = feed else {
+        return;
+    };
+    if !feed.should_publish(time.elapsed()) {
+        return;
+    }
+    let connections = if netclient.is_connected() {
+        vec![ConnectionSnapshot::new(
+            netclient.id(),
+            &connection_manager.ping_manager,
+            &connection_manager.message_manager,
+            &connection_manager.replication_sender,
+        )]
+    } else {
+        vec![]
+    };
+    feed.publish(&InspectorSnapshot {
+        source: InspectorSource::Client,
+        tick: tick_manager.tick(),
+        time: time.elapsed_seconds(),
+        connections,
+        rollbacks: std::mem::take(&mut rollbacks.0),
+    });
+}
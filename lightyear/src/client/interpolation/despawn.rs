@@ -1,10 +1,16 @@
-use bevy::prelude::{Commands, EventReader, Query, RemovedComponents, ResMut};
+use bevy::prelude::{
+    Added, Changed, Commands, Entity, EventReader, Query, RemovedComponents, ResMut, With,
+};
 
+use crate::_reexport::FromType;
 use crate::client::components::{Confirmed, SyncComponent};
 use crate::client::interpolation::interpolate::InterpolateStatus;
 use crate::client::interpolation::interpolation_history::ConfirmedHistory;
 use crate::client::interpolation::resource::InterpolationManager;
+use crate::client::interpolation::Interpolated;
+use crate::protocol::Protocol;
 use crate::shared::events::components::ComponentRemoveEvent;
+use crate::shared::replication::components::{Detached, DetachedComponents};
 
 /// Remove the component from interpolated entities when it gets removed from confirmed
 pub(crate) fn removed_components<C: SyncComponent>(
@@ -47,3 +53,48 @@ pub(crate) fn despawn_interpolated(
         }
     }
 }
+
+/// When the server detaches an interpolated entity (see [`ReplicationStopPolicy::Detach`](crate::prelude::ReplicationStopPolicy::Detach)),
+/// the interpolated entity is detached as well: it is not linked to the confirmed entity anymore,
+/// and stops being interpolated.
+pub(crate) fn detach_interpolated(
+    mut manager: ResMut<InterpolationManager>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Confirmed), Added<Detached>>,
+) {
+    for (confirmed_entity, mut confirmed) in query.iter_mut() {
+        let Some(interpolated) = confirmed.interpolated.take() else {
+            continue;
+        };
+        manager
+            .interpolated_entity_map
+            .confirmed_to_interpolated
+            .remove(&confirmed_entity);
+        if let Some(mut entity_mut) = commands.get_entity(interpolated) {
+            entity_mut.remove::<Interpolated>().insert(Detached);
+        }
+    }
+}
+
+/// Stop interpolating the component on the detached interpolated entities, or if the server detached the component:
+/// the interpolated entity keeps the last interpolated value of the component
+pub(crate) fn detach_interpolated_component<C: SyncComponent, P: Protocol>(
+    mut commands: Commands,
+    detached_entities: Query<Entity, (Added<Detached>, With<InterpolateStatus<C>>)>,
+    detached_components: Query<
+        (&Confirmed, &DetachedComponents<P>),
+        Changed<DetachedComponents<P>>,
+    >,
+) where
+    P::ComponentKinds: FromType<C>,
+{
+    let detached_components = detached_components
+        .iter()
+        .filter(|(_, detached)| detached.contains::<C>())
+        .filter_map(|(confirmed, _)| confirmed.interpolated);
+    for interpolated in detached_entities.iter().chain(detached_components) {
+        if let Some(mut entity_mut) = commands.get_entity(interpolated) {
+            entity_mut.remove::<(ConfirmedHistory<C>, InterpolateStatus<C>)>();
+        }
+    }
+}
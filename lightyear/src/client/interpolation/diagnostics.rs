@@ -0,0 +1,40 @@
+//! Diagnostics related to client-side interpolation
+use bevy::app::{App, Plugin};
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::ResMut;
+
+use crate::client::interpolation::resource::InterpolationManager;
+
+pub struct InterpolationDiagnosticsPlugin;
+
+impl InterpolationDiagnosticsPlugin {
+    /// How many interpolated components were extrapolated (instead of interpolated) during the frame
+    pub const EXTRAPOLATED_FRAMES: DiagnosticPath =
+        DiagnosticPath::const_new("interpolated components extrapolated per frame");
+
+    /// Max diagnostic history length.
+    pub const DIAGNOSTIC_HISTORY_LEN: usize = 60;
+}
+
+pub(crate) fn interpolation_diagnostics_system(
+    mut manager: ResMut<InterpolationManager>,
+    mut diagnostics: Diagnostics,
+) {
+    let extrapolated = std::mem::take(&mut manager.extrapolated_frames);
+    diagnostics.add_measurement(&InterpolationDiagnosticsPlugin::EXTRAPOLATED_FRAMES, || {
+        extrapolated as f64
+    });
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("extrapolated_frames").increment(extrapolated as u64);
+    }
+}
+
+impl Plugin for InterpolationDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(
+            Diagnostic::new(InterpolationDiagnosticsPlugin::EXTRAPOLATED_FRAMES)
+                .with_max_history_length(InterpolationDiagnosticsPlugin::DIAGNOSTIC_HISTORY_LEN),
+        );
+    }
+}
@@ -1,11 +1,13 @@
-use bevy::prelude::{Commands, Component, Entity, Query, Res, Without};
+use bevy::prelude::{Commands, Component, Entity, Query, Res, ResMut, Without};
 use tracing::{debug, trace};
 
 use crate::_reexport::ComponentProtocol;
-use crate::client::components::{SyncComponent, SyncMetadata};
+use crate::client::components::{InterpolationSamples, SyncComponent, SyncMetadata};
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
+use crate::client::easings::ease_out_quad;
 use crate::client::interpolation::interpolation_history::ConfirmedHistory;
+use crate::client::interpolation::resource::InterpolationManager;
 use crate::prelude::TickManager;
 use crate::protocol::Protocol;
 use crate::shared::tick_manager::Tick;
@@ -22,6 +24,9 @@ const SEND_INTERVAL_TICK_FACTOR: f32 = 1.5;
 // NOTE: there's not a strict need for this, it just makes the logic easier to follow
 /// Component that will tract the values to interpolate between, as well as the interpolation ratio.
 /// This is provided so that you can easily compute your own interpolation if you want to.
+///
+/// NOTE: the `previous`, `extrapolating` and `extrapolation_blend` fields were added to support extrapolation.
+/// This is a breaking change if you construct the struct directly (for example in tests).
 #[derive(Component, PartialEq, Debug)]
 pub struct InterpolateStatus<C: Component> {
     /// start tick to interpolate from, along with value
@@ -32,6 +37,13 @@ pub struct InterpolateStatus<C: Component> {
     pub current_tick: Tick,
     /// for more accurate interpolation, this is the fraction between [current_tick, current_tick + 1[
     pub current_overstep: f32,
+    /// the server state that preceded `start`; used to extrapolate past `start` when `end` is missing
+    pub previous: Option<(Tick, C)>,
+    /// true if the component currently holds an extrapolated value
+    pub extrapolating: bool,
+    /// when fresh server states arrive after we extrapolated, the tick at which we started blending
+    /// along with the last extrapolated value, so that we can blend back smoothly to the interpolated value
+    pub extrapolation_blend: Option<(Tick, C)>,
 }
 
 impl<C: Component> InterpolateStatus<C> {
@@ -47,6 +59,36 @@ impl<C: Component> InterpolateStatus<C> {
             })
         })
     }
+
+    /// The server states surrounding the interpolation tick, if we have a `start` and `end` state.
+    /// `next` is the first server state after `end`, if any (it is still in the [`ConfirmedHistory`])
+    pub fn samples<'a>(
+        &'a self,
+        next: Option<(Tick, &'a C)>,
+    ) -> Option<InterpolationSamples<'a, C>> {
+        let (start_tick, start_value) = self.start.as_ref()?;
+        let (end_tick, end_value) = self.end.as_ref()?;
+        Some(InterpolationSamples {
+            previous: self.previous.as_ref().map(|(tick, value)| (*tick, value)),
+            start: (*start_tick, start_value),
+            end: (*end_tick, end_value),
+            next,
+        })
+    }
+
+    /// Fraction used to extrapolate past `start` when there is no `end` state:
+    /// the time elapsed since `start`, relative to the interval between `previous` and `start`
+    pub fn extrapolation_fraction(&self) -> Option<f32> {
+        self.start.as_ref().and_then(|(start_tick, _)| {
+            self.previous
+                .as_ref()
+                .filter(|(previous_tick, _)| previous_tick < start_tick)
+                .map(|(previous_tick, _)| {
+                    ((self.current_tick - *start_tick) as f32 + self.current_overstep)
+                        / (*start_tick - *previous_tick) as f32
+                })
+        })
+    }
 }
 
 /// At the end of each frame, interpolate the components between the last 2 confirmed server states
@@ -74,6 +116,20 @@ pub(crate) fn update_interpolate_status<C: SyncComponent, P: Protocol>(
         * config.shared.server_send_interval.as_secs_f32()
         / config.shared.tick.tick_duration.as_secs_f32()) as i16
         + 1;
+    // if the component can be extrapolated, we keep the start value around for longer
+    let max_start_age = if P::Components::has_extrapolation::<C>() {
+        let max_extrapolation_delta_tick = (config
+            .interpolation
+            .extrapolation
+            .max_duration
+            .as_secs_f32()
+            / config.shared.tick.tick_duration.as_secs_f32())
+            as i16
+            + 1;
+        std::cmp::max(send_interval_delta_tick, max_extrapolation_delta_tick)
+    } else {
+        send_interval_delta_tick
+    };
 
     let current_interpolate_tick = connection
         .sync_manager
@@ -84,6 +140,7 @@ pub(crate) fn update_interpolate_status<C: SyncComponent, P: Protocol>(
     for (entity, component, mut status, mut history) in query.iter_mut() {
         let mut start = status.start.take();
         let mut end = status.end.take();
+        let mut previous = status.previous.take();
 
         // if the interpolation tick is beyond the previous end tick,
         // we need to replace start with end, and clear end
@@ -95,9 +152,10 @@ pub(crate) fn update_interpolate_status<C: SyncComponent, P: Protocol>(
                     ?current_interpolate_tick,
                     "interpolation is beyond previous end tick"
                 );
-                start = end.clone();
+                previous = std::mem::replace(&mut start, end.clone());
                 // TODO: this clone should be avoidable
-                if let Some(mut component) = component {
+                // (if we were extrapolating, keep the extrapolated value so that we can blend from it)
+                if let Some(mut component) = component.filter(|_| !status.extrapolating) {
                     *component = end_value.clone();
                 }
                 end = None;
@@ -119,7 +177,13 @@ pub(crate) fn update_interpolate_status<C: SyncComponent, P: Protocol>(
                     old_start = ?start.as_ref().map(|(tick, _)| tick),
                     new_start = ?new_tick,
                     "found more recent tick between start and interpolation tick");
-                start = new_start;
+                let old_start = std::mem::replace(&mut start, new_start);
+                if old_start
+                    .as_ref()
+                    .map_or(false, |(tick, _)| *tick < new_tick)
+                {
+                    previous = old_start;
+                }
             }
         }
 
@@ -178,11 +242,14 @@ pub(crate) fn update_interpolate_status<C: SyncComponent, P: Protocol>(
         if end.is_none() {
             let temp_start = std::mem::take(&mut start);
             if let Some((start_tick, _)) = temp_start {
-                if current_interpolate_tick - start_tick < send_interval_delta_tick {
+                if current_interpolate_tick - start_tick < max_start_age {
                     start = temp_start;
                 }
                 // else (if it's been too long), reset the server tick to None
             }
+            if start.is_none() {
+                previous = None;
+            }
         }
 
         debug!(
@@ -196,6 +263,7 @@ pub(crate) fn update_interpolate_status<C: SyncComponent, P: Protocol>(
             "update_interpolate_status");
         status.start = start;
         status.end = end;
+        status.previous = previous;
         status.current_tick = current_interpolate_tick;
         status.current_overstep = current_interpolate_overstep;
         if status.start.is_none() {
@@ -212,11 +280,11 @@ pub(crate) fn update_interpolate_status<C: SyncComponent, P: Protocol>(
 /// the component could be stuck at the 'start_tick' value until we have another update to interpolate towards
 pub(crate) fn insert_interpolated_component<C: SyncComponent, P: Protocol>(
     mut commands: Commands,
-    mut query: Query<(Entity, &InterpolateStatus<C>), Without<C>>,
+    mut query: Query<(Entity, &InterpolateStatus<C>, Option<&ConfirmedHistory<C>>), Without<C>>,
 ) where
     P::Components: SyncMetadata<C>,
 {
-    for (entity, status) in query.iter_mut() {
+    for (entity, status, history) in query.iter_mut() {
         debug!("checking if we do interpolation");
         let mut entity_commands = commands.entity(entity);
         // NOTE: it is possible that we reach start_tick when end_tick is not set
@@ -236,7 +304,10 @@ pub(crate) fn insert_interpolated_component<C: SyncComponent, P: Protocol>(
                 }
                 if start_tick != end_tick {
                     let t = status.interpolation_fraction().unwrap();
-                    let value = P::Components::lerp(start_value, end_value, t);
+                    let samples = status
+                        .samples(history.and_then(|history| history.peek()))
+                        .unwrap();
+                    let value = P::Components::interpolate(&samples, t);
                     entity_commands.insert(value);
                 } else {
                     entity_commands.insert(start_value.clone());
@@ -246,25 +317,73 @@ pub(crate) fn insert_interpolated_component<C: SyncComponent, P: Protocol>(
     }
 }
 
-/// Update the component value on the Interpolate entity
-pub(crate) fn interpolate<C: Component + Clone, P: Protocol>(
-    mut query: Query<(&mut C, &InterpolateStatus<C>)>,
+/// Update the component value on the Interpolate entity.
+///
+/// If we don't have a server state to interpolate towards and the component has an extrapolation function,
+/// we project the component forward from the last two server states for a bounded amount of time,
+/// and blend back to the interpolated value once new server states arrive.
+pub(crate) fn interpolate<C: SyncComponent, P: Protocol>(
+    config: Res<ClientConfig>,
+    mut manager: ResMut<InterpolationManager>,
+    mut query: Query<(
+        &mut C,
+        &mut InterpolateStatus<C>,
+        Option<&ConfirmedHistory<C>>,
+    )>,
 ) where
     P::Components: SyncMetadata<C>,
 {
-    for (mut component, status) in query.iter_mut() {
+    let tick_duration = config.shared.tick.tick_duration.as_secs_f32();
+    let extrapolation = &config.interpolation.extrapolation;
+    let max_extrapolation_ticks = extrapolation.max_duration.as_secs_f32() / tick_duration;
+    let blend_ticks = extrapolation.blend_duration.as_secs_f32() / tick_duration;
+    for (mut component, mut status, history) in query.iter_mut() {
+        let status = &mut *status;
         debug!("checking if we do interpolation");
         // NOTE: it is possible that we reach start_tick when end_tick is not set
         if let Some((start_tick, start_value)) = &status.start {
             if let Some((end_tick, end_value)) = &status.end {
                 debug!(?start_tick, interpolate_tick=?status.current_tick, ?end_tick, "doing interpolation!");
                 assert!(status.current_tick < *end_tick);
-                if start_tick != end_tick {
+                let mut value = if start_tick != end_tick {
                     let t = status.interpolation_fraction().unwrap();
-                    let value = P::Components::lerp(start_value, end_value, t);
-                    *component = value;
+                    let samples = status
+                        .samples(history.and_then(|history| history.peek()))
+                        .unwrap();
+                    P::Components::interpolate(&samples, t)
                 } else {
-                    *component = start_value.clone();
+                    start_value.clone()
+                };
+                // we were extrapolating: blend from the last extrapolated value instead of snapping.
+                // The blend uses the interpolation function, so components without one snap back directly
+                if std::mem::take(&mut status.extrapolating)
+                    && blend_ticks > 0.0
+                    && P::Components::has_interpolation::<C>()
+                {
+                    status.extrapolation_blend = Some((status.current_tick, component.clone()));
+                }
+                if let Some((blend_tick, blend_value)) = status.extrapolation_blend.take() {
+                    let t = ((status.current_tick - blend_tick) as f32 + status.current_overstep)
+                        / blend_ticks;
+                    if t < 1.0 {
+                        value = P::Components::lerp(&blend_value, &value, ease_out_quad(t));
+                        status.extrapolation_blend = Some((blend_tick, blend_value));
+                    }
+                }
+                *component = value;
+            } else if P::Components::has_extrapolation::<C>() {
+                let elapsed = (status.current_tick - *start_tick) as f32 + status.current_overstep;
+                if elapsed <= 0.0 || elapsed > max_extrapolation_ticks {
+                    continue;
+                }
+                if let (Some(t), Some((_, previous_value))) =
+                    (status.extrapolation_fraction(), &status.previous)
+                {
+                    trace!(?start_tick, interpolate_tick=?status.current_tick, ?t, "doing extrapolation!");
+                    *component = P::Components::extrapolate(previous_value, start_value, t);
+                    status.extrapolating = true;
+                    status.extrapolation_blend = None;
+                    manager.extrapolated_frames += 1;
                 }
             }
         }
@@ -55,7 +55,7 @@ impl<T: SyncComponent> ConfirmedHistory<T> {
         self.buffer = ReadyBuffer::new();
     }
 
-    pub(crate) fn peek(&mut self) -> Option<(Tick, &T)> {
+    pub(crate) fn peek(&self) -> Option<(Tick, &T)> {
         self.buffer.heap.peek().map(|item| (item.key, &item.item))
     }
 
@@ -71,8 +71,22 @@ impl<T: SyncComponent> ConfirmedHistory<T> {
     /// contains gaps. Therefore, we need to always leave a value in the history buffer so that we can
     /// get the values for the future ticks
     pub(crate) fn pop_until_tick(&mut self, tick: Tick) -> Option<(Tick, T)> {
+        self.remove_stale(tick);
         self.buffer.pop_until(&tick)
     }
+
+    /// Account for tick wrapping: remove the updates that are too far away from `tick`,
+    /// because they cannot be compared with it reliably (an old update could look like it's in the future)
+    fn remove_stale(&mut self, tick: Tick) {
+        let is_stale = |key: Tick| tick.is_too_far_from(key);
+        if self.buffer.heap.iter().any(|item| is_stale(item.key)) {
+            let heap = std::mem::take(&mut self.buffer.heap);
+            self.buffer.heap = heap
+                .into_iter()
+                .filter(|item| !is_stale(item.key))
+                .collect();
+        }
+    }
 }
 
 // TODO: maybe add the component history on the Confirmed entity instead of Interpolated? would make more sense maybe
@@ -118,6 +132,9 @@ pub(crate) fn add_component_history<C: SyncComponent, P: Protocol>(
                                     end: None,
                                     current_tick,
                                     current_overstep,
+                                    previous: None,
+                                    extrapolating: false,
+                                    extrapolation_blend: None,
                                 },
                             ));
                         }
@@ -198,3 +215,33 @@ pub(crate) fn apply_confirmed_update_mode_simple<C: SyncComponent, P: Protocol>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::protocol::Component1;
+
+    use super::*;
+
+    #[test]
+    fn test_pop_until_tick_tick_wrapping() {
+        let mut history = ConfirmedHistory::<Component1>::new();
+        history.buffer.add_item(Tick(u16::MAX), Component1(1.0));
+        history.buffer.add_item(Tick(1), Component1(2.0));
+
+        assert_eq!(
+            history.pop_until_tick(Tick(0)),
+            Some((Tick(u16::MAX), Component1(1.0)))
+        );
+        assert_eq!(history.peek(), Some((Tick(1), &Component1(2.0))));
+    }
+
+    /// An update that is more than i16::MAX ticks old should not look like it's in the future
+    #[test]
+    fn test_pop_until_tick_stale_update() {
+        let mut history = ConfirmedHistory::<Component1>::new();
+        history.buffer.add_item(Tick(1), Component1(1.0));
+
+        assert_eq!(history.pop_until_tick(Tick(1) + Tick(40000)), None);
+        assert_eq!(history.peek(), None);
+    }
+}
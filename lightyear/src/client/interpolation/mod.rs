@@ -7,19 +7,22 @@ use tracing::trace;
 pub use interpolate::InterpolateStatus;
 pub use interpolation_history::ConfirmedHistory;
 pub use plugin::{add_interpolation_systems, add_prepare_interpolation_systems};
+pub use spline::{CatmullRomInterpolator, HermiteInterpolator, HermiteVelocity};
 pub use visual_interpolation::{VisualInterpolateStatus, VisualInterpolationPlugin};
 
-use crate::client::components::{Confirmed, LerpFn, SyncComponent};
+use crate::client::components::{Confirmed, ExtrapolateFn, LerpFn, SyncComponent};
 use crate::client::connection::ConnectionManager;
 use crate::client::interpolation::resource::InterpolationManager;
 use crate::protocol::Protocol;
 use crate::shared::replication::components::ShouldBeInterpolated;
 
 mod despawn;
+pub mod diagnostics;
 mod interpolate;
 pub mod interpolation_history;
 pub mod plugin;
 mod resource;
+pub mod spline;
 mod visual_interpolation;
 
 /// Interpolator that performs linear interpolation.
@@ -43,6 +46,28 @@ impl<C: Clone> LerpFn<C> for NullInterpolator {
     }
 }
 
+/// Extrapolator that keeps the component moving at the rate of change between the last two
+/// server states (dead reckoning).
+pub struct LinearExtrapolator;
+impl<C> ExtrapolateFn<C> for LinearExtrapolator
+where
+    for<'a> &'a C: Mul<f32, Output = C>,
+    C: Add<C, Output = C>,
+{
+    fn extrapolate(previous: &C, last: &C, t: f32) -> C {
+        previous * (-t) + last * (1.0 + t)
+    }
+}
+
+/// Use this if you don't want to extrapolate this component: it will stay at the last
+/// server state until a new update is received.
+pub struct NullExtrapolator;
+impl<C: Clone> ExtrapolateFn<C> for NullExtrapolator {
+    fn extrapolate(_previous: &C, last: &C, _t: f32) -> C {
+        last.clone()
+    }
+}
+
 /// Marker component for an entity that is being interpolated by the client
 #[derive(Component, Debug)]
 pub struct Interpolated {
@@ -102,3 +127,190 @@ pub fn spawn_interpolated_entity<P: Protocol>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::diagnostic::DiagnosticsStore;
+    use bevy::prelude::Entity;
+    use bevy::utils::Duration;
+
+    use crate::client::components::ExtrapolateFn;
+    use crate::client::interpolation::diagnostics::InterpolationDiagnosticsPlugin;
+    use crate::prelude::client::*;
+    use crate::prelude::*;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    use super::{InterpolateStatus, LinearExtrapolator};
+
+    fn setup() -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(true);
+        let interpolation_config = InterpolationConfig::default()
+            .with_delay(InterpolationDelay::default().with_min_delay(Duration::from_millis(50)))
+            .with_extrapolation(
+                ExtrapolationConfig::default()
+                    .with_max_duration(Duration::from_millis(50))
+                    .with_blend_duration(Duration::from_millis(50)),
+            );
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+        stepper
+    }
+
+    fn interpolated_entity(stepper: &BevyStepper, server_entity: Entity) -> Entity {
+        let confirmed = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        stepper
+            .client_app
+            .world
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .interpolated
+            .unwrap()
+    }
+
+    fn extrapolated_frames(stepper: &BevyStepper) -> f64 {
+        stepper
+            .client_app
+            .world
+            .resource::<DiagnosticsStore>()
+            .get(&InterpolationDiagnosticsPlugin::EXTRAPOLATED_FRAMES)
+            .unwrap()
+            .value()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_linear_extrapolation() {
+        let status = InterpolateStatus::<Component1> {
+            start: Some((Tick(10), Component1(2.0))),
+            end: None,
+            current_tick: Tick(12),
+            current_overstep: 0.5,
+            previous: Some((Tick(5), Component1(1.0))),
+            extrapolating: false,
+            extrapolation_blend: None,
+        };
+        // we are 2.5 ticks past the start, and the previous state is 5 ticks before the start
+        let t = status.extrapolation_fraction().unwrap();
+        assert_eq!(t, 0.5);
+        let (_, previous) = status.previous.as_ref().unwrap();
+        let (_, last) = status.start.as_ref().unwrap();
+        assert_eq!(
+            LinearExtrapolator::extrapolate(previous, last, t),
+            Component1(2.5)
+        );
+
+        // we cannot extrapolate without a previous state
+        let status = InterpolateStatus::<Component1> {
+            previous: None,
+            ..status
+        };
+        assert_eq!(status.extrapolation_fraction(), None);
+    }
+
+    // When the server stops sending updates, the component keeps moving for `max_duration`,
+    // then blends back to the interpolated value once updates arrive again
+    #[test]
+    fn test_extrapolation() {
+        let mut stepper = setup();
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((
+                Component6(0.0),
+                Component7(0.0),
+                Replicate {
+                    interpolation_target: NetworkTarget::All,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        let move_server_entity = |stepper: &mut BevyStepper| {
+            let mut entity = stepper.server_app.world.entity_mut(server_entity);
+            entity.get_mut::<Component6>().unwrap().0 += 1.0;
+            entity.get_mut::<Component7>().unwrap().0 += 1.0;
+        };
+        for _ in 0..30 {
+            move_server_entity(&mut stepper);
+            stepper.frame_step();
+        }
+        let interpolated = interpolated_entity(&stepper, server_entity);
+
+        // the client stops receiving server updates: the components keep moving at the same rate,
+        // for at most `max_duration` (5 ticks) past the last server state (30.0)
+        let mut max_extrapolated_frames: f64 = 0.0;
+        for _ in 0..20 {
+            stepper.advance_time(stepper.frame_duration);
+            stepper.client_app.update();
+            max_extrapolated_frames = max_extrapolated_frames.max(extrapolated_frames(&stepper));
+        }
+        // both components were extrapolated during the same frames
+        assert_eq!(max_extrapolated_frames, 2.0);
+        assert_eq!(extrapolated_frames(&stepper), 0.0);
+        let interpolated_ref = stepper.client_app.world.entity(interpolated);
+        assert_eq!(
+            interpolated_ref.get::<Component6>(),
+            Some(&Component6(35.0))
+        );
+        assert_eq!(
+            interpolated_ref.get::<Component7>(),
+            Some(&Component7(35.0))
+        );
+
+        // server updates arrive again: Component6 blends from the extrapolated value back to the
+        // interpolated value, while Component7 (which has no interpolation function) snaps to it
+        let mut blended = false;
+        for _ in 0..20 {
+            move_server_entity(&mut stepper);
+            stepper.frame_step();
+            let interpolated_ref = stepper.client_app.world.entity(interpolated);
+            let component6 = interpolated_ref.get::<Component6>().unwrap().0;
+            let component7 = interpolated_ref.get::<Component7>().unwrap().0;
+            if component6 > component7 {
+                blended = true;
+            }
+            assert!(interpolated_ref
+                .get::<InterpolateStatus<Component7>>()
+                .unwrap()
+                .extrapolation_blend
+                .is_none());
+        }
+        assert!(blended);
+        let interpolated_ref = stepper.client_app.world.entity(interpolated);
+        let status = interpolated_ref
+            .get::<InterpolateStatus<Component6>>()
+            .unwrap();
+        assert!(!status.extrapolating);
+        assert!(status.extrapolation_blend.is_none());
+        assert_eq!(
+            interpolated_ref.get::<Component6>().unwrap().0,
+            interpolated_ref.get::<Component7>().unwrap().0
+        );
+    }
+}
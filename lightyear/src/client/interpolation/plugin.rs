@@ -3,8 +3,11 @@ use std::marker::PhantomData;
 use bevy::prelude::*;
 use bevy::utils::Duration;
 
+use crate::_reexport::FromType;
 use crate::client::components::{ComponentSyncMode, SyncComponent, SyncMetadata};
-use crate::client::interpolation::despawn::{despawn_interpolated, removed_components};
+use crate::client::interpolation::despawn::{
+    despawn_interpolated, detach_interpolated, detach_interpolated_component, removed_components,
+};
 use crate::client::interpolation::interpolate::{
     insert_interpolated_component, interpolate, update_interpolate_status,
 };
@@ -17,7 +20,6 @@ use super::interpolation_history::{
 };
 use super::spawn_interpolated_entity;
 
-// TODO: maybe this is not an enum and user can specify multiple values, and we use the max delay between all of them?
 #[derive(Clone)]
 pub struct InterpolationDelay {
     /// The minimum delay that we will apply for interpolation
@@ -29,6 +31,19 @@ pub struct InterpolationDelay {
     /// The higher the server update_rate (i.e. smaller send_interval), the smaller the interpolation delay
     /// Set to 0.0 if you want to only use the Delay
     pub send_interval_ratio: f32,
+    /// If true, the delay adapts at runtime to the network conditions: it starts at the value computed from
+    /// `min_delay` and `send_interval_ratio`, then shrinks towards one server send_interval on good connections
+    /// and grows when the measured jitter increases or when server updates arrive late.
+    pub adaptive: bool,
+    /// When the delay is adaptive, how many multiples of the measured jitter we keep as margin
+    pub jitter_multiple_margin: f32,
+    /// When the delay is adaptive, the maximum delay that we will apply
+    pub max_delay: Duration,
+    /// When the delay is adaptive, how fast the delay can change, in seconds per second.
+    ///
+    /// This should stay well below `SyncConfig::speedup_factor - 1.0`, so that the interpolation time can
+    /// follow the delay by changing its speed instead of snapping to it.
+    pub adaptation_rate: f32,
 }
 
 impl Default for InterpolationDelay {
@@ -36,6 +51,10 @@ impl Default for InterpolationDelay {
         Self {
             min_delay: Duration::from_millis(0),
             send_interval_ratio: 2.0,
+            adaptive: false,
+            jitter_multiple_margin: 2.0,
+            max_delay: Duration::from_millis(500),
+            adaptation_rate: 0.025,
         }
     }
 }
@@ -51,6 +70,26 @@ impl InterpolationDelay {
         self
     }
 
+    pub fn with_adaptive(mut self, adaptive: bool) -> Self {
+        self.adaptive = adaptive;
+        self
+    }
+
+    pub fn with_jitter_multiple_margin(mut self, jitter_multiple_margin: f32) -> Self {
+        self.jitter_multiple_margin = jitter_multiple_margin;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_adaptation_rate(mut self, adaptation_rate: f32) -> Self {
+        self.adaptation_rate = adaptation_rate;
+        self
+    }
+
     /// How much behind the latest server update we want the interpolation time to be
     pub(crate) fn to_duration(&self, server_send_interval: Duration) -> Duration {
         // TODO: deal with server_send_interval = 0 (set to frame rate)
@@ -59,10 +98,47 @@ impl InterpolationDelay {
     }
 }
 
+/// Config to specify how components that have an extrapolation function
+/// (`#[sync(full, extrapolate = "LinearExtrapolator")]`) behave when we run out of server snapshots
+#[derive(Clone)]
+pub struct ExtrapolationConfig {
+    /// Maximum amount of time that we will extrapolate past the last server snapshot.
+    /// After that, the component stays at the last extrapolated value until a new snapshot arrives.
+    pub max_duration: Duration,
+    /// Once fresh snapshots arrive, we blend from the extrapolated value back to the interpolated value
+    /// over this duration instead of snapping to it.
+    /// The blend uses the component's interpolation function: components that use the `NullInterpolator`
+    /// snap back to the interpolated value.
+    pub blend_duration: Duration,
+}
+
+impl Default for ExtrapolationConfig {
+    fn default() -> Self {
+        Self {
+            max_duration: Duration::from_millis(200),
+            blend_duration: Duration::from_millis(100),
+        }
+    }
+}
+
+impl ExtrapolationConfig {
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = max_duration;
+        self
+    }
+
+    pub fn with_blend_duration(mut self, blend_duration: Duration) -> Self {
+        self.blend_duration = blend_duration;
+        self
+    }
+}
+
 /// Config to specify how the snapshot interpolation should behave
 #[derive(Clone)]
 pub struct InterpolationConfig {
     pub delay: InterpolationDelay,
+    /// How to extrapolate components when the interpolation runs out of server snapshots
+    pub extrapolation: ExtrapolationConfig,
     /// If true, disable the interpolation logic (but still keep the internal component history buffers)
     /// The user will have to manually implement
     pub custom_interpolation_logic: bool,
@@ -75,6 +151,7 @@ impl Default for InterpolationConfig {
     fn default() -> Self {
         Self {
             delay: InterpolationDelay::default(),
+            extrapolation: ExtrapolationConfig::default(),
             custom_interpolation_logic: false,
             // interpolation_buffer_size: Duration::from_millis(100),
         }
@@ -86,6 +163,11 @@ impl InterpolationConfig {
         self.delay = delay;
         self
     }
+
+    pub fn with_extrapolation(mut self, extrapolation: ExtrapolationConfig) -> Self {
+        self.extrapolation = extrapolation;
+        self
+    }
 }
 
 pub struct InterpolationPlugin<P: Protocol> {
@@ -150,6 +232,7 @@ pub enum InterpolationSet {
 //  the tick we rollback to would not be the current client tick ?
 pub fn add_prepare_interpolation_systems<C: SyncComponent, P: Protocol>(app: &mut App)
 where
+    P::ComponentKinds: FromType<C>,
     P::Components: SyncMetadata<C>,
 {
     // TODO: maybe run this in PostUpdate?
@@ -159,6 +242,7 @@ where
         (
             add_component_history::<C, P>.in_set(InterpolationSet::SpawnHistory),
             removed_components::<C>.in_set(InterpolationSet::Despawn),
+            detach_interpolated_component::<C, P>.in_set(InterpolationSet::Despawn),
         ),
     );
     match P::Components::mode() {
@@ -189,7 +273,7 @@ where
 
 // We add the interpolate system in different function because we might not want to add them
 // in case there is custom interpolation logic.
-pub fn add_interpolation_systems<C: Component + Clone, P: Protocol>(app: &mut App)
+pub fn add_interpolation_systems<C: SyncComponent, P: Protocol>(app: &mut App)
 where
     P::Components: SyncMetadata<C>,
 {
@@ -238,7 +322,8 @@ impl<P: Protocol> Plugin for InterpolationPlugin<P> {
         app.add_systems(
             Update,
             (
-                spawn_interpolated_entity::<P>.in_set(InterpolationSet::SpawnInterpolation),
+                (spawn_interpolated_entity::<P>, detach_interpolated)
+                    .in_set(InterpolationSet::SpawnInterpolation),
                 despawn_interpolated.in_set(InterpolationSet::Despawn),
             ),
         );
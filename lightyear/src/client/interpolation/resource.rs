@@ -7,12 +7,15 @@ use crate::shared::replication::entity_map::InterpolatedEntityMap;
 pub struct InterpolationManager {
     /// Map between remote and predicted entities
     pub interpolated_entity_map: InterpolatedEntityMap,
+    /// Number of component values that were extrapolated since the last diagnostics update
+    pub(crate) extrapolated_frames: usize,
 }
 
 impl InterpolationManager {
     pub fn new() -> Self {
         Self {
             interpolated_entity_map: Default::default(),
+            extrapolated_frames: 0,
         }
     }
 }
@@ -0,0 +1,264 @@
+//! Interpolators that use more than two server states to compute a smooth curve between them.
+//!
+//! Linear interpolation only uses the two server states surrounding the interpolation tick, so the velocity
+//! of the interpolated entity changes abruptly at every server state, which is noticeable for curved motion.
+//! These interpolators use cubic hermite splines instead, with tangents computed either from the neighbouring
+//! server states ([`CatmullRomInterpolator`]) or from a velocity stored in the component ([`HermiteInterpolator`]).
+//!
+//! Select them with the `#[sync(full, lerp = "CatmullRomInterpolator")]` attribute.
+//! For `Transform`, use [`TransformCatmullRomInterpolation`](crate::utils::bevy::TransformCatmullRomInterpolation):
+//! only its translation and scale follow the spline, the rotation is interpolated with a plain slerp.
+use std::ops::{Add, Mul};
+
+use crate::client::components::{InterpolationSamples, LerpFn};
+
+/// Evaluate the cubic hermite spline between `p1` and `p2` with tangents `m1` and `m2` at `t` in [0, 1]
+///
+/// The tangents can have a different type than the points (for example a velocity), as long as they can be
+/// added to the points.
+pub(crate) fn hermite<P, M, C, V>(p1: P, m1: M, p2: P, m2: M, t: f32) -> C
+where
+    P: Mul<f32, Output = C>,
+    M: Mul<f32, Output = V>,
+    C: Add<C, Output = C> + Add<V, Output = C>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    p1 * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + m1 * (t3 - 2.0 * t2 + t)
+        + p2 * (-2.0 * t3 + 3.0 * t2)
+        + m2 * (t3 - t2)
+}
+
+/// Compute `(a - b) * scale`
+fn scaled_difference<C>(a: &C, b: &C, scale: f32) -> C
+where
+    for<'a> &'a C: Mul<f32, Output = C>,
+    C: Add<C, Output = C>,
+{
+    a * scale + b * -scale
+}
+
+/// Interpolator that goes through every server state along a Catmull-Rom spline.
+///
+/// The tangent at each server state is computed from its neighbours, so this needs the server states before
+/// `start` and after `end`; when they are not available we fall back to the slope between `start` and `end`.
+pub struct CatmullRomInterpolator;
+impl<C> LerpFn<C> for CatmullRomInterpolator
+where
+    for<'a> &'a C: Mul<f32, Output = C>,
+    C: Add<C, Output = C>,
+{
+    fn lerp(start: &C, other: &C, t: f32) -> C {
+        start * (1.0 - t) + other * t
+    }
+
+    fn interpolate(samples: &InterpolationSamples<C>, t: f32) -> C {
+        let (start_tick, start) = samples.start;
+        let (end_tick, end) = samples.end;
+        let interval = (end_tick - start_tick) as f32;
+        if interval <= 0.0 {
+            return Self::lerp(start, end, t);
+        }
+        // tangents are expressed relative to the [start, end] interval, so that the ticks don't need to be evenly spaced
+        let start_tangent = match samples.previous {
+            Some((previous_tick, previous)) if previous_tick < start_tick => {
+                scaled_difference(end, previous, interval / (end_tick - previous_tick) as f32)
+            }
+            _ => scaled_difference(end, start, 1.0),
+        };
+        let end_tangent = match samples.next {
+            Some((next_tick, next)) if next_tick > end_tick => {
+                scaled_difference(next, start, interval / (next_tick - start_tick) as f32)
+            }
+            _ => scaled_difference(end, start, 1.0),
+        };
+        hermite(start, &start_tangent, end, &end_tangent, t)
+    }
+}
+
+/// Components that store their own rate of change, so that they can be interpolated with [`HermiteInterpolator`]
+pub trait HermiteVelocity: Add<Self::Velocity, Output = Self> + Sized {
+    /// The type of the rate of change of the component
+    type Velocity: Mul<f32, Output = Self::Velocity>;
+
+    /// The rate of change of the component per tick
+    /// (i.e. the component is expected to be roughly `self + self.velocity() * n` after `n` ticks)
+    fn velocity(&self) -> Self::Velocity;
+}
+
+/// Interpolator that uses a cubic hermite spline between `start` and `end`, where the tangents are
+/// the velocities contained in the server states (see [`HermiteVelocity`]).
+///
+/// This is more accurate than [`CatmullRomInterpolator`] when the component contains its velocity,
+/// and doesn't need more than two server states.
+pub struct HermiteInterpolator;
+impl<C> LerpFn<C> for HermiteInterpolator
+where
+    for<'a> &'a C: Mul<f32, Output = C>,
+    C: Add<C, Output = C> + HermiteVelocity,
+{
+    fn lerp(start: &C, other: &C, t: f32) -> C {
+        start * (1.0 - t) + other * t
+    }
+
+    fn interpolate(samples: &InterpolationSamples<C>, t: f32) -> C {
+        let (start_tick, start) = samples.start;
+        let (end_tick, end) = samples.end;
+        let interval = (end_tick - start_tick) as f32;
+        if interval <= 0.0 {
+            return Self::lerp(start, end, t);
+        }
+        let start_tangent = start.velocity() * interval;
+        let end_tangent = end.velocity() * interval;
+        hermite(start, start_tangent, end, end_tangent, t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::Tick;
+    use crate::tests::protocol::Component1;
+
+    use super::*;
+
+    /// Component that contains its velocity
+    #[derive(Debug, PartialEq, Clone)]
+    struct Position {
+        value: f32,
+        velocity: f32,
+    }
+
+    impl Mul<f32> for &Position {
+        type Output = Position;
+
+        fn mul(self, rhs: f32) -> Position {
+            Position {
+                value: self.value * rhs,
+                velocity: self.velocity * rhs,
+            }
+        }
+    }
+
+    impl Add for Position {
+        type Output = Position;
+
+        fn add(self, rhs: Position) -> Position {
+            Position {
+                value: self.value + rhs.value,
+                velocity: self.velocity + rhs.velocity,
+            }
+        }
+    }
+
+    impl Add<f32> for Position {
+        type Output = Position;
+
+        fn add(self, rhs: f32) -> Position {
+            Position {
+                value: self.value + rhs,
+                velocity: self.velocity,
+            }
+        }
+    }
+
+    impl HermiteVelocity for Position {
+        type Velocity = f32;
+
+        fn velocity(&self) -> f32 {
+            self.velocity
+        }
+    }
+
+    #[test]
+    fn test_catmull_rom_interpolation() {
+        // samples of x = tick^2
+        let samples = InterpolationSamples {
+            previous: Some((Tick(0), &Component1(0.0))),
+            start: (Tick(1), &Component1(1.0)),
+            end: (Tick(2), &Component1(4.0)),
+            next: Some((Tick(3), &Component1(9.0))),
+        };
+        assert_eq!(
+            CatmullRomInterpolator::interpolate(&samples, 0.5),
+            Component1(2.25)
+        );
+        // the spline goes through the server states
+        assert_eq!(
+            CatmullRomInterpolator::interpolate(&samples, 0.0),
+            Component1(1.0)
+        );
+        assert_eq!(
+            CatmullRomInterpolator::interpolate(&samples, 1.0),
+            Component1(4.0)
+        );
+
+        // without the neighbouring states, we fall back to linear interpolation
+        let samples = InterpolationSamples {
+            previous: None,
+            next: None,
+            ..samples
+        };
+        assert_eq!(
+            CatmullRomInterpolator::interpolate(&samples, 0.5),
+            Component1(2.5)
+        );
+    }
+
+    #[test]
+    fn test_hermite_interpolation() {
+        // samples of x = tick^2, with velocity 2 * tick
+        let samples = InterpolationSamples {
+            previous: None,
+            start: (
+                Tick(1),
+                &Position {
+                    value: 1.0,
+                    velocity: 2.0,
+                },
+            ),
+            end: (
+                Tick(2),
+                &Position {
+                    value: 4.0,
+                    velocity: 4.0,
+                },
+            ),
+            next: None,
+        };
+        assert_eq!(
+            HermiteInterpolator::interpolate(&samples, 0.5),
+            Position {
+                value: 2.25,
+                velocity: 3.0,
+            }
+        );
+
+        // the velocities are scaled by the number of ticks between the server states
+        let samples = InterpolationSamples {
+            previous: None,
+            start: (
+                Tick(2),
+                &Position {
+                    value: 4.0,
+                    velocity: 4.0,
+                },
+            ),
+            end: (
+                Tick(4),
+                &Position {
+                    value: 16.0,
+                    velocity: 8.0,
+                },
+            ),
+            next: None,
+        };
+        assert_eq!(
+            HermiteInterpolator::interpolate(&samples, 0.5),
+            Position {
+                value: 9.0,
+                velocity: 6.0,
+            }
+        );
+    }
+}
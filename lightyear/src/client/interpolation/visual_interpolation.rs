@@ -233,6 +233,7 @@ mod tests {
         stepper
             .client_app
             .add_plugins(VisualInterpolationPlugin::<Component1, MyProtocol>::default());
+        stepper.build();
         let entity = stepper
             .client_app
             .world
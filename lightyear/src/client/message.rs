@@ -129,6 +129,11 @@ impl<P: Protocol> ClientMessage<P> {
                             }
                         }
                     }
+                    ReplicationMessageData::Snapshot(m) => {
+                        trace!(num_entities = ?m.actions.len(), "Sending snapshot");
+                        #[cfg(metrics)]
+                        metrics::counter!("send_snapshot").increment(1);
+                    }
                 }
             }
             ClientMessage::Sync(message) => match message {
@@ -11,6 +11,13 @@ pub mod events;
 
 pub mod input;
 
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "inspector", not(target_family = "wasm"))))
+)]
+#[cfg(all(feature = "inspector", not(target_family = "wasm")))]
+pub mod inspector;
+
 pub mod interpolation;
 
 pub mod plugin;
@@ -21,6 +28,8 @@ pub mod resource;
 
 pub mod sync;
 
+pub mod tick_event;
+
 mod diagnostics;
 mod easings;
 #[cfg_attr(docsrs, doc(cfg(feature = "leafwing")))]
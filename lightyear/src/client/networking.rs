@@ -6,16 +6,17 @@ use bevy::prelude::ResMut;
 use bevy::prelude::*;
 #[cfg(feature = "xpbd_2d")]
 use bevy_xpbd_2d::prelude::PhysicsTime;
-use tracing::{error, trace};
+use tracing::{error, info, trace};
 
 use crate::_reexport::ReplicationSend;
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
-use crate::client::events::{EntityDespawnEvent, EntitySpawnEvent};
+use crate::client::events::{DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent};
 use crate::connection::client::{ClientConnection, NetClient};
 use crate::prelude::{MainSet, TickManager, TimeManager};
 use crate::protocol::component::ComponentProtocol;
 use crate::protocol::message::MessageProtocol;
+use crate::protocol::message_registry::{receive_registered_messages, MessageRegistry};
 use crate::protocol::Protocol;
 use crate::shared::events::connection::{IterEntityDespawnEvent, IterEntitySpawnEvent};
 use crate::shared::tick_manager::TickEvent;
@@ -52,6 +53,10 @@ impl<P: Protocol> Plugin for ClientNetworkingPlugin<P> {
                 PreUpdate,
                 (
                     receive::<P>.in_set(MainSet::Receive),
+                    receive_registered_messages::<P, ()>
+                        .after(receive::<P>)
+                        .in_set(MainSet::Receive)
+                        .run_if(resource_exists::<MessageRegistry>),
                     apply_deferred.in_set(MainSet::ReceiveFlush),
                 ),
             )
@@ -95,6 +100,14 @@ pub(crate) fn receive<P: Protocol>(world: &mut World) {
                                                 error!("Error updating netcode: {}", e);
                                             });
 
+                                        // DISCONNECTION: notify the user of why the connection was closed
+                                        if let Some(reason) = netcode.take_disconnect_reason() {
+                                            info!(%reason, "Client disconnected");
+                                            world
+                                                .resource_mut::<Events<DisconnectEvent>>()
+                                                .send(DisconnectEvent::with_reason((), reason));
+                                        }
+
                                         // only start the connection (sending messages, sending pings, starting sync, etc.)
                                         // once we are connected
                                         if netcode.is_connected() {
@@ -110,6 +123,13 @@ pub(crate) fn receive<P: Protocol>(world: &mut World) {
                                                 .recv_packet(&mut reader, tick_manager.as_ref())
                                                 .unwrap();
                                         }
+                                        // the packets received on the reliable stream of the transport
+                                        while let Some(mut reader) = netcode.recv_reliable() {
+                                            connection
+                                                .message_manager
+                                                .recv_stream_packet(&mut reader)
+                                                .unwrap();
+                                        }
 
                                         // RECEIVE: receive packets from message managers
                                         let mut events = connection.receive(
@@ -127,13 +147,6 @@ pub(crate) fn receive<P: Protocol>(world: &mut World) {
                                             //     debug!("Client connected event");
                                             //     connect_event_writer.send(ConnectEvent::new(()));
                                             // }
-                                            //
-                                            // if events.has_disconnection() {
-                                            //     let mut disconnect_event_writer =
-                                            //         world.get_resource_mut::<Events<DisconnectEvent>>().unwrap();
-                                            //     debug!("Client disconnected event");
-                                            //     disconnect_event_writer.send(DisconnectEvent::new(()));
-                                            // }
 
                                             // Message Events
                                             P::Message::push_message_events(world, &mut events);
@@ -193,6 +206,9 @@ pub(crate) fn send<P: Protocol>(
             error!("Error preparing replicate send: {}", e);
         });
     // SEND_PACKETS: send buffered packets to io
+    connection
+        .message_manager
+        .set_reliable_stream(netcode.has_reliable_stream());
     let packet_bytes = connection
         .send_packets(time_manager.as_ref(), tick_manager.as_ref())
         .unwrap();
@@ -201,6 +217,16 @@ pub(crate) fn send<P: Protocol>(
             error!("Error sending packet: {}", e);
         });
     }
+    // the channels that use the reliable stream of the transport
+    let stream_packet_bytes = connection
+        .message_manager
+        .send_stream_packets(tick_manager.tick())
+        .unwrap();
+    for packet_byte in stream_packet_bytes {
+        let _ = netcode.send_reliable(packet_byte.as_slice()).map_err(|e| {
+            error!("Error sending packet on the reliable stream: {}", e);
+        });
+    }
 
     // no need to clear the connection, because we already std::mem::take it
     // client.connection.clear();
@@ -225,12 +251,15 @@ pub(crate) fn sync_update<P: Protocol>(
     if netclient.is_connected() {
         // NOTE: this triggers change detection
         // Handle pongs, update RTT estimates, update client prediction time
+        // we only measure the loss of the packets we send, use it as an estimate of the loss of the server updates
+        let packet_loss = connection.packet_loss();
         if let Some(tick_event) = connection.sync_manager.update(
             time_manager.deref_mut(),
             tick_manager.deref_mut(),
             &connection.ping_manager,
             &config.interpolation.delay,
             config.shared.server_send_interval,
+            packet_loss,
         ) {
             tick_events.send(tick_event);
         }
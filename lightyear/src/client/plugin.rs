@@ -12,8 +12,11 @@ use crate::client::interpolation::plugin::InterpolationPlugin;
 use crate::client::networking::ClientNetworkingPlugin;
 use crate::client::prediction::plugin::PredictionPlugin;
 use crate::client::replication::ClientReplicationPlugin;
+use crate::connection::client::{ClientConnection, NetClient};
 use crate::protocol::component::ComponentProtocol;
+use crate::protocol::fingerprint::ProtocolFingerprint;
 use crate::protocol::message::MessageProtocol;
+use crate::protocol::message_registry::MessageRegistry;
 use crate::protocol::Protocol;
 use crate::shared::events::connection::ConnectionEvents;
 use crate::shared::events::plugin::EventsPlugin;
@@ -56,12 +59,14 @@ impl<P: Protocol> Plugin for ClientPlugin<P> {
     fn build(&self, app: &mut App) {
         let config = self.config.lock().unwrap().deref_mut().take().unwrap();
 
-        let netclient = config.client_config.net.clone().build_client();
+        let fingerprint = ProtocolFingerprint::new(&config.protocol, &config.client_config.shared);
+        let netclient = config.client_config.net.clone().build_client(fingerprint.0);
         let tick_duration = config.client_config.shared.tick.tick_duration;
 
         app
             // RESOURCES //
             .insert_resource(config.client_config.clone())
+            .insert_resource(fingerprint)
             // TODO: move these into the Networking/Replication plugins
             .insert_resource(netclient)
             .insert_resource(ConnectionManager::<P>::new(
@@ -70,6 +75,11 @@ impl<P: Protocol> Plugin for ClientPlugin<P> {
                 config.client_config.sync,
                 config.client_config.ping,
                 config.client_config.prediction.input_delay_ticks,
+                config.client_config.replication.playout_delay_ticks,
+                config
+                    .client_config
+                    .replication
+                    .entity_reference_timeout_ticks,
             ))
             // PLUGINS //
             .add_plugins(SharedPlugin::<P> {
@@ -89,4 +99,23 @@ impl<P: Protocol> Plugin for ClientPlugin<P> {
             })
             .add_plugins(ClientDiagnosticsPlugin::<P>::default());
     }
+
+    fn finish(&self, app: &mut App) {
+        // all plugins are built: assign the net ids of the messages registered at runtime
+        if let Some(mut registry) = app.world.get_resource_mut::<MessageRegistry>() {
+            registry.build();
+            let registry = registry.clone();
+            let fingerprint = app
+                .world
+                .resource::<ProtocolFingerprint>()
+                .with_registered_messages(&registry);
+            app.world
+                .resource_mut::<ClientConnection>()
+                .set_protocol_hash(fingerprint.0);
+            app.world.insert_resource(fingerprint);
+            app.world
+                .resource_mut::<ConnectionManager<P>>()
+                .message_registry = registry;
+        }
+    }
 }
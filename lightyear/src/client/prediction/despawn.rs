@@ -2,15 +2,16 @@ use std::marker::PhantomData;
 
 use bevy::ecs::system::{Command, EntityCommands};
 use bevy::prelude::{
-    Commands, Component, Entity, Query, RemovedComponents, ResMut, With, Without, World,
+    Added, Commands, Component, Entity, Query, RemovedComponents, ResMut, With, Without, World,
 };
 use tracing::{debug, error, trace};
 
 use crate::client::components::{ComponentSyncMode, Confirmed, SyncComponent, SyncMetadata};
 use crate::client::prediction::resource::PredictionManager;
-use crate::client::prediction::Predicted;
+use crate::client::prediction::{Predicted, RollbackEligibility};
 use crate::prelude::{ShouldBePredicted, TickManager};
 use crate::protocol::Protocol;
+use crate::shared::replication::components::Detached;
 use crate::shared::tick_manager::Tick;
 
 // - TODO: despawning another client entity as a consequence from prediction, but we want to roll that back:
@@ -103,6 +104,32 @@ pub(crate) fn despawn_confirmed(
     }
 }
 
+/// When the server detaches a predicted entity (see [`ReplicationStopPolicy::Detach`](crate::prelude::ReplicationStopPolicy::Detach)),
+/// the predicted entity is handed over to the client: it is not linked to the confirmed entity anymore,
+/// and it is excluded from rollbacks since there is no server state to compare it with.
+pub(crate) fn detach_predicted(
+    mut manager: ResMut<PredictionManager>,
+    mut commands: Commands,
+    mut confirmed_query: Query<(Entity, &mut Confirmed), Added<Detached>>,
+    mut predicted_query: Query<&mut Predicted>,
+) {
+    for (confirmed_entity, mut confirmed) in confirmed_query.iter_mut() {
+        let Some(predicted) = confirmed.predicted.take() else {
+            continue;
+        };
+        manager
+            .predicted_entity_map
+            .confirmed_to_predicted
+            .remove(&confirmed_entity);
+        if let Ok(mut predicted_component) = predicted_query.get_mut(predicted) {
+            predicted_component.confirmed_entity = None;
+        }
+        if let Some(mut entity_mut) = commands.get_entity(predicted) {
+            entity_mut.insert((Detached, RollbackEligibility::Excluded));
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct RemovedCache<C: Component>(pub Option<C>);
 
@@ -0,0 +1,345 @@
+//! Client side of the desync detection: compare the checksums sent by the server with the prediction history.
+//!
+//! See [`crate::shared::desync`] for more details.
+//!
+//! ```rust,ignore
+//! use lightyear::prelude::client::*;
+//!
+//! fn setup(app: &mut App) {
+//!     app.add_plugins(DesyncDetectionPlugin::<MyProtocol, Position>::new(DesyncConfig::default()));
+//! }
+//!
+//! fn log_desyncs(mut events: EventReader<DesyncEvent>) {
+//!     for event in events.read() {
+//!         error!(?event, "prediction desync");
+//!     }
+//! }
+//! ```
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    App, Entity, Event, EventReader, EventWriter, IntoSystemConfigs, Plugin, PreUpdate, Query, Res,
+    Resource,
+};
+use serde::Serialize;
+use tracing::{error, trace, warn};
+
+use crate::_reexport::ComponentProtocol;
+use crate::client::components::{Confirmed, SyncComponent, SyncMetadata};
+use crate::client::connection::ConnectionManager;
+use crate::client::events::MessageEvent;
+use crate::client::prediction::plugin::PredictionSet;
+use crate::client::prediction::predicted_history::{ComponentState, PredictionHistory};
+use crate::protocol::message_registry::AppMessageExt;
+use crate::protocol::Protocol;
+use crate::shared::desync::{checksum, component_id, ChecksumMessage, DesyncConfig};
+use crate::shared::tick_manager::Tick;
+
+/// Plugin that checks the predicted values of the component `C` against the checksums sent by the server
+pub struct DesyncDetectionPlugin<P, C> {
+    config: DesyncConfig,
+    _marker: PhantomData<(P, C)>,
+}
+
+impl<P, C> DesyncDetectionPlugin<P, C> {
+    pub fn new(config: DesyncConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, C> Default for DesyncDetectionPlugin<P, C> {
+    fn default() -> Self {
+        Self::new(DesyncConfig::default())
+    }
+}
+
+/// Event emitted when the predicted value of a component doesn't match the server's value
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct DesyncEvent {
+    /// Tick at which the server computed the checksum
+    pub tick: Tick,
+    /// The predicted entity
+    pub entity: Entity,
+    /// The entity in the server world
+    pub server_entity: Entity,
+    /// Name of the component
+    pub component: &'static str,
+    pub server_checksum: u64,
+    /// Checksum of the predicted value, or None if the component was not present on the predicted entity
+    pub client_checksum: Option<u64>,
+}
+
+#[derive(Resource)]
+struct DesyncDetection {
+    config: DesyncConfig,
+}
+
+impl<P: Protocol, C> Plugin for DesyncDetectionPlugin<P, C>
+where
+    C: SyncComponent + Serialize + Debug,
+    P::Components: SyncMetadata<C>,
+{
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<DesyncDetection>() {
+            // RESOURCES
+            app.insert_resource(DesyncDetection {
+                config: self.config.clone(),
+            });
+            app.register_message::<ChecksumMessage>();
+            // EVENTS
+            app.add_event::<DesyncEvent>();
+        }
+        // SYSTEMS
+        // we need to check the history before the rollback check removes the older ticks
+        app.add_systems(
+            PreUpdate,
+            check_checksums::<P, C>
+                .after(PredictionSet::SpawnHistoryFlush)
+                .before(PredictionSet::CheckRollback),
+        );
+    }
+}
+
+/// Compare the checksums received from the server with the checksums of the predicted values at the same tick
+fn check_checksums<P: Protocol, C>(
+    detection: Res<DesyncDetection>,
+    connection: Res<ConnectionManager<P>>,
+    mut messages: EventReader<MessageEvent<ChecksumMessage>>,
+    confirmed_query: Query<&Confirmed>,
+    predicted_query: Query<&PredictionHistory<C>>,
+    mut desync_events: EventWriter<DesyncEvent>,
+) where
+    C: SyncComponent + Serialize + Debug,
+    P::Components: SyncMetadata<C>,
+{
+    let component = component_id::<C>();
+    for message in messages.read() {
+        let tick = message.message().tick;
+        for server_checksum in message
+            .message()
+            .checksums
+            .iter()
+            .filter(|c| c.component == component)
+        {
+            let server_entity = server_checksum.entity;
+            let Some(predicted) = connection
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .and_then(|confirmed| confirmed_query.get(*confirmed).ok())
+                .and_then(|confirmed| confirmed.predicted)
+            else {
+                trace!(?server_entity, "no predicted entity for the checksum");
+                continue;
+            };
+            let Ok(history) = predicted_query.get(predicted) else {
+                continue;
+            };
+            // the history doesn't go back far enough to check this tick
+            let Some(state) = history.get(tick) else {
+                trace!(?predicted, ?tick, "no prediction history for the checksum");
+                continue;
+            };
+            let predicted_value = match state {
+                ComponentState::Updated(value) => Some(P::Components::quantize(value.clone())),
+                ComponentState::Removed => None,
+            };
+            let client_checksum = match predicted_value.as_ref().map(checksum).transpose() {
+                Ok(client_checksum) => client_checksum,
+                Err(e) => {
+                    error!(?predicted, component = ?C::NAME, "could not compute checksum: {:?}", e);
+                    continue;
+                }
+            };
+            if client_checksum == Some(server_checksum.checksum) {
+                continue;
+            }
+            if detection.config.log_values {
+                warn!(
+                    ?tick,
+                    ?predicted,
+                    component = ?C::NAME,
+                    "prediction desync: predicted {:?}, server {:?}",
+                    predicted_value,
+                    server_checksum.value
+                );
+            } else {
+                warn!(?tick, ?predicted, component = ?C::NAME, "prediction desync");
+            }
+            desync_events.send(DesyncEvent {
+                tick,
+                entity: predicted,
+                server_entity,
+                component: C::NAME,
+                server_checksum: server_checksum.checksum,
+                client_checksum,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{
+        EventReader, Events, FixedPreUpdate, FixedUpdate, IntoSystemConfigs, Query, Res, ResMut,
+        With,
+    };
+    use bevy::utils::Duration;
+
+    use crate::prelude::client::*;
+    use crate::prelude::*;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    fn press_input(
+        mut connection: ResMut<ClientConnectionManager>,
+        tick_manager: Res<TickManager>,
+    ) {
+        connection.add_input(MyInput(1), tick_manager.tick());
+    }
+
+    /// Shared movement system: the client predicts it exactly like the server runs it
+    fn client_move(
+        mut query: Query<&mut Component1, With<Predicted>>,
+        mut inputs: EventReader<crate::client::events::InputEvent<MyInput>>,
+    ) {
+        for input in inputs.read() {
+            if let Some(input) = input.input() {
+                for mut component in query.iter_mut() {
+                    component.0 += input.0 as f32;
+                }
+            }
+        }
+    }
+
+    fn server_move(
+        mut query: Query<&mut Component1, With<Replicate>>,
+        mut inputs: EventReader<crate::server::events::InputEvent<MyInput>>,
+    ) {
+        for input in inputs.read() {
+            if let Some(input) = input.input() {
+                for mut component in query.iter_mut() {
+                    component.0 += input.0 as f32;
+                }
+            }
+        }
+    }
+
+    /// Server-only system, so that the client's prediction diverges from the server state
+    fn increment(mut query: Query<&mut Component1, With<Replicate>>) {
+        for mut component in query.iter_mut() {
+            component.0 += 1.0;
+        }
+    }
+
+    /// Step the apps and collect the desync events emitted by the client
+    fn step_and_collect(stepper: &mut BevyStepper, frames: usize) -> Vec<DesyncEvent> {
+        let mut events = vec![];
+        for _ in 0..frames {
+            stepper.frame_step();
+            events.extend(
+                stepper
+                    .client_app
+                    .world
+                    .resource_mut::<Events<DesyncEvent>>()
+                    .drain(),
+            );
+        }
+        events
+    }
+
+    #[test]
+    fn test_desync_event() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        let desync_config = DesyncConfig::default().with_interval(tick_duration);
+        stepper.server_app.add_plugins(
+            server::DesyncDetectionPlugin::<MyProtocol, Component1>::new(desync_config.clone()),
+        );
+        stepper
+            .client_app
+            .add_plugins(DesyncDetectionPlugin::<MyProtocol, Component1>::new(
+                desync_config,
+            ));
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper.client_app.add_systems(FixedUpdate, client_move);
+        stepper.server_app.add_systems(FixedUpdate, server_move);
+        stepper.init();
+
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(0.0),
+                Replicate {
+                    prediction_target: NetworkTarget::All,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        // the value changes every tick, but the client predicts it exactly
+        assert!(step_and_collect(&mut stepper, 30).is_empty());
+        assert!(
+            stepper
+                .server_app
+                .world
+                .get::<Component1>(server_entity)
+                .unwrap()
+                .0
+                > 10.0
+        );
+
+        // the server state changes but the client doesn't predict it
+        stepper.server_app.add_systems(FixedUpdate, increment);
+        let events = step_and_collect(&mut stepper, 10);
+        assert!(!events.is_empty());
+        let confirmed = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        let predicted = stepper
+            .client_app
+            .world
+            .get::<Confirmed>(confirmed)
+            .unwrap()
+            .predicted
+            .unwrap();
+        for event in events {
+            assert_eq!(event.entity, predicted);
+            assert_eq!(event.server_entity, server_entity);
+            assert_eq!(event.component, Component1::NAME);
+            assert_ne!(event.client_checksum, Some(event.server_checksum));
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Diagnostics related to client-side prediction
+use bevy::app::{App, Plugin};
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, RegisterDiagnostic};
+
+pub struct PredictionDiagnosticsPlugin;
+
+impl PredictionDiagnosticsPlugin {
+    /// How many entities are resimulated per rollback
+    pub const ROLLBACK_ENTITIES: DiagnosticPath =
+        DiagnosticPath::const_new("entities resimulated per rollback");
+
+    /// Max diagnostic history length.
+    pub const DIAGNOSTIC_HISTORY_LEN: usize = 60;
+}
+
+impl Plugin for PredictionDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(
+            Diagnostic::new(PredictionDiagnosticsPlugin::ROLLBACK_ENTITIES)
+                .with_max_history_length(PredictionDiagnosticsPlugin::DIAGNOSTIC_HISTORY_LEN),
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Handles client-side prediction
 use std::fmt::Debug;
 
+use bevy::ecs::entity::EntityHashSet;
 use bevy::prelude::*;
 use tracing::error;
 
@@ -19,6 +20,8 @@ use crate::shared::tick_manager::Tick;
 
 pub(crate) mod correction;
 mod despawn;
+pub mod desync;
+pub mod diagnostics;
 pub mod plugin;
 pub mod predicted_history;
 pub mod prespawn;
@@ -33,11 +36,89 @@ pub struct Predicted {
     pub confirmed_entity: Option<Entity>,
 }
 
+/// Controls whether a predicted entity takes part in rollbacks.
+///
+/// Entities that don't have this component are [`RollbackEligibility::Included`].
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RollbackEligibility {
+    /// The entity is restored to its historical state and resimulated during rollbacks
+    #[default]
+    Included,
+    /// The entity is not affected by rollbacks: it does not trigger a rollback when its prediction is wrong,
+    /// it is not restored to a past state, and it is marked with [`DisabledDuringRollback`] (without its synced
+    /// components) during the rollback resimulation.
+    ///
+    /// This is useful for entities such as [`PreSpawnedPlayerObject`](prespawn::PreSpawnedPlayerObject)s that
+    /// should not be moved by rollbacks that they are not part of.
+    Excluded,
+}
+
+impl RollbackEligibility {
+    pub(crate) fn is_excluded(eligibility: Option<&Self>) -> bool {
+        eligibility.is_some_and(|e| *e == RollbackEligibility::Excluded)
+    }
+}
+
+/// Marks an entity that only exists on the client and will never be confirmed by the server
+/// (for example client-only effects or projectiles).
+///
+/// Such entities still get a [`PredictionHistory`] for their components, so that they can be
+/// restored and resimulated during rollbacks like the other predicted entities.
+/// If a rollback goes back to a tick before the entity was spawned, the entity is despawned
+/// (it will be spawned again during the resimulation).
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PredictedOnly {
+    /// Tick at which the entity was spawned. This is set automatically.
+    pub(crate) spawn_tick: Option<Tick>,
+}
+
+/// Hint used for partial rollbacks: all the entities that share the same island are rolled back together.
+///
+/// When [`PredictionConfig::partial_rollback`](plugin::PredictionConfig::partial_rollback) is enabled, only the
+/// entities that mispredicted are rolled back. Use this component to also roll back the entities that they interact with.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RollbackIsland(pub u64);
+
+/// Marker added to the predicted entities that are not resimulated during the current rollback
+/// (entities that are [`RollbackEligibility::Excluded`], or that are not part of a partial rollback).
+///
+/// The marker is only present while the rollback runs the `FixedMain` schedule. During that time, the synced
+/// components of these entities are removed from them and put back once the rollback is over, so systems that
+/// query those components skip the entities automatically. Systems that only touch state that is not synced
+/// with the server should filter out these entities with `Without<DisabledDuringRollback>`, so that the rollback
+/// doesn't apply the same ticks to them a second time.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DisabledDuringRollback;
+
 /// Resource that indicates whether we are in a rollback state or not
 #[derive(Resource)]
 pub struct Rollback {
     pub state: RollbackState,
     // pub rollback_groups: EntityHashMap<ReplicationGroupId, RollbackState>,
+    /// If true, only the entities in `entities` are rolled back
+    pub(crate) partial: bool,
+    /// The entities that are rolled back during the current rollback (only used for partial rollbacks)
+    pub(crate) entities: EntityHashSet,
+}
+
+impl Rollback {
+    pub(crate) fn new(partial: bool) -> Self {
+        Self {
+            state: RollbackState::Default,
+            partial,
+            entities: EntityHashSet::default(),
+        }
+    }
+
+    /// Returns true if the entity should not be affected by the current rollback
+    pub(crate) fn is_excluded(
+        &self,
+        entity: Entity,
+        eligibility: Option<&RollbackEligibility>,
+    ) -> bool {
+        RollbackEligibility::is_excluded(eligibility)
+            || (self.partial && !self.entities.contains(&entity))
+    }
 }
 
 /// Resource that will track whether we should do rollback or not
@@ -12,14 +12,15 @@ use crate::client::prediction::correction::{
     get_visually_corrected_state, restore_corrected_state,
 };
 use crate::client::prediction::despawn::{
-    despawn_confirmed, remove_component_for_despawn_predicted, remove_despawn_marker,
-    restore_components_if_despawn_rolled_back,
+    despawn_confirmed, detach_predicted, remove_component_for_despawn_predicted,
+    remove_despawn_marker, restore_components_if_despawn_rolled_back,
 };
 use crate::client::prediction::predicted_history::{
-    add_prespawned_component_history, update_prediction_history,
+    add_prespawned_component_history, set_predicted_only_spawn_tick, update_prediction_history,
 };
 use crate::client::prediction::prespawn::{
     compute_prespawn_hash, pre_spawned_player_object_cleanup, spawn_pre_spawned_player_object,
+    PreSpawnEvent,
 };
 use crate::client::prediction::resource::PredictionManager;
 use crate::client::sync::client_is_synced;
@@ -31,8 +32,9 @@ use crate::shared::sets::MainSet;
 
 use super::predicted_history::{add_component_history, apply_confirmed_update};
 use super::rollback::{
-    check_rollback, increment_rollback_tick, prepare_rollback, prepare_rollback_prespawn,
-    run_rollback,
+    check_rollback, collect_rollback_entities, end_rollback, increment_rollback_tick,
+    prepare_rollback, prepare_rollback_predicted_only, prepare_rollback_prespawn,
+    restore_rollback_disabled, run_rollback, stash_rollback_disabled,
 };
 use super::{
     clean_pre_predicted_entity, handle_pre_prediction, spawn_predicted_entity, ComponentSyncMode,
@@ -47,6 +49,12 @@ pub struct PredictionConfig {
     /// If true, we always rollback whenever we receive a server update, instead of checking
     /// ff the confirmed state matches the predicted state history
     pub always_rollback: bool,
+    /// If true, we only roll back the entities that mispredicted (and the entities that share a
+    /// [`RollbackIsland`](super::RollbackIsland) with them), instead of all the predicted entities.
+    ///
+    /// The other entities are marked with [`DisabledDuringRollback`](super::DisabledDuringRollback) and lose their
+    /// synced components during the rollback, so that only the rolled back entities are resimulated.
+    pub partial_rollback: bool,
     /// The amount of ticks that the player's inputs will be delayed by.
     /// This can be useful to mitigate the amount of client-prediction
     /// This setting is global instead of per Actionlike because it affects how ahead the client will be
@@ -70,6 +78,11 @@ impl PredictionConfig {
         self
     }
 
+    pub fn partial_rollback(mut self, partial_rollback: bool) -> Self {
+        self.partial_rollback = partial_rollback;
+        self
+    }
+
     /// Update the amount of input delay (number of ticks)
     pub fn with_input_delay_ticks(mut self, tick: u16) -> Self {
         self.input_delay_ticks = tick;
@@ -122,6 +135,8 @@ pub enum PredictionSet {
     RestoreVisualCorrection,
     /// Check if rollback is needed
     CheckRollback,
+    /// Compute which entities are rolled back
+    CollectRollbackEntities,
     /// Prepare rollback by snapping the current state to the confirmed state and clearing histories
     /// For pre-spawned entities, we just roll them back to their historical state.
     /// If they didn't exist in the rollback tick, despawn them
@@ -132,6 +147,8 @@ pub enum PredictionSet {
     /// Perform rollback
     Rollback,
     // NOTE: no need to add RollbackFlush because running a schedule (which we do for rollback) will flush all commands at the end of each run
+    /// Reset the rollback state
+    EndRollback,
 
     // FixedPostUpdate Sets
     /// Increment the rollback tick after the main fixed-update physics loop has run
@@ -226,6 +243,14 @@ where
         }
         _ => {}
     };
+    app.add_systems(
+        PreUpdate,
+        (
+            // the entities that are not resimulated don't have their components during the rollback
+            stash_rollback_disabled::<C, P>.in_set(PredictionSet::PrepareRollback),
+            restore_rollback_disabled::<C>.in_set(PredictionSet::EndRollback),
+        ),
+    );
     app.add_systems(
         FixedPostUpdate,
         remove_component_for_despawn_predicted::<C, P>.in_set(PredictionSet::EntityDespawn),
@@ -241,9 +266,10 @@ impl<P: Protocol> Plugin for PredictionPlugin<P> {
 
         // RESOURCES
         app.init_resource::<PredictionManager>();
-        app.insert_resource(Rollback {
-            state: RollbackState::Default,
-        });
+        app.insert_resource(Rollback::new(self.config.partial_rollback));
+
+        // EVENTS
+        app.add_event::<PreSpawnEvent>();
 
         // PreUpdate systems:
         // 1. Receive confirmed entities, add Confirmed and Predicted components
@@ -257,9 +283,11 @@ impl<P: Protocol> Plugin for PredictionPlugin<P> {
                 PredictionSet::SpawnHistoryFlush,
                 PredictionSet::RestoreVisualCorrection,
                 PredictionSet::CheckRollback,
+                PredictionSet::CollectRollbackEntities.run_if(is_in_rollback),
                 PredictionSet::PrepareRollback.run_if(is_in_rollback),
                 PredictionSet::PrepareRollbackFlush.run_if(is_in_rollback),
                 PredictionSet::Rollback.run_if(is_in_rollback),
+                PredictionSet::EndRollback.run_if(is_in_rollback),
             )
                 .chain(),
         );
@@ -294,9 +322,13 @@ impl<P: Protocol> Plugin for PredictionPlugin<P> {
                     // NOTE: we put `despawn_confirmed` here because we only need to run it once per frame,
                     //  not at every fixed-update tick, since it only depends on server messages
                     despawn_confirmed,
+                    detach_predicted,
                 )
                     .in_set(PredictionSet::SpawnPrediction),
+                collect_rollback_entities.in_set(PredictionSet::CollectRollbackEntities),
+                prepare_rollback_predicted_only.in_set(PredictionSet::PrepareRollback),
                 run_rollback.in_set(PredictionSet::Rollback),
+                end_rollback.in_set(PredictionSet::EndRollback),
             ),
         );
 
@@ -333,6 +365,7 @@ impl<P: Protocol> Plugin for PredictionPlugin<P> {
                 (remove_despawn_marker, apply_deferred)
                     .chain()
                     .in_set(PredictionSet::EntityDespawnFlush),
+                set_predicted_only_spawn_tick.in_set(PredictionSet::SpawnHistory),
                 apply_deferred.in_set(PredictionSet::SpawnHistoryFlush),
                 increment_rollback_tick.in_set(PredictionSet::IncrementRollbackTick),
             ),
@@ -1,8 +1,8 @@
 use std::ops::Deref;
 
 use bevy::prelude::{
-    Commands, Component, DetectChanges, Entity, Or, Query, Ref, RemovedComponents, Res, ResMut,
-    With, Without,
+    Added, Commands, Component, DetectChanges, Entity, Or, Query, Ref, RemovedComponents, Res,
+    ResMut, Resource, With, Without,
 };
 use tracing::{debug, error};
 
@@ -13,7 +13,10 @@ use crate::protocol::Protocol;
 use crate::shared::tick_manager::Tick;
 use crate::utils::ready_buffer::ReadyBuffer;
 
-use super::{ComponentSyncMode, Confirmed, Predicted, Rollback, RollbackState};
+use super::{
+    ComponentSyncMode, Confirmed, Predicted, PredictedOnly, Rollback, RollbackEligibility,
+    RollbackState,
+};
 
 // TODO: maybe just option<T> ?
 #[derive(Debug, PartialEq, Clone)]
@@ -25,7 +28,9 @@ pub enum ComponentState<T> {
 }
 
 /// To know if we need to do rollback, we need to compare the predicted entity's history with the server's state updates
-#[derive(Component, Debug)]
+///
+/// This is also used as a resource for predicted resources.
+#[derive(Component, Resource, Debug)]
 pub struct PredictionHistory<T: PartialEq> {
     // TODO: add a max size for the buffer
     // We want to avoid using a SequenceBuffer for optimization (we don't want to store a copy of the component for each history tick)
@@ -54,7 +59,7 @@ impl<T: SyncComponent> PartialEq for PredictionHistory<T> {
     }
 }
 
-impl<T: SyncComponent> PredictionHistory<T> {
+impl<T: Clone + PartialEq> PredictionHistory<T> {
     /// Reset the history for this component
     pub(crate) fn clear(&mut self) {
         self.buffer = ReadyBuffer::new();
@@ -69,13 +74,36 @@ impl<T: SyncComponent> PredictionHistory<T> {
     /// contains gaps. Therefore, we need to always leave a value in the history buffer so that we can
     /// get the values for the future ticks
     pub(crate) fn pop_until_tick(&mut self, tick: Tick) -> Option<ComponentState<T>> {
-        self.buffer.pop_until(&tick).map(|(tick, state)| {
+        self.buffer.pop_until(&tick).map(|(_, state)| {
             // TODO: this clone is pretty bad and avoidable. Probably switch to a sequence buffer?
+            // NOTE: we store the value at the provided tick (and not at the tick where it was recorded),
+            //  so that the ticks in the history stay recent even if the component doesn't change for a long time.
+            //  Otherwise the history tick could end up more than i16::MAX ticks away and look like it's in the future.
             self.buffer.add_item(tick, state.clone());
             state
         })
     }
 
+    /// Get the most recent value recorded in the history
+    pub(crate) fn latest(&self) -> Option<&ComponentState<T>> {
+        self.buffer
+            .heap
+            .iter()
+            .max_by_key(|item| item.key)
+            .map(|item| &item.item)
+    }
+
+    /// Get the value of the component at the specified tick, without modifying the history.
+    /// (i.e. the most recent value recorded at a tick older or equal than the specified tick)
+    pub(crate) fn get(&self, tick: Tick) -> Option<&ComponentState<T>> {
+        self.buffer
+            .heap
+            .iter()
+            .filter(|item| item.key <= tick)
+            .max_by_key(|item| item.key)
+            .map(|item| &item.item)
+    }
+
     // /// Get the value of the component at the specified tick.
     // /// Clears the history buffer of all ticks older than the specified tick.
     // /// Returns None
@@ -195,8 +223,12 @@ pub fn add_prespawned_component_history<C: SyncComponent, P: Protocol>(
         (
             Without<PredictionHistory<C>>,
             Without<Confirmed>,
-            // for pre-spawned entities
-            Or<(With<ShouldBePredicted>, With<PreSpawnedPlayerObject>)>,
+            // for pre-spawned entities and client-only predicted entities
+            Or<(
+                With<ShouldBePredicted>,
+                With<PreSpawnedPlayerObject>,
+                With<PredictedOnly>,
+            )>,
         ),
     >,
 ) where
@@ -213,6 +245,24 @@ pub fn add_prespawned_component_history<C: SyncComponent, P: Protocol>(
     }
 }
 
+/// Record the tick at which the [`PredictedOnly`] entities were spawned, so that we can despawn them
+/// if we roll back to a tick before they existed
+pub(crate) fn set_predicted_only_spawn_tick(
+    tick_manager: Res<TickManager>,
+    rollback: Res<Rollback>,
+    mut query: Query<&mut PredictedOnly, Added<PredictedOnly>>,
+) {
+    let tick = match rollback.state {
+        RollbackState::Default => tick_manager.tick(),
+        RollbackState::ShouldRollback { current_tick } => current_tick,
+    };
+    for mut predicted_only in query.iter_mut() {
+        if predicted_only.spawn_tick.is_none() {
+            predicted_only.spawn_tick = Some(tick);
+        }
+    }
+}
+
 /// Add history when a predicted component gets added
 fn add_history<C: SyncComponent, P: Protocol>(
     tick: Tick,
@@ -279,9 +329,17 @@ fn add_history<C: SyncComponent, P: Protocol>(
 
 /// After one fixed-update tick, we record the predicted component history for the current tick
 pub fn update_prediction_history<T: SyncComponent>(
-    mut query: Query<(Ref<T>, &mut PredictionHistory<T>)>,
+    mut query: Query<(
+        Entity,
+        Ref<T>,
+        &mut PredictionHistory<T>,
+        Option<&RollbackEligibility>,
+    )>,
     mut removed_component: RemovedComponents<T>,
-    mut removed_entities: Query<&mut PredictionHistory<T>, Without<T>>,
+    mut removed_entities: Query<
+        (&mut PredictionHistory<T>, Option<&RollbackEligibility>),
+        Without<T>,
+    >,
     tick_manager: Res<TickManager>,
     rollback: Res<Rollback>,
 ) {
@@ -295,7 +353,12 @@ pub fn update_prediction_history<T: SyncComponent>(
     // update history if the predicted component changed
     // TODO: potentially change detection does not work during rollback!
     //  edit: looks like it does
-    for (component, mut history) in query.iter_mut() {
+    let in_rollback = matches!(rollback.state, RollbackState::ShouldRollback { .. });
+    for (entity, component, mut history, eligibility) in query.iter_mut() {
+        // entities excluded from rollback are not resimulated, keep their history from before the rollback
+        if in_rollback && rollback.is_excluded(entity, eligibility) {
+            continue;
+        }
         // change detection works even when running the schedule for rollback (with no time increase)
         if component.is_changed() {
             history
@@ -304,7 +367,10 @@ pub fn update_prediction_history<T: SyncComponent>(
         }
     }
     for entity in removed_component.read() {
-        if let Ok(mut history) = removed_entities.get_mut(entity) {
+        if let Ok((mut history, eligibility)) = removed_entities.get_mut(entity) {
+            if in_rollback && rollback.is_excluded(entity, eligibility) {
+                continue;
+            }
             history.buffer.add_item(tick, ComponentState::Removed);
         }
     }
@@ -390,4 +456,50 @@ mod tests {
     //     assert_eq!(component_history.get_history_at_tick(Tick(0)), None);
     //     assert_eq!(component_history.buffer.len(), 1);
     // }
+
+    use crate::shared::tick_manager::Tick;
+    use crate::tests::protocol::Component1;
+
+    use super::{ComponentState, PredictionHistory};
+
+    /// The history should keep working if the component doesn't change for more than i16::MAX ticks
+    #[test]
+    fn test_pop_until_tick_after_long_idle() {
+        let mut history = PredictionHistory::<Component1>::default();
+        history
+            .buffer
+            .add_item(Tick(1), ComponentState::Updated(Component1(1.0)));
+
+        let mut tick = Tick(1);
+        for _ in 0..10 {
+            tick += (i16::MAX / 4) as u16;
+            assert_eq!(
+                history.pop_until_tick(tick),
+                Some(ComponentState::Updated(Component1(1.0)))
+            );
+        }
+    }
+
+    #[test]
+    fn test_get() {
+        let mut history = PredictionHistory::<Component1>::default();
+        history
+            .buffer
+            .add_item(Tick(2), ComponentState::Updated(Component1(2.0)));
+        history
+            .buffer
+            .add_item(Tick(4), ComponentState::Updated(Component1(4.0)));
+
+        assert_eq!(history.get(Tick(1)), None);
+        assert_eq!(
+            history.get(Tick(3)),
+            Some(&ComponentState::Updated(Component1(2.0)))
+        );
+        assert_eq!(
+            history.get(Tick(5)),
+            Some(&ComponentState::Updated(Component1(4.0)))
+        );
+        // the history is not modified
+        assert_eq!(history.buffer.len(), 2);
+    }
 }
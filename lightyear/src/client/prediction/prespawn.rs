@@ -5,8 +5,8 @@ use std::hash::{BuildHasher, Hash, Hasher};
 
 use bevy::ecs::system::Command;
 use bevy::prelude::{
-    Commands, Component, DespawnRecursiveExt, DetectChanges, EntityRef, EventReader, Mut, Query,
-    Ref, Res, ResMut, Without, World,
+    Commands, Component, DespawnRecursiveExt, DetectChanges, Entity, EntityRef, Event, EventReader,
+    EventWriter, Mut, Query, Ref, Res, ResMut, Without, World,
 };
 use serde::{Deserialize, Serialize};
 use tracing::{debug, trace, warn};
@@ -18,7 +18,7 @@ use crate::client::components::Confirmed;
 use crate::client::connection::ConnectionManager;
 use crate::client::events::ComponentInsertEvent;
 use crate::client::prediction::resource::PredictionManager;
-use crate::client::prediction::{Predicted, Rollback, RollbackState};
+use crate::client::prediction::{Predicted, PredictedOnly, Rollback, RollbackState};
 use crate::prelude::{ShouldBePredicted, TickManager};
 use crate::protocol::Protocol;
 use crate::shared::replication::components::{DespawnTracker, Replicate};
@@ -31,36 +31,100 @@ pub struct PreSpawnedPlayerObject {
     /// By default, if the hash is not set, it will be generated from the entity's archetype (list of components) and spawn tick
     /// Otherwise you can manually set it to a value that will be the same on both the client and server
     pub hash: Option<u64>,
-    //
-    // pub conflict_resolution: ConflictResolution,
+    /// What happens to the client entity if no server entity matches it.
+    /// (only read on the client's entity)
+    pub client_no_match: ClientNoMatchHandling,
+    /// What happens to the server entity if it doesn't match any client entity.
+    /// (only read on the server's entity)
+    pub server_no_match: ServerNoMatchHandling,
+    /// What happens if several client entities match the server entity.
+    /// (only read on the server's entity)
+    pub conflict_resolution: ConflictResolution,
 }
 
-// pub enum ClientNoMatchHandling {
-//     /// If we don't get any server-entity that matches this prespawned player object, then we despawn it on the client
-//     /// Once we are sure that we won't get any more server updates for that entity
-//     /// (i.e. once interpolation_tick is reached)
-//     Despawn,
-//
-//     /// Even if we don't get any server-entity that matches this prespawned player object, we don't bother despawning it
-//     /// and we just leave it as is
-//     Allow,
-// }
-//
-// pub enum ServerNoMatchHandling {
-//     /// If the server sends an entity that doesn't match any existing client prespawned player object, we consider that the server
-//     /// entity is still valid and we spawn a Predicted entity for it.
-//     ForcePrediction,
-// }
+impl PreSpawnedPlayerObject {
+    /// Use a hash that will be the same on the client and the server, instead of computing it
+    /// from the entity's components and spawn tick
+    pub fn with_hash(mut self, hash: u64) -> Self {
+        self.hash = Some(hash);
+        self
+    }
 
-// pub enum ConflictResolution {
-//     /// If we don't get any server-entity that matches this prespawned player object, then we despawn it on the client
-//     /// Once we are sure that we won't get any more server updates for that entity
-//     /// (i.e. once interpolation_tick is reached)
-//     DespawnClient,
-//     /// If the server sends us an entity that doesn't match any client prespawned player object, we consider that the entity
-//     /// should still be predicted normally.
-//     AllowDuplicate,
-// }
+    pub fn with_client_no_match(mut self, client_no_match: ClientNoMatchHandling) -> Self {
+        self.client_no_match = client_no_match;
+        self
+    }
+
+    pub fn with_server_no_match(mut self, server_no_match: ServerNoMatchHandling) -> Self {
+        self.server_no_match = server_no_match;
+        self
+    }
+
+    pub fn with_conflict_resolution(mut self, conflict_resolution: ConflictResolution) -> Self {
+        self.conflict_resolution = conflict_resolution;
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClientNoMatchHandling {
+    /// If we don't get any server-entity that matches this prespawned player object, then we despawn it on the client
+    /// Once we are sure that we won't get any more server updates for that entity
+    /// (i.e. once interpolation_tick is reached)
+    #[default]
+    Despawn,
+    /// Even if we don't get any server-entity that matches this prespawned player object, we don't despawn it:
+    /// it becomes a [`PredictedOnly`] entity
+    Allow,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ServerNoMatchHandling {
+    /// If the server sends an entity that doesn't match any existing client prespawned player object, we consider that the server
+    /// entity is still valid and we spawn a Predicted entity for it.
+    #[default]
+    ForcePrediction,
+    /// The server entity is handled like any other replicated entity: it is only predicted if it has
+    /// the [`ShouldBePredicted`] component
+    Ignore,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// The server entity is matched with the client entity that was spawned first.
+    /// The other client entities can still be matched by other server entities with the same hash
+    #[default]
+    MatchOldest,
+    /// The server entity is matched with the client entity that was spawned first, and the other client
+    /// entities with the same hash are despawned
+    DespawnOthers,
+}
+
+/// Event emitted on the client to report how the client and server pre-spawned entities were matched
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreSpawnEvent {
+    /// The server entity was matched with a client entity, which is now its `Predicted` entity
+    Matched {
+        hash: u64,
+        confirmed: Entity,
+        predicted: Entity,
+    },
+    /// The client entity shared the hash of a server entity that was matched with another client entity,
+    /// and was despawned because of [`ConflictResolution::DespawnOthers`]
+    ConflictDespawned { hash: u64, entity: Entity },
+    /// No server entity matched the client entity, which was despawned
+    ClientDespawned { hash: u64, entity: Entity },
+    /// No server entity matched the client entity, which was kept as a [`PredictedOnly`] entity
+    ClientKept { hash: u64, entity: Entity },
+    /// The server entity didn't match any client entity, and a `Predicted` entity was spawned for it
+    ServerForcePredicted {
+        hash: u64,
+        confirmed: Entity,
+        predicted: Entity,
+    },
+    /// The server entity didn't match any client entity, and was not predicted
+    ServerIgnored { hash: u64, confirmed: Entity },
+}
 
 // TODO: maybe provide a prediction_spawn command instead of running the `compute_hash` in both FixedUpdate and PostUpdate?
 
@@ -186,8 +250,8 @@ pub(crate) fn compute_prespawn_hash<P: Protocol>(world: &mut World) {
                 },
             );
 
-            // TODO: what to do in multiple entities share the same hash?
-            //  just match a random one of them? or should the user have a more precise hash?
+            // entities that share the same hash are stored in the order in which their hash was computed;
+            // see `ConflictResolution` for how they get matched
             manager
                 .prespawn_hash_to_entities
                 .entry(hash)
@@ -212,12 +276,15 @@ pub(crate) fn compute_prespawn_hash<P: Protocol>(world: &mut World) {
     });
 }
 
-/// Cleanup the client prespawned entities for which we couldn't find a mapped server entity
+/// Cleanup the client prespawned entities for which we couldn't find a mapped server entity,
+/// according to their [`ClientNoMatchHandling`]
 pub(crate) fn pre_spawned_player_object_cleanup<P: Protocol>(
     mut commands: Commands,
     tick_manager: Res<TickManager>,
     connection: Res<ConnectionManager<P>>,
     mut manager: ResMut<PredictionManager>,
+    query: Query<&PreSpawnedPlayerObject>,
+    mut prespawn_events: EventWriter<PreSpawnEvent>,
 ) {
     let tick = tick_manager.tick();
     // TODO: why is interpolation tick not good enough and we need to use an earlier tick?
@@ -226,24 +293,41 @@ pub(crate) fn pre_spawned_player_object_cleanup<P: Protocol>(
     let interpolation_tick = connection.sync_manager.interpolation_tick(&tick_manager);
     let tick_diff = ((tick - interpolation_tick) * 2) as u16;
     let past_tick = tick - tick_diff;
-    // remove all the prespawned entities that have not been matched with a server entity
-    for (_, hash) in manager.prespawn_tick_to_hash.drain_until(&past_tick) {
-        manager
+    // handle all the prespawned entities that have not been matched with a server entity
+    for (spawn_tick, hash) in manager.prespawn_tick_to_hash.drain_until(&past_tick) {
+        for entity in manager
             .prespawn_hash_to_entities
             .remove(&hash)
-            .iter()
+            .into_iter()
             .flatten()
-            .for_each(|entity| {
-                if let Some(entity_commands) = commands.get_entity(*entity) {
+        {
+            // the entity could have been despawned in the meantime
+            let Ok(prespawn) = query.get(entity) else {
+                continue;
+            };
+            match prespawn.client_no_match {
+                ClientNoMatchHandling::Despawn => {
                     trace!(
                         ?tick,
                         ?entity,
                         "Cleaning up prespawned player object up to past tick: {:?}",
                         past_tick
                     );
-                    entity_commands.despawn_recursive();
+                    commands.entity(entity).despawn_recursive();
+                    prespawn_events.send(PreSpawnEvent::ClientDespawned { hash, entity });
                 }
-            });
+                ClientNoMatchHandling::Allow => {
+                    trace!(?tick, ?entity, "Keeping unmatched prespawned player object");
+                    commands
+                        .entity(entity)
+                        .remove::<PreSpawnedPlayerObject>()
+                        .insert(PredictedOnly {
+                            spawn_tick: Some(spawn_tick),
+                        });
+                    prespawn_events.send(PreSpawnEvent::ClientKept { hash, entity });
+                }
+            }
+        }
     }
 }
 
@@ -254,11 +338,15 @@ pub(crate) fn pre_spawned_player_object_cleanup<P: Protocol>(
 /// When we receive an entity from the server that contains the PreSpawnedPlayerObject component,
 /// that means that we already spawned it on the client.
 /// Try to match which client entity it is and take authority over it.
+///
+/// If no client entity matches, or if several client entities match, the server entity's
+/// [`ServerNoMatchHandling`] and [`ConflictResolution`] are applied.
 pub(crate) fn spawn_pre_spawned_player_object<P: Protocol>(
     mut commands: Commands,
     connection: Res<ConnectionManager<P>>,
     mut manager: ResMut<PredictionManager>,
     mut events: EventReader<ComponentInsertEvent<PreSpawnedPlayerObject>>,
+    mut prespawn_events: EventWriter<PreSpawnEvent>,
     query: Query<&PreSpawnedPlayerObject>,
 ) {
     for event in events.read() {
@@ -273,24 +361,58 @@ pub(crate) fn spawn_pre_spawned_player_object<P: Protocol>(
             warn!("Received a PreSpawnedPlayerObject entity from the server without a hash");
             continue;
         };
-        let Some(mut client_entity_list) = manager.prespawn_hash_to_entities.remove(&server_hash)
-        else {
-            warn!(?server_hash, "Received a PreSpawnedPlayerObject entity from the server with a hash that does not match any client entity");
-            // remove the PreSpawnedPlayerObject so that the entity can be normal-predicted
-            commands
-                .entity(confirmed_entity)
-                .remove::<PreSpawnedPlayerObject>();
-            continue;
+        let client_entity = match manager.prespawn_hash_to_entities.remove(&server_hash) {
+            Some(mut client_entity_list) => {
+                // if there are multiple entities, we use the oldest one: the entities are pushed in the order
+                // in which they were spawned, so it is the first one.
+                // (the newer entities are the ones that can still be matched by server entities that
+                // are replicated later with the same hash, see `ConflictResolution::MatchOldest`)
+                let client_entity = client_entity_list.remove(0);
+                debug!("found a client pre-spawned entity corresponding to server pre-spawned entity! Spawning a Predicted entity for it");
+                match server_prespawn.conflict_resolution {
+                    // re-add the remaining entities in the map
+                    ConflictResolution::MatchOldest => {
+                        if !client_entity_list.is_empty() {
+                            manager
+                                .prespawn_hash_to_entities
+                                .insert(server_hash, client_entity_list);
+                        }
+                    }
+                    ConflictResolution::DespawnOthers => {
+                        for entity in client_entity_list {
+                            if let Some(entity_commands) = commands.get_entity(entity) {
+                                debug!(?entity, "despawning conflicting pre-spawned entity");
+                                entity_commands.despawn_recursive();
+                                prespawn_events.send(PreSpawnEvent::ConflictDespawned {
+                                    hash: server_hash,
+                                    entity,
+                                });
+                            }
+                        }
+                    }
+                }
+                Some(client_entity)
+            }
+            None => match server_prespawn.server_no_match {
+                ServerNoMatchHandling::ForcePrediction => {
+                    warn!(?server_hash, "Received a PreSpawnedPlayerObject entity from the server with a hash that does not match any client entity, spawning a Predicted entity for it");
+                    None
+                }
+                ServerNoMatchHandling::Ignore => {
+                    warn!(?server_hash, "Received a PreSpawnedPlayerObject entity from the server with a hash that does not match any client entity");
+                    prespawn_events.send(PreSpawnEvent::ServerIgnored {
+                        hash: server_hash,
+                        confirmed: confirmed_entity,
+                    });
+                    continue;
+                }
+            },
         };
 
-        // if there are multiple entities, we will use the first one
-        let client_entity = client_entity_list.pop().unwrap();
-        debug!("found a client pre-spawned entity corresponding to server pre-spawned entity! Spawning a Predicted entity for it");
-
-        // we found the corresponding client entity!
         // 1.a if the client_entity exists, remove the PreSpawnedPlayerObject component from the client entity
         //  and add a Predicted component to it
-        let predicted_entity = if let Some(mut entity_commands) = commands.get_entity(client_entity)
+        let predicted_entity = if let Some(mut entity_commands) =
+            client_entity.and_then(|client_entity| commands.get_entity(client_entity))
         {
             debug!("re-using existing entity");
             entity_commands
@@ -298,7 +420,7 @@ pub(crate) fn spawn_pre_spawned_player_object<P: Protocol>(
                 .insert(Predicted {
                     confirmed_entity: Some(confirmed_entity),
                 });
-            client_entity
+            entity_commands.id()
         } else {
             debug!("spawning new entity");
             // 1.b if the client_entity does not exist, re-create it (because server has authority)
@@ -328,13 +450,18 @@ pub(crate) fn spawn_pre_spawned_player_object<P: Protocol>(
             "Added/Spawned the Predicted entity: {:?} for the confirmed entity: {:?}",
             predicted_entity, confirmed_entity
         );
-
-        // 3. re-add the remaining entities in the map
-        if !client_entity_list.is_empty() {
-            manager
-                .prespawn_hash_to_entities
-                .insert(server_hash, client_entity_list);
-        }
+        prespawn_events.send(match client_entity {
+            Some(_) => PreSpawnEvent::Matched {
+                hash: server_hash,
+                confirmed: confirmed_entity,
+                predicted: predicted_entity,
+            },
+            None => PreSpawnEvent::ServerForcePredicted {
+                hash: server_hash,
+                confirmed: confirmed_entity,
+                predicted: predicted_entity,
+            },
+        });
     }
 }
 
@@ -365,7 +492,7 @@ pub(crate) fn spawn_pre_spawned_player_object<P: Protocol>(
 
 #[cfg(test)]
 mod tests {
-    use bevy::prelude::Entity;
+    use bevy::prelude::{Entity, Events};
     use bevy::utils::Duration;
     use hashbrown::HashMap;
 
@@ -376,6 +503,48 @@ mod tests {
     use crate::tests::protocol::*;
     use crate::tests::stepper::{BevyStepper, Step};
 
+    fn setup(interpolation_config: InterpolationConfig) -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+        stepper
+    }
+
+    /// Step the stepper and collect the [`PreSpawnEvent`]s emitted on the client
+    fn step_and_read_events(stepper: &mut BevyStepper, num_frames: usize) -> Vec<PreSpawnEvent> {
+        let mut events = vec![];
+        for _ in 0..num_frames {
+            stepper.frame_step();
+            events.extend(
+                stepper
+                    .client_app
+                    .world
+                    .resource_mut::<Events<PreSpawnEvent>>()
+                    .drain(),
+            );
+        }
+        events
+    }
+
     #[test]
     fn test_compute_hash() {
         let frame_duration = Duration::from_millis(10);
@@ -415,7 +584,7 @@ mod tests {
 
         let current_tick = stepper.client_app.world.resource::<TickManager>().tick();
         let prediction_manager = stepper.client_app.world.resource::<PredictionManager>();
-        let expected_hash: u64 = 11844036307541615334;
+        let expected_hash: u64 = 6236655736469163560;
         dbg!(&prediction_manager.prespawn_hash_to_entities);
         assert_eq!(
             prediction_manager.prespawn_hash_to_entities,
@@ -449,4 +618,342 @@ mod tests {
             })
         );
     }
+
+    // Several client entities share the hash of the server entity: the oldest one gets matched,
+    // and the other ones are despawned because of `ConflictResolution::DespawnOthers`
+    #[test]
+    fn test_prespawn_conflict_resolution() {
+        let mut stepper = setup(InterpolationConfig::default());
+
+        let prespawn = PreSpawnedPlayerObject::default().with_hash(1);
+        let client_entity_1 = stepper
+            .client_app
+            .world
+            .spawn((Component1(1.0), prespawn))
+            .id();
+        let client_entity_2 = stepper
+            .client_app
+            .world
+            .spawn((Component1(1.0), prespawn))
+            .id();
+        stepper.frame_step();
+
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(1.0),
+                prespawn.with_conflict_resolution(ConflictResolution::DespawnOthers),
+                Replicate {
+                    prediction_target: NetworkTarget::All,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        let events = step_and_read_events(&mut stepper, 2);
+
+        let confirmed_entity = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .entity(client_entity_1)
+                .get::<Predicted>()
+                .unwrap()
+                .confirmed_entity,
+            Some(confirmed_entity)
+        );
+        assert!(stepper
+            .client_app
+            .world
+            .get_entity(client_entity_2)
+            .is_none());
+        assert_eq!(
+            events,
+            vec![
+                PreSpawnEvent::ConflictDespawned {
+                    hash: 1,
+                    entity: client_entity_2,
+                },
+                PreSpawnEvent::Matched {
+                    hash: 1,
+                    confirmed: confirmed_entity,
+                    predicted: client_entity_1,
+                },
+            ]
+        );
+    }
+
+    // With `ConflictResolution::MatchOldest`, the client entities that share a hash are matched in the
+    // order in which they were spawned
+    #[test]
+    fn test_prespawn_match_oldest() {
+        // use a large interpolation delay so that the client entities don't get cleaned up
+        // before the second server entity is replicated
+        let mut stepper =
+            setup(InterpolationConfig::default().with_delay(
+                InterpolationDelay::default().with_min_delay(Duration::from_millis(200)),
+            ));
+
+        let prespawn = PreSpawnedPlayerObject::default().with_hash(1);
+        let client_entity_1 = stepper
+            .client_app
+            .world
+            .spawn((Component1(1.0), prespawn))
+            .id();
+        let client_entity_2 = stepper
+            .client_app
+            .world
+            .spawn((Component1(1.0), prespawn))
+            .id();
+        // let the server catch up with the spawn tick of the client entities, otherwise the rollback
+        // triggered by the first match would despawn the second client entity
+        step_and_read_events(&mut stepper, 5);
+
+        let replicate = Replicate {
+            prediction_target: NetworkTarget::All,
+            ..Default::default()
+        };
+        let server_entity_1 = stepper
+            .server_app
+            .world
+            .spawn((Component1(1.0), prespawn, replicate.clone()))
+            .id();
+        let mut events = step_and_read_events(&mut stepper, 2);
+        let server_entity_2 = stepper
+            .server_app
+            .world
+            .spawn((Component1(1.0), prespawn, replicate))
+            .id();
+        events.extend(step_and_read_events(&mut stepper, 2));
+        assert_eq!(events.len(), 2);
+
+        for (server_entity, client_entity) in [
+            (server_entity_1, client_entity_1),
+            (server_entity_2, client_entity_2),
+        ] {
+            let confirmed_entity = *stepper
+                .client_app
+                .world
+                .resource::<ClientConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .unwrap();
+            assert_eq!(
+                stepper
+                    .client_app
+                    .world
+                    .entity(client_entity)
+                    .get::<Predicted>()
+                    .unwrap()
+                    .confirmed_entity,
+                Some(confirmed_entity)
+            );
+            assert!(events.contains(&PreSpawnEvent::Matched {
+                hash: 1,
+                confirmed: confirmed_entity,
+                predicted: client_entity,
+            }));
+        }
+    }
+
+    // The client entity is despawned once the interpolation tick shows that no server entity will match it
+    #[test]
+    fn test_client_no_match_despawn() {
+        let mut stepper = setup(InterpolationConfig::default());
+
+        let client_entity = stepper
+            .client_app
+            .world
+            .spawn((
+                Component1(1.0),
+                PreSpawnedPlayerObject::default()
+                    .with_hash(1)
+                    .with_client_no_match(ClientNoMatchHandling::Despawn),
+            ))
+            .id();
+        let events = step_and_read_events(&mut stepper, 100);
+
+        assert!(stepper.client_app.world.get_entity(client_entity).is_none());
+        assert_eq!(
+            events,
+            vec![PreSpawnEvent::ClientDespawned {
+                hash: 1,
+                entity: client_entity,
+            }]
+        );
+        assert!(stepper
+            .client_app
+            .world
+            .resource::<PredictionManager>()
+            .prespawn_hash_to_entities
+            .is_empty());
+    }
+
+    // The client entity is kept as a `PredictedOnly` entity if no server entity matches it
+    #[test]
+    fn test_client_no_match_allow() {
+        let mut stepper = setup(InterpolationConfig::default());
+
+        let client_entity = stepper
+            .client_app
+            .world
+            .spawn((
+                Component1(1.0),
+                PreSpawnedPlayerObject::default()
+                    .with_hash(1)
+                    .with_client_no_match(ClientNoMatchHandling::Allow),
+            ))
+            .id();
+        let mut events = step_and_read_events(&mut stepper, 1);
+        let spawn_tick = stepper
+            .client_app
+            .world
+            .resource::<PredictionManager>()
+            .prespawn_tick_to_hash
+            .heap
+            .peek()
+            .unwrap()
+            .key;
+        events.extend(step_and_read_events(&mut stepper, 100));
+
+        let entity = stepper.client_app.world.entity(client_entity);
+        assert!(entity.get::<PreSpawnedPlayerObject>().is_none());
+        assert_eq!(
+            entity.get::<PredictedOnly>(),
+            Some(&PredictedOnly {
+                spawn_tick: Some(spawn_tick),
+            })
+        );
+        assert_eq!(
+            events,
+            vec![PreSpawnEvent::ClientKept {
+                hash: 1,
+                entity: client_entity,
+            }]
+        );
+    }
+
+    // A server entity that doesn't match any client entity gets a Predicted entity
+    #[test]
+    fn test_server_no_match_force_prediction() {
+        let mut stepper = setup(InterpolationConfig::default());
+
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(1.0),
+                PreSpawnedPlayerObject::default()
+                    .with_hash(1)
+                    .with_server_no_match(ServerNoMatchHandling::ForcePrediction),
+                Replicate {
+                    prediction_target: NetworkTarget::All,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        let events = step_and_read_events(&mut stepper, 2);
+
+        let confirmed_entity = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        let predicted_entity = stepper
+            .client_app
+            .world
+            .entity(confirmed_entity)
+            .get::<Confirmed>()
+            .unwrap()
+            .predicted
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .entity(predicted_entity)
+                .get::<Predicted>()
+                .unwrap()
+                .confirmed_entity,
+            Some(confirmed_entity)
+        );
+        assert_eq!(
+            events,
+            vec![PreSpawnEvent::ServerForcePredicted {
+                hash: 1,
+                confirmed: confirmed_entity,
+                predicted: predicted_entity,
+            }]
+        );
+    }
+
+    // A server entity that doesn't match any client entity is handled like a regular replicated entity
+    // with `ServerNoMatchHandling::Ignore`
+    #[test]
+    fn test_server_no_match_ignore() {
+        let mut stepper = setup(InterpolationConfig::default());
+
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(1.0),
+                PreSpawnedPlayerObject::default()
+                    .with_hash(1)
+                    .with_server_no_match(ServerNoMatchHandling::Ignore),
+                Replicate {
+                    prediction_target: NetworkTarget::All,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        let events = step_and_read_events(&mut stepper, 2);
+
+        let confirmed_entity = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        // the entity still goes through the regular prediction flow because it has `ShouldBePredicted`
+        let predicted_entity = stepper
+            .client_app
+            .world
+            .entity(confirmed_entity)
+            .get::<Confirmed>()
+            .unwrap()
+            .predicted
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .entity(predicted_entity)
+                .get::<Predicted>()
+                .unwrap()
+                .confirmed_entity,
+            Some(confirmed_entity)
+        );
+        assert_eq!(
+            events,
+            vec![PreSpawnEvent::ServerIgnored {
+                hash: 1,
+                confirmed: confirmed_entity,
+            }]
+        );
+    }
 }
@@ -15,7 +15,7 @@ pub(crate) struct PredictionManager {
     pub(crate) predicted_entity_map: PredictedEntityMap,
     /// Map from the hash of a PrespawnedPlayerObject to the corresponding local entity
     /// NOTE: multiple entities could share the same hash. In which case, upon receiving a server prespawned entity,
-    /// we will randomly select a random entity in the set to be its predicted counterpart
+    /// we select the oldest entity in the list to be its predicted counterpart (see [`ConflictResolution`](super::prespawn::ConflictResolution))
     ///
     /// Also stores the tick at which the entities was spawned.
     /// If the interpolation_tick reaches that tick and there is till no match, we should despawn the entity
@@ -1,11 +1,13 @@
 use std::fmt::Debug;
 
 use bevy::app::FixedMain;
+use bevy::diagnostic::Diagnostics;
 use bevy::ecs::entity::EntityHashSet;
 use bevy::prelude::{
-    Commands, DespawnRecursiveExt, DetectChanges, Entity, Query, Ref, Res, ResMut, With, Without,
-    World,
+    Commands, Component, DespawnRecursiveExt, DetectChanges, Entity, Or, Query, Ref, Res, ResMut,
+    With, Without, World,
 };
+use bevy::utils::HashSet;
 use tracing::{debug, error, trace, trace_span};
 
 use crate::_reexport::{ComponentProtocol, FromType};
@@ -13,14 +15,19 @@ use crate::client::components::{ComponentSyncMode, Confirmed, SyncComponent};
 use crate::client::config::ClientConfig;
 use crate::client::connection::ConnectionManager;
 use crate::client::prediction::correction::Correction;
+use crate::client::prediction::diagnostics::PredictionDiagnosticsPlugin;
 use crate::client::prediction::predicted_history::ComponentState;
 use crate::client::prediction::resource::PredictionManager;
 use crate::prelude::client::SyncMetadata;
 use crate::prelude::{PreSpawnedPlayerObject, TickManager};
 use crate::protocol::Protocol;
+use crate::shared::replication::components::{DetachedComponents, Replicate};
 
 use super::predicted_history::PredictionHistory;
-use super::{Predicted, Rollback, RollbackState};
+use super::{
+    DisabledDuringRollback, Predicted, PredictedOnly, Rollback, RollbackEligibility,
+    RollbackIsland, RollbackState,
+};
 
 #[allow(clippy::type_complexity)]
 #[allow(clippy::too_many_arguments)]
@@ -31,8 +38,16 @@ pub(crate) fn check_rollback<C: SyncComponent, P: Protocol>(
 
     // We also snap the value of the component to the server state if we are in rollback
     // We use Option<> because the predicted component could have been removed while it still exists in Confirmed
-    mut predicted_query: Query<&mut PredictionHistory<C>, (With<Predicted>, Without<Confirmed>)>,
-    confirmed_query: Query<(Entity, Option<&C>, Ref<Confirmed>)>,
+    mut predicted_query: Query<
+        (&mut PredictionHistory<C>, Option<&RollbackEligibility>),
+        (With<Predicted>, Without<Confirmed>),
+    >,
+    confirmed_query: Query<(
+        Entity,
+        Option<&C>,
+        Ref<Confirmed>,
+        Option<&DetachedComponents<P>>,
+    )>,
     mut rollback: ResMut<Rollback>,
 ) where
     <P as Protocol>::ComponentKinds: FromType<C>,
@@ -53,7 +68,7 @@ pub(crate) fn check_rollback<C: SyncComponent, P: Protocol>(
     // TODO: can just enable bevy spans?
     let _span = trace_span!("client rollback check");
 
-    for (confirmed_entity, confirmed_component, confirmed) in confirmed_query.iter() {
+    for (confirmed_entity, confirmed_component, confirmed, detached) in confirmed_query.iter() {
         // 0. only check rollback when any entity in the replication group has been updated
         // (i.e. the confirmed tick has been updated)
         if !confirmed.is_changed() {
@@ -67,10 +82,12 @@ pub(crate) fn check_rollback<C: SyncComponent, P: Protocol>(
         let Some(p) = confirmed.predicted else {
             continue;
         };
-        let Ok(mut predicted_history) = predicted_query.get_mut(p) else {
+        let Ok((mut predicted_history, eligibility)) = predicted_query.get_mut(p) else {
             debug!("Predicted entity {:?} was not found", confirmed.predicted);
             continue;
         };
+        // entities that are excluded from rollback cannot trigger a rollback
+        let excluded = RollbackEligibility::is_excluded(eligibility);
 
         // 2. We will compare the predicted history and the confirmed entity at the current confirmed entity tick
         // - Confirmed contains the server state at the tick
@@ -91,47 +108,60 @@ pub(crate) fn check_rollback<C: SyncComponent, P: Protocol>(
         // that we should rollback (RollbackState::Default)
         // That is not the case, because if we do rollback we will need to snap the client entity to the server state
         // So either way we will need to do an operation.
-        match rollback.state {
-            // 3.a We are still not sure if we should do rollback. Compare history against confirmed
-            // We rollback if there's no history (newly added predicted entity, or if there is a mismatch)
-            RollbackState::Default => {
-                let history_value = predicted_history.pop_until_tick(tick);
-                let predicted_exist = history_value.is_some();
-                let confirmed_exist = confirmed_component.is_some();
-                let should_rollback = match confirmed_component {
-                    // TODO: history-value should not be empty here; should we panic if it is?
-                    // confirm does not exist. rollback if history value is not Removed
-                    None => history_value.map_or(false, |history_value| {
-                        history_value != ComponentState::Removed
-                    }),
-                    // confirm exist. rollback if history value is different
-                    Some(c) => history_value.map_or(true, |history_value| match history_value {
-                        ComponentState::Updated(history_value) => history_value != *c,
-                        ComponentState::Removed => true,
-                    }),
-                };
-                if should_rollback {
-                    debug!(
-                   ?predicted_exist, ?confirmed_exist,
-                   "Rollback check: mismatch for component between predicted and confirmed {:?} on tick {:?} for component {:?}. Current tick: {:?}",
-                   confirmed_entity, tick, kind, current_tick
-                   );
-                    // TODO: try atomic enum update
-                    rollback.state = RollbackState::ShouldRollback {
-                        // we already rolled-back the state for the entity's latest_tick
-                        // after this we will start right away with a physics update, so we need to start taking the inputs from the next tick
-                        current_tick: tick + 1,
-                    };
+        // 3.a We are still not sure if we should do rollback. Compare history against confirmed
+        // 3.b We already know we should do rollback from this tick or an older one (because of another
+        //  entity/component/resource). For partial rollbacks, we still need to compare to know which entities mispredicted
+        let rollback_covers_tick = matches!(
+            rollback.state,
+            RollbackState::ShouldRollback { current_tick } if current_tick <= tick + 1
+        );
+        if rollback_covers_tick && !rollback.partial {
+            trace!(
+               "Rollback check: should roll back for component between predicted and confirmed on tick {:?} for component {:?}. Current tick: {:?}",
+               tick, kind, current_tick
+               );
+            continue;
+        }
+        // We rollback if there's no history (newly added predicted entity, or if there is a mismatch)
+        let history_value = predicted_history.pop_until_tick(tick);
+        // the server doesn't replicate the component anymore, so there is nothing to compare with
+        if detached.is_some_and(|detached| detached.contains::<C>()) {
+            continue;
+        }
+        let predicted_exist = history_value.is_some();
+        let confirmed_exist = confirmed_component.is_some();
+        let should_rollback = match confirmed_component {
+            // TODO: history-value should not be empty here; should we panic if it is?
+            // confirm does not exist. rollback if history value is not Removed
+            None => history_value.map_or(false, |history_value| {
+                history_value != ComponentState::Removed
+            }),
+            // confirm exist. rollback if history value is different
+            Some(c) => history_value.map_or(true, |history_value| match history_value {
+                // the confirmed value was quantized by the server, so we compare with the same precision
+                ComponentState::Updated(history_value) => {
+                    P::Components::quantize(history_value) != *c
                 }
-            }
-            // 3.b We already know we should do rollback (because of another entity/component), start the rollback
-            RollbackState::ShouldRollback { .. } => {
-                trace!(
-                   "Rollback check: should roll back for component between predicted and confirmed on tick {:?} for component {:?}. Current tick: {:?}",
-                   tick, kind, current_tick
-                   );
-            }
+                ComponentState::Removed => true,
+            }),
         };
+        if should_rollback && !excluded {
+            debug!(
+           ?predicted_exist, ?confirmed_exist,
+           "Rollback check: mismatch for component between predicted and confirmed {:?} on tick {:?} for component {:?}. Current tick: {:?}",
+           confirmed_entity, tick, kind, current_tick
+           );
+            // we already rolled-back the state for the entity's latest_tick
+            // after this we will start right away with a physics update, so we need to start taking the inputs from the next tick
+            // If a rollback was already triggered at a more recent tick (for example by a resource), start from the oldest tick
+            if !rollback_covers_tick {
+                // TODO: try atomic enum update
+                rollback.state = RollbackState::ShouldRollback {
+                    current_tick: tick + 1,
+                };
+            }
+            rollback.entities.insert(p);
+        }
     }
 }
 
@@ -150,6 +180,7 @@ pub(crate) fn prepare_rollback<C: SyncComponent, P: Protocol>(
             Option<&mut C>,
             &mut PredictionHistory<C>,
             Option<&mut Correction<C>>,
+            Option<&RollbackEligibility>,
         ),
         (
             With<Predicted>,
@@ -157,7 +188,12 @@ pub(crate) fn prepare_rollback<C: SyncComponent, P: Protocol>(
             Without<PreSpawnedPlayerObject>,
         ),
     >,
-    confirmed_query: Query<(Entity, Option<&C>, Ref<Confirmed>)>,
+    confirmed_query: Query<(
+        Entity,
+        Option<&C>,
+        Ref<Confirmed>,
+        Option<&DetachedComponents<P>>,
+    )>,
     rollback: Res<Rollback>,
 ) where
     <P as Protocol>::ComponentKinds: FromType<C>,
@@ -169,11 +205,18 @@ pub(crate) fn prepare_rollback<C: SyncComponent, P: Protocol>(
     if P::Components::mode() != ComponentSyncMode::Full {
         return;
     }
+    let RollbackState::ShouldRollback {
+        current_tick: rollback_tick_plus_one,
+    } = rollback.state
+    else {
+        error!("prepare_rollback should only be called when we are in rollback");
+        return;
+    };
     let _span = trace_span!("client rollback prepare");
     debug!("in prepare rollback");
 
     let current_tick = tick_manager.tick();
-    for (confirmed_entity, confirmed_component, confirmed) in confirmed_query.iter() {
+    for (confirmed_entity, confirmed_component, confirmed, detached) in confirmed_query.iter() {
         let rollback_tick = confirmed.tick;
         //
         // // 0. Confirm that we are in rollback.
@@ -190,12 +233,50 @@ pub(crate) fn prepare_rollback<C: SyncComponent, P: Protocol>(
         };
 
         // 1. Get the predicted entity, and it's history
-        let Ok((predicted_entity, predicted_component, mut predicted_history, mut correction)) =
-            predicted_query.get_mut(p)
+        let Ok((
+            predicted_entity,
+            predicted_component,
+            mut predicted_history,
+            mut correction,
+            eligibility,
+        )) = predicted_query.get_mut(p)
         else {
             debug!("Predicted entity {:?} was not found", confirmed.predicted);
             continue;
         };
+        // the entity keeps its current predicted state
+        if rollback.is_excluded(predicted_entity, eligibility) {
+            continue;
+        }
+
+        // the server doesn't replicate the component anymore: restore the value that we predicted
+        if detached.is_some_and(|detached| detached.contains::<C>()) {
+            let rollback_tick = rollback_tick_plus_one - 1;
+            match predicted_history.pop_until_tick(rollback_tick) {
+                Some(ComponentState::Updated(c)) => {
+                    match predicted_component {
+                        Some(mut predicted_component) => *predicted_component = c.clone(),
+                        None => {
+                            commands.entity(predicted_entity).insert(c.clone());
+                        }
+                    }
+                    predicted_history.clear();
+                    predicted_history
+                        .buffer
+                        .add_item(rollback_tick, ComponentState::Updated(c));
+                }
+                Some(ComponentState::Removed) => {
+                    commands.entity(predicted_entity).remove::<C>();
+                    predicted_history.clear();
+                    predicted_history
+                        .buffer
+                        .add_item(rollback_tick, ComponentState::Removed);
+                }
+                // we don't know the value at the rollback tick, keep the current one
+                None => {}
+            }
+            continue;
+        }
 
         // 2. we need to clear the history so we can write a new one
         predicted_history.clear();
@@ -269,7 +350,7 @@ pub(crate) fn prepare_rollback<C: SyncComponent, P: Protocol>(
     }
 }
 
-/// For prespawned predicted entities, we do not have a Confirmed component,
+/// For prespawned predicted entities (and [`PredictedOnly`] entities), we do not have a Confirmed component,
 /// we just rollback the entity to the previous state
 /// - entities that did not exist at the rollback tick are despawned (and should be respawned during rollback)
 /// - component that were inserted since rollback are removed
@@ -293,13 +374,16 @@ pub(crate) fn prepare_rollback_prespawn<C: SyncComponent, P: Protocol>(
             Option<&mut C>,
             &mut PredictionHistory<C>,
             Option<&mut Correction<C>>,
+            Option<&RollbackEligibility>,
+            Option<&PredictedOnly>,
         ),
         (
-            With<PreSpawnedPlayerObject>,
+            Or<(With<PreSpawnedPlayerObject>, With<PredictedOnly>)>,
             Without<Confirmed>,
             Without<Predicted>,
         ),
     >,
+    eligibility_query: Query<&RollbackEligibility>,
     rollback: Res<Rollback>,
 ) where
     <P as Protocol>::ComponentKinds: FromType<C>,
@@ -330,12 +414,24 @@ pub(crate) fn prepare_rollback_prespawn<C: SyncComponent, P: Protocol>(
     // NOTE: if rollback happened at current_tick - 1, then we will start running systems starting from current_tick.
     //  so if the entity was spawned at tick >= current_tick, we despawn it, and it can get respawned again
     let mut entities_to_despawn = EntityHashSet::default();
-    for (_, hash) in prediction_manager
+    for (tick, hash) in prediction_manager
         .prespawn_tick_to_hash
         .drain_after(&rollback_tick_plus_one)
     {
         if let Some(entities) = prediction_manager.prespawn_hash_to_entities.remove(&hash) {
-            entities_to_despawn.extend(entities);
+            // entities that are excluded from rollback are kept, so they can still be matched with the server entity
+            let (excluded, included): (Vec<_>, Vec<_>) = entities.into_iter().partition(|entity| {
+                rollback.is_excluded(*entity, eligibility_query.get(*entity).ok())
+            });
+            entities_to_despawn.extend(included);
+            if !excluded.is_empty() {
+                prediction_manager
+                    .prespawn_tick_to_hash
+                    .add_item(tick, hash);
+                prediction_manager
+                    .prespawn_hash_to_entities
+                    .insert(hash, excluded);
+            }
         }
     }
     entities_to_despawn.iter().for_each(|entity| {
@@ -348,10 +444,25 @@ pub(crate) fn prepare_rollback_prespawn<C: SyncComponent, P: Protocol>(
         }
     });
 
-    for (prespawned_entity, predicted_component, mut predicted_history, mut correction) in
-        predicted_query.iter_mut()
+    for (
+        prespawned_entity,
+        predicted_component,
+        mut predicted_history,
+        mut correction,
+        eligibility,
+        predicted_only,
+    ) in predicted_query.iter_mut()
     {
-        if entities_to_despawn.contains(&prespawned_entity) {
+        if entities_to_despawn.contains(&prespawned_entity)
+            || rollback.is_excluded(prespawned_entity, eligibility)
+        {
+            continue;
+        }
+        // predicted-only entities that didn't exist at the rollback tick are despawned in `prepare_rollback_predicted_only`
+        if predicted_only
+            .and_then(|predicted_only| predicted_only.spawn_tick)
+            .is_some_and(|spawn_tick| spawn_tick > rollback_tick)
+        {
             continue;
         }
 
@@ -418,6 +529,146 @@ pub(crate) fn prepare_rollback_prespawn<C: SyncComponent, P: Protocol>(
     }
 }
 
+/// Compute the list of entities that will be rolled back, and record how many entities will be resimulated.
+///
+/// For partial rollbacks, the entities that mispredicted are extended with all the entities that share
+/// a [`RollbackIsland`] with them.
+/// The entities that are not resimulated are marked with [`DisabledDuringRollback`].
+#[allow(clippy::type_complexity)]
+pub(crate) fn collect_rollback_entities(
+    mut commands: Commands,
+    mut rollback: ResMut<Rollback>,
+    query: Query<
+        (
+            Entity,
+            Option<&RollbackIsland>,
+            Option<&RollbackEligibility>,
+        ),
+        Or<(
+            With<Predicted>,
+            With<PreSpawnedPlayerObject>,
+            With<PredictedOnly>,
+        )>,
+    >,
+    mut diagnostics: Diagnostics,
+) {
+    if rollback.partial {
+        let islands: HashSet<RollbackIsland> = rollback
+            .entities
+            .iter()
+            .filter_map(|entity| query.get(*entity).ok())
+            .filter_map(|(_, island, _)| island.copied())
+            .collect();
+        if !islands.is_empty() {
+            for (entity, island, _) in query.iter() {
+                if island.is_some_and(|island| islands.contains(island)) {
+                    rollback.entities.insert(entity);
+                }
+            }
+        }
+    }
+    let mut num_entities = 0;
+    for (entity, _, eligibility) in query.iter() {
+        if rollback.is_excluded(entity, eligibility) {
+            commands.entity(entity).insert(DisabledDuringRollback);
+        } else {
+            num_entities += 1;
+        }
+    }
+    debug!(?num_entities, partial = ?rollback.partial, "Entities resimulated during rollback");
+    diagnostics.add_measurement(&PredictionDiagnosticsPlugin::ROLLBACK_ENTITIES, || {
+        num_entities as f64
+    });
+    #[cfg(feature = "metrics")]
+    {
+        metrics::histogram!("rollback_entities").record(num_entities as f64);
+    }
+}
+
+/// Despawn the [`PredictedOnly`] entities that were spawned after the rollback tick.
+/// They will be spawned again during the rollback resimulation.
+pub(crate) fn prepare_rollback_predicted_only(
+    mut commands: Commands,
+    query: Query<(Entity, &PredictedOnly, Option<&RollbackEligibility>)>,
+    rollback: Res<Rollback>,
+) {
+    let RollbackState::ShouldRollback {
+        current_tick: rollback_tick_plus_one,
+    } = rollback.state
+    else {
+        error!("prepare_rollback_predicted_only should only be called when we are in rollback");
+        return;
+    };
+    let rollback_tick = rollback_tick_plus_one - 1;
+    for (entity, predicted_only, eligibility) in query.iter() {
+        if rollback.is_excluded(entity, eligibility) {
+            continue;
+        }
+        if predicted_only
+            .spawn_tick
+            .is_some_and(|spawn_tick| spawn_tick > rollback_tick)
+        {
+            debug!(
+                ?entity,
+                "deleting predicted-only entity because it was created after the rollback tick"
+            );
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Synced component of an entity that is not resimulated during the current rollback.
+///
+/// The component is taken out of the entity for the duration of the rollback, so that the systems that run
+/// during the rollback resimulation don't apply the same ticks to it a second time.
+#[derive(Component, Debug)]
+pub(crate) struct StashedDuringRollback<C>(C);
+
+/// Remove the synced component `C` from the entities that are not resimulated during the rollback, and
+/// stash it until the end of the rollback.
+///
+/// Entities that are replicated by the client keep their components (the removal would be replicated),
+/// systems should filter them with [`DisabledDuringRollback`] instead.
+#[allow(clippy::type_complexity)]
+pub(crate) fn stash_rollback_disabled<C: SyncComponent, P: Protocol>(
+    mut commands: Commands,
+    rollback: Res<Rollback>,
+    query: Query<
+        (Entity, &C, Option<&RollbackEligibility>),
+        (
+            Or<(
+                With<Predicted>,
+                With<PreSpawnedPlayerObject>,
+                With<PredictedOnly>,
+            )>,
+            Without<Replicate<P>>,
+        ),
+    >,
+) {
+    for (entity, component, eligibility) in query.iter() {
+        if rollback.is_excluded(entity, eligibility) {
+            trace!(?entity, component = ?std::any::type_name::<C>(), "stashing component during rollback");
+            commands
+                .entity(entity)
+                .remove::<C>()
+                .insert(StashedDuringRollback(component.clone()));
+        }
+    }
+}
+
+/// Put back the components that were stashed during the rollback
+pub(crate) fn restore_rollback_disabled<C: SyncComponent>(
+    mut commands: Commands,
+    query: Query<(Entity, &StashedDuringRollback<C>)>,
+) {
+    for (entity, stashed) in query.iter() {
+        commands
+            .entity(entity)
+            .remove::<StashedDuringRollback<C>>()
+            .insert(stashed.0.clone());
+    }
+}
+
 pub(crate) fn run_rollback(world: &mut World) {
     let tick_manager = world.get_resource::<TickManager>().unwrap();
     let rollback = world.get_resource::<Rollback>().unwrap();
@@ -448,10 +699,19 @@ pub(crate) fn run_rollback(world: &mut World) {
         }
         debug!("Finished rollback. Current tick: {:?}", current_tick);
     }
+}
 
+pub(crate) fn end_rollback(
+    mut commands: Commands,
+    mut rollback: ResMut<Rollback>,
+    disabled: Query<Entity, With<DisabledDuringRollback>>,
+) {
     // revert the state of Rollback for the next frame
-    let mut rollback = world.get_resource_mut::<Rollback>().unwrap();
     rollback.state = RollbackState::Default;
+    rollback.entities.clear();
+    for entity in disabled.iter() {
+        commands.entity(entity).remove::<DisabledDuringRollback>();
+    }
 }
 
 pub(crate) fn increment_rollback_tick(mut rollback: ResMut<Rollback>) {
@@ -905,3 +1165,320 @@ pub(crate) fn increment_rollback_tick(mut rollback: ResMut<Rollback>) {
 //         Ok(())
 //     }
 // }
+
+#[cfg(test)]
+mod rollback_eligibility_tests {
+    use bevy::diagnostic::DiagnosticsStore;
+    use bevy::prelude::{Component, FixedUpdate, Query, Without};
+    use bevy::utils::Duration;
+
+    use crate::client::prediction::diagnostics::PredictionDiagnosticsPlugin;
+    use crate::client::prediction::{Rollback, RollbackState};
+    use crate::prelude::client::*;
+    use crate::prelude::*;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    fn increment(mut query: Query<&mut Component1>) {
+        for mut component in query.iter_mut() {
+            component.0 += 1.0;
+        }
+    }
+
+    /// State that is not synced with the server, so it is not restored during rollbacks
+    #[derive(Component, Debug, Default)]
+    struct Counter(u32);
+
+    fn count(mut query: Query<&mut Counter, Without<DisabledDuringRollback>>) {
+        for mut counter in query.iter_mut() {
+            counter.0 += 1;
+        }
+    }
+
+    /// Entities that are predicted-only should be restored and resimulated during rollback,
+    /// and entities that are excluded from rollback should not be affected by it
+    #[test]
+    fn test_rollback_predicted_only_and_excluded() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+        stepper.client_app.add_systems(FixedUpdate, increment);
+
+        let predicted_only = stepper
+            .client_app
+            .world
+            .spawn((Component1(0.0), PredictedOnly::default()))
+            .id();
+        let excluded = stepper
+            .client_app
+            .world
+            .spawn((
+                Component1(0.0),
+                PredictedOnly::default(),
+                RollbackEligibility::Excluded,
+            ))
+            .id();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let get_value = |stepper: &BevyStepper, entity| {
+            stepper
+                .client_app
+                .world
+                .get::<Component1>(entity)
+                .unwrap()
+                .0
+        };
+        let predicted_only_value = get_value(&stepper, predicted_only);
+        let excluded_value = get_value(&stepper, excluded);
+
+        // trigger a rollback 3 ticks in the past
+        let current_tick = stepper.client_app.world.resource::<TickManager>().tick();
+        stepper.client_app.world.resource_mut::<Rollback>().state = RollbackState::ShouldRollback {
+            current_tick: current_tick - 2,
+        };
+        stepper.frame_step();
+
+        // the predicted-only entity got restored and resimulated, so the rollback is invisible
+        assert_eq!(
+            get_value(&stepper, predicted_only),
+            predicted_only_value + 1.0
+        );
+        // the excluded entity was not modified by the rollback
+        assert_eq!(get_value(&stepper, excluded), excluded_value + 1.0);
+        assert!(matches!(
+            stepper.client_app.world.resource::<Rollback>().state,
+            RollbackState::Default
+        ));
+    }
+
+    /// With partial rollback, only the entities that mispredicted (and the entities in the same island)
+    /// are resimulated: the other entities are not affected by the rollback at all, even their non-synced state
+    #[test]
+    fn test_partial_rollback() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default()
+            .disable(false)
+            .partial_rollback(true);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+        stepper
+            .client_app
+            .add_systems(FixedUpdate, (increment, count));
+
+        let mut spawn = |island| {
+            stepper
+                .client_app
+                .world
+                .spawn((
+                    Component1(0.0),
+                    Counter::default(),
+                    PredictedOnly::default(),
+                    RollbackIsland(island),
+                ))
+                .id()
+        };
+        let mispredicted = spawn(0);
+        let same_island = spawn(0);
+        let other_island = spawn(1);
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let get_value = |stepper: &BevyStepper, entity| {
+            stepper
+                .client_app
+                .world
+                .get::<Component1>(entity)
+                .unwrap()
+                .0
+        };
+        let get_counter = |stepper: &BevyStepper, entity| {
+            stepper.client_app.world.get::<Counter>(entity).unwrap().0
+        };
+        let value = get_value(&stepper, mispredicted);
+        let counter = get_counter(&stepper, mispredicted);
+
+        // trigger a rollback for one entity
+        let current_tick = stepper.client_app.world.resource::<TickManager>().tick();
+        let mut rollback = stepper.client_app.world.resource_mut::<Rollback>();
+        rollback.state = RollbackState::ShouldRollback {
+            current_tick: current_tick - 2,
+        };
+        rollback.entities.insert(mispredicted);
+        stepper.frame_step();
+
+        // only the entities in the island of the mispredicted entity were resimulated
+        let diagnostics = stepper.client_app.world.resource::<DiagnosticsStore>();
+        assert_eq!(
+            diagnostics
+                .get(&PredictionDiagnosticsPlugin::ROLLBACK_ENTITIES)
+                .unwrap()
+                .value(),
+            Some(2.0)
+        );
+        for entity in [mispredicted, same_island, other_island] {
+            assert_eq!(get_value(&stepper, entity), value + 1.0);
+        }
+        // the non-synced state of the resimulated entities went through the 3 rollback ticks and the new tick,
+        // the entity in the other island only went through the new tick
+        for entity in [mispredicted, same_island] {
+            assert_eq!(get_counter(&stepper, entity), counter + 4);
+        }
+        assert_eq!(get_counter(&stepper, other_island), counter + 1);
+        assert!(!stepper
+            .client_app
+            .world
+            .entity(other_island)
+            .contains::<DisabledDuringRollback>());
+        assert!(stepper
+            .client_app
+            .world
+            .resource::<Rollback>()
+            .entities
+            .is_empty());
+    }
+}
+
+#[cfg(test)]
+mod quantization_tests {
+    use bevy::diagnostic::DiagnosticsStore;
+    use bevy::prelude::{FixedUpdate, Query, With};
+    use bevy::utils::Duration;
+
+    use crate::client::prediction::diagnostics::PredictionDiagnosticsPlugin;
+    use crate::prelude::client::*;
+    use crate::prelude::*;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    const VALUE: f32 = 1.2345;
+
+    /// The client predicts the exact value that the server computes
+    fn predict(mut query: Query<&mut Component5, With<Predicted>>) {
+        for mut component in query.iter_mut() {
+            component.0 = VALUE;
+        }
+    }
+
+    /// Update another component so that the server keeps sending updates for the entity
+    fn increment(mut query: Query<&mut Component2, With<Replicate>>) {
+        for mut component in query.iter_mut() {
+            component.0 += 1.0;
+        }
+    }
+
+    /// The server sends the component with a lower precision; the precision loss should not
+    /// be considered as a misprediction
+    #[test]
+    fn test_quantization_does_not_trigger_rollback() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+        stepper.client_app.add_systems(FixedUpdate, predict);
+        stepper.server_app.add_systems(FixedUpdate, increment);
+
+        stepper.server_app.world.spawn((
+            Component5(VALUE),
+            Component2(0.0),
+            Replicate {
+                prediction_target: NetworkTarget::All,
+                ..Default::default()
+            },
+        ));
+        // the predicted entity rolls back once when it is spawned, because it has no history yet
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let num_rollbacks = |stepper: &BevyStepper| {
+            stepper
+                .client_app
+                .world
+                .resource::<DiagnosticsStore>()
+                .get(&PredictionDiagnosticsPlugin::ROLLBACK_ENTITIES)
+                .unwrap()
+                .measurements()
+                .count()
+        };
+        let initial_rollbacks = num_rollbacks(&stepper);
+        for _ in 0..20 {
+            stepper.frame_step();
+        }
+        let predicted = stepper
+            .client_app
+            .world
+            .query_filtered::<&Component5, With<Predicted>>()
+            .single(&stepper.client_app.world)
+            .clone();
+        assert_eq!(predicted, Component5(VALUE));
+        // the confirmed value lost some precision
+        let confirmed = stepper
+            .client_app
+            .world
+            .query_filtered::<&Component5, With<Confirmed>>()
+            .single(&stepper.client_app.world)
+            .clone();
+        assert_ne!(confirmed, predicted);
+        assert!((confirmed.0 - VALUE).abs() <= 0.005);
+
+        assert_eq!(num_rollbacks(&stepper), initial_rollbacks);
+    }
+}
@@ -20,6 +20,7 @@ use crate::packet::message::Message;
 use crate::prelude::client::NetConfig;
 use crate::prelude::{generate_key, NetworkTarget};
 use crate::protocol::channel::ChannelKind;
+use crate::protocol::fingerprint::ProtocolFingerprint;
 use crate::protocol::Protocol;
 use crate::shared::events::connection::ConnectionEvents;
 use crate::shared::replication::components::{Replicate, ReplicationGroupId};
@@ -67,6 +68,7 @@ pub struct ClientMut<'w, 's, P: Protocol> {
 pub fn connect_with_token(world: &mut World, connect_token: ConnectToken) -> Result<()> {
     // remove the existing ClientConnection
     world.remove_resource::<ClientConnection>();
+    let fingerprint = *world.resource::<ProtocolFingerprint>();
     world.resource_scope(|world, mut config: Mut<ClientConfig>| {
         // update the authentication token
         match &mut config.net {
@@ -77,7 +79,7 @@ pub fn connect_with_token(world: &mut World, connect_token: ConnectToken) -> Res
                 panic!("Invalid netcode config");
             }
         }
-        let netclient = config.net.clone().build_client();
+        let netclient = config.net.clone().build_client(fingerprint.0);
         world.insert_resource(netclient);
     });
     world.resource_mut::<ClientConnection>().connect()
@@ -353,6 +355,19 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
         Ok(())
     }
 
+    fn prepare_entity_detach(
+        &mut self,
+        entity: Entity,
+        replicate: &Replicate<P>,
+        target: NetworkTarget,
+        system_current_tick: BevyTick,
+    ) -> Result<()> {
+        let group_id = replicate.replication_group.group_id(Some(entity));
+        self.replication_sender
+            .prepare_entity_detach(entity, group_id);
+        Ok(())
+    }
+
     fn prepare_component_insert(
         &mut self,
         entity: Entity,
@@ -400,6 +415,21 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
         Ok(())
     }
 
+    fn prepare_component_detach(
+        &mut self,
+        entity: Entity,
+        component_kind: P::ComponentKinds,
+        replicate: &Replicate<P>,
+        target: NetworkTarget,
+        system_current_tick: BevyTick,
+    ) -> Result<()> {
+        let group_id = replicate.replication_group.group_id(Some(entity));
+        debug!(?entity, ?component_kind, "Sending DetachComponent");
+        self.replication_sender
+            .prepare_component_detach(entity, group_id, component_kind);
+        Ok(())
+    }
+
     fn prepare_entity_update(
         &mut self,
         entity: Entity,
@@ -450,35 +480,8 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
     }
     fn cleanup(&mut self, tick: Tick) {
         debug!("Running replication clean");
-        // if it's been enough time since we last any action for the group, we can set the last_action_tick to None
-        // (meaning that there's no need when we receive the update to check if we have already received a previous action)
-        for group_channel in self.replication_sender.group_channels.values_mut() {
-            debug!("Checking group channel: {:?}", group_channel);
-            if let Some(last_action_tick) = group_channel.last_action_tick {
-                if tick - last_action_tick > (i16::MAX / 2) {
-                    debug!(
-                    ?tick,
-                    ?last_action_tick,
-                    ?group_channel,
-                    "Setting the last_action tick to None because there hasn't been any new actions in a while");
-                    group_channel.last_action_tick = None;
-                }
-            }
-        }
-        // if it's been enough time since we last had any update for the group, we update the latest_tick for the group
-        for group_channel in self.replication_receiver.group_channels.values_mut() {
-            debug!("Checking group channel: {:?}", group_channel);
-            if let Some(latest_tick) = group_channel.latest_tick {
-                if tick - latest_tick > (i16::MAX / 2) {
-                    debug!(
-                    ?tick,
-                    ?latest_tick,
-                    ?group_channel,
-                    "Setting the latest_tick tick to tick because there hasn't been any new updates in a while");
-                    group_channel.latest_tick = Some(tick);
-                }
-            }
-        }
+        self.replication_sender.cleanup(tick);
+        self.replication_receiver.cleanup(tick);
     }
 }
 
@@ -15,6 +15,13 @@ use crate::shared::tick_manager::{Tick, TickEvent};
 use crate::shared::time_manager::{TimeManager, WrappedTime};
 use crate::utils::ready_buffer::ReadyBuffer;
 
+/// Smoothing applied to the estimate of how late server updates arrive, when the new sample is lower
+/// than the current estimate (the estimate increases immediately, but decreases slowly)
+const SERVER_UPDATE_LATENESS_SMOOTHING: f32 = 0.95;
+
+/// Packet loss above which the adaptive interpolation delay stops growing with the packet loss
+const MAX_PACKET_LOSS: f32 = 0.5;
+
 /// Run condition to run systems only if the client is synced
 pub fn client_is_synced<P: Protocol>(connection: Res<ConnectionManager<P>>) -> bool {
     connection.sync_manager.is_synced()
@@ -99,6 +106,13 @@ pub struct SyncManager {
     server_time_estimate: WrappedTime,
     pub(crate) interpolation_time: WrappedTime,
     interpolation_speed_ratio: f32,
+    /// How long we waited for the latest server update with a new tick (not yet taken into account
+    /// for the adaptive interpolation delay)
+    latest_server_update_gap: Option<Duration>,
+    /// Estimate of how late server updates arrive compared to the server send_interval
+    server_update_lateness: Duration,
+    /// Margin added to the server send_interval to compute the adaptive interpolation delay
+    interpolation_margin: Duration,
 
     // ticks
     // TODO: see if this is correct; should we instead attach the tick on every update message?
@@ -125,6 +139,9 @@ impl SyncManager {
             server_time_estimate: WrappedTime::default(),
             interpolation_time: WrappedTime::default(),
             interpolation_speed_ratio: 1.0,
+            latest_server_update_gap: None,
+            server_update_lateness: Duration::default(),
+            interpolation_margin: Duration::default(),
             // server tick
             latest_received_server_tick: None,
             duration_since_latest_received_server_tick: Duration::default(),
@@ -145,6 +162,7 @@ impl SyncManager {
         ping_manager: &PingManager,
         interpolation_delay: &InterpolationDelay,
         server_send_interval: Duration,
+        packet_loss: f32,
     ) -> Option<TickEvent> {
         // TODO: we are in PostUpdate, so this seems incorrect? this uses the previous-frame's delta,
         //  but instead we want to add the duration since the start of frame?
@@ -155,6 +173,10 @@ impl SyncManager {
         // check if we are ready to finalize the handshake
         if !self.synced && ping_manager.sync_stats.len() >= self.config.handshake_pings as usize {
             self.synced = true;
+            // the adaptive interpolation delay starts from the configured delay
+            self.interpolation_margin = interpolation_delay
+                .to_duration(server_send_interval)
+                .saturating_sub(server_send_interval);
             self.interpolation_time = self.interpolation_objective(
                 interpolation_delay,
                 server_send_interval,
@@ -168,11 +190,94 @@ impl SyncManager {
         }
 
         if self.synced {
+            if interpolation_delay.adaptive {
+                self.update_interpolation_margin(
+                    interpolation_delay,
+                    server_send_interval,
+                    ping_manager.jitter(),
+                    packet_loss,
+                    time_manager.delta(),
+                );
+            }
             self.update_interpolation_time(interpolation_delay, server_send_interval, tick_manager);
         }
         None
     }
 
+    /// Record how long we waited for a server update with a new tick, to adapt the interpolation delay.
+    /// Needs to be called before `duration_since_latest_received_server_tick` is reset.
+    pub(crate) fn record_server_update_gap(&mut self) {
+        let gap = self.duration_since_latest_received_server_tick;
+        self.latest_server_update_gap = Some(
+            self.latest_server_update_gap
+                .map_or(gap, |previous_gap| std::cmp::max(previous_gap, gap)),
+        );
+    }
+
+    /// Update the margin used by the adaptive interpolation delay, from the measured jitter and packet loss,
+    /// and from how late the server updates arrive.
+    ///
+    /// The margin changes progressively, so that the interpolation time can follow the objective by
+    /// adjusting its speed, instead of snapping to it.
+    fn update_interpolation_margin(
+        &mut self,
+        interpolation_delay: &InterpolationDelay,
+        server_send_interval: Duration,
+        jitter: Duration,
+        packet_loss: f32,
+        delta: Duration,
+    ) {
+        if let Some(gap) = self.latest_server_update_gap.take() {
+            let lateness = gap.saturating_sub(server_send_interval);
+            self.server_update_lateness = if lateness > self.server_update_lateness {
+                lateness
+            } else {
+                self.server_update_lateness
+                    .mul_f32(SERVER_UPDATE_LATENESS_SMOOTHING)
+                    + lateness.mul_f32(1.0 - SERVER_UPDATE_LATENESS_SMOOTHING)
+            };
+        }
+        // with a packet loss of p, we expect p / (1 - p) server updates to be lost in a row
+        // before one arrives, so we keep enough margin to wait for them
+        let packet_loss = packet_loss.clamp(0.0, MAX_PACKET_LOSS);
+        let loss_margin = server_send_interval.mul_f32(packet_loss / (1.0 - packet_loss));
+        let target = std::cmp::max(
+            jitter.mul_f32(interpolation_delay.jitter_multiple_margin),
+            self.server_update_lateness,
+        ) + loss_margin;
+        let max_change = delta.mul_f32(interpolation_delay.adaptation_rate.max(0.0));
+        self.interpolation_margin = if target > self.interpolation_margin {
+            std::cmp::min(target, self.interpolation_margin + max_change)
+        } else {
+            std::cmp::max(target, self.interpolation_margin.saturating_sub(max_change))
+        };
+        trace!(
+            ?target,
+            margin = ?self.interpolation_margin,
+            lateness = ?self.server_update_lateness,
+            "updated adaptive interpolation margin"
+        );
+    }
+
+    /// How much behind the latest server update we want the interpolation time to be
+    pub(crate) fn interpolation_delay(
+        &self,
+        interpolation_delay: &InterpolationDelay,
+        server_send_interval: Duration,
+    ) -> Duration {
+        if interpolation_delay.adaptive {
+            std::cmp::max(
+                interpolation_delay.min_delay,
+                std::cmp::min(
+                    server_send_interval + self.interpolation_margin,
+                    interpolation_delay.max_delay,
+                ),
+            )
+        } else {
+            interpolation_delay.to_duration(server_send_interval)
+        }
+    }
+
     pub(crate) fn is_synced(&self) -> bool {
         self.synced
     }
@@ -190,20 +295,13 @@ impl SyncManager {
         //  let's assume that this is the case after we did tick syncing
         //  so if we are behind, that means that the client tick wrapped around.
         //  for the purposes of the sync computations, the client tick should be ahead
-        let client_tick_raw = tick_manager.tick().0 as i32;
-
-        // client can only be this behind server if it wrapped around... if that's the case, we need to update
-        // the generation to compute the time correctly
+        // the client tick can be in a different generation than the server tick if one of them wrapped around,
+        // if that's the case we need to update the generation to compute the time correctly
         // SAFETY: we only call this when we are synced, so we know that the latest_received_server_tick is not None
-        // NOTE: we only call this when we are synced, so we know that the client tick is ahead of the server tick
-        let generation = if (client_tick_raw - self.latest_received_server_tick.unwrap().0 as i32)
-            < (i16::MIN as i32)
-        {
-            debug!("client tick is one generation ahead of server tick");
-            self.server_latest_tick_generation() + 1
-        } else {
-            self.server_latest_tick_generation()
-        };
+        let generation = tick_manager.tick().generation(
+            self.latest_received_server_tick.unwrap(),
+            self.server_latest_tick_generation(),
+        );
 
         let res = WrappedTime::from_tick(
             tick_manager.tick(),
@@ -227,13 +325,12 @@ impl SyncManager {
     }
 
     fn server_latest_tick_generation(&self) -> u16 {
-        // check if the latest_server_tick has crossed a generation compared to the latest pong tick
-        if self.latest_received_server_tick.unwrap().0 < self.server_pong_tick.0 {
-            debug!("latest server tick is a generation compared to the server pong tick");
-            self.server_pong_generation + 1
-        } else {
-            self.server_pong_generation
-        }
+        // check if the latest_server_tick has crossed a generation compared to the latest pong tick.
+        // We need to compare the ticks with wrapping (the latest server tick can also be slightly
+        // older than the pong tick), otherwise the generation would be wrong around every tick wrap
+        self.latest_received_server_tick
+            .unwrap()
+            .generation(self.server_pong_tick, self.server_pong_generation)
     }
 
     /// Everytime we receive a new server update:
@@ -331,9 +428,10 @@ impl SyncManager {
         // let objective_time = self.server_time_estimate();
         // how much we want interpolation time to be behind the latest received server tick?
         // TODO: use a specified config margin + add std of time_between_server_updates?
-        let objective_delta =
-            chrono::Duration::from_std(interpolation_delay.to_duration(server_send_interval))
-                .unwrap();
+        let objective_delta = chrono::Duration::from_std(
+            self.interpolation_delay(interpolation_delay, server_send_interval),
+        )
+        .unwrap();
         // info!("objective_delta: {:?}", objective_delta);
         self.server_time_estimate() - objective_delta
     }
@@ -569,6 +667,127 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_adaptive_interpolation_delay() {
+        let send_interval = Duration::from_millis(100);
+        let frame = Duration::from_millis(10);
+        let interpolation_delay = client::InterpolationDelay::default().with_adaptive(true);
+        let mut sync_manager = SyncManager::new(SyncConfig::default(), 0);
+        assert_eq!(
+            sync_manager.interpolation_delay(&interpolation_delay, send_interval),
+            send_interval
+        );
+
+        // a server update arrives 50ms late: the delay grows, but progressively
+        sync_manager.duration_since_latest_received_server_tick = Duration::from_millis(150);
+        sync_manager.record_server_update_gap();
+        sync_manager.update_interpolation_margin(
+            &interpolation_delay,
+            send_interval,
+            Duration::default(),
+            0.0,
+            frame,
+        );
+        let delay = sync_manager.interpolation_delay(&interpolation_delay, send_interval);
+        assert!(delay > send_interval);
+        assert!(delay < Duration::from_millis(150));
+        for _ in 0..500 {
+            sync_manager.update_interpolation_margin(
+                &interpolation_delay,
+                send_interval,
+                Duration::default(),
+                0.0,
+                frame,
+            );
+        }
+        assert_eq!(
+            sync_manager.interpolation_delay(&interpolation_delay, send_interval),
+            Duration::from_millis(150)
+        );
+
+        // server updates arrive on time again: the delay shrinks back
+        for _ in 0..500 {
+            sync_manager.duration_since_latest_received_server_tick = send_interval;
+            sync_manager.record_server_update_gap();
+            sync_manager.update_interpolation_margin(
+                &interpolation_delay,
+                send_interval,
+                Duration::default(),
+                0.0,
+                frame,
+            );
+        }
+        assert!(
+            sync_manager.interpolation_delay(&interpolation_delay, send_interval)
+                < Duration::from_millis(110)
+        );
+    }
+
+    /// The adaptation rate of the interpolation delay does not depend on the speedup factor
+    #[test]
+    fn test_adaptive_interpolation_delay_rate() {
+        let send_interval = Duration::from_millis(100);
+        let frame = Duration::from_millis(10);
+        let interpolation_delay = client::InterpolationDelay::default()
+            .with_adaptive(true)
+            .with_adaptation_rate(0.1);
+        let mut sync_manager = SyncManager::new(SyncConfig::default().speedup_factor(1.0), 0);
+
+        sync_manager.duration_since_latest_received_server_tick = Duration::from_millis(150);
+        sync_manager.record_server_update_gap();
+        sync_manager.update_interpolation_margin(
+            &interpolation_delay,
+            send_interval,
+            Duration::default(),
+            0.0,
+            frame,
+        );
+        let delay = sync_manager.interpolation_delay(&interpolation_delay, send_interval);
+        assert!((delay.as_secs_f32() - 0.101).abs() < 1e-5);
+    }
+
+    /// The interpolation delay grows with the packet loss, to wait for the lost server updates
+    #[test]
+    fn test_adaptive_interpolation_delay_packet_loss() {
+        let send_interval = Duration::from_millis(100);
+        let frame = Duration::from_millis(10);
+        let interpolation_delay = client::InterpolationDelay::default().with_adaptive(true);
+        let mut sync_manager = SyncManager::new(SyncConfig::default(), 0);
+        for _ in 0..500 {
+            sync_manager.update_interpolation_margin(
+                &interpolation_delay,
+                send_interval,
+                Duration::default(),
+                0.2,
+                frame,
+            );
+        }
+        // with 20% packet loss, we expect 0.25 server updates to be lost in a row
+        let delay = sync_manager.interpolation_delay(&interpolation_delay, send_interval);
+        assert!((delay.as_secs_f32() - 0.125).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_server_latest_tick_generation() {
+        let mut sync_manager = SyncManager::new(SyncConfig::default(), 0);
+        sync_manager.server_pong_generation = 3;
+        sync_manager.server_pong_tick = Tick(u16::MAX - 10);
+
+        // the latest server tick wrapped after the pong tick
+        sync_manager.latest_received_server_tick = Some(Tick(5));
+        assert_eq!(sync_manager.server_latest_tick_generation(), 4);
+
+        // the pong tick wrapped, but the latest server tick is from before the wrap
+        sync_manager.server_pong_tick = Tick(2);
+        sync_manager.latest_received_server_tick = Some(Tick(u16::MAX - 2));
+        assert_eq!(sync_manager.server_latest_tick_generation(), 2);
+
+        // a slightly older server tick in the same generation
+        sync_manager.server_pong_tick = Tick(1000);
+        sync_manager.latest_received_server_tick = Some(Tick(990));
+        assert_eq!(sync_manager.server_latest_tick_generation(), 3);
+    }
+
     #[test]
     fn test_sync_after_tick_wrap() {
         let frame_duration = Duration::from_secs_f32(1.0 / 60.0);
@@ -624,6 +843,7 @@ mod tests {
             ))
             .id();
 
+        stepper.build();
         for i in 0..200 {
             stepper.frame_step();
         }
@@ -0,0 +1,135 @@
+//! Client side of the tick events: release the events sent by the server when the client's timeline reaches their tick.
+//!
+//! See [`crate::shared::tick_event`] for more details.
+//!
+//! ```rust,ignore
+//! use lightyear::prelude::client::*;
+//!
+//! fn setup(app: &mut App) {
+//!     // play the explosions when the interpolated entities reach the tick of the explosion
+//!     app.add_plugins(TickEventPlugin::<MyProtocol, Explosion>::new(TickEventTimeline::Interpolation));
+//! }
+//!
+//! fn play_explosions(mut events: EventReader<TickEvent<Explosion>>) {
+//!     for event in events.read() {
+//!         info!(tick = ?event.tick(), "explosion at {:?}", event.event().position);
+//!     }
+//! }
+//! ```
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    App, EventReader, EventWriter, FixedPreUpdate, IntoSystemConfigs, Plugin, PreUpdate, Res,
+    ResMut,
+};
+
+use crate::client::connection::ConnectionManager;
+use crate::client::events::MessageEvent;
+use crate::prelude::MainSet;
+use crate::protocol::Protocol;
+use crate::shared::tick_event::{
+    register_tick_event, NetworkedEvent, TickEventBuffer, TickEventMessage,
+};
+use crate::shared::tick_manager::TickManager;
+
+/// Bevy [`Event`](bevy::prelude::Event) emitted on the client when the tick of an event sent by the server is reached
+pub type TickEvent<E> = crate::shared::tick_event::TickEvent<E, ()>;
+
+/// Timeline of the client that is used to release the tick events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TickEventTimeline {
+    /// Release the events when the interpolation tick reaches their tick, so that they line up with the
+    /// interpolated entities.
+    /// The events are emitted in `PreUpdate`
+    #[default]
+    Interpolation,
+    /// Release the events when the current (predicted) tick reaches their tick.
+    /// The events are emitted in `FixedPreUpdate`, so that the `FixedUpdate` systems of that tick can handle them.
+    ///
+    /// Because the client is ahead of the server, the events sent by the server are usually released as soon as they
+    /// are received
+    Prediction,
+}
+
+/// Plugin that emits the events `E` sent by the server as [`TickEvent`]s at the right point of the client's timeline
+pub struct TickEventPlugin<P, E> {
+    timeline: TickEventTimeline,
+    _marker: PhantomData<(P, E)>,
+}
+
+impl<P, E> TickEventPlugin<P, E> {
+    pub fn new(timeline: TickEventTimeline) -> Self {
+        Self {
+            timeline,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, E> Default for TickEventPlugin<P, E> {
+    fn default() -> Self {
+        Self::new(TickEventTimeline::default())
+    }
+}
+
+impl<P: Protocol, E: NetworkedEvent> Plugin for TickEventPlugin<P, E> {
+    fn build(&self, app: &mut App) {
+        register_tick_event::<E>(app);
+        // RESOURCES
+        app.init_resource::<TickEventBuffer<E, ()>>();
+        // EVENTS
+        app.add_event::<TickEvent<E>>();
+        // SYSTEMS
+        app.add_systems(PreUpdate, buffer_tick_events::<E>.after(MainSet::Receive));
+        match self.timeline {
+            TickEventTimeline::Interpolation => {
+                app.add_systems(
+                    PreUpdate,
+                    release_interpolation_tick_events::<P, E>.after(buffer_tick_events::<E>),
+                );
+            }
+            TickEventTimeline::Prediction => {
+                app.add_systems(FixedPreUpdate, release_prediction_tick_events::<P, E>);
+            }
+        }
+    }
+}
+
+/// Buffer the received events until their tick is reached
+fn buffer_tick_events<E: NetworkedEvent>(
+    mut messages: EventReader<MessageEvent<TickEventMessage<E>>>,
+    mut buffer: ResMut<TickEventBuffer<E, ()>>,
+) {
+    for message in messages.read() {
+        buffer.add(message.message().clone(), ());
+    }
+}
+
+/// Emit the events whose tick is reached by the interpolation tick
+fn release_interpolation_tick_events<P: Protocol, E: NetworkedEvent>(
+    tick_manager: Res<TickManager>,
+    connection: Res<ConnectionManager<P>>,
+    mut buffer: ResMut<TickEventBuffer<E, ()>>,
+    mut events: EventWriter<TickEvent<E>>,
+) {
+    // the interpolation tick is only meaningful once the client is synced with the server
+    if !connection.is_synced() {
+        return;
+    }
+    let tick = connection.sync_manager.interpolation_tick(&tick_manager);
+    events.send_batch(buffer.release(tick));
+}
+
+/// Emit the events whose tick is reached by the current tick
+fn release_prediction_tick_events<P: Protocol, E: NetworkedEvent>(
+    tick_manager: Res<TickManager>,
+    connection: Res<ConnectionManager<P>>,
+    mut buffer: ResMut<TickEventBuffer<E, ()>>,
+    mut events: EventWriter<TickEvent<E>>,
+) {
+    // the client tick is only meaningful once the client is synced with the server
+    if !connection.is_synced() {
+        return;
+    }
+    events.send_batch(buffer.release(tick_manager.tick()));
+}
@@ -5,7 +5,7 @@ use bevy::prelude::Resource;
 
 use crate::_reexport::ReadWordBuffer;
 use crate::client::config::NetcodeConfig;
-use crate::connection::netcode::ClientId;
+use crate::connection::netcode::{ClientId, DisconnectReason};
 use crate::prelude::client::Authentication;
 use crate::prelude::{Io, IoConfig};
 
@@ -16,9 +16,24 @@ pub trait NetClient: Send + Sync {
     /// Connect to server
     fn connect(&mut self) -> Result<()>;
 
+    /// Disconnect from the server
+    ///
+    /// By default, the client doesn't support disconnecting and returns an error
+    fn disconnect(&mut self) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "this client does not support disconnecting"
+        ))
+    }
+
     /// Returns true if the client is connected to the server
     fn is_connected(&self) -> bool;
 
+    /// Returns the reason why the connection could not be established or was lost, if any
+    fn connection_error(&self) -> Option<ConnectionError>;
+
+    /// Returns the reason of the last disconnection (if the client got disconnected since the last call), and clears it
+    fn take_disconnect_reason(&mut self) -> Option<DisconnectReason>;
+
     /// Update the connection state + internal bookkeeping (keep-alives, etc.)
     fn try_update(&mut self, delta_ms: f64) -> Result<()>;
 
@@ -28,6 +43,30 @@ pub trait NetClient: Send + Sync {
     /// Send a packet to the server
     fn send(&mut self, buf: &[u8]) -> Result<()>;
 
+    /// Receive a packet that the server sent on the reliable ordered stream of the transport
+    ///
+    /// By default, there is no reliable stream and this returns None
+    fn recv_reliable(&mut self) -> Option<ReadWordBuffer> {
+        None
+    }
+
+    /// Send a packet to the server on the reliable ordered stream of the transport
+    ///
+    /// By default, the packet is sent like any other packet
+    fn send_reliable(&mut self, buf: &[u8]) -> Result<()> {
+        self.send(buf)
+    }
+
+    /// Returns true if the transport has a reliable ordered stream to the server (for example WebTransport)
+    fn has_reliable_stream(&self) -> bool {
+        false
+    }
+
+    /// Set the fingerprint of the protocol, which is sent to the server when connecting.
+    ///
+    /// By default, the client doesn't send the fingerprint of the protocol and this does nothing
+    fn set_protocol_hash(&mut self, _protocol_hash: u64) {}
+
     /// Get the id of the client
     fn id(&self) -> ClientId;
 
@@ -41,6 +80,19 @@ pub trait NetClient: Send + Sync {
     fn io_mut(&mut self) -> Option<&mut Io>;
 }
 
+/// Reason why the client could not connect to the server, or lost its connection
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionError {
+    #[error("the connect token expired")]
+    TokenExpired,
+    #[error("the connection timed out")]
+    TimedOut,
+    #[error("the server denied the connection request")]
+    Denied,
+    #[error("the client protocol (fingerprint {protocol_hash:#x}) does not match the server protocol; make sure that the client and server use the same protocol and shared config")]
+    ProtocolMismatch { protocol_hash: u64 },
+}
+
 /// Resource that holds the client connection
 #[derive(Resource)]
 pub struct ClientConnection {
@@ -71,7 +123,10 @@ impl Default for NetConfig {
 }
 
 impl NetConfig {
-    pub fn build_client(self) -> ClientConnection {
+    /// Build the client connection.
+    ///
+    /// `protocol_hash` is the [`ProtocolFingerprint`](crate::protocol::ProtocolFingerprint) sent to the server during the handshake
+    pub fn build_client(self, protocol_hash: u64) -> ClientConnection {
         match self {
             NetConfig::Netcode {
                 auth,
@@ -83,9 +138,11 @@ impl NetConfig {
                     .get_token(config.client_timeout_secs)
                     .expect("could not generate token");
                 let token_bytes = token.try_into_bytes().unwrap();
-                let netcode =
-                    super::netcode::NetcodeClient::with_config(&token_bytes, config.build())
-                        .expect("could not create netcode client");
+                let netcode = super::netcode::NetcodeClient::with_config(
+                    &token_bytes,
+                    config.build().protocol_hash(protocol_hash),
+                )
+                .expect("could not create netcode client");
                 let client = super::netcode::Client {
                     client: netcode,
                     io_config,
@@ -110,10 +167,22 @@ impl NetClient for ClientConnection {
         self.client.connect()
     }
 
+    fn disconnect(&mut self) -> Result<()> {
+        self.client.disconnect()
+    }
+
     fn is_connected(&self) -> bool {
         self.client.is_connected()
     }
 
+    fn connection_error(&self) -> Option<ConnectionError> {
+        self.client.connection_error()
+    }
+
+    fn take_disconnect_reason(&mut self) -> Option<DisconnectReason> {
+        self.client.take_disconnect_reason()
+    }
+
     fn try_update(&mut self, delta_ms: f64) -> Result<()> {
         self.client.try_update(delta_ms)
     }
@@ -126,6 +195,22 @@ impl NetClient for ClientConnection {
         self.client.send(buf)
     }
 
+    fn recv_reliable(&mut self) -> Option<ReadWordBuffer> {
+        self.client.recv_reliable()
+    }
+
+    fn send_reliable(&mut self, buf: &[u8]) -> Result<()> {
+        self.client.send_reliable(buf)
+    }
+
+    fn has_reliable_stream(&self) -> bool {
+        self.client.has_reliable_stream()
+    }
+
+    fn set_protocol_hash(&mut self, protocol_hash: u64) {
+        self.client.set_protocol_hash(protocol_hash)
+    }
+
     fn id(&self) -> ClientId {
         self.client.id()
     }
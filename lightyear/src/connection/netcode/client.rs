@@ -6,7 +6,7 @@ use anyhow::Context;
 use bevy::prelude::Resource;
 use tracing::{debug, error, info, trace};
 
-use crate::connection::client::NetClient;
+use crate::connection::client::{ConnectionError, NetClient};
 use crate::prelude::IoConfig;
 use crate::serialize::reader::ReadBuffer;
 use crate::serialize::wordbuffer::reader::ReadWordBuffer;
@@ -17,11 +17,12 @@ use super::{
     bytes::Bytes,
     error::{Error, Result},
     packet::{
-        DisconnectPacket, KeepAlivePacket, Packet, PayloadPacket, RequestPacket, ResponsePacket,
+        DisconnectPacket, DisconnectReason, KeepAlivePacket, Packet, PayloadPacket, RequestPacket,
+        ResponsePacket,
     },
     replay::ReplayProtection,
     token::{ChallengeToken, ConnectToken},
-    utils, ClientId, MAX_PACKET_SIZE, MAX_PKT_BUF_SIZE, PACKET_SEND_RATE_SEC,
+    utils, ClientId, MAX_PACKET_SIZE, MAX_PKT_BUF_SIZE, PACKET_SEND_RATE_SEC, STREAM_SEQUENCE_BIT,
 };
 
 type Callback<Ctx> = Box<dyn FnMut(ClientState, ClientState, &mut Ctx) + Send + Sync + 'static>;
@@ -30,6 +31,7 @@ type Callback<Ctx> = Box<dyn FnMut(ClientState, ClientState, &mut Ctx) + Send +
 ///
 /// * `num_disconnect_packets` - The number of redundant disconnect packets that will be sent to a server when the clients wants to disconnect.
 /// * `packet_send_rate` - The rate at which periodic packets will be sent to the server.
+/// * `protocol_hash` - The fingerprint of the protocol, sent to the server in the connection request.
 /// * `on_state_change` - A callback that will be called when the client changes states.
 ///
 /// # Example
@@ -56,6 +58,7 @@ type Callback<Ctx> = Box<dyn FnMut(ClientState, ClientState, &mut Ctx) + Send +
 pub struct ClientConfig<Ctx> {
     num_disconnect_packets: usize,
     packet_send_rate: f64,
+    protocol_hash: u64,
     context: Ctx,
     on_state_change: Option<Callback<Ctx>>,
 }
@@ -65,6 +68,7 @@ impl Default for ClientConfig<()> {
         Self {
             num_disconnect_packets: 10,
             packet_send_rate: PACKET_SEND_RATE_SEC,
+            protocol_hash: 0,
             context: (),
             on_state_change: None,
         }
@@ -81,6 +85,7 @@ impl<Ctx> ClientConfig<Ctx> {
         Self {
             num_disconnect_packets: 10,
             packet_send_rate: PACKET_SEND_RATE_SEC,
+            protocol_hash: 0,
             context: ctx,
             on_state_change: None,
         }
@@ -97,6 +102,13 @@ impl<Ctx> ClientConfig<Ctx> {
         self.packet_send_rate = rate_seconds;
         self
     }
+    /// Set the fingerprint of the protocol used by the client.
+    /// The server will deny the connection if it doesn't match its own fingerprint.
+    /// The default is 0.
+    pub fn protocol_hash(mut self, protocol_hash: u64) -> Self {
+        self.protocol_hash = protocol_hash;
+        self
+    }
     /// Set a callback that will be called when the client changes states.
     pub fn on_state_change<F>(mut self, cb: F) -> Self
     where
@@ -144,6 +156,8 @@ pub enum ClientState {
     ChallengeResponseTimedOut,
     /// The server has denied the client's connection request, most likely due to the server being full.
     ConnectionDenied,
+    /// The server has denied the client's connection request because the client's protocol does not match the server's.
+    ProtocolMismatch,
     /// The client is disconnected from the server.
     Disconnected,
     /// The client is waiting for a response from the server after sending a connection request packet.
@@ -172,7 +186,7 @@ pub enum ClientState {
 /// # let addr =  SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
 /// # let mut io = Io::from_config(IoConfig::from_transport(TransportConfig::UdpSocket(
 /// #    addr))
-/// # );
+/// # ).unwrap();
 /// # let mut server = NetcodeServer::new(0, [0; 32]).unwrap();
 /// # let token_bytes = server.token(0, addr).generate().unwrap().try_into_bytes().unwrap();
 /// let mut client = NetcodeClient::new(&token_bytes).unwrap();
@@ -188,13 +202,18 @@ pub struct NetcodeClient<Ctx = ()> {
     last_receive_time: f64,
     server_addr_idx: usize,
     sequence: u64,
+    /// Sequence of the packets sent on the reliable stream of the transport
+    stream_sequence: u64,
     challenge_token_sequence: u64,
     challenge_token_data: [u8; ChallengeToken::SIZE],
     token: ConnectToken,
     replay_protection: ReplayProtection,
     should_disconnect: bool,
     should_disconnect_state: ClientState,
+    should_disconnect_reason: Option<DisconnectReason>,
+    disconnect_reason: Option<DisconnectReason>,
     packet_queue: VecDeque<ReadWordBuffer>,
+    stream_packet_queue: VecDeque<ReadWordBuffer>,
     cfg: ClientConfig<Ctx>,
 }
 
@@ -222,13 +241,17 @@ impl<Ctx> NetcodeClient<Ctx> {
             last_receive_time: f64::NEG_INFINITY,
             server_addr_idx: 0,
             sequence: 0,
+            stream_sequence: 0,
             challenge_token_sequence: 0,
             challenge_token_data: [0u8; ChallengeToken::SIZE],
             token,
             replay_protection: ReplayProtection::new(),
             should_disconnect: false,
             should_disconnect_state: ClientState::Disconnected,
+            should_disconnect_reason: None,
+            disconnect_reason: None,
             packet_queue: VecDeque::new(),
+            stream_packet_queue: VecDeque::new(),
             cfg,
         })
     }
@@ -305,11 +328,13 @@ impl<Ctx> NetcodeClient<Ctx> {
         self.last_receive_time = self.time;
         self.should_disconnect = false;
         self.should_disconnect_state = ClientState::Disconnected;
+        self.should_disconnect_reason = None;
         self.challenge_token_sequence = 0;
         self.replay_protection = ReplayProtection::new();
     }
     fn reset(&mut self, new_state: ClientState) {
         self.sequence = 0;
+        self.stream_sequence = 0;
         self.start_time = 0.0;
         self.server_addr_idx = 0;
         self.set_state(new_state);
@@ -325,6 +350,7 @@ impl<Ctx> NetcodeClient<Ctx> {
                 debug!("client sending connection request packet to server");
                 RequestPacket::create(
                     self.token.protocol_id,
+                    self.cfg.protocol_hash,
                     self.token.expire_timestamp,
                     self.token.nonce,
                     self.token.private_data,
@@ -352,17 +378,31 @@ impl<Ctx> NetcodeClient<Ctx> {
         Ok(())
     }
     fn send_packet(&mut self, packet: Packet, io: &mut Io) -> Result<()> {
+        self.write_packet(packet, io, false)
+    }
+    fn write_packet(&mut self, packet: Packet, io: &mut Io, reliable: bool) -> Result<()> {
         let mut buf = [0u8; MAX_PKT_BUF_SIZE];
+        let sequence = if reliable {
+            STREAM_SEQUENCE_BIT | self.stream_sequence
+        } else {
+            self.sequence
+        };
         let size = packet.write(
             &mut buf,
-            self.sequence,
+            sequence,
             &self.token.client_to_server_key,
             self.token.protocol_id,
         )?;
-        io.send(&buf[..size], &self.server_addr())
-            .map_err(Error::from)?;
+        if reliable {
+            io.send_reliable(&buf[..size], &self.server_addr())
+                .map_err(Error::from)?;
+            self.stream_sequence += 1;
+        } else {
+            io.send(&buf[..size], &self.server_addr())
+                .map_err(Error::from)?;
+            self.sequence += 1;
+        }
         self.last_send_time = self.time;
-        self.sequence += 1;
         Ok(())
     }
 
@@ -375,11 +415,22 @@ impl<Ctx> NetcodeClient<Ctx> {
         }
         match (packet, self.state) {
             (
-                Packet::Denied(_),
+                Packet::Denied(pkt),
                 ClientState::SendingConnectionRequest | ClientState::SendingChallengeResponse,
             ) => {
+                debug!(reason = %pkt.reason, "client received connection denied packet from server");
                 self.should_disconnect = true;
-                self.should_disconnect_state = ClientState::ConnectionDenied;
+                self.should_disconnect_state = match pkt.reason {
+                    DisconnectReason::ProtocolMismatch => {
+                        error!(
+                            protocol_hash = self.cfg.protocol_hash,
+                            "connection denied: the client protocol does not match the server protocol"
+                        );
+                        ClientState::ProtocolMismatch
+                    }
+                    _ => ClientState::ConnectionDenied,
+                };
+                self.should_disconnect_reason = Some(pkt.reason);
             }
             (Packet::Challenge(pkt), ClientState::SendingConnectionRequest) => {
                 debug!("client received connection challenge packet from server");
@@ -399,12 +450,17 @@ impl<Ctx> NetcodeClient<Ctx> {
             (Packet::Payload(pkt), ClientState::Connected) => {
                 trace!("client received payload packet from server");
                 let reader = ReadWordBuffer::start_read(pkt.buf);
-                self.packet_queue.push_back(reader);
+                if pkt.stream {
+                    self.stream_packet_queue.push_back(reader);
+                } else {
+                    self.packet_queue.push_back(reader);
+                }
             }
-            (Packet::Disconnect(_), ClientState::Connected) => {
-                debug!("client received disconnect packet from server");
+            (Packet::Disconnect(pkt), ClientState::Connected) => {
+                debug!(reason = %pkt.reason, "client received disconnect packet from server");
                 self.should_disconnect = true;
                 self.should_disconnect_state = ClientState::Disconnected;
+                self.should_disconnect_reason = Some(pkt.reason);
             }
             _ => return Ok(()),
         }
@@ -416,43 +472,57 @@ impl<Ctx> NetcodeClient<Ctx> {
             >= self.token.expire_timestamp as f64 - self.token.create_timestamp as f64;
         let is_connection_timed_out = self.token.timeout_seconds.is_positive()
             && (self.last_receive_time + (self.token.timeout_seconds as f64) < self.time);
-        let new_state = match self.state {
+        let (new_state, reason) = match self.state {
             ClientState::SendingConnectionRequest | ClientState::SendingChallengeResponse
                 if is_token_expired =>
             {
                 info!("client connect failed. connect token expired");
-                ClientState::ConnectTokenExpired
+                (
+                    ClientState::ConnectTokenExpired,
+                    DisconnectReason::TokenExpired,
+                )
             }
             _ if self.should_disconnect => {
                 debug!(
                     "client should disconnect -> {:?}",
                     self.should_disconnect_state
                 );
+                let reason = self
+                    .should_disconnect_reason
+                    .take()
+                    .unwrap_or(DisconnectReason::ClientDisconnected);
                 if self.connect_to_next_server().is_ok() {
                     return;
                 };
-                self.should_disconnect_state
+                (self.should_disconnect_state, reason)
             }
             ClientState::SendingConnectionRequest if is_connection_timed_out => {
                 info!("client connect failed. connection request timed out");
                 if self.connect_to_next_server().is_ok() {
                     return;
                 };
-                ClientState::ConnectionRequestTimedOut
+                (
+                    ClientState::ConnectionRequestTimedOut,
+                    DisconnectReason::Timeout,
+                )
             }
             ClientState::SendingChallengeResponse if is_connection_timed_out => {
                 info!("client connect failed. connection response timed out");
                 if self.connect_to_next_server().is_ok() {
                     return;
                 };
-                ClientState::ChallengeResponseTimedOut
+                (
+                    ClientState::ChallengeResponseTimedOut,
+                    DisconnectReason::Timeout,
+                )
             }
             ClientState::Connected if is_connection_timed_out => {
                 info!("client connection timed out");
-                ClientState::ConnectionTimedOut
+                (ClientState::ConnectionTimedOut, DisconnectReason::Timeout)
             }
             _ => return,
         };
+        self.disconnect_reason = Some(reason);
         self.reset(new_state);
     }
 
@@ -555,7 +625,7 @@ impl<Ctx> NetcodeClient<Ctx> {
     /// # let server_addr = SocketAddr::from(([127, 0, 0, 1], 40001));
     /// # let mut server = NetcodeServer::new(0, [0; 32]).unwrap();
     /// # let token_bytes = server.token(0, server_addr).generate().unwrap().try_into_bytes().unwrap();
-    /// # let mut io = Io::from_config(IoConfig::from_transport(TransportConfig::UdpSocket(client_addr)));
+    /// # let mut io = Io::from_config(IoConfig::from_transport(TransportConfig::UdpSocket(client_addr))).unwrap();
     /// let mut client = NetcodeClient::new(&token_bytes).unwrap();
     /// client.connect();
     ///
@@ -574,6 +644,11 @@ impl<Ctx> NetcodeClient<Ctx> {
         self.packet_queue.pop_front()
     }
 
+    /// Receives a packet that the server sent on the reliable ordered stream of the transport, if any.
+    pub fn recv_reliable(&mut self) -> Option<ReadWordBuffer> {
+        self.stream_packet_queue.pop_front()
+    }
+
     /// Sends a packet to the server.
     ///
     /// The provided buffer must be smaller than [`MAX_PACKET_SIZE`].
@@ -588,6 +663,21 @@ impl<Ctx> NetcodeClient<Ctx> {
         self.send_packet(PayloadPacket::create(buf), io)?;
         Ok(())
     }
+
+    /// Sends a packet to the server on the reliable ordered stream of the transport.
+    ///
+    /// The provided buffer must be smaller than [`MAX_PACKET_SIZE`].
+    pub fn send_reliable(&mut self, buf: &[u8], io: &mut Io) -> Result<()> {
+        if self.state != ClientState::Connected {
+            trace!("tried to send but not connected");
+            return Ok(());
+        }
+        if buf.len() > MAX_PACKET_SIZE {
+            return Err(Error::SizeMismatch(MAX_PACKET_SIZE, buf.len()));
+        }
+        self.write_packet(PayloadPacket::create(buf), io, true)
+    }
+
     /// Disconnects the client from the server.
     ///
     /// The client will send a number of redundant disconnect packets to the server before transitioning to `Disconnected`.
@@ -597,8 +687,12 @@ impl<Ctx> NetcodeClient<Ctx> {
             self.cfg.num_disconnect_packets
         );
         for _ in 0..self.cfg.num_disconnect_packets {
-            self.send_packet(DisconnectPacket::create(), io)?;
+            self.send_packet(
+                DisconnectPacket::create(DisconnectReason::ClientDisconnected),
+                io,
+            )?;
         }
+        self.disconnect_reason = Some(DisconnectReason::ClientDisconnected);
         self.reset(ClientState::Disconnected);
         Ok(())
     }
@@ -611,6 +705,24 @@ impl<Ctx> NetcodeClient<Ctx> {
     pub fn is_error(&self) -> bool {
         self.state < ClientState::Disconnected
     }
+    /// Returns the [`ConnectionError`] that caused the client to stop connecting, if the client is in an error state.
+    pub fn connection_error(&self) -> Option<ConnectionError> {
+        match self.state {
+            ClientState::ConnectTokenExpired => Some(ConnectionError::TokenExpired),
+            ClientState::ConnectionTimedOut
+            | ClientState::ConnectionRequestTimedOut
+            | ClientState::ChallengeResponseTimedOut => Some(ConnectionError::TimedOut),
+            ClientState::ConnectionDenied => Some(ConnectionError::Denied),
+            ClientState::ProtocolMismatch => Some(ConnectionError::ProtocolMismatch {
+                protocol_hash: self.cfg.protocol_hash,
+            }),
+            _ => None,
+        }
+    }
+    /// Returns the reason of the last disconnection, and clears it.
+    pub fn take_disconnect_reason(&mut self) -> Option<DisconnectReason> {
+        self.disconnect_reason.take()
+    }
     /// Returns true if the client is in a pending state.
     pub fn is_pending(&self) -> bool {
         self.state == ClientState::SendingConnectionRequest
@@ -636,17 +748,30 @@ pub struct Client<Ctx> {
 
 impl<Ctx: Send + Sync> NetClient for Client<Ctx> {
     fn connect(&mut self) -> anyhow::Result<()> {
-        self.io = Some(Io::from_config(self.io_config.clone()));
+        self.io = Some(Io::from_config(self.io_config.clone()).context("could not create the io")?);
         self.client.connect();
         // TODO: have a separate explicit function to start listening on the io
         // creating the io starts the io connection!
         Ok(())
     }
 
+    fn disconnect(&mut self) -> anyhow::Result<()> {
+        let io = self.io.as_mut().context("io is not initialized")?;
+        self.client.disconnect(io).context("could not disconnect")
+    }
+
     fn is_connected(&self) -> bool {
         self.client.is_connected()
     }
 
+    fn connection_error(&self) -> Option<ConnectionError> {
+        self.client.connection_error()
+    }
+
+    fn take_disconnect_reason(&mut self) -> Option<DisconnectReason> {
+        self.client.take_disconnect_reason()
+    }
+
     fn try_update(&mut self, delta_ms: f64) -> anyhow::Result<()> {
         let io = self.io.as_mut().context("io is not initialized")?;
         self.client
@@ -659,11 +784,32 @@ impl<Ctx: Send + Sync> NetClient for Client<Ctx> {
         self.client.recv()
     }
 
+    fn recv_reliable(&mut self) -> Option<ReadWordBuffer> {
+        self.client.recv_reliable()
+    }
+
     fn send(&mut self, buf: &[u8]) -> anyhow::Result<()> {
         let io = self.io.as_mut().context("io is not initialized")?;
         self.client.send(buf, io).context("could not send")
     }
 
+    fn send_reliable(&mut self, buf: &[u8]) -> anyhow::Result<()> {
+        let io = self.io.as_mut().context("io is not initialized")?;
+        self.client
+            .send_reliable(buf, io)
+            .context("could not send on the reliable stream")
+    }
+
+    fn has_reliable_stream(&self) -> bool {
+        self.io
+            .as_ref()
+            .is_some_and(|io| io.has_reliable_stream(&self.client.server_addr()))
+    }
+
+    fn set_protocol_hash(&mut self, protocol_hash: u64) {
+        self.client.cfg.protocol_hash = protocol_hash;
+    }
+
     fn id(&self) -> ClientId {
         self.client.id()
     }
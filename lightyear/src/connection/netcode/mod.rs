@@ -52,7 +52,7 @@
 
  // Create an io
  let client_addr = SocketAddr::from(([127, 0, 0, 1], 40000));
- let mut io = Io::from_config(IoConfig::from_transport(TransportConfig::UdpSocket(client_addr)));
+ let mut io = Io::from_config(IoConfig::from_transport(TransportConfig::UdpSocket(client_addr))).unwrap();
 
  // Create a server
  let protocol_id = 0x11223344;
@@ -92,7 +92,7 @@ use crate::lightyear::transport::io::Io;
 
 // Create an io
 let client_addr = SocketAddr::from(([127, 0, 0, 1], 40000));
-let mut io = Io::from_config(IoConfig::from_transport(TransportConfig::UdpSocket(client_addr)));
+let mut io = Io::from_config(IoConfig::from_transport(TransportConfig::UdpSocket(client_addr))).unwrap();
 
 // Generate a connection token for the client
 let protocol_id = 0x11223344;
@@ -126,7 +126,8 @@ loop {
 pub use client::{Client, ClientConfig, ClientState, NetcodeClient};
 pub use crypto::{generate_key, try_generate_key, Key};
 pub use error::{Error, Result};
-pub use server::{Callback, ClientId, NetcodeServer, Server, ServerConfig};
+pub use packet::{DisconnectReason, MAX_DISCONNECT_MESSAGE_BYTES};
+pub use server::{Callback, ClientId, NetcodeServer, Server, ServerConfig, MAX_CLIENTS};
 pub use token::{ConnectToken, ConnectTokenBuilder, InvalidTokenError};
 
 mod bytes;
@@ -145,6 +146,9 @@ pub(crate) const MAC_BYTES: usize = 16;
 pub(crate) const MAX_PKT_BUF_SIZE: usize = 1300;
 pub(crate) const CONNECTION_TIMEOUT_SEC: i32 = 15;
 pub(crate) const PACKET_SEND_RATE_SEC: f64 = 1.0 / 10.0;
+/// Packets sent on the reliable stream of the transport use their own sequence numbers, with this bit set,
+/// so that they never share a nonce or a replay protection window with the datagrams.
+pub(crate) const STREAM_SEQUENCE_BIT: u64 = 1 << 62;
 
 /// The size of a private key in bytes.
 pub const PRIVATE_KEY_BYTES: usize = 32;
@@ -155,4 +159,7 @@ pub const CONNECT_TOKEN_BYTES: usize = 2048;
 /// The maximum size of a packet in bytes.
 pub const MAX_PACKET_SIZE: usize = 1200;
 /// The version of the netcode protocol implemented by this crate.
-pub const NETCODE_VERSION: &[u8; 13] = b"NETCODE 1.02\0";
+///
+/// This differs from the standard netcode 1.02 because the connection request packet also contains
+/// the fingerprint of the client's protocol.
+pub const NETCODE_VERSION: &[u8; 13] = b"NETCODE 1.03\0";
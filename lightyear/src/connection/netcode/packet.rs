@@ -15,7 +15,7 @@ use super::{
     error::Error as NetcodeError,
     replay::ReplayProtection,
     token::{ChallengeToken, ConnectTokenPrivate},
-    MAC_BYTES, MAX_PKT_BUF_SIZE, NETCODE_VERSION,
+    MAC_BYTES, MAX_PKT_BUF_SIZE, NETCODE_VERSION, STREAM_SEQUENCE_BIT,
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -75,6 +75,8 @@ where
 pub struct RequestPacket {
     pub version_info: [u8; NETCODE_VERSION.len()],
     pub protocol_id: u64,
+    /// Fingerprint of the client's protocol, checked by the server against its own
+    pub protocol_hash: u64,
     pub expire_timestamp: u64,
     pub token_nonce: XNonce,
     pub token_data: Box<[u8; ConnectTokenPrivate::SIZE]>,
@@ -83,6 +85,7 @@ pub struct RequestPacket {
 impl RequestPacket {
     pub fn create(
         protocol_id: u64,
+        protocol_hash: u64,
         expire_timestamp: u64,
         token_nonce: XNonce,
         token_data: [u8; ConnectTokenPrivate::SIZE],
@@ -90,6 +93,7 @@ impl RequestPacket {
         Packet::Request(RequestPacket {
             version_info: *NETCODE_VERSION,
             protocol_id,
+            protocol_hash,
             expire_timestamp,
             token_nonce,
             token_data: Box::new(token_data),
@@ -130,6 +134,7 @@ impl Bytes for RequestPacket {
     fn write_to(&self, writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
         writer.write_all(&self.version_info)?;
         writer.write_u64::<LittleEndian>(self.protocol_id)?;
+        writer.write_u64::<LittleEndian>(self.protocol_hash)?;
         writer.write_u64::<LittleEndian>(self.expire_timestamp)?;
         writer.write_all(&self.token_nonce)?;
         writer.write_all(&self.token_data[..])?;
@@ -140,6 +145,7 @@ impl Bytes for RequestPacket {
         let mut version_info = [0; NETCODE_VERSION.len()];
         reader.read_exact(&mut version_info)?;
         let protocol_id = reader.read_u64::<LittleEndian>()?;
+        let protocol_hash = reader.read_u64::<LittleEndian>()?;
         let expire_timestamp = reader.read_u64::<LittleEndian>()?;
         let mut nonce = [0; size_of::<XNonce>()];
         reader.read_exact(&mut nonce)?;
@@ -149,6 +155,7 @@ impl Bytes for RequestPacket {
         Ok(Self {
             version_info,
             protocol_id,
+            protocol_hash,
             expire_timestamp,
             token_nonce,
             token_data: Box::new(token_data),
@@ -156,22 +163,128 @@ impl Bytes for RequestPacket {
     }
 }
 
-pub struct DeniedPacket {}
+/// Maximum number of bytes of the message attached to [`DisconnectReason::Kicked`]
+pub const MAX_DISCONNECT_MESSAGE_BYTES: usize = 256;
+
+/// The reason why a client got disconnected, or why its connection request was denied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The client disconnected on its own
+    ClientDisconnected,
+    /// No packets were received for longer than the timeout
+    Timeout,
+    /// The server kicked the client, with a custom message
+    Kicked(String),
+    /// The server has reached the maximum number of clients
+    ServerFull,
+    /// The client's protocol fingerprint does not match the server's
+    ProtocolMismatch,
+    /// The connect token expired before the connection could be established
+    TokenExpired,
+    /// The server is shutting down
+    ServerShutdown,
+}
+
+impl DisconnectReason {
+    const CLIENT_DISCONNECTED: u8 = 0;
+    const TIMEOUT: u8 = 1;
+    const KICKED: u8 = 2;
+    const SERVER_FULL: u8 = 3;
+    const PROTOCOL_MISMATCH: u8 = 4;
+    const TOKEN_EXPIRED: u8 = 5;
+    const SERVER_SHUTDOWN: u8 = 6;
+}
+
+impl std::fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisconnectReason::ClientDisconnected => write!(f, "client disconnected"),
+            DisconnectReason::Timeout => write!(f, "connection timed out"),
+            DisconnectReason::Kicked(message) => write!(f, "kicked by the server: {message}"),
+            DisconnectReason::ServerFull => write!(f, "server is full"),
+            DisconnectReason::ProtocolMismatch => write!(f, "protocol mismatch"),
+            DisconnectReason::TokenExpired => write!(f, "connect token expired"),
+            DisconnectReason::ServerShutdown => write!(f, "server shut down"),
+        }
+    }
+}
+
+impl Bytes for DisconnectReason {
+    type Error = io::Error;
+    fn write_to(&self, writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
+        match self {
+            DisconnectReason::ClientDisconnected => writer.write_u8(Self::CLIENT_DISCONNECTED)?,
+            DisconnectReason::Timeout => writer.write_u8(Self::TIMEOUT)?,
+            DisconnectReason::Kicked(message) => {
+                writer.write_u8(Self::KICKED)?;
+                // truncate the message on a char boundary so that it fits in a single packet
+                let mut len = message.len().min(MAX_DISCONNECT_MESSAGE_BYTES);
+                while !message.is_char_boundary(len) {
+                    len -= 1;
+                }
+                writer.write_u16::<LittleEndian>(len as u16)?;
+                writer.write_all(&message.as_bytes()[..len])?;
+            }
+            DisconnectReason::ServerFull => writer.write_u8(Self::SERVER_FULL)?,
+            DisconnectReason::ProtocolMismatch => writer.write_u8(Self::PROTOCOL_MISMATCH)?,
+            DisconnectReason::TokenExpired => writer.write_u8(Self::TOKEN_EXPIRED)?,
+            DisconnectReason::ServerShutdown => writer.write_u8(Self::SERVER_SHUTDOWN)?,
+        }
+        Ok(())
+    }
+
+    fn read_from(reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
+        let reason = match reader.read_u8()? {
+            Self::CLIENT_DISCONNECTED => DisconnectReason::ClientDisconnected,
+            Self::TIMEOUT => DisconnectReason::Timeout,
+            Self::KICKED => {
+                let len = reader.read_u16::<LittleEndian>()? as usize;
+                if len > MAX_DISCONNECT_MESSAGE_BYTES {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("disconnect message is too long: {len} bytes"),
+                    ));
+                }
+                let mut message = vec![0; len];
+                reader.read_exact(&mut message)?;
+                let message = String::from_utf8(message)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                DisconnectReason::Kicked(message)
+            }
+            Self::SERVER_FULL => DisconnectReason::ServerFull,
+            Self::PROTOCOL_MISMATCH => DisconnectReason::ProtocolMismatch,
+            Self::TOKEN_EXPIRED => DisconnectReason::TokenExpired,
+            Self::SERVER_SHUTDOWN => DisconnectReason::ServerShutdown,
+            value => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid disconnect reason {value}"),
+                ))
+            }
+        };
+        Ok(reason)
+    }
+}
+
+pub struct DeniedPacket {
+    pub reason: DisconnectReason,
+}
 
 impl DeniedPacket {
-    pub fn create() -> Packet<'static> {
-        Packet::Denied(DeniedPacket {})
+    pub fn create(reason: DisconnectReason) -> Packet<'static> {
+        Packet::Denied(DeniedPacket { reason })
     }
 }
 
 impl Bytes for DeniedPacket {
     type Error = io::Error;
-    fn write_to(&self, _writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
-        Ok(())
+    fn write_to(&self, writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
+        self.reason.write_to(writer)
     }
 
-    fn read_from(_reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
-        Ok(Self {})
+    fn read_from(reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
+        let reason = DisconnectReason::read_from(reader)?;
+        Ok(Self { reason })
     }
 }
 
@@ -260,30 +373,35 @@ impl Bytes for KeepAlivePacket {
 
 pub struct PayloadPacket<'p> {
     pub buf: &'p [u8],
+    /// True if the packet was received on the reliable stream of the transport
+    pub stream: bool,
 }
 
 impl PayloadPacket<'_> {
     pub fn create(buf: &[u8]) -> Packet {
-        Packet::Payload(PayloadPacket { buf })
+        Packet::Payload(PayloadPacket { buf, stream: false })
     }
 }
 
-pub struct DisconnectPacket {}
+pub struct DisconnectPacket {
+    pub reason: DisconnectReason,
+}
 
 impl DisconnectPacket {
-    pub fn create() -> Packet<'static> {
-        Packet::Disconnect(Self {})
+    pub fn create(reason: DisconnectReason) -> Packet<'static> {
+        Packet::Disconnect(Self { reason })
     }
 }
 
 impl Bytes for DisconnectPacket {
     type Error = io::Error;
-    fn write_to(&self, _writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
-        Ok(())
+    fn write_to(&self, writer: &mut impl WriteBytesExt) -> Result<(), Self::Error> {
+        self.reason.write_to(writer)
     }
 
-    fn read_from(_reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
-        Ok(Self {})
+    fn read_from(reader: &mut impl byteorder::ReadBytesExt) -> Result<Self, io::Error> {
+        let reason = DisconnectReason::read_from(reader)?;
+        Ok(Self { reason })
     }
 }
 
@@ -375,7 +493,7 @@ impl<'p> Packet<'p> {
             Packet::Response(pkt) => pkt.write_to(&mut cursor)?,
             Packet::KeepAlive(pkt) => pkt.write_to(&mut cursor)?,
             Packet::Disconnect(pkt) => pkt.write_to(&mut cursor)?,
-            Packet::Payload(PayloadPacket { buf }) => cursor.write_all(buf)?,
+            Packet::Payload(PayloadPacket { buf, .. }) => cursor.write_all(buf)?,
             _ => unreachable!(), // Packet::Request variant is handled above
         }
         if cursor.position() as usize > len - MAC_BYTES {
@@ -461,6 +579,7 @@ impl<'p> Packet<'p> {
                 buf.copy_within(decryption_start..(decryption_end - MAC_BYTES), 0);
                 Packet::Payload(PayloadPacket {
                     buf: &buf[..decryption_end - decryption_start - MAC_BYTES],
+                    stream: sequence & STREAM_SEQUENCE_BIT != 0,
                 })
             }
             t => return Err(Error::InvalidType(t).into()),
@@ -533,6 +652,7 @@ mod tests {
         let packet = Packet::Request(RequestPacket {
             version_info: *NETCODE_VERSION,
             protocol_id,
+            protocol_hash: 0xdead_beef,
             expire_timestamp,
             token_nonce: nonce,
             token_data: Box::new(token_data),
@@ -559,6 +679,7 @@ mod tests {
 
         assert_eq!(req_pkt.version_info, *NETCODE_VERSION);
         assert_eq!(req_pkt.protocol_id, protocol_id);
+        assert_eq!(req_pkt.protocol_hash, 0xdead_beef);
         assert_eq!(req_pkt.expire_timestamp, expire_timestamp);
         assert_eq!(req_pkt.token_nonce, nonce);
 
@@ -583,7 +704,9 @@ mod tests {
         let sequence = 0u64;
         let mut replay_protection = ReplayProtection::new();
 
-        let packet = Packet::Denied(DeniedPacket {});
+        let packet = Packet::Denied(DeniedPacket {
+            reason: DisconnectReason::ProtocolMismatch,
+        });
 
         let mut buf = [0u8; MAX_PKT_BUF_SIZE];
         let size = packet
@@ -600,9 +723,10 @@ mod tests {
         )
         .unwrap();
 
-        let Packet::Denied(_denied_pkt) = packet else {
+        let Packet::Denied(denied_pkt) = packet else {
             panic!("wrong packet type");
         };
+        assert_eq!(denied_pkt.reason, DisconnectReason::ProtocolMismatch);
     }
 
     #[test]
@@ -677,7 +801,9 @@ mod tests {
         let sequence = 0u64;
         let mut replay_protection = ReplayProtection::new();
 
-        let packet = Packet::Disconnect(DisconnectPacket {});
+        let packet = Packet::Disconnect(DisconnectPacket {
+            reason: DisconnectReason::Kicked("cheating".to_string()),
+        });
 
         let mut buf = [0u8; MAX_PKT_BUF_SIZE];
         let size = packet
@@ -694,9 +820,13 @@ mod tests {
         )
         .unwrap();
 
-        let Packet::Disconnect(_disconnect_pkt) = packet else {
+        let Packet::Disconnect(disconnect_pkt) = packet else {
             panic!("wrong packet type");
         };
+        assert_eq!(
+            disconnect_pkt.reason,
+            DisconnectReason::Kicked("cheating".to_string())
+        );
     }
 
     #[test]
@@ -707,7 +837,10 @@ mod tests {
         let mut replay_protection = ReplayProtection::new();
 
         let payload = vec![0u8; 100];
-        let packet = Packet::Payload(PayloadPacket { buf: &payload });
+        let packet = Packet::Payload(PayloadPacket {
+            buf: &payload,
+            stream: false,
+        });
 
         let mut buf = [0u8; MAX_PACKET_SIZE];
         let size = packet
@@ -1,20 +1,22 @@
+use super::STREAM_SEQUENCE_BIT;
+
 const REPLAY_PROTECTION_BUFFER_SIZE: usize = 256;
 const UNRECEIVED: u64 = u64::MAX;
 
 #[derive(Clone)]
-pub struct ReplayProtection {
+struct ReplayWindow {
     most_recent_sequence: u64,
     received_packet: [u64; REPLAY_PROTECTION_BUFFER_SIZE],
 }
 
-impl ReplayProtection {
-    pub fn new() -> Self {
+impl ReplayWindow {
+    fn new() -> Self {
         Self {
             most_recent_sequence: 0,
             received_packet: [UNRECEIVED; REPLAY_PROTECTION_BUFFER_SIZE],
         }
     }
-    pub fn advance_sequence(&mut self, sequence: u64) {
+    fn advance_sequence(&mut self, sequence: u64) {
         if sequence > self.most_recent_sequence {
             self.most_recent_sequence = sequence;
         }
@@ -24,7 +26,7 @@ impl ReplayProtection {
         self.received_packet[index] = sequence;
     }
 
-    pub fn is_already_received(&self, sequence: u64) -> bool {
+    fn is_already_received(&self, sequence: u64) -> bool {
         if sequence + self.received_packet.len() as u64 <= self.most_recent_sequence {
             return true;
         }
@@ -39,6 +41,45 @@ impl ReplayProtection {
     }
 }
 
+/// Keeps track of the received sequence numbers to reject replayed packets.
+///
+/// The packets received on the reliable stream of the transport (see [`STREAM_SEQUENCE_BIT`]) are tracked
+/// in a separate window, since their sequence numbers are independent from the datagrams.
+#[derive(Clone)]
+pub struct ReplayProtection {
+    datagrams: ReplayWindow,
+    stream: ReplayWindow,
+}
+
+impl ReplayProtection {
+    pub fn new() -> Self {
+        Self {
+            datagrams: ReplayWindow::new(),
+            stream: ReplayWindow::new(),
+        }
+    }
+
+    fn window(&self, sequence: u64) -> &ReplayWindow {
+        if sequence & STREAM_SEQUENCE_BIT != 0 {
+            &self.stream
+        } else {
+            &self.datagrams
+        }
+    }
+
+    pub fn advance_sequence(&mut self, sequence: u64) {
+        if sequence & STREAM_SEQUENCE_BIT != 0 {
+            self.stream.advance_sequence(sequence)
+        } else {
+            self.datagrams.advance_sequence(sequence)
+        }
+    }
+
+    pub fn is_already_received(&self, sequence: u64) -> bool {
+        self.window(sequence).is_already_received(sequence)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,8 +108,25 @@ mod tests {
 
         // Check that the last packet was the most recent
         assert_eq!(
-            replay_protection.most_recent_sequence,
+            replay_protection.datagrams.most_recent_sequence,
             (REPLAY_PROTECTION_BUFFER_SIZE * 2 - 1) as u64
         );
     }
+
+    #[test]
+    fn replay_protection_stream() {
+        let mut replay_protection = ReplayProtection::new();
+        for i in 0..REPLAY_PROTECTION_BUFFER_SIZE * 2 {
+            replay_protection.advance_sequence(STREAM_SEQUENCE_BIT | i as u64);
+        }
+
+        // the stream packets don't advance the window of the datagrams
+        assert!(!replay_protection.is_already_received(0));
+        assert!(replay_protection.is_already_received(STREAM_SEQUENCE_BIT));
+
+        replay_protection.advance_sequence(0);
+        assert!(replay_protection.is_already_received(0));
+        assert!(!replay_protection
+            .is_already_received(STREAM_SEQUENCE_BIT | (REPLAY_PROTECTION_BUFFER_SIZE * 2) as u64));
+    }
 }
@@ -4,7 +4,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::Context;
 use bevy::prelude::Resource;
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 
 use crate::connection::netcode::token::TOKEN_EXPIRE_SEC;
 use crate::connection::server::NetServer;
@@ -20,12 +20,12 @@ use super::{
     error::{Error, Result},
     generate_key,
     packet::{
-        ChallengePacket, DeniedPacket, DisconnectPacket, KeepAlivePacket, Packet, PayloadPacket,
-        RequestPacket, ResponsePacket,
+        ChallengePacket, DeniedPacket, DisconnectPacket, DisconnectReason, KeepAlivePacket, Packet,
+        PayloadPacket, RequestPacket, ResponsePacket,
     },
     replay::ReplayProtection,
     token::{ChallengeToken, ConnectToken, ConnectTokenBuilder, ConnectTokenPrivate},
-    MAC_BYTES, MAX_PACKET_SIZE, MAX_PKT_BUF_SIZE, PACKET_SEND_RATE_SEC,
+    MAC_BYTES, MAX_PACKET_SIZE, MAX_PKT_BUF_SIZE, PACKET_SEND_RATE_SEC, STREAM_SEQUENCE_BIT,
 };
 
 pub const MAX_CLIENTS: usize = 256;
@@ -89,6 +89,8 @@ struct Connection {
     send_key: Key,
     receive_key: Key,
     sequence: u64,
+    /// Sequence of the packets sent on the reliable stream of the transport
+    stream_sequence: u64,
 }
 
 impl Connection {
@@ -125,6 +127,9 @@ struct ConnectionCache {
     // packet queue for all clients
     packet_queue: VecDeque<(ReadWordBuffer, ClientId)>,
 
+    // queue of the packets received on the reliable stream of the transport, for all clients
+    stream_packet_queue: VecDeque<(ReadWordBuffer, ClientId)>,
+
     // corresponds to the server time
     time: f64,
 }
@@ -136,6 +141,7 @@ impl ConnectionCache {
             client_id_map: HashMap::with_capacity(MAX_CLIENTS),
             replay_protection: HashMap::with_capacity(MAX_CLIENTS),
             packet_queue: VecDeque::with_capacity(MAX_CLIENTS * 2),
+            stream_packet_queue: VecDeque::new(),
             time: server_time,
         }
     }
@@ -167,6 +173,7 @@ impl ConnectionCache {
             send_key,
             receive_key,
             sequence: 0,
+            stream_sequence: 0,
         };
         self.clients.insert(client_id, conn);
         self.replay_protection
@@ -174,16 +181,17 @@ impl ConnectionCache {
 
         self.client_id_map.insert(addr, client_id);
     }
-    fn remove(&mut self, client_id: ClientId) {
-        let Some(conn) = self.clients.get(&client_id) else {
-            return;
-        };
+    /// Remove a connected client, and return its address
+    fn remove(&mut self, client_id: ClientId) -> Option<SocketAddr> {
+        let conn = self.clients.get(&client_id)?;
         if !conn.is_connected() {
-            return;
+            return None;
         }
-        self.client_id_map.remove(&conn.addr);
+        let addr = conn.addr;
+        self.client_id_map.remove(&addr);
         self.replay_protection.remove(&client_id);
         self.clients.remove(&client_id);
+        Some(addr)
     }
 
     fn ids(&self) -> Vec<ClientId> {
@@ -213,13 +221,16 @@ impl ConnectionCache {
 }
 
 pub type Callback<Ctx> = Box<dyn FnMut(ClientId, &mut Ctx) + Send + Sync + 'static>;
+pub type DisconnectCallback<Ctx> =
+    Box<dyn FnMut(ClientId, DisconnectReason, &mut Ctx) + Send + Sync + 'static>;
 
 /// Configuration for a server.
 ///
 /// * `num_disconnect_packets` - The number of redundant disconnect packets that will be sent to a client when the server is disconnecting it.
 /// * `keep_alive_send_rate` - The rate at which keep-alive packets will be sent to clients.
+/// * `protocol_hash` - The fingerprint of the protocol; connection requests from clients with a different fingerprint are denied.
 /// * `on_connect` - A callback that will be called when a client is connected to the server.
-/// * `on_disconnect` - A callback that will be called when a client is disconnected from the server.
+/// * `on_disconnect` - A callback that will be called when a client is disconnected from the server, with the reason of the disconnection.
 ///
 /// # Example
 /// ```
@@ -243,9 +254,10 @@ pub struct ServerConfig<Ctx> {
     token_expire_secs: i32,
     client_timeout_secs: i32,
     server_addr: SocketAddr,
+    protocol_hash: u64,
     context: Ctx,
     on_connect: Option<Callback<Ctx>>,
-    on_disconnect: Option<Callback<Ctx>>,
+    on_disconnect: Option<DisconnectCallback<Ctx>>,
 }
 
 impl Default for ServerConfig<()> {
@@ -256,6 +268,7 @@ impl Default for ServerConfig<()> {
             token_expire_secs: TOKEN_EXPIRE_SEC,
             client_timeout_secs: CLIENT_TIMEOUT_SECS,
             server_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            protocol_hash: 0,
             context: (),
             on_connect: None,
             on_disconnect: None,
@@ -276,6 +289,7 @@ impl<Ctx> ServerConfig<Ctx> {
             token_expire_secs: TOKEN_EXPIRE_SEC,
             client_timeout_secs: CLIENT_TIMEOUT_SECS,
             server_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            protocol_hash: 0,
             context: ctx,
             on_connect: None,
             on_disconnect: None,
@@ -311,6 +325,13 @@ impl<Ctx> ServerConfig<Ctx> {
         self.server_addr = server_addr;
         self
     }
+    /// Set the fingerprint of the protocol used by the server. <br>
+    /// Connection requests from clients with a different fingerprint will be denied with [`DisconnectReason::ProtocolMismatch`].
+    /// The default is 0.
+    pub fn protocol_hash(mut self, protocol_hash: u64) -> Self {
+        self.protocol_hash = protocol_hash;
+        self
+    }
     /// Provide a callback that will be called when a client is connected to the server. <br>
     /// The callback will be called with the client index and the context that was provided (provide a `None` context if you don't need one).
     ///
@@ -323,12 +344,12 @@ impl<Ctx> ServerConfig<Ctx> {
         self
     }
     /// Provide a callback that will be called when a client is disconnected from the server. <br>
-    /// The callback will be called with the client index and the context that was provided (provide a `None` context if you don't need one).
+    /// The callback will be called with the client index, the [`DisconnectReason`] and the context that was provided (provide a `None` context if you don't need one).
     ///
     /// See [`ServerConfig`] for an example.
     pub fn on_disconnect<F>(mut self, cb: F) -> Self
     where
-        F: FnMut(ClientId, &mut Ctx) + Send + Sync + 'static,
+        F: FnMut(ClientId, DisconnectReason, &mut Ctx) + Send + Sync + 'static,
     {
         self.on_disconnect = Some(Box::new(cb));
         self
@@ -350,7 +371,7 @@ impl<Ctx> ServerConfig<Ctx> {
 /// # use lightyear::prelude::{Io, IoConfig, TransportConfig};
 /// let mut io = Io::from_config(IoConfig::from_transport(TransportConfig::UdpSocket(
 ///    SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)))
-/// );
+/// ).unwrap();
 /// let private_key = generate_key();
 /// let protocol_id = 0x123456789ABCDEF0;
 /// let mut server = NetcodeServer::new(protocol_id, private_key).unwrap();
@@ -448,9 +469,9 @@ impl<Ctx> NetcodeServer<Ctx> {
             cb(client_id, &mut self.cfg.context)
         }
     }
-    fn on_disconnect(&mut self, client_id: ClientId) {
+    fn on_disconnect(&mut self, client_id: ClientId, reason: DisconnectReason) {
         if let Some(cb) = self.cfg.on_disconnect.as_mut() {
-            cb(client_id, &mut self.cfg.context)
+            cb(client_id, reason, &mut self.cfg.context)
         }
     }
     fn touch_client(&mut self, client_id: Option<ClientId>) -> Result<()> {
@@ -488,17 +509,22 @@ impl<Ctx> NetcodeServer<Ctx> {
             Packet::Payload(packet) => {
                 self.touch_client(client_id)?;
                 if let Some(idx) = client_id {
-                    self.conn_cache
-                        .packet_queue
-                        .push_back((ReadWordBuffer::start_read(packet.buf), idx));
+                    let reader = ReadWordBuffer::start_read(packet.buf);
+                    if packet.stream {
+                        self.conn_cache.stream_packet_queue.push_back((reader, idx));
+                    } else {
+                        self.conn_cache.packet_queue.push_back((reader, idx));
+                    }
                 }
                 Ok(())
             }
-            Packet::Disconnect(_) => {
+            Packet::Disconnect(packet) => {
                 if let Some(idx) = client_id {
-                    debug!("server disconnected client {idx}");
-                    self.on_disconnect(idx);
-                    self.conn_cache.remove(idx);
+                    debug!(reason = %packet.reason, "server disconnected client {idx}");
+                    self.on_disconnect(idx, packet.reason);
+                    if let Some(addr) = self.conn_cache.remove(idx) {
+                        sender.disconnected(&addr);
+                    }
                 }
                 Ok(())
             }
@@ -523,6 +549,16 @@ impl<Ctx> NetcodeServer<Ctx> {
         packet: Packet,
         id: ClientId,
         sender: &mut impl PacketSender,
+    ) -> Result<()> {
+        self.write_to_client(packet, id, sender, false)
+    }
+
+    fn write_to_client(
+        &mut self,
+        packet: Packet,
+        id: ClientId,
+        sender: &mut impl PacketSender,
+        reliable: bool,
     ) -> Result<()> {
         let mut buf = [0u8; MAX_PKT_BUF_SIZE];
         let conn = &mut self
@@ -530,11 +566,23 @@ impl<Ctx> NetcodeServer<Ctx> {
             .clients
             .get_mut(&id)
             .expect("invalid client id");
-        let size = packet.write(&mut buf, conn.sequence, &conn.send_key, self.protocol_id)?;
-        sender.send(&buf[..size], &conn.addr).map_err(Error::from)?;
+        let sequence = if reliable {
+            STREAM_SEQUENCE_BIT | conn.stream_sequence
+        } else {
+            conn.sequence
+        };
+        let size = packet.write(&mut buf, sequence, &conn.send_key, self.protocol_id)?;
+        if reliable {
+            sender
+                .send_reliable(&buf[..size], &conn.addr)
+                .map_err(Error::from)?;
+            conn.stream_sequence += 1;
+        } else {
+            sender.send(&buf[..size], &conn.addr).map_err(Error::from)?;
+            conn.sequence += 1;
+        }
         conn.last_access_time = self.time;
         conn.last_send_time = self.time;
-        conn.sequence += 1;
         Ok(())
     }
 
@@ -619,10 +667,24 @@ impl<Ctx> NetcodeServer<Ctx> {
             debug!("server ignored connection request. connect token has already been used");
             return Ok(());
         };
+        if packet.protocol_hash != self.cfg.protocol_hash {
+            warn!(
+                client_hash = packet.protocol_hash,
+                server_hash = self.cfg.protocol_hash,
+                "server denied connection request. protocol mismatch"
+            );
+            self.send_to_addr(
+                DeniedPacket::create(DisconnectReason::ProtocolMismatch),
+                from_addr,
+                token.server_to_client_key,
+                sender,
+            )?;
+            return Ok(());
+        };
         if self.num_connected_clients() >= MAX_CLIENTS {
             debug!("server denied connection request. server is full");
             self.send_to_addr(
-                DeniedPacket::create(),
+                DeniedPacket::create(DisconnectReason::ServerFull),
                 from_addr,
                 token.server_to_client_key,
                 sender,
@@ -679,7 +741,7 @@ impl<Ctx> NetcodeServer<Ctx> {
         if self.num_connected_clients() >= MAX_CLIENTS {
             debug!("server denied connection response. server is full");
             self.send_to_addr(
-                DeniedPacket::create(),
+                DeniedPacket::create(DisconnectReason::ServerFull),
                 from_addr,
                 self.conn_cache
                     .clients
@@ -703,10 +765,11 @@ impl<Ctx> NetcodeServer<Ctx> {
             id, challenge_token.client_id
         );
         self.send_to_client(KeepAlivePacket::create(id), id, sender)?;
+        sender.connected(&from_addr);
         self.on_connect(id);
         Ok(())
     }
-    fn check_for_timeouts(&mut self) {
+    fn check_for_timeouts(&mut self, sender: &mut impl PacketSender) {
         for id in self.conn_cache.ids() {
             let Some(client) = self.conn_cache.clients.get_mut(&id) else {
                 continue;
@@ -718,8 +781,10 @@ impl<Ctx> NetcodeServer<Ctx> {
                 && client.last_receive_time + (client.timeout as f64) < self.time
             {
                 debug!("server timed out client {id}");
-                self.on_disconnect(id);
-                self.conn_cache.remove(id);
+                self.on_disconnect(id, DisconnectReason::Timeout);
+                if let Some(addr) = self.conn_cache.remove(id) {
+                    sender.disconnected(&addr);
+                }
             }
         }
     }
@@ -829,7 +894,7 @@ impl<Ctx> NetcodeServer<Ctx> {
         self.time += delta_ms;
         self.conn_cache.update(delta_ms);
         let (sender, receiver) = io.split();
-        self.check_for_timeouts();
+        self.check_for_timeouts(sender);
         self.recv_packets(sender, receiver)?;
         self.send_packets(io)?;
         Ok(())
@@ -852,7 +917,7 @@ impl<Ctx> NetcodeServer<Ctx> {
     /// # let mut server = NetcodeServer::new(protocol_id, private_key).unwrap();
     /// # let mut io = Io::from_config(
     /// #     IoConfig::from_transport(TransportConfig::UdpSocket(addr))
-    /// # );
+    /// # ).unwrap();
     /// let start = Instant::now();
     /// loop {
     ///    let now = start.elapsed().as_secs_f64();
@@ -866,6 +931,10 @@ impl<Ctx> NetcodeServer<Ctx> {
     pub fn recv(&mut self) -> Option<(ReadWordBuffer, ClientId)> {
         self.conn_cache.packet_queue.pop_front()
     }
+    /// Receives a packet that a client sent on the reliable ordered stream of the transport, if any.
+    pub fn recv_reliable(&mut self) -> Option<(ReadWordBuffer, ClientId)> {
+        self.conn_cache.stream_packet_queue.pop_front()
+    }
     /// Sends a packet to a client.
     ///
     /// The provided buffer must be smaller than [`MAX_PACKET_SIZE`].
@@ -890,6 +959,23 @@ impl<Ctx> NetcodeServer<Ctx> {
         self.send_to_client(packet, client_id, io)
     }
 
+    /// Sends a packet to a client on the reliable ordered stream of the transport.
+    ///
+    /// The provided buffer must be smaller than [`MAX_PACKET_SIZE`].
+    pub fn send_reliable(&mut self, buf: &[u8], client_id: ClientId, io: &mut Io) -> Result<()> {
+        if buf.len() > MAX_PACKET_SIZE {
+            return Err(Error::SizeMismatch(MAX_PACKET_SIZE, buf.len()));
+        }
+        let Some(conn) = self.conn_cache.clients.get(&client_id) else {
+            return Err(Error::ClientNotFound);
+        };
+        if !conn.is_connected() {
+            return Err(Error::ClientNotConnected);
+        }
+        let packet = PayloadPacket::create(buf);
+        self.write_to_client(packet, client_id, io, true)
+    }
+
     /// Sends a packet to all connected clients.
     ///
     /// The provided buffer must be smaller than [`MAX_PACKET_SIZE`].
@@ -941,21 +1027,35 @@ impl<Ctx> NetcodeServer<Ctx> {
     ///
     /// The server will send a number of redundant disconnect packets to the client, and then remove its connection info.
     pub fn disconnect(&mut self, client_id: ClientId, io: &mut Io) -> Result<()> {
+        self.disconnect_with_reason(client_id, DisconnectReason::Kicked(String::new()), io)
+    }
+    /// Disconnects a client, letting it know why it was disconnected.
+    ///
+    /// The server will send a number of redundant disconnect packets containing the [`DisconnectReason`] to the client,
+    /// and then remove its connection info.
+    pub fn disconnect_with_reason(
+        &mut self,
+        client_id: ClientId,
+        reason: DisconnectReason,
+        io: &mut Io,
+    ) -> Result<()> {
         let Some(conn) = self.conn_cache.clients.get_mut(&client_id) else {
             return Ok(());
         };
         if !conn.is_connected() {
             return Ok(());
         }
-        debug!("server disconnecting client {client_id}");
+        debug!(%reason, "server disconnecting client {client_id}");
         for _ in 0..self.cfg.num_disconnect_packets {
-            self.send_to_client(DisconnectPacket::create(), client_id, io)?;
+            self.send_to_client(DisconnectPacket::create(reason.clone()), client_id, io)?;
+        }
+        self.on_disconnect(client_id, reason);
+        if let Some(addr) = self.conn_cache.remove(client_id) {
+            io.disconnected(&addr);
         }
-        self.on_disconnect(client_id);
-        self.conn_cache.remove(client_id);
         Ok(())
     }
-    /// Disconnects all clients.
+    /// Disconnects all clients, with the [`DisconnectReason::ServerShutdown`] reason.
     pub fn disconnect_all(&mut self, io: &mut Io) -> Result<()> {
         debug!("server disconnecting all clients");
         for id in self.conn_cache.ids() {
@@ -963,7 +1063,7 @@ impl<Ctx> NetcodeServer<Ctx> {
                 continue;
             };
             if conn.is_connected() {
-                self.disconnect(id, io)?;
+                self.disconnect_with_reason(id, DisconnectReason::ServerShutdown, io)?;
             }
         }
         Ok(())
@@ -1005,13 +1105,15 @@ impl<Ctx> NetcodeServer<Ctx> {
 #[derive(Default)]
 pub(crate) struct NetcodeServerContext {
     pub(crate) connections: Vec<ClientId>,
-    pub(crate) disconnections: Vec<ClientId>,
+    pub(crate) disconnections: Vec<(ClientId, DisconnectReason)>,
 }
 
 #[derive(Resource)]
 pub struct Server {
     server: NetcodeServer<NetcodeServerContext>,
     io: Io,
+    /// Clients that should be disconnected during the next update
+    pending_disconnections: Vec<(ClientId, DisconnectReason)>,
 }
 
 impl NetServer for Server {
@@ -1026,6 +1128,14 @@ impl NetServer for Server {
         self.server.cfg.context.connections.clear();
         self.server.cfg.context.disconnections.clear();
 
+        // disconnect the clients that were kicked since the last update, so that the disconnections
+        // get reported in `new_disconnections`
+        for (client_id, reason) in std::mem::take(&mut self.pending_disconnections) {
+            self.server
+                .disconnect_with_reason(client_id, reason, &mut self.io)
+                .context("could not disconnect client")?;
+        }
+
         self.server
             .try_update(delta_ms, &mut self.io)
             .context("could not update server")
@@ -1035,27 +1145,62 @@ impl NetServer for Server {
         self.server.recv()
     }
 
+    fn recv_reliable(&mut self) -> Option<(ReadWordBuffer, ClientId)> {
+        self.server.recv_reliable()
+    }
+
     fn send(&mut self, buf: &[u8], client_id: ClientId) -> anyhow::Result<()> {
         self.server
             .send(buf, client_id, &mut self.io)
             .context("could not send packet")
     }
 
+    fn send_reliable(&mut self, buf: &[u8], client_id: ClientId) -> anyhow::Result<()> {
+        self.server
+            .send_reliable(buf, client_id, &mut self.io)
+            .context("could not send packet on the reliable stream")
+    }
+
+    fn has_reliable_stream(&self, client_id: ClientId) -> bool {
+        self.server
+            .client_addr(client_id)
+            .is_some_and(|addr| self.io.has_reliable_stream(&addr))
+    }
+
+    fn set_protocol_hash(&mut self, protocol_hash: u64) {
+        self.server.cfg.protocol_hash = protocol_hash;
+    }
+
     fn new_connections(&self) -> Vec<ClientId> {
         self.server.cfg.context.connections.clone()
     }
 
     fn new_disconnections(&self) -> Vec<ClientId> {
+        self.server
+            .cfg
+            .context
+            .disconnections
+            .iter()
+            .map(|(client_id, _)| *client_id)
+            .collect()
+    }
+
+    fn new_disconnections_with_reason(&self) -> Vec<(ClientId, DisconnectReason)> {
         self.server.cfg.context.disconnections.clone()
     }
 
+    fn disconnect(&mut self, client_id: ClientId, reason: DisconnectReason) -> anyhow::Result<()> {
+        self.pending_disconnections.push((client_id, reason));
+        Ok(())
+    }
+
     fn io(&self) -> &Io {
         &self.io
     }
 }
 
 impl Server {
-    pub(crate) fn new(config: NetcodeConfig, io: Io) -> Self {
+    pub(crate) fn new(config: NetcodeConfig, io: Io, protocol_hash: u64) -> Self {
         let private_key = config.private_key.unwrap_or(generate_key());
         // create context
         let context = NetcodeServerContext::default();
@@ -1063,14 +1208,19 @@ impl Server {
             .on_connect(|id, ctx| {
                 ctx.connections.push(id);
             })
-            .on_disconnect(|id, ctx| {
-                ctx.disconnections.push(id);
+            .on_disconnect(|id, reason, ctx| {
+                ctx.disconnections.push((id, reason));
             });
         cfg = cfg.keep_alive_send_rate(config.keep_alive_send_rate);
         cfg = cfg.num_disconnect_packets(config.num_disconnect_packets);
+        cfg = cfg.protocol_hash(protocol_hash);
         let server = NetcodeServer::with_config(config.protocol_id, private_key, cfg)
             .expect("Could not create server netcode");
 
-        Self { server, io }
+        Self {
+            server,
+            io,
+            pending_disconnections: Vec::new(),
+        }
     }
 }
@@ -2,7 +2,7 @@ use anyhow::Result;
 use bevy::prelude::Resource;
 
 use crate::_reexport::ReadWordBuffer;
-use crate::connection::netcode::ClientId;
+use crate::connection::netcode::{ClientId, DisconnectReason};
 use crate::prelude::{Io, IoConfig};
 use crate::server::config::NetcodeConfig;
 
@@ -22,10 +22,56 @@ pub trait NetServer: Send + Sync {
     /// Send a packet to one of the connected clients
     fn send(&mut self, buf: &[u8], client_id: ClientId) -> Result<()>;
 
+    /// Receive a packet that one of the connected clients sent on the reliable ordered stream of the transport
+    ///
+    /// By default, there is no reliable stream and this returns None
+    fn recv_reliable(&mut self) -> Option<(ReadWordBuffer, ClientId)> {
+        None
+    }
+
+    /// Send a packet to one of the connected clients on the reliable ordered stream of the transport
+    ///
+    /// By default, the packet is sent like any other packet
+    fn send_reliable(&mut self, buf: &[u8], client_id: ClientId) -> Result<()> {
+        self.send(buf, client_id)
+    }
+
+    /// Returns true if the transport has a reliable ordered stream to the client (for example WebTransport)
+    fn has_reliable_stream(&self, _client_id: ClientId) -> bool {
+        false
+    }
+
+    /// Set the fingerprint of the protocol; the connection requests of clients with a different fingerprint are denied.
+    ///
+    /// By default, the server doesn't check the fingerprint of the protocol and this does nothing
+    fn set_protocol_hash(&mut self, _protocol_hash: u64) {}
+
     fn new_connections(&self) -> Vec<ClientId>;
 
+    /// Return the clients that got disconnected during the last update
     fn new_disconnections(&self) -> Vec<ClientId>;
 
+    /// Return the clients that got disconnected during the last update, with the reason of the disconnection.
+    ///
+    /// By default, the reason is not known and is reported as [`DisconnectReason::ClientDisconnected`]
+    fn new_disconnections_with_reason(&self) -> Vec<(ClientId, DisconnectReason)> {
+        self.new_disconnections()
+            .into_iter()
+            .map(|client_id| (client_id, DisconnectReason::ClientDisconnected))
+            .collect()
+    }
+
+    /// Disconnect a client, sending it the [`DisconnectReason`].
+    ///
+    /// The disconnection is applied during the next update.
+    /// By default, disconnecting a client is not supported and this returns an error.
+    fn disconnect(&mut self, client_id: ClientId, _reason: DisconnectReason) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "this server cannot disconnect the client {}",
+            client_id
+        ))
+    }
+
     fn io(&self) -> &Io;
 }
 
@@ -52,11 +98,14 @@ impl Default for NetConfig {
 }
 
 impl NetConfig {
-    pub fn build_server(self) -> ServerConnection {
+    /// Build the server connection.
+    ///
+    /// `protocol_hash` is the [`ProtocolFingerprint`](crate::protocol::ProtocolFingerprint) that connecting clients must match
+    pub fn build_server(self, protocol_hash: u64) -> ServerConnection {
         match self {
             NetConfig::Netcode { config, io } => {
-                let io = io.get_io();
-                let server = super::netcode::Server::new(config, io);
+                let io = io.get_io().expect("could not create the server io");
+                let server = super::netcode::Server::new(config, io, protocol_hash);
                 ServerConnection {
                     server: Box::new(server),
                 }
@@ -68,6 +117,15 @@ impl NetConfig {
     }
 }
 
+impl ServerConnection {
+    /// Kick a client from the server; the client receives a [`DisconnectReason::Kicked`] with the provided message.
+    ///
+    /// The message is truncated to [`MAX_DISCONNECT_MESSAGE_BYTES`](crate::connection::netcode::MAX_DISCONNECT_MESSAGE_BYTES) bytes.
+    pub fn kick(&mut self, client_id: ClientId, message: impl Into<String>) -> Result<()> {
+        self.disconnect(client_id, DisconnectReason::Kicked(message.into()))
+    }
+}
+
 impl NetServer for ServerConnection {
     fn start(&mut self) {
         self.server.start()
@@ -89,6 +147,22 @@ impl NetServer for ServerConnection {
         self.server.send(buf, client_id)
     }
 
+    fn recv_reliable(&mut self) -> Option<(ReadWordBuffer, ClientId)> {
+        self.server.recv_reliable()
+    }
+
+    fn send_reliable(&mut self, buf: &[u8], client_id: ClientId) -> Result<()> {
+        self.server.send_reliable(buf, client_id)
+    }
+
+    fn has_reliable_stream(&self, client_id: ClientId) -> bool {
+        self.server.has_reliable_stream(client_id)
+    }
+
+    fn set_protocol_hash(&mut self, protocol_hash: u64) {
+        self.server.set_protocol_hash(protocol_hash)
+    }
+
     fn new_connections(&self) -> Vec<ClientId> {
         self.server.new_connections()
     }
@@ -97,7 +171,84 @@ impl NetServer for ServerConnection {
         self.server.new_disconnections()
     }
 
+    fn new_disconnections_with_reason(&self) -> Vec<(ClientId, DisconnectReason)> {
+        self.server.new_disconnections_with_reason()
+    }
+
+    fn disconnect(&mut self, client_id: ClientId, reason: DisconnectReason) -> Result<()> {
+        self.server.disconnect(client_id, reason)
+    }
+
     fn io(&self) -> &Io {
         self.server.io()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Events;
+    use bevy::utils::Duration;
+
+    use crate::prelude::client::*;
+    use crate::prelude::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    use super::*;
+
+    #[test]
+    fn test_kick_client() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            SyncConfig::default(),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+        let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+
+        stepper
+            .server_app
+            .world
+            .resource_mut::<ServerConnection>()
+            .kick(client_id, "cheating")
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let expected = DisconnectReason::Kicked("cheating".to_string());
+        let server_events: Vec<_> = stepper
+            .server_app
+            .world
+            .resource_mut::<Events<server::DisconnectEvent>>()
+            .drain()
+            .map(|event| (*event.context(), event.reason().clone()))
+            .collect();
+        assert_eq!(server_events, vec![(client_id, expected.clone())]);
+        let client_events: Vec<_> = stepper
+            .client_app
+            .world
+            .resource_mut::<Events<DisconnectEvent>>()
+            .drain()
+            .map(|event| event.reason().clone())
+            .collect();
+        assert_eq!(client_events, vec![expected]);
+        assert!(!stepper
+            .client_app
+            .world
+            .resource::<ClientConnection>()
+            .is_connected());
+    }
+}
@@ -219,6 +219,8 @@ impl<A: LeafwingUserAction> ActionDiff<A> {
 /// We will store the last N inputs starting from start_tick (in case of packet loss)
 pub struct InputMessage<A: LeafwingUserAction> {
     pub(crate) end_tick: Tick,
+    /// Interpolation tick of the client at the time the message was created (used for lag compensation)
+    pub(crate) interpolation_tick: Option<Tick>,
     // first element is tick end_tick-N+1, last element is end_tick
     pub(crate) diffs: Vec<(InputTarget, Vec<Vec<ActionDiff<A>>>)>,
 }
@@ -263,6 +265,7 @@ impl<T: LeafwingUserAction> InputMessage<T> {
     pub fn new(end_tick: Tick) -> Self {
         Self {
             end_tick,
+            interpolation_tick: None,
             diffs: vec![],
         }
     }
@@ -276,13 +279,20 @@ impl<T: LeafwingUserAction> InputMessage<T> {
 }
 
 impl<T: LeafwingUserAction> InputBuffer<T> {
+    /// Account for tick wrapping: ticks that are too far away from the start of the buffer
+    /// cannot be compared with it reliably, which means that the buffer hasn't been updated in a long time
+    fn is_stale(&self, tick: Tick) -> bool {
+        self.start_tick
+            .map_or(false, |start_tick| tick.is_too_far_from(start_tick))
+    }
+
     // Note: we expect this to be set every tick?
     //  i.e. there should be an ActionState for every tick, even if the action is None
     pub(crate) fn set(&mut self, tick: Tick, value: &ActionState<T>) {
-        let Some(start_tick) = self.start_tick else {
-            // initialize the buffer
+        let Some(start_tick) = self.start_tick.filter(|_| !self.is_stale(tick)) else {
+            // initialize the buffer (or reset it if the buffered inputs are too old)
             self.start_tick = Some(tick);
-            self.buffer.push_back(BufferItem::Data(value.clone()));
+            self.buffer = VecDeque::from([BufferItem::Data(value.clone())]);
             return;
         };
 
@@ -333,6 +343,12 @@ impl<T: LeafwingUserAction> InputBuffer<T> {
         let Some(start_tick) = self.start_tick else {
             return None;
         };
+        if self.is_stale(tick) {
+            // the buffered inputs are too old to be used
+            self.buffer = VecDeque::new();
+            self.start_tick = Some(tick + 1);
+            return None;
+        }
         if tick < start_tick {
             return None;
         }
@@ -375,7 +391,7 @@ impl<T: LeafwingUserAction> InputBuffer<T> {
         let Some(start_tick) = self.start_tick else {
             return None;
         };
-        if self.buffer.is_empty() {
+        if self.buffer.is_empty() || self.is_stale(tick) {
             return None;
         }
         if tick < start_tick || tick > start_tick + (self.buffer.len() as i16 - 1) {
@@ -421,6 +437,13 @@ impl<A: LeafwingUserAction> Default for ActionDiffBuffer<A> {
 }
 
 impl<A: LeafwingUserAction> ActionDiffBuffer<A> {
+    /// Account for tick wrapping: ticks that are too far away from the start of the buffer
+    /// cannot be compared with it reliably, which means that the buffer hasn't been updated in a long time
+    fn is_stale(&self, tick: Tick) -> bool {
+        self.start_tick
+            .map_or(false, |start_tick| tick.is_too_far_from(start_tick))
+    }
+
     pub(crate) fn end_tick(&self) -> Tick {
         self.start_tick.map_or(Tick(0), |start_tick| {
             start_tick + (self.buffer.len() as i16 - 1)
@@ -435,10 +458,10 @@ impl<A: LeafwingUserAction> ActionDiffBuffer<A> {
             .into_iter()
             .map(|diff| (diff.action(), diff))
             .collect();
-        let Some(start_tick) = self.start_tick else {
-            // initialize the buffer
+        let Some(start_tick) = self.start_tick.filter(|_| !self.is_stale(tick)) else {
+            // initialize the buffer (or reset it if the buffered diffs are too old)
             self.start_tick = Some(tick);
-            self.buffer.push_back(diffs);
+            self.buffer = VecDeque::from([diffs]);
             return;
         };
 
@@ -473,6 +496,12 @@ impl<A: LeafwingUserAction> ActionDiffBuffer<A> {
         let Some(start_tick) = self.start_tick else {
             return vec![];
         };
+        if self.is_stale(tick) {
+            // the buffered diffs are too old to be used
+            self.buffer = VecDeque::new();
+            self.start_tick = Some(tick + 1);
+            return vec![];
+        }
         if tick < start_tick {
             return vec![];
         }
@@ -500,6 +529,9 @@ impl<A: LeafwingUserAction> ActionDiffBuffer<A> {
         let Some(start_tick) = self.start_tick else {
             return vec![];
         };
+        if self.is_stale(tick) {
+            return vec![];
+        }
         if tick < start_tick || tick > start_tick + (self.buffer.len() as i16 - 1) {
             return vec![];
         }
@@ -623,6 +655,7 @@ mod tests {
             message,
             InputMessage {
                 end_tick: Tick(10),
+                interpolation_tick: None,
                 diffs: vec![(
                     InputTarget::Entity(entity),
                     vec![
@@ -688,4 +721,37 @@ mod tests {
         );
         assert_eq!(diff_buffer.get(Tick(12)), vec![]);
     }
+
+    /// The buffers should keep working if they are not updated for more than i16::MAX ticks
+    #[test]
+    fn test_stale_buffer() {
+        let mut input_buffer = InputBuffer::default();
+        let mut a1 = ActionState::default();
+        a1.press(&Action::Jump);
+        let a2 = ActionState::default();
+        input_buffer.set(Tick(1), &a1);
+
+        // this tick looks older than the start of the buffer because of wrapping
+        let tick = Tick(1) + Tick(40000);
+        assert_eq!(input_buffer.get(tick), None);
+        input_buffer.set(tick, &a2);
+        assert_eq!(input_buffer.get(tick), Some(&a2));
+        assert_eq!(input_buffer.pop(tick), Some(a2.clone()));
+
+        // popping a tick that is too far from the buffer resets it
+        input_buffer.set(Tick(2), &a1);
+        assert_eq!(input_buffer.pop(Tick(2) + Tick(40000)), None);
+        assert_eq!(input_buffer.start_tick, Some(Tick(40003)));
+        assert_eq!(input_buffer.buffer.len(), 0);
+
+        let mut diff_buffer = ActionDiffBuffer::default();
+        let diff = ActionDiff::Pressed {
+            action: Action::Jump,
+        };
+        diff_buffer.set(Tick(1), vec![diff.clone()]);
+        assert_eq!(diff_buffer.get(tick), vec![]);
+        diff_buffer.set(tick, vec![diff.clone()]);
+        assert_eq!(diff_buffer.start_tick, Some(tick));
+        assert_eq!(diff_buffer.pop(tick), vec![diff]);
+    }
 }
@@ -33,6 +33,8 @@ pub(crate) enum InputData<T: UserAction> {
 /// We will store the last N inputs starting from start_tick (in case of packet loss)
 pub struct InputMessage<T: UserAction> {
     pub(crate) end_tick: Tick,
+    /// Interpolation tick of the client at the time the message was created (used for lag compensation)
+    pub(crate) interpolation_tick: Option<Tick>,
     // first element is tick end_tick-N+1, last element is end_tick
     pub(crate) inputs: Vec<InputData<T>>,
 }
@@ -69,12 +71,25 @@ impl<T: UserAction> InputBuffer<T> {
     //     self.buffer.remove(&tick)
     // }
 
+    /// Account for tick wrapping: ticks that are too far away from the start of the buffer
+    /// cannot be compared with it reliably, which means that the buffer hasn't been updated in a long time
+    fn is_stale(&self, tick: Tick) -> bool {
+        self.start_tick
+            .map_or(false, |start_tick| tick.is_too_far_from(start_tick))
+    }
+
     /// Remove all the inputs that are older than the given tick, then return the input
     /// for the given tick
     pub(crate) fn pop(&mut self, tick: Tick) -> Option<T> {
         let Some(start_tick) = self.start_tick else {
             return None;
         };
+        if self.is_stale(tick) {
+            // the buffered inputs are too old to be used
+            self.buffer = VecDeque::new();
+            self.start_tick = Some(tick + 1);
+            return None;
+        }
         if tick < start_tick {
             return None;
         }
@@ -99,7 +114,7 @@ impl<T: UserAction> InputBuffer<T> {
         let Some(start_tick) = self.start_tick else {
             return None;
         };
-        if self.buffer.is_empty() {
+        if self.buffer.is_empty() || self.is_stale(tick) {
             return None;
         }
         if tick < start_tick || tick > start_tick + (self.buffer.len() as i16 - 1) {
@@ -112,10 +127,10 @@ impl<T: UserAction> InputBuffer<T> {
     }
 
     pub(crate) fn set(&mut self, tick: Tick, value: Option<T>) {
-        let Some(start_tick) = self.start_tick else {
-            // initialize the buffer
+        let Some(start_tick) = self.start_tick.filter(|_| !self.is_stale(tick)) else {
+            // initialize the buffer (or reset it if the buffered inputs are too old)
             self.start_tick = Some(tick);
-            self.buffer.push_back(value);
+            self.buffer = VecDeque::from([value]);
             return;
         };
         // cannot set lower values than start_tick
@@ -189,7 +204,11 @@ impl<T: UserAction> InputBuffer<T> {
                 inputs.push(value);
             }
         }
-        InputMessage { inputs, end_tick }
+        InputMessage {
+            inputs,
+            end_tick,
+            interpolation_tick: None,
+        }
     }
 }
 
@@ -232,6 +251,7 @@ mod tests {
             message,
             InputMessage {
                 end_tick: Tick(10),
+                interpolation_tick: None,
                 inputs: vec![
                     InputData::Absent,
                     InputData::Input(0),
@@ -252,6 +272,7 @@ mod tests {
 
         let message = InputMessage {
             end_tick: Tick(20),
+            interpolation_tick: None,
             inputs: vec![
                 InputData::Absent,
                 InputData::Input(0),
@@ -274,4 +295,40 @@ mod tests {
         assert_eq!(input_buffer.get(Tick(14)), Some(&0));
         assert_eq!(input_buffer.get(Tick(13)), None);
     }
+
+    #[test]
+    fn test_tick_wrapping() {
+        let mut input_buffer = InputBuffer::default();
+
+        input_buffer.set(Tick(u16::MAX - 1), Some(0));
+        input_buffer.set(Tick(1), Some(1));
+
+        assert_eq!(input_buffer.get(Tick(u16::MAX - 1)), Some(&0));
+        assert_eq!(input_buffer.get(Tick(u16::MAX)), None);
+        assert_eq!(input_buffer.get(Tick(1)), Some(&1));
+
+        assert_eq!(input_buffer.pop(Tick(0)), None);
+        assert_eq!(input_buffer.start_tick, Some(Tick(1)));
+        assert_eq!(input_buffer.pop(Tick(1)), Some(1));
+    }
+
+    /// The buffer should keep working if it is not updated for more than i16::MAX ticks
+    #[test]
+    fn test_stale_buffer() {
+        let mut input_buffer = InputBuffer::default();
+        input_buffer.set(Tick(1), Some(0));
+
+        // this tick looks older than the start of the buffer because of wrapping
+        let tick = Tick(1) + Tick(40000);
+        assert_eq!(input_buffer.get(tick), None);
+        input_buffer.set(tick, Some(1));
+        assert_eq!(input_buffer.get(tick), Some(&1));
+        assert_eq!(input_buffer.pop(tick), Some(1));
+
+        // popping a tick that is too far from the buffer resets it
+        input_buffer.set(Tick(2), Some(2));
+        assert_eq!(input_buffer.pop(Tick(2) + Tick(40000)), None);
+        assert_eq!(input_buffer.start_tick, Some(Tick(40003)));
+        assert_eq!(input_buffer.buffer.len(), 0);
+    }
 }
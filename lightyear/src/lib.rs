@@ -22,7 +22,8 @@ pub mod _reexport {
     pub use paste::paste;
 
     pub use lightyear_macros::{
-        component_protocol_internal, message_protocol_internal, ChannelInternal, MessageInternal,
+        component_protocol_internal, message_protocol_internal, quantized_internal,
+        ChannelInternal, MessageInternal,
     };
 
     pub use crate::channel::builder::TickBufferChannel;
@@ -32,7 +33,10 @@ pub mod _reexport {
     pub use crate::client::interpolation::{
         add_interpolation_systems, add_prepare_interpolation_systems,
     };
-    pub use crate::client::interpolation::{LinearInterpolator, NullInterpolator};
+    pub use crate::client::interpolation::{
+        CatmullRomInterpolator, HermiteInterpolator, LinearExtrapolator, LinearInterpolator,
+        NullExtrapolator, NullInterpolator,
+    };
     pub use crate::client::prediction::add_prediction_systems;
     pub use crate::client::prediction::correction::{InstantCorrector, InterpolatedCorrector};
     pub use crate::protocol::component::{
@@ -41,6 +45,7 @@ pub mod _reexport {
     };
     pub use crate::protocol::message::InputMessageKind;
     pub use crate::protocol::message::{MessageKind, MessageProtocol};
+    pub use crate::protocol::message_registry::DynamicMessage;
     pub use crate::protocol::{BitSerializable, EventContext};
     pub use crate::serialize::reader::ReadBuffer;
     pub use crate::serialize::wordbuffer::reader::ReadWordBuffer;
@@ -66,31 +71,45 @@ pub mod _reexport {
 
 /// Prelude containing commonly used types
 pub mod prelude {
-    pub use lightyear_macros::{component_protocol, message_protocol, Channel, Message};
+    pub use lightyear_macros::{component_protocol, message_protocol, quantized, Channel, Message};
 
     pub use crate::channel::builder::TickBufferChannel;
     pub use crate::channel::builder::{
         Channel, ChannelBuilder, ChannelContainer, ChannelDirection, ChannelMode, ChannelSettings,
         DefaultUnorderedUnreliableChannel, ReliableSettings,
     };
-    pub use crate::client::prediction::prespawn::PreSpawnedPlayerObject;
-    pub use crate::connection::netcode::{generate_key, ClientId, Key};
+    pub use crate::client::prediction::prespawn::{
+        ClientNoMatchHandling, ConflictResolution, PreSpawnEvent, PreSpawnedPlayerObject,
+        ServerNoMatchHandling,
+    };
+    pub use crate::connection::netcode::{generate_key, ClientId, DisconnectReason, Key};
     #[cfg(feature = "leafwing")]
     pub use crate::inputs::leafwing::LeafwingUserAction;
     pub use crate::inputs::native::UserAction;
     pub use crate::packet::message::Message;
     pub use crate::protocol::channel::{ChannelKind, ChannelRegistry};
+    pub use crate::protocol::fingerprint::ProtocolFingerprint;
+    pub use crate::protocol::message_registry::{AppMessageExt, MessageRegistry};
     pub use crate::protocol::Protocol;
     pub use crate::protocolize;
+    pub use crate::serialize::quantize::Quantize;
     pub use crate::shared::config::SharedConfig;
+    pub use crate::shared::desync::DesyncConfig;
+    #[cfg(all(feature = "inspector", not(target_family = "wasm")))]
+    pub use crate::shared::inspector::InspectorConfig;
     pub use crate::shared::ping::manager::PingConfig;
     pub use crate::shared::plugin::{NetworkIdentity, SharedPlugin};
     pub use crate::shared::replication::components::{
-        NetworkTarget, ReplicationGroup, ReplicationMode, ShouldBePredicted,
+        Detached, DetachedComponents, NetworkTarget, ReplicationGroup, ReplicationMode,
+        ReplicationStopPolicy, ShouldBePredicted,
     };
     pub use crate::shared::replication::entity_map::{LightyearMapEntities, RemoteEntityMap};
     pub use crate::shared::replication::hierarchy::ParentSync;
+    pub use crate::shared::replication::resources::{
+        ReplicateResource, ReplicateResourceMessage, ResourceReplicationPlugin,
+    };
     pub use crate::shared::sets::{FixedUpdateSet, MainSet, ReplicationSet};
+    pub use crate::shared::tick_event::NetworkedEvent;
     pub use crate::shared::tick_manager::TickManager;
     pub use crate::shared::tick_manager::{Tick, TickConfig};
     pub use crate::shared::time_manager::TimeManager;
@@ -100,9 +119,12 @@ pub mod prelude {
 
     pub mod client {
         pub use crate::client::components::{
-            ComponentSyncMode, Confirmed, LerpFn, SyncComponent, SyncMetadata,
+            ComponentSyncMode, Confirmed, ExtrapolateFn, InterpolationSamples, LerpFn,
+            SyncComponent, SyncMetadata,
+        };
+        pub use crate::client::config::{
+            ClientConfig, NetcodeConfig, PacketConfig, ReplicationConfig,
         };
-        pub use crate::client::config::{ClientConfig, NetcodeConfig, PacketConfig};
         pub use crate::client::events::{
             ComponentInsertEvent, ComponentRemoveEvent, ComponentUpdateEvent, ConnectEvent,
             DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, InputEvent, MessageEvent,
@@ -110,20 +132,33 @@ pub mod prelude {
         pub use crate::client::input::{InputConfig, InputSystemSet};
         pub use crate::client::interpolation::interpolation_history::ConfirmedHistory;
         pub use crate::client::interpolation::plugin::{
-            InterpolationConfig, InterpolationDelay, InterpolationSet,
+            ExtrapolationConfig, InterpolationConfig, InterpolationDelay, InterpolationSet,
         };
         pub use crate::client::interpolation::{
-            InterpolateStatus, Interpolated, VisualInterpolateStatus, VisualInterpolationPlugin,
+            HermiteVelocity, InterpolateStatus, Interpolated, VisualInterpolateStatus,
+            VisualInterpolationPlugin,
         };
         pub use crate::client::plugin::{ClientPlugin, PluginConfig};
         pub use crate::client::prediction::correction::Correction;
+        pub use crate::client::prediction::desync::{DesyncDetectionPlugin, DesyncEvent};
         pub use crate::client::prediction::plugin::is_in_rollback;
         pub use crate::client::prediction::plugin::{PredictionConfig, PredictionSet};
         pub use crate::client::prediction::predicted_history::{ComponentState, PredictionHistory};
-        pub use crate::client::prediction::{Predicted, PredictionDespawnCommandsExt};
+        pub use crate::client::prediction::{
+            DisabledDuringRollback, Predicted, PredictedOnly, PredictionDespawnCommandsExt,
+            RollbackEligibility, RollbackIsland,
+        };
         pub use crate::client::resource::Authentication;
         pub use crate::client::sync::SyncConfig;
-        pub use crate::connection::client::{ClientConnection, NetClient, NetConfig};
+        pub use crate::client::tick_event::{TickEvent, TickEventPlugin, TickEventTimeline};
+        pub use crate::connection::client::{
+            ClientConnection, ConnectionError, NetClient, NetConfig,
+        };
+
+        #[cfg(all(feature = "inspector", not(target_family = "wasm")))]
+        pub use crate::client::inspector::InspectorPlugin;
+        #[cfg(feature = "websocket")]
+        pub use crate::transport::websocket::WebSocketClientTls;
 
         #[cfg(feature = "leafwing")]
         pub use crate::client::input_leafwing::{
@@ -131,17 +166,29 @@ pub mod prelude {
         };
     }
     pub mod server {
-        pub use crate::server::config::{NetcodeConfig, PacketConfig, ServerConfig};
+        pub use crate::server::config::{
+            NetcodeConfig, PacketConfig, ReplicationConfig, ReplicationSendMode, ServerConfig,
+        };
+        pub use crate::server::desync::{DesyncDetectionPlugin, DesyncSet};
         pub use crate::server::events::{
             ComponentInsertEvent, ComponentRemoveEvent, ComponentUpdateEvent, ConnectEvent,
             DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent, InputEvent, MessageEvent,
         };
+        pub use crate::server::lag_compensation::{
+            LagCompensated, LagCompensation, LagCompensationConfig, LagCompensationHistory,
+            LagCompensationPlugin,
+        };
         pub use crate::server::plugin::{PluginConfig, ServerPlugin};
         pub use crate::server::room::{RoomId, RoomManager, RoomMut, RoomRef};
+        pub use crate::server::tick_event::{TickEvent, TickEventPlugin};
 
         pub use crate::connection::server::{NetConfig, NetServer, ServerConnection};
         #[cfg(feature = "leafwing")]
         pub use crate::server::input_leafwing::LeafwingInputPlugin;
+        #[cfg(all(feature = "inspector", not(target_family = "wasm")))]
+        pub use crate::server::inspector::InspectorPlugin;
+        #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
+        pub use crate::transport::certificate::TlsCertificate;
         #[cfg(all(feature = "webtransport", not(target_family = "wasm")))]
         pub use wtransport::tls::Certificate;
     }
@@ -167,6 +214,11 @@ pub mod shared;
 #[cfg(test)]
 pub(crate) mod tests;
 
+/// Helpers to write deterministic integration tests with a server and multiple clients
+#[cfg_attr(docsrs, doc(cfg(feature = "test_utils")))]
+#[cfg(any(test, feature = "test_utils"))]
+pub mod test_utils;
+
 /// Provides an abstraction over an unreliable transport
 pub mod transport;
 /// Extra utilities
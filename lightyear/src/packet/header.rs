@@ -91,6 +91,11 @@ impl PacketHeaderManager {
         }
     }
 
+    /// Fraction of the sent packets that were lost recently
+    pub(crate) fn packet_loss(&self) -> f32 {
+        self.stats_manager.packet_loss()
+    }
+
     pub(crate) fn update(&mut self, time_manager: &TimeManager) {
         self.current_time = time_manager.current_time();
         self.stats_manager.update(time_manager);
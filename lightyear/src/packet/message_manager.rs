@@ -1,11 +1,11 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 use anyhow::{anyhow, Context};
 use bevy::reflect::Reflect;
 use crossbeam_channel::Receiver;
 use tracing::trace;
 
-use crate::channel::builder::ChannelContainer;
+use crate::channel::builder::{ChannelContainer, ChannelStats};
 use crate::channel::receivers::ChannelReceive;
 use crate::channel::senders::ChannelSend;
 use crate::packet::message::{FragmentData, MessageAck, MessageId, SingleData};
@@ -34,6 +34,9 @@ pub const DEFAULT_MESSAGE_PRIORITY: f32 = 1.0;
 pub struct MessageManager {
     /// Handles sending/receiving packets (including acks)
     packet_manager: PacketBuilder,
+    /// Builds the packets sent on the reliable stream of the transport.
+    /// They have their own packet ids, since they are never acked.
+    stream_packet_manager: PacketBuilder,
     priority_manager: PriorityManager,
     pub(crate) channels: HashMap<ChannelKind, ChannelContainer>,
     pub(crate) channel_registry: ChannelRegistry,
@@ -42,20 +45,49 @@ pub struct MessageManager {
     /// reliable senders can stop trying to send a message that has already been received
     packet_to_message_ack_map: HashMap<PacketId, HashMap<ChannelKind, Vec<MessageAck>>>,
     writer: WriteWordBuffer,
+    /// True if the transport provides a reliable ordered stream to the remote.
+    /// In that case, the channels that use the transport stream are sent with [`MessageManager::send_stream_packets`]
+    reliable_stream: bool,
 }
 
 impl MessageManager {
     pub fn new(channel_registry: &ChannelRegistry, priority_config: PriorityConfig) -> Self {
         Self {
             packet_manager: PacketBuilder::new(),
+            stream_packet_manager: PacketBuilder::new(),
             priority_manager: PriorityManager::new(priority_config),
             channels: channel_registry.channels(),
             channel_registry: channel_registry.clone(),
             packet_to_message_ack_map: HashMap::new(),
             writer: WriteWordBuffer::with_capacity(PACKET_BUFFER_CAPACITY),
+            reliable_stream: false,
         }
     }
 
+    /// Fraction of the sent packets that were lost recently
+    pub(crate) fn packet_loss(&self) -> f32 {
+        self.packet_manager.header_manager.packet_loss()
+    }
+
+    /// Number of messages and bytes sent and received on each channel since the start of the connection
+    pub(crate) fn channel_stats(&self) -> impl Iterator<Item = (&ChannelKind, &ChannelStats)> {
+        self.channels
+            .iter()
+            .map(|(kind, channel)| (kind, &channel.stats))
+    }
+
+    /// Total number of bytes buffered for sending on the channel
+    pub(crate) fn bytes_sent(&self, channel_kind: &ChannelKind) -> u64 {
+        self.channels
+            .get(channel_kind)
+            .map_or(0, |channel| channel.stats.bytes_sent)
+    }
+
+    /// Notify the message manager if the transport provides a reliable ordered stream to the remote
+    pub(crate) fn set_reliable_stream(&mut self, reliable_stream: bool) {
+        self.reliable_stream = reliable_stream;
+    }
+
     pub(crate) fn get_replication_update_send_receiver(&mut self) -> Receiver<MessageId> {
         self.priority_manager
             .subscribe_replication_update_sent_messages()
@@ -69,6 +101,9 @@ impl MessageManager {
         tick_manager: &TickManager,
     ) {
         self.packet_manager.header_manager.update(time_manager);
+        self.stream_packet_manager
+            .header_manager
+            .update(time_manager);
         for channel in self.channels.values_mut() {
             channel
                 .sender
@@ -112,6 +147,8 @@ impl MessageManager {
         self.writer.start_write();
         message.encode(&mut self.writer)?;
         let message_bytes: Vec<u8> = self.writer.finish_write().into();
+        channel.stats.messages_sent += 1;
+        channel.stats.bytes_sent += message_bytes.len() as u64;
         Ok(channel.sender.buffer_send(message_bytes.into(), priority))
     }
 
@@ -126,6 +163,10 @@ impl MessageManager {
         let mut data_to_send: Vec<(NetId, (VecDeque<SingleData>, VecDeque<FragmentData>))> = vec![];
         let mut has_data_to_send = false;
         for (channel_kind, channel) in self.channels.iter_mut() {
+            // these channels are sent in `send_stream_packets`
+            if self.reliable_stream && channel.setting.mode.uses_transport_stream() {
+                continue;
+            }
             let channel_id = self
                 .channel_registry
                 .get_net_from_kind(channel_kind)
@@ -194,7 +235,12 @@ impl MessageManager {
                 })?;
         }
 
-        // adjust the real amount of bytes that we sent through the limiter (to account for the actual packet size)
+        self.update_limiter(&bytes, num_bytes_added_to_limiter);
+        Ok(bytes)
+    }
+
+    /// Adjust the real amount of bytes that we sent through the limiter (to account for the actual packet size)
+    fn update_limiter(&mut self, bytes: &[Payload], num_bytes_added_to_limiter: u32) {
         if self.priority_manager.config.enabled {
             let total_bytes_sent = bytes.iter().map(|b| b.len() as u32).sum::<u32>();
             if let Ok(remaining_bytes_to_add) =
@@ -206,7 +252,69 @@ impl MessageManager {
                     .check_n(remaining_bytes_to_add);
             }
         }
+    }
+
+    /// Prepare the packets for the channels that are sent on the reliable stream of the transport,
+    /// and return the bytes to send on that stream.
+    ///
+    /// Since the transport guarantees that these packets will be delivered, the messages are
+    /// considered delivered as soon as they are sent and are never resent by lightyear.
+    /// The packets count against the same bandwidth quota as the datagrams.
+    pub fn send_stream_packets(&mut self, current_tick: Tick) -> anyhow::Result<Vec<Payload>> {
+        if !self.reliable_stream {
+            return Ok(vec![]);
+        }
+        let mut data_to_send: Vec<(NetId, (VecDeque<SingleData>, VecDeque<FragmentData>))> = vec![];
+        for (channel_kind, channel) in self.channels.iter_mut() {
+            if !channel.setting.mode.uses_transport_stream() {
+                continue;
+            }
+            let channel_id = self
+                .channel_registry
+                .get_net_from_kind(channel_kind)
+                .context("cannot find channel id")?;
+            channel.sender.collect_messages_to_send();
+            if channel.sender.has_messages_to_send() {
+                let (single_data, fragment_data) = channel.sender.send_packet();
+                if !single_data.is_empty() || !fragment_data.is_empty() {
+                    data_to_send.push((*channel_id, (single_data, fragment_data)));
+                }
+            }
+        }
+        if data_to_send.is_empty() {
+            return Ok(vec![]);
+        }
 
+        // the messages that don't fit in the bandwidth quota are not marked as delivered, so the
+        // reliable senders will send them again later
+        let (data_to_send, num_bytes_added_to_limiter) = self.priority_manager.priority_filter(
+            data_to_send,
+            &self.channel_registry,
+            current_tick,
+        );
+
+        let packets = self.stream_packet_manager.build_packets(data_to_send);
+        let mut bytes = Vec::new();
+        for mut packet in packets {
+            trace!(num_messages = ?packet.data.num_messages(), "sending packet on stream");
+            packet.header.tick = current_tick;
+            bytes.push(self.stream_packet_manager.encode_packet(&packet)?);
+
+            // the stream is reliable: the messages don't need to wait for an ack to be considered delivered
+            for (channel_id, message_acks) in packet.message_acks() {
+                let channel_kind = self
+                    .channel_registry
+                    .get_kind_from_net_id(channel_id)
+                    .context("cannot find channel kind")?;
+                let channel = self
+                    .channels
+                    .get_mut(channel_kind)
+                    .context("Channel not found")?;
+                for message_ack in message_acks {
+                    channel.sender.notify_message_delivered(&message_ack);
+                }
+            }
+        }
         Ok(bytes)
     }
 
@@ -248,6 +356,25 @@ impl MessageManager {
         }
 
         // Step 4. Put the messages from the packet in the internal buffers for each channel
+        self.buffer_received_messages(packet)?;
+        Ok(tick)
+    }
+
+    /// Process a packet received on the reliable stream of the transport.
+    ///
+    /// These packets don't carry any ack information, so the messages are directly put in the internal buffers.
+    /// Returns the tick of the packet
+    pub fn recv_stream_packet(&mut self, reader: &mut impl ReadBuffer) -> anyhow::Result<Tick> {
+        let packet: Packet = self.stream_packet_manager.decode_packet(reader)?;
+        let tick = packet.header().tick;
+        trace!(?packet, "Received packet on stream");
+        self.buffer_received_messages(packet)?;
+        Ok(tick)
+    }
+
+    /// Put the messages from the packet in the internal buffers for each channel
+    fn buffer_received_messages(&mut self, packet: Packet) -> anyhow::Result<()> {
+        let tick = packet.header().tick;
         for (channel_net_id, messages) in packet.data.contents() {
             let channel_kind = self
                 .channel_registry
@@ -270,7 +397,7 @@ impl MessageManager {
                 channel.receiver.buffer_recv(message)?;
             }
         }
-        Ok(tick)
+        Ok(())
     }
 
     /// Read all the messages in the internal buffers that are ready to be processed
@@ -282,6 +409,8 @@ impl MessageManager {
             let mut messages = vec![];
             while let Some(single_data) = channel.receiver.read_message() {
                 trace!(?channel_kind, "reading message: {:?}", single_data);
+                channel.stats.messages_received += 1;
+                channel.stats.bytes_received += single_data.bytes.len() as u64;
                 let mut reader = ReadWordBuffer::start_read(single_data.bytes.as_ref());
                 let message = M::decode(&mut reader).expect("Could not decode message");
                 // TODO: why do we need finish read? to check for errors?
@@ -306,12 +435,15 @@ mod tests {
     use std::collections::HashMap;
 
     use bevy::utils::Duration;
+    use governor::{DefaultDirectRateLimiter, Quota};
+    use nonzero_ext::nonzero;
 
     use crate::_reexport::*;
     use crate::packet::message::MessageId;
     use crate::packet::packet::FRAGMENT_SIZE;
     use crate::packet::priority_manager::PriorityConfig;
     use crate::prelude::*;
+    use crate::shared::ping::manager::PingConfig;
     use crate::tests::protocol::*;
 
     use super::*;
@@ -572,4 +704,178 @@ mod tests {
         assert_eq!(update_acks_tracker.try_recv()?, message_id);
         Ok(())
     }
+
+    #[test]
+    fn test_channel_stats() -> anyhow::Result<()> {
+        let protocol = protocol();
+        let mut client_message_manager =
+            MessageManager::new(protocol.channel_registry(), PriorityConfig::default());
+        let mut server_message_manager =
+            MessageManager::new(protocol.channel_registry(), PriorityConfig::default());
+        let stats = |manager: &MessageManager, kind: ChannelKind| {
+            *manager
+                .channel_stats()
+                .find(|(k, _)| **k == kind)
+                .unwrap()
+                .1
+        };
+
+        client_message_manager.buffer_send(
+            MyMessageProtocol::Message1(Message1("1".to_string())),
+            Channel1::kind(),
+        )?;
+        client_message_manager
+            .buffer_send(MyMessageProtocol::Message2(Message2(1)), Channel1::kind())?;
+        for payload in client_message_manager.send_packets(Tick(0))?.iter_mut() {
+            server_message_manager
+                .recv_packet(&mut ReadWordBuffer::start_read(payload.as_slice()))?;
+        }
+        server_message_manager.read_messages::<MyMessageProtocol>();
+
+        let client_stats = stats(&client_message_manager, Channel1::kind());
+        assert_eq!(client_stats.messages_sent, 2);
+        assert!(client_stats.bytes_sent > 0);
+        assert_eq!(client_stats.messages_received, 0);
+        let server_stats = stats(&server_message_manager, Channel1::kind());
+        assert_eq!(server_stats.messages_received, 2);
+        assert_eq!(server_stats.bytes_received, client_stats.bytes_sent);
+        assert_eq!(server_stats.messages_sent, 0);
+        // the other channels are not affected
+        assert_eq!(
+            stats(&client_message_manager, Channel2::kind()),
+            ChannelStats::default()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_send_on_transport_stream() -> anyhow::Result<()> {
+        let protocol = protocol();
+        let stream_settings = ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings {
+                use_transport_stream: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut client_message_manager =
+            MessageManager::new(protocol.channel_registry(), PriorityConfig::default());
+        let mut server_message_manager =
+            MessageManager::new(protocol.channel_registry(), PriorityConfig::default());
+        for manager in [&mut client_message_manager, &mut server_message_manager] {
+            manager.channels.insert(
+                Channel2::kind(),
+                ChannelContainer::new(stream_settings.clone()),
+            );
+        }
+        let message = MyMessageProtocol::Message2(Message2(1));
+
+        // without a reliable stream, the channel is sent as datagrams
+        client_message_manager.buffer_send(message.clone(), Channel2::kind())?;
+        assert!(client_message_manager
+            .send_stream_packets(Tick(0))?
+            .is_empty());
+        let payloads = client_message_manager.send_packets(Tick(0))?;
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(client_message_manager.packet_to_message_ack_map.len(), 1);
+        for payload in payloads.iter() {
+            server_message_manager.recv_packet(&mut ReadWordBuffer::start_read(payload))?;
+        }
+        assert_eq!(
+            server_message_manager.read_messages(),
+            HashMap::from([(Channel2::kind(), vec![(Tick(0), message.clone())])])
+        );
+
+        // with a reliable stream, the channel is only sent on the stream
+        client_message_manager.set_reliable_stream(true);
+        client_message_manager.packet_to_message_ack_map.clear();
+        // simulate the ack of the first message
+        client_message_manager
+            .channels
+            .get_mut(&Channel2::kind())
+            .unwrap()
+            .sender
+            .notify_message_delivered(&MessageAck {
+                message_id: MessageId(0),
+                fragment_id: None,
+            });
+        client_message_manager.buffer_send(message.clone(), Channel2::kind())?;
+        assert!(client_message_manager.send_packets(Tick(0))?.is_empty());
+        let next_packet_id = client_message_manager
+            .packet_manager
+            .header_manager
+            .next_packet_id();
+        let payloads = client_message_manager.send_stream_packets(Tick(0))?;
+        assert_eq!(payloads.len(), 1);
+        // the stream packets don't use the packet ids of the datagrams
+        assert_eq!(
+            client_message_manager
+                .packet_manager
+                .header_manager
+                .next_packet_id(),
+            next_packet_id
+        );
+        // the message is considered delivered right away, without waiting for an ack
+        assert!(client_message_manager.packet_to_message_ack_map.is_empty());
+        let channel = client_message_manager
+            .channels
+            .get_mut(&Channel2::kind())
+            .unwrap();
+        channel.sender.collect_messages_to_send();
+        assert!(!channel.sender.has_messages_to_send());
+
+        for payload in payloads.iter() {
+            server_message_manager.recv_stream_packet(&mut ReadWordBuffer::start_read(payload))?;
+        }
+        assert_eq!(
+            server_message_manager.read_messages(),
+            HashMap::from([(Channel2::kind(), vec![(Tick(0), message)])])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_packets_use_bandwidth_quota() -> anyhow::Result<()> {
+        let protocol = protocol();
+        let mut message_manager = MessageManager::new(
+            protocol.channel_registry(),
+            PriorityConfig {
+                bandwidth_quota: Quota::per_second(nonzero!(1000u32)),
+                enabled: true,
+            },
+        );
+        message_manager.channels.insert(
+            Channel2::kind(),
+            ChannelContainer::new(ChannelSettings {
+                mode: ChannelMode::OrderedReliable(ReliableSettings {
+                    use_transport_stream: true,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        );
+        message_manager.set_reliable_stream(true);
+
+        // use up the bandwidth quota
+        message_manager
+            .priority_manager
+            .limiter
+            .check_n(nonzero!(1000u32))?
+            .unwrap();
+        message_manager.buffer_send(MyMessageProtocol::Message2(Message2(1)), Channel2::kind())?;
+        assert!(message_manager.send_stream_packets(Tick(0))?.is_empty());
+
+        // the message was not considered delivered, so it is sent again once there is bandwidth available
+        message_manager.priority_manager.limiter =
+            DefaultDirectRateLimiter::direct(Quota::per_second(nonzero!(1000u32)));
+        let mut time_manager = TimeManager::new(Duration::default());
+        time_manager.update(Duration::from_secs(1));
+        message_manager.update(
+            &time_manager,
+            &PingManager::new(PingConfig::default()),
+            &TickManager::from_config(TickConfig::new(Duration::from_millis(10))),
+        );
+        assert_eq!(message_manager.send_stream_packets(Tick(0))?.len(), 1);
+        Ok(())
+    }
 }
@@ -88,6 +88,11 @@ impl PacketStatsManager {
         self.current_stats.num_sent_packets += 1;
     }
 
+    /// Fraction of the sent packets that were lost, over the stats buffer duration
+    pub(crate) fn packet_loss(&self) -> f32 {
+        self.final_stats.packet_loss
+    }
+
     /// Notify that a packet we sent got lost (we did not receive an ack for it)
     pub(crate) fn sent_packet_lost(&mut self) {
         #[cfg(feature = "metrics")]
@@ -84,6 +84,16 @@ impl ChannelRegistry {
         self.name_map.get(kind).map(|s| s.as_str())
     }
 
+    /// Iterate through the channel names and settings, in registration order
+    pub(crate) fn iter_ordered(&self) -> impl Iterator<Item = (&str, &ChannelSettings)> {
+        (0..self.kind_map.next_net_id).filter_map(|net_id| {
+            let kind = self.kind_map.kind(net_id)?;
+            let name = self.name(kind)?;
+            let builder = self.get_builder_from_kind(kind)?;
+            Some((name, &builder.settings))
+        })
+    }
+
     pub fn get_builder_from_net_id(&self, channel_id: ChannelId) -> Option<&ChannelBuilder> {
         let channel_kind = self.get_kind_from_net_id(channel_id)?;
         self.get_builder_from_kind(channel_kind)
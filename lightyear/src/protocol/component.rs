@@ -6,11 +6,13 @@ use bevy::prelude::{App, Component, Entity, EntityWorldMut, World};
 use bevy::utils::HashMap;
 use cfg_if::cfg_if;
 
-use crate::_reexport::{InstantCorrector, NullInterpolator};
+use crate::_reexport::{InstantCorrector, NullExtrapolator, NullInterpolator};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::client::components::{ComponentSyncMode, LerpFn, SyncMetadata};
+use crate::client::components::{
+    ComponentSyncMode, ExtrapolateFn, InterpolationSamples, LerpFn, SyncMetadata,
+};
 use crate::prelude::{LightyearMapEntities, Message, Named, PreSpawnedPlayerObject};
 use crate::protocol::{BitSerializable, EventContext, Protocol};
 use crate::shared::events::connection::{
@@ -43,6 +45,9 @@ pub trait ComponentProtocol:
     /// Map from the type-id to the component kind for each component in the protocol
     fn type_ids() -> HashMap<TypeId, <Self::Protocol as Protocol>::ComponentKinds>;
 
+    /// Names of all the component types in the protocol, in registration order
+    fn type_names() -> Vec<&'static str>;
+
     /// Apply a ComponentInsert to an entity
     fn insert(self, entity: &mut EntityWorldMut);
 
@@ -94,6 +99,14 @@ pub trait ComponentProtocol:
         TypeId::of::<<Self as SyncMetadata<C>>::Interpolator>() != TypeId::of::<NullInterpolator>()
     }
 
+    /// If false, we don't want to extrapolate the component when we run out of server updates
+    fn has_extrapolation<C>() -> bool
+    where
+        Self: SyncMetadata<C>,
+    {
+        TypeId::of::<<Self as SyncMetadata<C>>::Extrapolator>() != TypeId::of::<NullExtrapolator>()
+    }
+
     /// If false, we don't want to apply any corrections
     fn has_correction<C>() -> bool
     where
@@ -110,6 +123,22 @@ pub trait ComponentProtocol:
         <Self as SyncMetadata<C>>::Interpolator::lerp(start, other, t)
     }
 
+    /// Interpolate the component using the server states surrounding the interpolation tick
+    fn interpolate<C>(samples: &InterpolationSamples<C>, t: f32) -> C
+    where
+        Self: SyncMetadata<C>,
+    {
+        <Self as SyncMetadata<C>>::Interpolator::interpolate(samples, t)
+    }
+
+    /// Project the component forward past the last received server state
+    fn extrapolate<C>(previous: &C, last: &C, t: f32) -> C
+    where
+        Self: SyncMetadata<C>,
+    {
+        <Self as SyncMetadata<C>>::Extrapolator::extrapolate(previous, last, t)
+    }
+
     fn correct<C>(predicted: &C, corrected: &C, t: f32) -> C
     where
         Self: SyncMetadata<C>,
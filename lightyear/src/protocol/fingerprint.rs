@@ -0,0 +1,224 @@
+//! Fingerprint of the [`Protocol`], used to reject clients whose protocol does not match the server's.
+//!
+//! The `NetId`s of messages, components and channels are assigned by registration order, so a client
+//! built with a slightly different protocol would silently decode garbage.
+//! Instead, the fingerprint is sent in the connection request and the server denies the connection if it differs.
+use bevy::prelude::Resource;
+
+use crate::channel::builder::{ChannelDirection, ChannelMode, ChannelSettings, ReliableSettings};
+use crate::protocol::component::ComponentProtocol;
+use crate::protocol::message::MessageProtocol;
+use crate::protocol::message_registry::MessageRegistry;
+use crate::protocol::Protocol;
+use crate::shared::config::SharedConfig;
+
+/// Deterministic hash of everything that has to be identical between the client and the server:
+/// - the message, component and channel types, in registration order
+/// - the channel settings
+/// - the tick duration
+/// - the messages registered at runtime in the [`MessageRegistry`]
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProtocolFingerprint(pub u64);
+
+impl ProtocolFingerprint {
+    pub fn new<P: Protocol>(protocol: &P, shared_config: &SharedConfig) -> Self {
+        let mut hasher = FnvHasher::default();
+        hasher.write_str("messages");
+        for name in <P::Message as MessageProtocol>::type_names() {
+            hasher.write_str(name);
+        }
+        hasher.write_str("components");
+        for name in <P::Components as ComponentProtocol>::type_names() {
+            hasher.write_str(name);
+        }
+        hasher.write_str("channels");
+        for (name, settings) in protocol.channel_registry().iter_ordered() {
+            hasher.write_str(name);
+            hasher.write_channel_settings(settings);
+        }
+        hasher.write_str("tick");
+        hasher.write(&shared_config.tick.tick_duration.as_nanos().to_le_bytes());
+        Self(hasher.finish())
+    }
+
+    /// Extend the fingerprint with the messages registered in the [`MessageRegistry`]
+    pub(crate) fn with_registered_messages(self, registry: &MessageRegistry) -> Self {
+        let mut hasher = FnvHasher(self.0);
+        hasher.write_str("registered messages");
+        for name in registry.names() {
+            hasher.write_str(name);
+        }
+        Self(hasher.finish())
+    }
+}
+
+/// 64-bit FNV-1a hasher.
+///
+/// We don't use the std `DefaultHasher` because its output is not guaranteed to be the same
+/// across Rust versions, and the client and server could be compiled with different toolchains.
+pub(crate) struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl FnvHasher {
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// Write a length-prefixed string, so that `["ab", "c"]` and `["a", "bc"]` hash differently
+    pub(crate) fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    /// Write every field of the channel settings.
+    ///
+    /// The settings are destructured, so that adding a field fails to compile until it is hashed here.
+    fn write_channel_settings(&mut self, settings: &ChannelSettings) {
+        let ChannelSettings {
+            mode,
+            direction,
+            priority,
+        } = settings;
+        let (mode, reliable_settings) = match mode {
+            ChannelMode::UnorderedUnreliableWithAcks => (0u8, None),
+            ChannelMode::UnorderedUnreliable => (1, None),
+            ChannelMode::SequencedUnreliable => (2, None),
+            ChannelMode::UnorderedReliable(settings) => (3, Some(settings)),
+            ChannelMode::SequencedReliable(settings) => (4, Some(settings)),
+            ChannelMode::OrderedReliable(settings) => (5, Some(settings)),
+            ChannelMode::TickBuffered => (6, None),
+        };
+        self.write(&[mode]);
+        if let Some(ReliableSettings {
+            rtt_resend_factor,
+            rtt_resend_min_delay,
+            use_transport_stream,
+        }) = reliable_settings
+        {
+            self.write(&rtt_resend_factor.to_le_bytes());
+            self.write(&rtt_resend_min_delay.as_nanos().to_le_bytes());
+            self.write(&[*use_transport_stream as u8]);
+        }
+        let direction: u8 = match direction {
+            ChannelDirection::ClientToServer => 0,
+            ChannelDirection::ServerToClient => 1,
+            ChannelDirection::Bidirectional => 2,
+        };
+        self.write(&[direction]);
+        self.write(&priority.to_le_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::prelude::default;
+    use lightyear_macros::MessageInternal;
+    use serde::{Deserialize, Serialize};
+
+    use crate::connection::client::{ClientConnection, ConnectionError, NetClient};
+    use crate::prelude::client::{InterpolationConfig, PredictionConfig, SyncConfig};
+    use crate::prelude::*;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::BevyStepper;
+
+    use super::*;
+
+    #[derive(MessageInternal, Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct ClientOnlyMessage(u32);
+
+    #[test]
+    fn test_protocol_fingerprint() {
+        let shared_config = SharedConfig::default();
+        let fingerprint = ProtocolFingerprint::new(&protocol(), &shared_config);
+        // the fingerprint is deterministic
+        assert_eq!(
+            fingerprint,
+            ProtocolFingerprint::new(&protocol(), &shared_config)
+        );
+
+        // a different tick duration changes the fingerprint
+        let other_shared_config = SharedConfig {
+            tick: TickConfig::new(Duration::from_millis(10)),
+            ..default()
+        };
+        assert_ne!(
+            fingerprint,
+            ProtocolFingerprint::new(&protocol(), &other_shared_config)
+        );
+
+        // different channel settings change the fingerprint
+        let mut other_protocol = MyProtocol::default();
+        other_protocol.add_channel::<Channel1>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        });
+        other_protocol.add_channel::<Channel2>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliableWithAcks,
+            ..default()
+        });
+        assert_ne!(
+            fingerprint,
+            ProtocolFingerprint::new(&other_protocol, &shared_config)
+        );
+    }
+
+    /// The server denies the connection of a client whose protocol doesn't match its own
+    #[test]
+    fn test_protocol_mismatch_denies_connection() {
+        let frame_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(Duration::from_millis(10)),
+            ..default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            SyncConfig::default(),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            link_conditioner,
+            frame_duration,
+        );
+        // only the client registers this message, so the fingerprints differ
+        stepper.client_app.register_message::<ClientOnlyMessage>();
+        stepper.init();
+
+        let client_fingerprint = stepper.client_app.world.resource::<ProtocolFingerprint>().0;
+        assert_ne!(
+            client_fingerprint,
+            stepper.server_app.world.resource::<ProtocolFingerprint>().0
+        );
+        let connection = stepper.client_app.world.resource::<ClientConnection>();
+        assert!(!connection.is_connected());
+        assert_eq!(
+            connection.connection_error(),
+            Some(ConnectionError::ProtocolMismatch {
+                protocol_hash: client_fingerprint
+            })
+        );
+        assert!(stepper
+            .server_app
+            .world
+            .resource::<ServerConnectionManager>()
+            .connections
+            .is_empty());
+    }
+}
@@ -8,6 +8,7 @@ use serde::Serialize;
 use crate::inputs::native::input_buffer::InputMessage;
 use crate::packet::message::Message;
 use crate::prelude::LightyearMapEntities;
+use crate::protocol::message_registry::DynamicMessage;
 use crate::protocol::registry::TypeKind;
 use crate::protocol::{BitSerializable, EventContext, Protocol};
 #[cfg(feature = "leafwing")]
@@ -37,12 +38,16 @@ pub trait MessageProtocol:
     + Sync
     + From<InputMessage<<<Self as MessageProtocol>::Protocol as Protocol>::Input>>
     + TryInto<InputMessage<<<Self as MessageProtocol>::Protocol as Protocol>::Input>, Error = ()>
+    + From<DynamicMessage>
 {
     type Protocol: Protocol;
 
     /// Get the name of the Message
     fn name(&self) -> &'static str;
 
+    /// Names of all the message types in the protocol, in registration order
+    fn type_names() -> Vec<&'static str>;
+
     /// Returns the MessageKind of the Message
     fn kind(&self) -> MessageKind;
 
@@ -0,0 +1,429 @@
+//! Register messages at runtime, without adding them to the `message_protocol` enum
+//!
+//! Plugins can call [`AppMessageExt::register_message`] in their [`Plugin::build`](bevy::prelude::Plugin::build)
+//! to add their own networked message types. Registered messages are sent with the `send_registered_message`
+//! methods of the client and server `ConnectionManager`s, and are received as regular [`MessageEvent`]s.
+//!
+//! On the wire, a registered message is serialized into a [`DynamicMessage`], a variant that is added
+//! automatically to every message protocol.
+//! The net ids of the registered messages are assigned by sorting the message names when the client or
+//! server plugin finishes building, so that they don't depend on the order in which the plugins were added.
+//! The registered messages are also part of the [`ProtocolFingerprint`](crate::protocol::fingerprint::ProtocolFingerprint):
+//! the fingerprint of the client and server connections is updated at that point, before they connect.
+//!
+//! The entities contained in a registered message are mapped to the local entities with the
+//! [`RemoteEntityMap`] of the connection, like for the messages of the message protocol.
+//!
+//! Only messages can be registered at runtime: components still need to be added to the `component_protocol` enum.
+use std::any::TypeId;
+use std::fmt::{Debug, Formatter};
+
+use anyhow::Context;
+use bevy::prelude::{App, Events, Mut, Resource, World};
+use bevy::utils::HashMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use lightyear_macros::MessageInternal;
+
+use crate::_reexport::{ReadBuffer, ReadWordBuffer, WriteBuffer, WriteWordBuffer};
+use crate::connection::netcode::ClientId;
+use crate::packet::message::Message;
+use crate::protocol::registry::NetId;
+use crate::protocol::{EventContext, Protocol};
+use crate::shared::events::components::MessageEvent;
+use crate::shared::replication::entity_map::{LightyearMapEntities, RemoteEntityMap};
+
+/// A message registered with [`AppMessageExt::register_message`], in its serialized form
+#[derive(MessageInternal, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DynamicMessage {
+    /// Net id of the message type in the [`MessageRegistry`]
+    pub(crate) net_id: NetId,
+    /// The serialized message
+    pub(crate) bytes: Vec<u8>,
+}
+
+type ReceiveFn<Ctx> = fn(&mut World, &[u8], Ctx, &mut RemoteEntityMap) -> anyhow::Result<()>;
+
+#[derive(Clone)]
+pub(crate) struct RegisteredMessage {
+    type_id: TypeId,
+    name: String,
+    receive_client: ReceiveFn<()>,
+    receive_server: ReceiveFn<ClientId>,
+}
+
+/// Contains the messages registered at runtime with [`AppMessageExt::register_message`]
+#[derive(Resource, Default, Clone)]
+pub struct MessageRegistry {
+    messages: Vec<RegisteredMessage>,
+    net_ids: HashMap<TypeId, NetId>,
+    built: bool,
+}
+
+impl Debug for MessageRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageRegistry")
+            .field("messages", &self.names().collect::<Vec<_>>())
+            .field("built", &self.built)
+            .finish()
+    }
+}
+
+impl MessageRegistry {
+    /// Add the message `M` under the given name.
+    ///
+    /// The name is usually [`Named::NAME`](crate::prelude::Named::NAME), but generic wrapper messages need
+    /// to include the name of the type they wrap.
+    pub(crate) fn add<M: Message + Serialize + DeserializeOwned>(&mut self, name: String) {
+        assert!(
+            !self.built,
+            "message {} was registered after the client or server plugin finished building; register messages in `Plugin::build`",
+            name
+        );
+        let type_id = TypeId::of::<M>();
+        if self.messages.iter().any(|m| m.type_id == type_id) {
+            return;
+        }
+        // the net ids are derived from the names, so they must be unique
+        assert!(
+            self.messages.iter().all(|m| m.name != name),
+            "a different message named {} is already registered",
+            name
+        );
+        self.messages.push(RegisteredMessage {
+            type_id,
+            name,
+            receive_client: receive_message::<M, ()>,
+            receive_server: receive_message::<M, ClientId>,
+        });
+    }
+
+    /// Assign the net ids of the registered messages.
+    ///
+    /// The messages are sorted by name so that the net ids are the same on the client and the server.
+    pub(crate) fn build(&mut self) {
+        if self.built {
+            return;
+        }
+        self.messages.sort_by(|a, b| a.name.cmp(&b.name));
+        self.net_ids = self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(net_id, m)| (m.type_id, net_id as NetId))
+            .collect();
+        self.built = true;
+    }
+
+    /// Names of the registered messages, in net id order once the registry is built
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.messages.iter().map(|m| m.name.as_str())
+    }
+
+    /// Returns true if the message `M` was registered
+    pub fn is_registered<M: 'static>(&self) -> bool {
+        self.messages.iter().any(|m| m.type_id == TypeId::of::<M>())
+    }
+
+    /// Serialize a registered message into a [`DynamicMessage`]
+    pub(crate) fn serialize<M: Message + Serialize>(
+        &self,
+        message: &M,
+    ) -> anyhow::Result<DynamicMessage> {
+        let net_id = *self
+            .net_ids
+            .get(&TypeId::of::<M>())
+            .with_context(|| format!("message {} is not registered", M::NAME))?;
+        let mut writer = WriteWordBuffer::with_capacity(64);
+        writer.serialize(message)?;
+        Ok(DynamicMessage {
+            net_id,
+            bytes: writer.finish_write().to_vec(),
+        })
+    }
+}
+
+/// Deserialize a registered message, map its entities and write it as a [`MessageEvent`]
+fn receive_message<M: Message + DeserializeOwned, Ctx: EventContext>(
+    world: &mut World,
+    bytes: &[u8],
+    context: Ctx,
+    entity_map: &mut RemoteEntityMap,
+) -> anyhow::Result<()> {
+    let mut reader = ReadWordBuffer::start_read(bytes);
+    let mut message = reader.deserialize::<M>()?;
+    message.map_entities(entity_map);
+    world
+        .resource_mut::<Events<MessageEvent<M, Ctx>>>()
+        .send(MessageEvent::new(message, context));
+    Ok(())
+}
+
+/// Context of the [`MessageEvent`]s of registered messages: `()` on the client, the [`ClientId`] of the sender on the server
+pub(crate) trait RegisteredMessageContext<P: Protocol>: EventContext + Copy {
+    /// The connection manager that holds the entity maps of the connections
+    type Manager: Resource;
+
+    fn receive_fn(message: &RegisteredMessage) -> ReceiveFn<Self>;
+
+    /// Entity map of the connection that received the message
+    fn entity_map(manager: &mut Self::Manager, context: Self) -> Option<&mut RemoteEntityMap>;
+}
+
+impl<P: Protocol> RegisteredMessageContext<P> for () {
+    type Manager = crate::client::connection::ConnectionManager<P>;
+
+    fn receive_fn(message: &RegisteredMessage) -> ReceiveFn<Self> {
+        message.receive_client
+    }
+
+    fn entity_map(manager: &mut Self::Manager, _: Self) -> Option<&mut RemoteEntityMap> {
+        Some(&mut manager.replication_receiver.remote_entity_map)
+    }
+}
+
+impl<P: Protocol> RegisteredMessageContext<P> for ClientId {
+    type Manager = crate::server::connection::ConnectionManager<P>;
+
+    fn receive_fn(message: &RegisteredMessage) -> ReceiveFn<Self> {
+        message.receive_server
+    }
+
+    fn entity_map(manager: &mut Self::Manager, client_id: Self) -> Option<&mut RemoteEntityMap> {
+        manager
+            .connection_mut(client_id)
+            .ok()
+            .map(|connection| &mut connection.replication_receiver.remote_entity_map)
+    }
+}
+
+/// Convert the received [`DynamicMessage`]s into [`MessageEvent`]s of the registered message types
+pub(crate) fn receive_registered_messages<P: Protocol, Ctx: RegisteredMessageContext<P>>(
+    world: &mut World,
+) {
+    let received: Vec<_> = world
+        .resource_mut::<Events<MessageEvent<DynamicMessage, Ctx>>>()
+        .drain()
+        .collect();
+    if received.is_empty() {
+        return;
+    }
+    world.resource_scope(|world: &mut World, registry: Mut<MessageRegistry>| {
+        world.resource_scope(|world: &mut World, mut manager: Mut<Ctx::Manager>| {
+            for event in received {
+                let net_id = event.message().net_id;
+                let Some(registered) = registry.messages.get(net_id as usize) else {
+                    error!(
+                        ?net_id,
+                        "received a registered message with an unknown net id"
+                    );
+                    continue;
+                };
+                let context = *event.context();
+                // the connection could have been closed since the message was received
+                let Some(entity_map) = Ctx::entity_map(&mut manager, context) else {
+                    debug!(message = ?registered.name, "dropping registered message from a closed connection");
+                    continue;
+                };
+                if let Err(e) =
+                    Ctx::receive_fn(registered)(world, &event.message().bytes, context, entity_map)
+                {
+                    error!(message = ?registered.name, "could not receive registered message: {:?}", e);
+                }
+            }
+        });
+    });
+}
+
+/// Extension trait to register messages on the [`App`] without adding them to the message protocol
+///
+/// Components cannot be registered at runtime: they still need to be added to the `component_protocol` enum,
+/// along with their prediction and interpolation settings.
+pub trait AppMessageExt {
+    /// Register the message `M`.
+    ///
+    /// Must be called on both the client and the server apps, before the client or server plugin finishes building.
+    fn register_message<M: Message + Serialize + DeserializeOwned>(&mut self) -> &mut Self;
+}
+
+impl AppMessageExt for App {
+    fn register_message<M: Message + Serialize + DeserializeOwned>(&mut self) -> &mut Self {
+        self.world
+            .get_resource_or_insert_with(MessageRegistry::default)
+            .add::<M>(M::NAME.to_string());
+        self.add_event::<MessageEvent<M>>();
+        self.add_event::<MessageEvent<M, ClientId>>();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::entity::EntityMapper;
+    use bevy::prelude::Entity;
+    use bevy::utils::Duration;
+
+    use crate::prelude::client::*;
+    use crate::prelude::*;
+    use crate::shared::events::components::MessageEvent;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    use super::*;
+
+    #[derive(MessageInternal, Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct RegisteredMessage1(u32);
+
+    #[derive(MessageInternal, Serialize, Deserialize, Debug, PartialEq, Clone)]
+    struct RegisteredMessage2(String);
+
+    #[derive(MessageInternal, Serialize, Deserialize, Debug, PartialEq, Clone)]
+    #[message(custom_map)]
+    struct RegisteredEntityMessage(Entity);
+
+    impl LightyearMapEntities for RegisteredEntityMessage {
+        fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+            self.0 = entity_mapper.map_entity(self.0);
+        }
+    }
+
+    fn stepper() -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        BevyStepper::new(
+            shared_config,
+            SyncConfig::default(),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            link_conditioner,
+            frame_duration,
+        )
+    }
+
+    #[test]
+    fn test_registered_messages() {
+        let mut stepper = stepper();
+        // the registration order doesn't matter
+        stepper
+            .client_app
+            .register_message::<RegisteredMessage1>()
+            .register_message::<RegisteredMessage2>();
+        stepper
+            .server_app
+            .register_message::<RegisteredMessage2>()
+            .register_message::<RegisteredMessage1>();
+        stepper.init();
+        assert!(stepper
+            .client_app
+            .world
+            .resource::<ClientConnection>()
+            .is_connected());
+        let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+
+        stepper
+            .client_app
+            .world
+            .resource_mut::<ClientConnectionManager>()
+            .send_registered_message::<Channel1, _>(RegisteredMessage1(1))
+            .unwrap();
+        stepper
+            .server_app
+            .world
+            .resource_mut::<ServerConnectionManager>()
+            .send_registered_message::<Channel1, _>(
+                client_id,
+                RegisteredMessage2("hello".to_string()),
+            )
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let server_events: Vec<_> = stepper
+            .server_app
+            .world
+            .resource_mut::<Events<MessageEvent<RegisteredMessage1, ClientId>>>()
+            .drain()
+            .map(|event| (event.message().clone(), *event.context()))
+            .collect();
+        assert_eq!(server_events, vec![(RegisteredMessage1(1), client_id)]);
+        let client_events: Vec<_> = stepper
+            .client_app
+            .world
+            .resource_mut::<Events<MessageEvent<RegisteredMessage2>>>()
+            .drain()
+            .map(|event| event.message().clone())
+            .collect();
+        assert_eq!(client_events, vec![RegisteredMessage2("hello".to_string())]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_register_after_build() {
+        let mut registry = MessageRegistry::default();
+        registry.add::<RegisteredMessage1>(RegisteredMessage1::NAME.to_string());
+        registry.build();
+        registry.add::<RegisteredMessage2>(RegisteredMessage2::NAME.to_string());
+    }
+
+    /// The entities in registered messages are mapped to the local entities
+    #[test]
+    fn test_registered_message_entity_mapping() {
+        let mut stepper = stepper();
+        stepper
+            .client_app
+            .register_message::<RegisteredEntityMessage>();
+        stepper
+            .server_app
+            .register_message::<RegisteredEntityMessage>();
+        stepper.init();
+        let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((Component1(0.0), Replicate::default()))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+
+        stepper
+            .server_app
+            .world
+            .resource_mut::<ServerConnectionManager>()
+            .send_registered_message::<Channel1, _>(
+                client_id,
+                RegisteredEntityMessage(server_entity),
+            )
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let client_events: Vec<_> = stepper
+            .client_app
+            .world
+            .resource_mut::<Events<MessageEvent<RegisteredEntityMessage>>>()
+            .drain()
+            .map(|event| event.message().clone())
+            .collect();
+        assert_eq!(client_events, vec![RegisteredEntityMessage(client_entity)]);
+    }
+}
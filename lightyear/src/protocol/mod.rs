@@ -31,6 +31,12 @@ pub(crate) mod component;
 /// Defines the various messages that can be sent over the network
 pub(crate) mod message;
 
+/// Registers messages at runtime, without the message protocol enum
+pub(crate) mod message_registry;
+
+/// Computes a fingerprint of the protocol that must match between client and server
+pub(crate) mod fingerprint;
+
 /// Provides a mapping from a type to a unique identifier that can be serialized
 pub(crate) mod registry;
 
@@ -1,4 +1,5 @@
 //! Serialization and deserialization of types
+pub mod quantize;
 pub mod reader;
 pub mod wordbuffer;
 pub mod writer;
@@ -0,0 +1,404 @@
+//! Quantization of the fields of messages and components, to reduce their size on the wire
+//!
+//! Add the [`quantized`](crate::prelude::quantized) attribute on the struct (above the serde derives),
+//! then annotate the fields to quantize:
+//! - floats (`f32`, `f64`, `Vec2`, `Vec3`) are mapped to a fixed range: `#[quantize(min = -100.0, max = 100.0, precision = 0.01)]`
+//!   or `#[quantize(min = -100.0, max = 100.0, bits = 12)]`
+//! - integers are packed into the number of bits needed for their range: `#[quantize(min = 0, max = 100)]`
+//! - unit quaternions (`Quat`) are compressed with the smallest-three encoding: `#[quantize(bits = 10)]`
+//!
+//! ```rust,ignore
+//! #[quantized]
+//! #[derive(Component, Message, Serialize, Deserialize, Clone, Debug, PartialEq)]
+//! pub struct Position {
+//!     #[quantize(min = -1000.0, max = 1000.0, precision = 0.01)]
+//!     pub translation: Vec3,
+//!     #[quantize(bits = 12)]
+//!     pub rotation: Quat,
+//! }
+//! ```
+//!
+//! The quantized values are serialized as tuples of bools, which bitcode writes as exactly one bit each,
+//! so a value quantized to `N` bits costs `N` bits.
+//!
+//! For predicted components, use `#[sync(full, quantized)]` in the component protocol so that the rollback
+//! check compares the predicted value with the precision that the server used to send the confirmed value.
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::fmt::Formatter;
+
+use bevy::math::{Quat, Vec2, Vec3};
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserializer, Serializer};
+
+/// Maximum number of values that can be packed together
+const MAX_PACKED_VALUES: usize = 4;
+
+/// Implemented by the [`quantized`](crate::prelude::quantized) attribute macro
+pub trait Quantize {
+    /// Returns the value after a round-trip through the quantized encoding
+    fn quantized(&self) -> Self;
+}
+
+/// A quantized encoding for values of type `T`
+pub trait Quantization<T> {
+    fn serialize<S: Serializer>(&self, value: &T, serializer: S) -> Result<S::Ok, S::Error>;
+
+    fn deserialize<'de, D: Deserializer<'de>>(&self, deserializer: D) -> Result<T, D::Error>;
+
+    /// Returns the value after a round-trip through the quantized encoding
+    fn quantize(&self, value: &T) -> T;
+}
+
+/// Maps floats in the range `[min, max]` to integers of `bits` bits.
+///
+/// Values outside the range are clamped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatQuantization {
+    min: f64,
+    max: f64,
+    bits: u8,
+}
+
+impl FloatQuantization {
+    /// Use the smallest number of bits such that the quantization error is at most `precision / 2`
+    ///
+    /// The [`quantize`](crate::prelude::quantized) attribute checks at compile time that the range
+    /// is not empty and that the precision is positive.
+    pub fn new(min: f64, max: f64, precision: f64) -> Self {
+        let steps = ((max - min) / precision).ceil() + 1.0;
+        Self::with_bits(min, max, steps.log2().ceil() as u8)
+    }
+
+    /// Use `bits` bits for each float; the number of bits is clamped to `[1, 32]`
+    pub fn with_bits(min: f64, max: f64, bits: u8) -> Self {
+        Self {
+            min: min.min(max),
+            max: max.max(min),
+            bits: bits.clamp(1, 32),
+        }
+    }
+
+    /// Number of bits used for each float
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    fn max_step(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    fn quantize_value(&self, value: f64) -> u64 {
+        let normalized = (value.clamp(self.min, self.max) - self.min) / (self.max - self.min);
+        (normalized * self.max_step() as f64).round() as u64
+    }
+
+    fn dequantize_value(&self, quantized: u64) -> f64 {
+        self.min + (quantized as f64 / self.max_step() as f64) * (self.max - self.min)
+    }
+}
+
+/// Types made of floats that can be quantized with a [`FloatQuantization`]
+pub trait FloatValues: Sized {
+    /// Number of floats
+    const LEN: usize;
+
+    fn get(&self, index: usize) -> f64;
+
+    fn from_values(values: impl Fn(usize) -> f64) -> Self;
+}
+
+impl FloatValues for f32 {
+    const LEN: usize = 1;
+
+    fn get(&self, _: usize) -> f64 {
+        *self as f64
+    }
+
+    fn from_values(values: impl Fn(usize) -> f64) -> Self {
+        values(0) as f32
+    }
+}
+
+impl FloatValues for f64 {
+    const LEN: usize = 1;
+
+    fn get(&self, _: usize) -> f64 {
+        *self
+    }
+
+    fn from_values(values: impl Fn(usize) -> f64) -> Self {
+        values(0)
+    }
+}
+
+impl FloatValues for Vec2 {
+    const LEN: usize = 2;
+
+    fn get(&self, index: usize) -> f64 {
+        self[index] as f64
+    }
+
+    fn from_values(values: impl Fn(usize) -> f64) -> Self {
+        Vec2::new(values(0) as f32, values(1) as f32)
+    }
+}
+
+impl FloatValues for Vec3 {
+    const LEN: usize = 3;
+
+    fn get(&self, index: usize) -> f64 {
+        self[index] as f64
+    }
+
+    fn from_values(values: impl Fn(usize) -> f64) -> Self {
+        Vec3::new(values(0) as f32, values(1) as f32, values(2) as f32)
+    }
+}
+
+impl<T: FloatValues> Quantization<T> for FloatQuantization {
+    fn serialize<S: Serializer>(&self, value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut packed = [0; MAX_PACKED_VALUES];
+        for (i, p) in packed.iter_mut().enumerate().take(T::LEN) {
+            *p = self.quantize_value(value.get(i));
+        }
+        serialize_packed(
+            &packed[..T::LEN],
+            &[self.bits; MAX_PACKED_VALUES][..T::LEN],
+            serializer,
+        )
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(&self, deserializer: D) -> Result<T, D::Error> {
+        let packed = deserialize_packed(&[self.bits; MAX_PACKED_VALUES][..T::LEN], deserializer)?;
+        Ok(T::from_values(|i| self.dequantize_value(packed[i])))
+    }
+
+    fn quantize(&self, value: &T) -> T {
+        T::from_values(|i| self.dequantize_value(self.quantize_value(value.get(i))))
+    }
+}
+
+/// Packs integers in the range `[min, max]` into the number of bits needed to represent the range.
+///
+/// Values outside the range are clamped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntQuantization {
+    min: i64,
+    max: i64,
+    bits: u8,
+}
+
+impl IntQuantization {
+    pub fn new(min: i64, max: i64) -> Self {
+        let range = max.abs_diff(min);
+        Self {
+            min: min.min(max),
+            max: max.max(min),
+            bits: (u64::BITS - range.leading_zeros()) as u8,
+        }
+    }
+
+    /// Number of bits used for each integer
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+}
+
+macro_rules! impl_int_quantization {
+    ($($int:ty),*) => {
+        $(
+            impl Quantization<$int> for IntQuantization {
+                fn serialize<S: Serializer>(&self, value: &$int, serializer: S) -> Result<S::Ok, S::Error> {
+                    let packed = (*value as i64).clamp(self.min, self.max).abs_diff(self.min);
+                    serialize_packed(&[packed], &[self.bits], serializer)
+                }
+
+                fn deserialize<'de, D: Deserializer<'de>>(&self, deserializer: D) -> Result<$int, D::Error> {
+                    let packed = deserialize_packed(&[self.bits], deserializer)?;
+                    Ok(self.min.wrapping_add_unsigned(packed[0]) as $int)
+                }
+
+                fn quantize(&self, value: &$int) -> $int {
+                    (*value as i64).clamp(self.min, self.max) as $int
+                }
+            }
+        )*
+    };
+}
+
+impl_int_quantization!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Compresses unit quaternions with the smallest-three encoding.
+///
+/// We send the index of the largest component (2 bits), and the three other components quantized
+/// to `bits` bits each. The largest component is recomputed from the unit norm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuatQuantization {
+    component: FloatQuantization,
+}
+
+impl QuatQuantization {
+    pub fn new(bits: u8) -> Self {
+        // the components other than the largest one are in [-1/sqrt(2), 1/sqrt(2)]
+        Self {
+            component: FloatQuantization::with_bits(
+                -FRAC_1_SQRT_2 as f64,
+                FRAC_1_SQRT_2 as f64,
+                bits,
+            ),
+        }
+    }
+
+    /// Number of bits used for each quaternion
+    pub fn bits(&self) -> u8 {
+        2 + 3 * self.component.bits
+    }
+
+    fn pack(&self, value: &Quat) -> [u64; MAX_PACKED_VALUES] {
+        let components = value.normalize().to_array();
+        let largest = (0..4)
+            .max_by(|a, b| components[*a].abs().total_cmp(&components[*b].abs()))
+            .unwrap();
+        // q and -q represent the same rotation: make the largest component positive
+        let sign = components[largest].signum();
+        let mut packed = [largest as u64, 0, 0, 0];
+        for (p, i) in packed[1..].iter_mut().zip((0..4).filter(|i| *i != largest)) {
+            *p = self.component.quantize_value((components[i] * sign) as f64);
+        }
+        packed
+    }
+
+    fn unpack(&self, packed: [u64; MAX_PACKED_VALUES]) -> Quat {
+        let largest = packed[0] as usize;
+        let mut components = [0.0; 4];
+        for (p, i) in packed[1..].iter().zip((0..4).filter(|i| *i != largest)) {
+            components[i] = self.component.dequantize_value(*p) as f32;
+        }
+        let squares: f32 = components.iter().map(|c| c * c).sum();
+        components[largest] = (1.0 - squares).max(0.0).sqrt();
+        Quat::from_array(components).normalize()
+    }
+}
+
+impl Quantization<Quat> for QuatQuantization {
+    fn serialize<S: Serializer>(&self, value: &Quat, serializer: S) -> Result<S::Ok, S::Error> {
+        let bits = self.component.bits;
+        serialize_packed(&self.pack(value), &[2, bits, bits, bits], serializer)
+    }
+
+    fn deserialize<'de, D: Deserializer<'de>>(&self, deserializer: D) -> Result<Quat, D::Error> {
+        let bits = self.component.bits;
+        let packed = deserialize_packed(&[2, bits, bits, bits], deserializer)?;
+        if packed[0] > 3 {
+            return Err(D::Error::custom("invalid quaternion component index"));
+        }
+        Ok(self.unpack(packed))
+    }
+
+    fn quantize(&self, value: &Quat) -> Quat {
+        self.unpack(self.pack(value))
+    }
+}
+
+/// Serialize the lowest `bits[i]` bits of each `values[i]`, as a tuple of bools
+fn serialize_packed<S: Serializer>(
+    values: &[u64],
+    bits: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let num_bits = bits.iter().map(|b| *b as usize).sum();
+    let mut tuple = serializer.serialize_tuple(num_bits)?;
+    for (value, bits) in values.iter().zip(bits) {
+        for bit in 0..*bits {
+            tuple.serialize_element(&((value >> bit) & 1 == 1))?;
+        }
+    }
+    tuple.end()
+}
+
+fn deserialize_packed<'de, D: Deserializer<'de>>(
+    bits: &[u8],
+    deserializer: D,
+) -> Result<[u64; MAX_PACKED_VALUES], D::Error> {
+    struct PackedVisitor<'a> {
+        bits: &'a [u8],
+    }
+
+    impl<'de> Visitor<'de> for PackedVisitor<'_> {
+        type Value = [u64; MAX_PACKED_VALUES];
+
+        fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+            formatter.write_str("a tuple of bits")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut values = [0; MAX_PACKED_VALUES];
+            let mut read = 0;
+            for (value, bits) in values.iter_mut().zip(self.bits) {
+                for bit in 0..*bits {
+                    let set: bool = seq
+                        .next_element()?
+                        .ok_or_else(|| A::Error::invalid_length(read, &self))?;
+                    *value |= (set as u64) << bit;
+                    read += 1;
+                }
+            }
+            Ok(values)
+        }
+    }
+
+    let num_bits = bits.iter().map(|b| *b as usize).sum();
+    deserializer.deserialize_tuple(num_bits, PackedVisitor { bits })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use lightyear_macros::quantized_internal;
+
+    use crate::_reexport::{ReadBuffer, ReadWordBuffer, WriteBuffer, WriteWordBuffer};
+
+    use super::*;
+
+    #[quantized_internal]
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct Transform {
+        #[quantize(min = -100.0, max = 100.0, precision = 0.01)]
+        translation: Vec3,
+        #[quantize(bits = 10)]
+        rotation: Quat,
+        #[quantize(min = 0, max = 100)]
+        health: u32,
+        name: u8,
+    }
+
+    #[test]
+    fn test_quantized_serialization() {
+        let transform = Transform {
+            translation: Vec3::new(1.234, -56.789, 200.0),
+            rotation: Quat::from_rotation_y(0.5) * Quat::from_rotation_x(-1.2),
+            health: 42,
+            name: 7,
+        };
+
+        let mut writer = WriteWordBuffer::with_capacity(64);
+        writer.serialize(&transform).unwrap();
+        // 3 * 15 bits for the translation, 2 + 3 * 10 bits for the rotation, 7 bits for the health
+        assert_eq!(writer.num_bits_written(), 3 * 15 + 32 + 7 + 8);
+        let bytes = writer.finish_write();
+        let mut reader = ReadWordBuffer::start_read(bytes);
+        let decoded = reader.deserialize::<Transform>().unwrap();
+
+        // the decoded value matches the quantized value
+        assert_eq!(decoded, transform.quantized());
+        // out of range values are clamped
+        assert_eq!(decoded.translation.z, 100.0);
+        assert!((decoded.translation.x - 1.234).abs() <= 0.005);
+        assert!((decoded.translation.y + 56.789).abs() <= 0.005);
+        assert!(decoded.rotation.angle_between(transform.rotation) < 0.01);
+        assert_eq!(decoded.health, 42);
+        assert_eq!(decoded.name, 7);
+    }
+}
@@ -7,6 +7,7 @@ use crate::connection::netcode::Key;
 use crate::connection::server::NetConfig;
 use crate::shared::config::SharedConfig;
 use crate::shared::ping::manager::PingConfig;
+use crate::shared::replication::receive::DEFAULT_ENTITY_REFERENCE_TIMEOUT_TICKS;
 
 #[derive(Clone, Debug)]
 pub struct NetcodeConfig {
@@ -84,6 +85,64 @@ impl PacketConfig {
     }
 }
 
+/// How the server sends replication messages to the clients
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReplicationSendMode {
+    /// Each replication group is replicated separately: entity actions (spawns, despawns, inserts, removals)
+    /// are sent on a reliable channel and component updates on an unreliable channel
+    #[default]
+    PerGroup,
+    /// Every send interval, we build a snapshot of all the entities that are visible to a client,
+    /// delta-encode it against the last snapshot acknowledged by the client, and send it on an unreliable channel.
+    /// The client applies each snapshot atomically, with a single confirmed tick for all entities.
+    ///
+    /// This is useful for fast-paced games where we always want the clients to see a consistent world state,
+    /// at the cost of more bandwidth and cpu usage on the server.
+    Snapshot,
+}
+
+/// Configuration related to replication
+#[derive(Clone, Debug)]
+pub struct ReplicationConfig {
+    pub send_mode: ReplicationSendMode,
+    /// Number of ticks that replication messages received from clients are held back before being applied.
+    /// 0 means that messages are applied as soon as possible.
+    pub playout_delay_ticks: u16,
+    /// Maximum number of ticks that a component received from a client is held back while waiting for
+    /// the entities it references to be replicated
+    pub entity_reference_timeout_ticks: u16,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            send_mode: ReplicationSendMode::default(),
+            playout_delay_ticks: 0,
+            entity_reference_timeout_ticks: DEFAULT_ENTITY_REFERENCE_TIMEOUT_TICKS,
+        }
+    }
+}
+
+impl ReplicationConfig {
+    pub fn with_entity_reference_timeout_ticks(
+        mut self,
+        entity_reference_timeout_ticks: u16,
+    ) -> Self {
+        self.entity_reference_timeout_ticks = entity_reference_timeout_ticks;
+        self
+    }
+
+    pub fn with_playout_delay_ticks(mut self, playout_delay_ticks: u16) -> Self {
+        self.playout_delay_ticks = playout_delay_ticks;
+        self
+    }
+
+    pub fn with_send_mode(mut self, send_mode: ReplicationSendMode) -> Self {
+        self.send_mode = send_mode;
+        self
+    }
+}
+
 /// Configuration for the server plugin
 #[derive(Clone, Debug, Default, Resource)]
 pub struct ServerConfig {
@@ -91,4 +150,5 @@ pub struct ServerConfig {
     pub net: NetConfig,
     pub packet: PacketConfig,
     pub ping: PingConfig,
+    pub replication: ReplicationConfig,
 }
@@ -11,15 +11,16 @@ use tracing::{debug, info, trace, trace_span};
 use crate::_reexport::{EntityUpdatesChannel, InputMessageKind, MessageProtocol, PingChannel};
 use crate::channel::senders::ChannelSend;
 use crate::client::message::ClientMessage;
-use crate::connection::netcode::ClientId;
-use crate::inputs::native::input_buffer::InputBuffer;
+use crate::connection::netcode::{ClientId, DisconnectReason};
+use crate::inputs::native::input_buffer::{InputBuffer, InputMessage};
 use crate::packet::message_manager::MessageManager;
 use crate::packet::packet_manager::Payload;
 use crate::prelude::{Channel, ChannelKind, LightyearMapEntities, Message};
 use crate::protocol::channel::ChannelRegistry;
+use crate::protocol::message_registry::MessageRegistry;
 use crate::protocol::Protocol;
 use crate::serialize::reader::ReadBuffer;
-use crate::server::config::PacketConfig;
+use crate::server::config::{PacketConfig, ReplicationConfig, ReplicationSendMode};
 use crate::server::events::ServerEvents;
 use crate::server::message::ServerMessage;
 use crate::shared::events::connection::ConnectionEvents;
@@ -28,8 +29,10 @@ use crate::shared::ping::message::SyncMessage;
 use crate::shared::replication::components::{NetworkTarget, Replicate};
 use crate::shared::replication::receive::ReplicationReceiver;
 use crate::shared::replication::send::ReplicationSender;
+use crate::shared::replication::snapshot::{SnapshotSender, SNAPSHOT_GROUP_ID};
 use crate::shared::replication::ReplicationMessage;
 use crate::shared::replication::ReplicationMessageData;
+use crate::shared::tick_event::{NetworkedEvent, TickEventMessage};
 use crate::shared::tick_manager::Tick;
 use crate::shared::tick_manager::TickManager;
 use crate::shared::time_manager::TimeManager;
@@ -53,6 +56,9 @@ pub struct ConnectionManager<P: Protocol> {
 
     packet_config: PacketConfig,
     ping_config: PingConfig,
+    pub(crate) replication_config: ReplicationConfig,
+    /// Messages registered at runtime; copied from the app when the plugin finishes building
+    pub(crate) message_registry: MessageRegistry,
 }
 
 impl<P: Protocol> ConnectionManager<P> {
@@ -60,6 +66,7 @@ impl<P: Protocol> ConnectionManager<P> {
         channel_registry: ChannelRegistry,
         packet_config: PacketConfig,
         ping_config: PingConfig,
+        replication_config: ReplicationConfig,
     ) -> Self {
         Self {
             connections: EntityHashMap::default(),
@@ -69,6 +76,8 @@ impl<P: Protocol> ConnectionManager<P> {
             new_clients: vec![],
             packet_config,
             ping_config,
+            replication_config,
+            message_registry: MessageRegistry::default(),
         }
     }
 
@@ -141,6 +150,9 @@ impl<P: Protocol> ConnectionManager<P> {
                 &self.channel_registry,
                 self.packet_config.clone(),
                 self.ping_config.clone(),
+                self.replication_config.send_mode,
+                self.replication_config.playout_delay_ticks,
+                self.replication_config.entity_reference_timeout_ticks,
             );
             connection.events.push_connection();
             self.new_clients.push(client_id);
@@ -150,15 +162,28 @@ impl<P: Protocol> ConnectionManager<P> {
         }
     }
 
-    pub(crate) fn remove(&mut self, client_id: ClientId) {
+    pub(crate) fn remove(&mut self, client_id: ClientId, reason: DisconnectReason) {
         #[cfg(feature = "metrics")]
         metrics::gauge!("connected_clients").decrement(1.0);
 
-        info!("Client {} disconnected", client_id);
-        self.events.push_disconnects(client_id);
+        info!(%reason, "Client {} disconnected", client_id);
+        self.events.push_disconnects(client_id, reason);
         self.connections.remove(&client_id);
     }
 
+    /// Get the tick of the client's interpolation timeline at the time the client generated its input for `tick`.
+    ///
+    /// This is the tick at which the client was seeing the interpolated entities, and can be used for lag compensation.
+    /// Returns None if the client has not sent any input message yet.
+    pub fn client_interpolation_tick(&self, client_id: ClientId, tick: Tick) -> Option<Tick> {
+        self.connections
+            .get(&client_id)
+            .and_then(|connection| connection.interpolation_delay_ticks)
+            // a corrupt or malicious input message could report a negative delay: the interpolation
+            // timeline can never be ahead of the input tick
+            .map(|delay| tick - (delay.max(0) as u16))
+    }
+
     /// Get the inputs for all clients for the given tick
     pub(crate) fn pop_inputs(
         &mut self,
@@ -238,6 +263,51 @@ impl<P: Protocol> ConnectionManager<P> {
         self.send_message_to_target::<C, M>(message, NetworkTarget::Only(vec![client_id]))
     }
 
+    /// Queues up a message registered with [`AppMessageExt::register_message`](crate::prelude::AppMessageExt::register_message) to be sent to all clients
+    pub fn send_registered_message_to_target<C: Channel, M: Message + Serialize>(
+        &mut self,
+        message: M,
+        target: NetworkTarget,
+    ) -> Result<()> {
+        let message = self.message_registry.serialize(&message)?;
+        self.buffer_message(message.into(), ChannelKind::of::<C>(), target)
+    }
+
+    /// Queues up a registered message to be sent to a client
+    pub fn send_registered_message<C: Channel, M: Message + Serialize>(
+        &mut self,
+        client_id: ClientId,
+        message: M,
+    ) -> Result<()> {
+        self.send_registered_message_to_target::<C, M>(
+            message,
+            NetworkTarget::Only(vec![client_id]),
+        )
+    }
+
+    /// Queues up an event that happened at `tick`, to be emitted as a [`TickEvent`](crate::server::tick_event::TickEvent)
+    /// when the timeline of the clients reaches `tick`.
+    ///
+    /// The event type must be added with the [`TickEventPlugin`](crate::server::tick_event::TickEventPlugin).
+    pub fn send_tick_event_to_target<C: Channel, E: NetworkedEvent>(
+        &mut self,
+        event: E,
+        tick: Tick,
+        target: NetworkTarget,
+    ) -> Result<()> {
+        self.send_registered_message_to_target::<C, _>(TickEventMessage { tick, event }, target)
+    }
+
+    /// Queues up an event that happened at `tick` to be sent to a client
+    pub fn send_tick_event<C: Channel, E: NetworkedEvent>(
+        &mut self,
+        client_id: ClientId,
+        event: E,
+        tick: Tick,
+    ) -> Result<()> {
+        self.send_tick_event_to_target::<C, E>(event, tick, NetworkTarget::Only(vec![client_id]))
+    }
+
     /// Buffer all the replication messages to send.
     /// Keep track of the bevy Change Tick: when a message is acked, we know that we only have to send
     /// the updates since that Change Tick
@@ -285,6 +355,8 @@ pub struct Connection<P: Protocol> {
     pub message_manager: MessageManager,
     pub(crate) replication_sender: ReplicationSender<P>,
     pub(crate) replication_receiver: ReplicationReceiver<P>,
+    /// Builds the snapshots sent to the client, if we are using [`ReplicationSendMode::Snapshot`]
+    pub(crate) snapshot_sender: Option<SnapshotSender<P>>,
     pub(crate) events: ConnectionEvents<P>,
 
     pub(crate) ping_manager: PingManager,
@@ -293,6 +365,9 @@ pub struct Connection<P: Protocol> {
     /// Stores the last input we have received from the client.
     /// In case we are missing the client input for a tick, we will fallback to using this.
     pub(crate) last_input: Option<P::Input>,
+    /// Number of ticks between the client's input tick and the client's interpolation tick, taken
+    /// from the latest input message received from the client. Used for lag compensation
+    pub(crate) interpolation_delay_ticks: Option<i16>,
     // TODO: maybe don't do any replication until connection is synced?
 
     // messages that we have received that need to be rebroadcasted to other clients
@@ -304,6 +379,9 @@ impl<P: Protocol> Connection<P> {
         channel_registry: &ChannelRegistry,
         packet_config: PacketConfig,
         ping_config: PingConfig,
+        replication_send_mode: ReplicationSendMode,
+        playout_delay_ticks: u16,
+        entity_reference_timeout_ticks: u16,
     ) -> Self {
         // create the message manager and the channels
         let mut message_manager = MessageManager::new(channel_registry, packet_config.into());
@@ -314,19 +392,34 @@ impl<P: Protocol> Connection<P> {
             .unwrap()
             .sender
             .subscribe_acks();
-        // get a channel to get notified when a replication update message gets actually send (to update priority)
-        let replication_update_send_receiver =
-            message_manager.get_replication_update_send_receiver();
-        let replication_sender =
-            ReplicationSender::new(update_acks_tracker, replication_update_send_receiver);
-        let replication_receiver = ReplicationReceiver::new();
+        let (replication_sender, snapshot_sender) = match replication_send_mode {
+            ReplicationSendMode::PerGroup => {
+                // get a channel to get notified when a replication update message gets actually send (to update priority)
+                let replication_update_send_receiver =
+                    message_manager.get_replication_update_send_receiver();
+                (
+                    ReplicationSender::new(update_acks_tracker, replication_update_send_receiver),
+                    None,
+                )
+            }
+            // snapshots are sent on the entity updates channel, so the acks are handled by the snapshot sender
+            ReplicationSendMode::Snapshot => (
+                ReplicationSender::new(crossbeam_channel::never(), crossbeam_channel::never()),
+                Some(SnapshotSender::new(update_acks_tracker)),
+            ),
+        };
+        let replication_receiver = ReplicationReceiver::new()
+            .with_playout_delay_ticks(playout_delay_ticks)
+            .with_entity_reference_timeout_ticks(entity_reference_timeout_ticks);
         Self {
             message_manager,
             replication_sender,
             replication_receiver,
+            snapshot_sender,
             ping_manager: PingManager::new(ping_config),
             input_buffer: InputBuffer::default(),
             last_input: None,
+            interpolation_delay_ticks: None,
             events: ConnectionEvents::default(),
             messages_to_rebroadcast: vec![],
         }
@@ -362,6 +455,28 @@ impl<P: Protocol> Connection<P> {
         tick: Tick,
         bevy_tick: BevyTick,
     ) -> Result<()> {
+        if let Some(snapshot_sender) = &mut self.snapshot_sender {
+            let snapshot = snapshot_sender.finalize(tick, bevy_tick);
+            let channel = ChannelKind::of::<EntityUpdatesChannel>();
+            let channel_name = self
+                .message_manager
+                .channel_registry
+                .name(&channel)
+                .unwrap_or("unknown")
+                .to_string();
+            let message = ServerMessage::<P>::Replication(ReplicationMessage {
+                group_id: SNAPSHOT_GROUP_ID,
+                data: ReplicationMessageData::Snapshot(snapshot),
+            });
+            message.emit_send_logs(&channel_name);
+            let message_id = self
+                .message_manager
+                .buffer_send(message, channel)?
+                .expect("The replication channels should always return a message_id");
+            // keep track of the snapshot associated with the message, so that we can update the baseline when it is acked
+            snapshot_sender.track_message(message_id, tick);
+            return Ok(());
+        }
         self.replication_sender
             .finalize(tick)
             .into_iter()
@@ -378,10 +493,19 @@ impl<P: Protocol> Connection<P> {
                     data: message_data,
                 });
                 message.emit_send_logs(&channel_name);
+                let bytes_before = self.message_manager.bytes_sent(&channel);
                 let message_id = self
                     .message_manager
                     .buffer_send_with_priority(message, channel, priority)?
                     .expect("The replication channels should always return a message_id");
+                self.replication_sender
+                    .group_channels
+                    .entry(group_id)
+                    .or_default()
+                    .record_sent_message(
+                        should_track_ack,
+                        self.message_manager.bytes_sent(&channel) - bytes_before,
+                    );
 
                 // keep track of the group associated with the message, so we can handle receiving an ACK for that message_id later
                 if should_track_ack {
@@ -478,8 +602,15 @@ impl<P: Protocol> Connection<P> {
                                     self.events.push_input_message(message);
                                 }
                                 InputMessageKind::Native => {
-                                    let input_message = message.try_into().unwrap();
+                                    let input_message: InputMessage<P::Input> =
+                                        message.try_into().unwrap();
                                     debug!("Received input message: {:?}", input_message.end_tick);
+                                    if let Some(interpolation_tick) =
+                                        input_message.interpolation_tick
+                                    {
+                                        self.interpolation_delay_ticks =
+                                            Some(input_message.end_tick - interpolation_tick);
+                                    }
                                     self.input_buffer.update_from_message(input_message);
                                 }
                                 InputMessageKind::None => {
@@ -513,8 +644,10 @@ impl<P: Protocol> Connection<P> {
 
         // NOTE: we run this outside `messages.is_empty()` because we might have some messages from a future tick that we can now process
         // Check if we have any replication messages we can apply to the World (and emit events)
-        for (group, replication_list) in
-            self.replication_receiver.read_messages(tick_manager.tick())
+        // the client's ticks are already ahead of the server's, so we hold back messages relative to our own tick
+        for (group, replication_list) in self
+            .replication_receiver
+            .read_messages(tick_manager.tick(), tick_manager.tick())
         {
             trace!(?group, ?replication_list, "read replication messages");
             replication_list
@@ -530,6 +663,9 @@ impl<P: Protocol> Connection<P> {
                     );
                 });
         }
+        // components can reference entities from other replication groups, that we might have just received
+        self.replication_receiver
+            .resolve_pending_components(world, &mut self.events);
 
         // TODO: do i really need this? I could just create events in this function directly?
         //  why do i need to make events a field of the connection?
@@ -546,6 +682,9 @@ impl<P: Protocol> Connection<P> {
         let tick = self.message_manager.recv_packet(reader)?;
         // notify the replication sender that some sent messages were received
         self.replication_sender.recv_update_acks();
+        if let Some(snapshot_sender) = &mut self.snapshot_sender {
+            snapshot_sender.recv_acks();
+        }
         debug!("Received server packet with tick: {:?}", tick);
         Ok(())
     }
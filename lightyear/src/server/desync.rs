@@ -0,0 +1,211 @@
+//! Server side of the desync detection: periodically send the checksums of the predicted components to the clients.
+//!
+//! See [`crate::shared::desync`] for more details.
+//!
+//! ```rust,ignore
+//! use lightyear::prelude::server::*;
+//!
+//! fn setup(app: &mut App) {
+//!     app.add_plugins(DesyncDetectionPlugin::<MyProtocol, Position>::new(DesyncConfig::default()));
+//! }
+//! ```
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    App, Component, Entity, IntoSystemConfigs, IntoSystemSetConfigs, Plugin, PostUpdate, Query,
+    Res, ResMut, Resource, SystemSet,
+};
+use serde::Serialize;
+use tracing::{error, trace};
+
+use crate::_reexport::ComponentProtocol;
+use crate::channel::builder::DefaultUnorderedUnreliableChannel;
+use crate::client::components::SyncMetadata;
+use crate::connection::netcode::ClientId;
+use crate::prelude::{MainSet, Named, NetworkTarget};
+use crate::protocol::message_registry::AppMessageExt;
+use crate::protocol::Protocol;
+use crate::server::config::ServerConfig;
+use crate::server::connection::ConnectionManager;
+use crate::shared::desync::{
+    checksum, component_id, ChecksumMessage, ComponentChecksum, DesyncConfig,
+};
+use crate::shared::replication::components::Replicate;
+use crate::shared::tick_manager::{Tick, TickManager};
+
+/// Plugin that sends the checksums of the component `C` of the predicted entities to the clients
+pub struct DesyncDetectionPlugin<P, C> {
+    config: DesyncConfig,
+    _marker: PhantomData<(P, C)>,
+}
+
+impl<P, C> DesyncDetectionPlugin<P, C> {
+    pub fn new(config: DesyncConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, C> Default for DesyncDetectionPlugin<P, C> {
+    fn default() -> Self {
+        Self::new(DesyncConfig::default())
+    }
+}
+
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum DesyncSet {
+    /// Check if we should send the checksums this frame
+    Prepare,
+    /// Compute the checksums of the components
+    Compute,
+    /// Send the checksums to the clients
+    Send,
+}
+
+/// Checksums computed during the current frame
+#[derive(Resource)]
+struct ChecksumManager {
+    config: DesyncConfig,
+    last_checksum_tick: Option<Tick>,
+    /// True if we send the checksums this frame
+    ready: bool,
+    /// Checksums of the components, along with the clients that predict the entity
+    checksums: Vec<(NetworkTarget, ComponentChecksum)>,
+}
+
+impl<P: Protocol, C> Plugin for DesyncDetectionPlugin<P, C>
+where
+    C: Component + Clone + Serialize + Debug + Named,
+    P::Components: SyncMetadata<C>,
+{
+    fn build(&self, app: &mut App) {
+        // the systems shared by all the components are only added once
+        if !app.world.contains_resource::<ChecksumManager>() {
+            // RESOURCES
+            app.insert_resource(ChecksumManager {
+                config: self.config.clone(),
+                last_checksum_tick: None,
+                ready: false,
+                checksums: vec![],
+            });
+            app.register_message::<ChecksumMessage>();
+            // SETS
+            app.configure_sets(
+                PostUpdate,
+                (DesyncSet::Prepare, DesyncSet::Compute, DesyncSet::Send)
+                    .chain()
+                    .in_set(MainSet::Send)
+                    .before(MainSet::SendPackets),
+            );
+            // SYSTEMS
+            app.add_systems(
+                PostUpdate,
+                (
+                    prepare_checksums.in_set(DesyncSet::Prepare),
+                    send_checksums::<P>.in_set(DesyncSet::Send),
+                ),
+            );
+        }
+        app.add_systems(
+            PostUpdate,
+            compute_checksums::<P, C>.in_set(DesyncSet::Compute),
+        );
+    }
+}
+
+/// Check if it's been long enough since we last sent the checksums
+fn prepare_checksums(
+    config: Res<ServerConfig>,
+    tick_manager: Res<TickManager>,
+    mut manager: ResMut<ChecksumManager>,
+) {
+    let tick = tick_manager.tick();
+    let interval_ticks = (manager.config.interval.as_secs_f64()
+        / config.shared.tick.tick_duration.as_secs_f64())
+    .clamp(1.0, i16::MAX as f64) as i16;
+    manager.ready = manager
+        .last_checksum_tick
+        .map_or(true, |last_tick| tick - last_tick >= interval_ticks);
+    if manager.ready {
+        manager.last_checksum_tick = Some(tick);
+    }
+}
+
+/// Compute the checksums of the component `C` for all the predicted entities
+fn compute_checksums<P: Protocol, C>(
+    mut manager: ResMut<ChecksumManager>,
+    query: Query<(Entity, &C, &Replicate<P>)>,
+) where
+    C: Component + Clone + Serialize + Debug + Named,
+    P::Components: SyncMetadata<C>,
+{
+    if !manager.ready {
+        return;
+    }
+    for (entity, component, replicate) in query.iter() {
+        if replicate.prediction_target == NetworkTarget::None {
+            continue;
+        }
+        // the clients receive the quantized value, so that's what they should predict
+        let quantized = P::Components::quantize(component.clone());
+        let checksum = match checksum(&quantized) {
+            Ok(checksum) => checksum,
+            Err(e) => {
+                error!(?entity, component = ?C::NAME, "could not compute checksum: {:?}", e);
+                continue;
+            }
+        };
+        let value = manager
+            .config
+            .log_values
+            .then(|| format!("{:?}", quantized));
+        manager.checksums.push((
+            replicate.prediction_target.clone(),
+            ComponentChecksum {
+                entity,
+                component: component_id::<C>(),
+                checksum,
+                value,
+            },
+        ));
+    }
+}
+
+/// Send the checksums of the entities that each client predicts
+fn send_checksums<P: Protocol>(
+    tick_manager: Res<TickManager>,
+    mut manager: ResMut<ChecksumManager>,
+    mut connection_manager: ResMut<ConnectionManager<P>>,
+) {
+    if !manager.ready {
+        return;
+    }
+    let tick = tick_manager.tick();
+    let checksums = std::mem::take(&mut manager.checksums);
+    let client_ids: Vec<ClientId> = connection_manager.connections.keys().copied().collect();
+    for client_id in client_ids {
+        let client_checksums: Vec<ComponentChecksum> = checksums
+            .iter()
+            .filter(|(target, _)| target.should_send_to(&client_id))
+            .map(|(_, checksum)| checksum.clone())
+            .collect();
+        if client_checksums.is_empty() {
+            continue;
+        }
+        trace!(?client_id, ?tick, num_checksums = ?client_checksums.len(), "sending checksums");
+        if let Err(e) = connection_manager
+            .send_registered_message::<DefaultUnorderedUnreliableChannel, _>(
+                client_id,
+                ChecksumMessage {
+                    tick,
+                    checksums: client_checksums,
+                },
+            )
+        {
+            error!(?client_id, "could not send checksums: {:?}", e);
+        }
+    }
+}
@@ -6,7 +6,7 @@ use tracing::trace;
 use crate::_reexport::{
     FromType, IterComponentInsertEvent, IterComponentRemoveEvent, IterComponentUpdateEvent,
 };
-use crate::connection::netcode::ClientId;
+use crate::connection::netcode::{ClientId, DisconnectReason};
 #[cfg(feature = "leafwing")]
 use crate::inputs::leafwing::{InputMessage, LeafwingUserAction};
 use crate::packet::message::Message;
@@ -49,7 +49,7 @@ impl<P: Protocol> Plugin for ServerEventsPlugin<P> {
 #[derive(Debug)]
 pub struct ServerEvents<P: Protocol> {
     // have to handle disconnects separately because the [`ConnectionEvents`] are removed upon disconnection
-    pub disconnects: Vec<ClientId>,
+    pub disconnects: Vec<(ClientId, DisconnectReason)>,
     pub events: EntityHashMap<ClientId, ConnectionEvents<P>>,
     pub empty: bool,
 }
@@ -108,7 +108,10 @@ impl<P: Protocol> ServerEvents<P> {
             .any(|(_, connection_events)| connection_events.has_connection())
     }
 
-    pub fn iter_disconnections(&mut self) -> impl Iterator<Item = ClientId> + '_ {
+    /// Iterate through the clients that got disconnected, along with the reason of the disconnection
+    pub fn iter_disconnections(
+        &mut self,
+    ) -> impl Iterator<Item = (ClientId, DisconnectReason)> + '_ {
         std::mem::take(&mut self.disconnects).into_iter()
     }
 
@@ -118,8 +121,8 @@ impl<P: Protocol> ServerEvents<P> {
 
     // Cannot only use the 'disconnect' field in the events, because we remove the events
     // upon disconnection
-    pub(crate) fn push_disconnects(&mut self, client_id: ClientId) {
-        self.disconnects.push(client_id);
+    pub(crate) fn push_disconnects(&mut self, client_id: ClientId, reason: DisconnectReason) {
+        self.disconnects.push((client_id, reason));
         self.events.remove(&client_id);
         self.empty = false;
     }
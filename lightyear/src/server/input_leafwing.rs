@@ -119,8 +119,16 @@ fn update_action_diff_buffers<P: Protocol, A: LeafwingUserAction>(
     P::Message: TryInto<InputMessage<A>, Error = ()>,
 {
     // let manager = &mut server.connection_manager;
-    for (mut message, client_id) in connection_manager.events.into_iter_input_messages::<A>() {
+    let manager = connection_manager.as_mut();
+    for (mut message, client_id) in manager.events.into_iter_input_messages::<A>() {
         debug!(action = ?A::short_type_path(), ?message.end_tick, ?message.diffs, "received input message");
+        // keep track of the client's interpolation tick for lag compensation
+        if let (Some(interpolation_tick), Some(connection)) = (
+            message.interpolation_tick,
+            manager.connections.get_mut(&client_id),
+        ) {
+            connection.interpolation_delay_ticks = Some(message.end_tick - interpolation_tick);
+        }
 
         for (target, diffs) in std::mem::take(&mut message.diffs) {
             match target {
@@ -0,0 +1,92 @@
+//! Server side of the network inspector: publish the state of all the client connections.
+//!
+//! See [`crate::shared::inspector`] for more details.
+//!
+//! ```rust,ignore
+//! use lightyear::prelude::server::*;
+//!
+//! fn setup(app: &mut App) {
+//!     app.add_plugins(InspectorPlugin::<MyProtocol>::new(InspectorConfig::server()));
+//! }
+//! ```
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    App, Commands, IntoSystemConfigs, Plugin, PostUpdate, Real, Res, ResMut, Startup, Time,
+};
+
+use crate::prelude::MainSet;
+use crate::protocol::Protocol;
+use crate::server::connection::ConnectionManager;
+use crate::shared::inspector::feed::InspectorFeed;
+use crate::shared::inspector::{
+    ConnectionSnapshot, InspectorConfig, InspectorSnapshot, InspectorSource,
+};
+use crate::shared::tick_manager::TickManager;
+
+/// Plugin that publishes the state of the server's connections to the network inspector feed
+pub struct InspectorPlugin<P> {
+    config: InspectorConfig,
+    _marker: PhantomData<P>,
+}
+
+impl<P> InspectorPlugin<P> {
+    pub fn new(config: InspectorConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P> Default for InspectorPlugin<P> {
+    fn default() -> Self {
+        Self::new(InspectorConfig::server())
+    }
+}
+
+impl<P: Protocol> Plugin for InspectorPlugin<P> {
+    fn build(&self, app: &mut App) {
+        let config = self.config.clone();
+        // the feed uses the IoTaskPool, which is only available once the app is running
+        app.add_systems(Startup, move |mut commands: Commands| {
+            commands.insert_resource(InspectorFeed::start(config.clone()));
+        });
+        // publish the snapshot after the packets of the frame were sent, so that they are included in the stats
+        app.add_systems(PostUpdate, publish_snapshot::<P>.after(MainSet::Send));
+    }
+}
+
+fn publish_snapshot<P: Protocol>(
+    time: Res<Time<Real>>,
+    tick_manager: Res<TickManager>,
+    connection_manager: Res<ConnectionManager<P>>,
+    feed: Option<ResMut<InspectorFeed>>,
+) {
+    let Some(mut feed) = feed else {
+        return;
+    };
+    if !feed.should_publish(time.elapsed()) {
+        return;
+    }
+    let mut connections: Vec<ConnectionSnapshot> = connection_manager
+        .connections
+        .iter()
+        .map(|(client_id, connection)| {
+            ConnectionSnapshot::new(
+                *client_id,
+                &connection.ping_manager,
+                &connection.message_manager,
+                &connection.replication_sender,
+            )
+        })
+        .collect();
+    connections.sort_by_key(|connection| connection.client_id);
+    feed.publish(&InspectorSnapshot {
+        source: InspectorSource::Server,
+        tick: tick_manager.tick(),
+        time: time.elapsed_seconds(),
+        connections,
+        rollbacks: vec![],
+    });
+}
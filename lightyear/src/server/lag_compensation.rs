@@ -0,0 +1,468 @@
+//! Server-side lag compensation (rewinding) for hit detection
+//!
+//! # Lag compensation
+//! Clients see remote entities in the interpolation timeline, i.e. a few ticks behind the server's current tick.
+//! When a client shoots at a target, it aims at where the target was at its interpolation tick, not at where the
+//! target currently is on the server.
+//!
+//! To evaluate hits fairly, the server keeps a short history of some components (for example `Transform`
+//! or xpbd's `Position`) for every entity marked with [`LagCompensated`]. The client includes its interpolation tick
+//! with each input message, which lets the server compute, for each input tick, the tick at which the client
+//! was seeing the world. The [`LagCompensation`] [`SystemParam`] can then temporarily rewind the components
+//! to that tick, so that you can run your hit-detection queries, and restore them afterwards.
+//!
+//! ```rust,ignore
+//! use lightyear::prelude::server::*;
+//!
+//! fn setup(app: &mut App) {
+//!     app.add_plugins(LagCompensationPlugin::<Position>::default());
+//! }
+//!
+//! fn shoot(
+//!     mut lag_compensation: LagCompensation<MyProtocol, Position>,
+//!     mut inputs: EventReader<InputEvent<MyInput, ClientId>>,
+//! ) {
+//!     for input in inputs.read() {
+//!         let client_id = *input.context();
+//!         lag_compensation.rewind(client_id);
+//!         // .. run the hit-detection queries
+//!         lag_compensation.restore();
+//!     }
+//! }
+//! ```
+//!
+//! NOTE: the rewind is done with tick granularity; the interpolation overstep of the client is not taken into account.
+//!
+//! NOTE: if you are using a physics engine that maintains its own acceleration structures (for example
+//! xpbd's `SpatialQuery`), you will need to update them after rewinding and after restoring the components.
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::{
+    App, Commands, Component, DetectChanges, DetectChangesMut, Entity, FixedPostUpdate,
+    IntoSystemConfigs, IntoSystemSetConfigs, Plugin, Query, Ref, Res, Resource, SystemSet, With,
+    Without,
+};
+use tracing::{debug, trace};
+
+use crate::connection::netcode::ClientId;
+use crate::protocol::Protocol;
+use crate::server::connection::ConnectionManager;
+use crate::shared::tick_manager::{Tick, TickManager};
+
+/// Configuration for the [`LagCompensationPlugin`]
+#[derive(Clone, Debug)]
+pub struct LagCompensationConfig {
+    /// Maximum number of ticks of history that we keep for each lag-compensated entity.
+    ///
+    /// Clients that are further behind than this will be rewound to the oldest tick available
+    pub max_history_ticks: u16,
+}
+
+impl Default for LagCompensationConfig {
+    fn default() -> Self {
+        Self {
+            max_history_ticks: 64,
+        }
+    }
+}
+
+impl LagCompensationConfig {
+    pub fn with_max_history_ticks(mut self, max_history_ticks: u16) -> Self {
+        self.max_history_ticks = max_history_ticks;
+        self
+    }
+}
+
+/// Marker component to indicate that the history of this entity should be kept,
+/// so that it can be rewound for lag compensation
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct LagCompensated;
+
+/// Stores the recent values of the component `C` for a [`LagCompensated`] entity
+#[derive(Component, Debug)]
+pub struct LagCompensationHistory<C> {
+    /// History of the component, ordered from oldest to most recent.
+    /// We only store the values for the ticks where the component got updated
+    buffer: VecDeque<(Tick, C)>,
+    /// Value of the component before it was rewound, so that it can be restored
+    rewound_from: Option<C>,
+}
+
+impl<C> Default for LagCompensationHistory<C> {
+    fn default() -> Self {
+        Self {
+            buffer: VecDeque::new(),
+            rewound_from: None,
+        }
+    }
+}
+
+impl<C: Clone> LagCompensationHistory<C> {
+    /// Record the value of the component for the given tick
+    pub(crate) fn add(&mut self, tick: Tick, value: C) {
+        // we record the value at most once per tick
+        if let Some((last_tick, last_value)) = self.buffer.back_mut() {
+            if *last_tick == tick {
+                *last_value = value;
+                return;
+            }
+        }
+        self.buffer.push_back((tick, value));
+    }
+
+    /// Get the value of the component at the given tick.
+    ///
+    /// Returns None if the component did not exist yet at that tick.
+    pub fn get(&self, tick: Tick) -> Option<&C> {
+        self.buffer
+            .iter()
+            .rev()
+            .find(|(t, _)| *t <= tick)
+            .map(|(_, value)| value)
+    }
+
+    /// Remove all the values that are not needed to compute the value of the component at `oldest_tick` or later.
+    ///
+    /// We always keep the most recent value that is older or equal to `oldest_tick`, because the history
+    /// only contains the ticks where the component got updated
+    pub(crate) fn clear_until(&mut self, oldest_tick: Tick) {
+        while self.buffer.len() > 1 && self.buffer[1].0 <= oldest_tick {
+            self.buffer.pop_front();
+        }
+    }
+
+    /// Oldest tick for which we have a value in the history
+    pub fn oldest_tick(&self) -> Option<Tick> {
+        self.buffer.front().map(|(tick, _)| *tick)
+    }
+
+    /// Returns true if the component is currently rewound
+    pub fn is_rewound(&self) -> bool {
+        self.rewound_from.is_some()
+    }
+}
+
+/// Plugin that keeps the history of the component `C` for every [`LagCompensated`] entity
+pub struct LagCompensationPlugin<C> {
+    config: LagCompensationConfig,
+    _marker: PhantomData<C>,
+}
+
+impl<C> LagCompensationPlugin<C> {
+    pub fn new(config: LagCompensationConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<C> Default for LagCompensationPlugin<C> {
+    fn default() -> Self {
+        Self::new(LagCompensationConfig::default())
+    }
+}
+
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum LagCompensationSet {
+    /// Record the history of the lag-compensated components, after the main fixed-update logic has run
+    UpdateHistory,
+}
+
+/// Stores the [`LagCompensationConfig`] used for the component `C`
+#[derive(Resource, Clone, Debug)]
+struct LagCompensationSettings<C> {
+    config: LagCompensationConfig,
+    _marker: PhantomData<C>,
+}
+
+impl<C: Component + Clone> Plugin for LagCompensationPlugin<C> {
+    fn build(&self, app: &mut App) {
+        // RESOURCES
+        app.insert_resource(LagCompensationSettings::<C> {
+            config: self.config.clone(),
+            _marker: PhantomData,
+        });
+        // SETS
+        app.configure_sets(FixedPostUpdate, LagCompensationSet::UpdateHistory);
+        // SYSTEMS
+        app.add_systems(
+            FixedPostUpdate,
+            (
+                add_lag_compensation_history::<C>,
+                update_lag_compensation_history::<C>,
+            )
+                .chain()
+                .in_set(LagCompensationSet::UpdateHistory),
+        );
+    }
+}
+
+/// Add a [`LagCompensationHistory`] to every [`LagCompensated`] entity that has the component `C`
+fn add_lag_compensation_history<C: Component + Clone>(
+    mut commands: Commands,
+    tick_manager: Res<TickManager>,
+    query: Query<(Entity, &C), (With<LagCompensated>, Without<LagCompensationHistory<C>>)>,
+) {
+    let tick = tick_manager.tick();
+    for (entity, component) in query.iter() {
+        debug!(?entity, ?tick, "Adding lag compensation history");
+        let mut history = LagCompensationHistory::<C>::default();
+        history.add(tick, component.clone());
+        commands.entity(entity).insert(history);
+    }
+}
+
+/// Record the value of the component for the current tick, and remove values that are too old
+fn update_lag_compensation_history<C: Component + Clone>(
+    tick_manager: Res<TickManager>,
+    settings: Res<LagCompensationSettings<C>>,
+    mut query: Query<(Ref<C>, &mut LagCompensationHistory<C>)>,
+) {
+    let tick = tick_manager.tick();
+    let oldest_tick = tick - settings.config.max_history_ticks;
+    for (component, mut history) in query.iter_mut() {
+        if component.is_changed() {
+            history.add(tick, component.clone());
+        }
+        history.clear_until(oldest_tick);
+    }
+}
+
+/// [`SystemParam`] used to rewind the component `C` of all [`LagCompensated`] entities
+/// to the tick that a client was seeing when it generated its inputs.
+///
+/// The component is modified without triggering change detection, so rewinding does not cause any replication updates.
+/// You must call [`LagCompensation::restore`] before the end of the tick!
+#[derive(SystemParam)]
+pub struct LagCompensation<'w, 's, P: Protocol, C: Component + Clone> {
+    connection_manager: Res<'w, ConnectionManager<P>>,
+    tick_manager: Res<'w, TickManager>,
+    query: Query<'w, 's, (&'static mut C, &'static mut LagCompensationHistory<C>)>,
+}
+
+impl<'w, 's, P: Protocol, C: Component + Clone> LagCompensation<'w, 's, P, C> {
+    /// Tick at which the client was seeing the world when it generated its input for the current tick
+    ///
+    /// Returns None if the client has not sent its interpolation tick yet
+    pub fn rewind_tick(&self, client_id: ClientId) -> Option<Tick> {
+        self.connection_manager
+            .client_interpolation_tick(client_id, self.tick_manager.tick())
+    }
+
+    /// Rewind all the lag-compensated entities to the tick the client `client_id` was seeing when
+    /// it generated its input for the current tick.
+    ///
+    /// Returns the tick that we rewound to, or None if the client's interpolation tick is unknown
+    pub fn rewind(&mut self, client_id: ClientId) -> Option<Tick> {
+        let tick = self.rewind_tick(client_id)?;
+        self.rewind_to_tick(tick);
+        Some(tick)
+    }
+
+    /// Rewind all the lag-compensated entities to the given tick.
+    ///
+    /// Entities that did not exist yet at that tick are left untouched.
+    pub fn rewind_to_tick(&mut self, tick: Tick) {
+        trace!(?tick, current_tick = ?self.tick_manager.tick(), "lag compensation rewind");
+        for (mut component, mut history) in self.query.iter_mut() {
+            let Some(rewound) = history.get(tick).cloned() else {
+                continue;
+            };
+            let current = std::mem::replace(component.bypass_change_detection(), rewound);
+            // if we were already rewound, keep the original value
+            if history.rewound_from.is_none() {
+                history.rewound_from = Some(current);
+            }
+        }
+    }
+
+    /// Restore the components of all the lag-compensated entities to their value before the rewind
+    pub fn restore(&mut self) {
+        for (mut component, mut history) in self.query.iter_mut() {
+            if let Some(original) = history.rewound_from.take() {
+                *component.bypass_change_detection() = original;
+            }
+        }
+    }
+
+    /// Rewind the lag-compensated entities for the client `client_id`, run `f`, then restore the entities.
+    ///
+    /// Returns None (without running `f`) if the client's interpolation tick is unknown
+    pub fn with_rewind<R>(&mut self, client_id: ClientId, f: impl FnOnce(&Self) -> R) -> Option<R> {
+        self.rewind(client_id)?;
+        let res = f(self);
+        self.restore();
+        Some(res)
+    }
+
+    /// Get the current (potentially rewound) value of the component for the given entity
+    pub fn get(&self, entity: Entity) -> Option<&C> {
+        self.query.get(entity).ok().map(|(component, _)| component)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{FixedPreUpdate, FixedUpdate, ResMut};
+    use bevy::utils::Duration;
+
+    use crate::client::input::InputSystemSet;
+    use crate::prelude::client::{
+        ClientConnection, InterpolationConfig, NetClient, PredictionConfig, SyncConfig,
+    };
+    use crate::prelude::*;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    use super::*;
+
+    #[test]
+    fn test_lag_compensation_history() {
+        let mut history = LagCompensationHistory::<u32>::default();
+        assert_eq!(history.get(Tick(0)), None);
+
+        history.add(Tick(1), 1);
+        history.add(Tick(3), 3);
+        history.add(Tick(3), 4);
+        history.add(Tick(6), 6);
+
+        // the component did not exist yet
+        assert_eq!(history.get(Tick(0)), None);
+        assert_eq!(history.get(Tick(1)), Some(&1));
+        // we only store the ticks where the component got updated
+        assert_eq!(history.get(Tick(2)), Some(&1));
+        // only one value per tick
+        assert_eq!(history.get(Tick(3)), Some(&4));
+        assert_eq!(history.get(Tick(5)), Some(&4));
+        assert_eq!(history.get(Tick(10)), Some(&6));
+
+        // we keep the most recent value older than the oldest tick
+        history.clear_until(Tick(4));
+        assert_eq!(history.oldest_tick(), Some(Tick(3)));
+        assert_eq!(history.get(Tick(4)), Some(&4));
+
+        history.clear_until(Tick(6));
+        assert_eq!(history.oldest_tick(), Some(Tick(6)));
+        history.clear_until(Tick(20));
+        assert_eq!(history.oldest_tick(), Some(Tick(6)));
+    }
+
+    fn press_input(
+        mut connection: ResMut<ClientConnectionManager>,
+        tick_manager: Res<TickManager>,
+    ) {
+        connection.add_input(MyInput(0), tick_manager.tick());
+    }
+
+    /// The value of the component is the tick at which it was last updated
+    fn set_to_tick(tick_manager: Res<TickManager>, mut query: Query<&mut Component1>) {
+        for mut component in query.iter_mut() {
+            component.0 = tick_manager.tick().0 as f32;
+        }
+    }
+
+    /// For each tick: (current tick, rewind tick, rewound value, restored value)
+    #[derive(Resource)]
+    struct Rewinds {
+        client_id: ClientId,
+        rewinds: Vec<(Tick, Tick, f32, f32)>,
+    }
+
+    fn rewind(
+        mut lag_compensation: LagCompensation<MyProtocol, Component1>,
+        query: Query<Entity, With<LagCompensated>>,
+        tick_manager: Res<TickManager>,
+        rewinds: Option<ResMut<Rewinds>>,
+    ) {
+        let (Some(mut rewinds), Ok(entity)) = (rewinds, query.get_single()) else {
+            return;
+        };
+        let Some(rewind_tick) = lag_compensation.rewind(rewinds.client_id) else {
+            return;
+        };
+        let rewound = lag_compensation.get(entity).unwrap().0;
+        lag_compensation.restore();
+        let restored = lag_compensation.get(entity).unwrap().0;
+        rewinds
+            .rewinds
+            .push((tick_manager.tick(), rewind_tick, rewound, restored));
+    }
+
+    /// The server rewinds the component to the tick that the client was seeing, using the
+    /// interpolation tick sent along with the client's inputs
+    #[test]
+    fn test_rewind_and_restore() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(40),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            SyncConfig::default(),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.client_app.add_systems(
+            FixedPreUpdate,
+            press_input.in_set(InputSystemSet::BufferInputs),
+        );
+        stepper
+            .server_app
+            .add_plugins(LagCompensationPlugin::<Component1>::default())
+            .add_systems(FixedUpdate, (set_to_tick, rewind).chain());
+        stepper.init();
+        let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+        stepper.server_app.world.insert_resource(Rewinds {
+            client_id,
+            rewinds: vec![],
+        });
+
+        let entity = stepper
+            .server_app
+            .world
+            .spawn((Component1(0.0), LagCompensated))
+            .id();
+        let spawn_tick = stepper.server_app.world.resource::<TickManager>().tick();
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+
+        let rewinds = &stepper.server_app.world.resource::<Rewinds>().rewinds;
+        assert!(rewinds
+            .iter()
+            .any(|(_, rewind_tick, _, _)| *rewind_tick > spawn_tick));
+        for (tick, rewind_tick, rewound, restored) in rewinds.iter().copied() {
+            // the client is viewing the world in the past
+            assert!(rewind_tick < tick);
+            // the entity didn't exist yet at the rewind tick: it is left untouched
+            if rewind_tick <= spawn_tick {
+                assert_eq!(rewound, tick.0 as f32);
+            } else {
+                assert_eq!(rewound, rewind_tick.0 as f32);
+            }
+            assert_eq!(restored, tick.0 as f32);
+        }
+        assert!(!stepper
+            .server_app
+            .world
+            .get::<LagCompensationHistory<Component1>>(entity)
+            .unwrap()
+            .is_rewound());
+        assert_eq!(
+            stepper.server_app.world.get::<Component1>(entity).unwrap().0,
+            stepper.server_app.world.resource::<TickManager>().tick().0 as f32
+        );
+    }
+}
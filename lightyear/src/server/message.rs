@@ -120,6 +120,11 @@ impl<P: Protocol> ServerMessage<P> {
                             }
                         }
                     }
+                    ReplicationMessageData::Snapshot(m) => {
+                        trace!(num_entities = ?m.actions.len(), "Sending snapshot");
+                        #[cfg(metrics)]
+                        metrics::counter!("send_snapshot").increment(1);
+                    }
                 }
             }
             ServerMessage::Sync(message) => match message {
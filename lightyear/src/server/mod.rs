@@ -7,16 +7,29 @@ pub mod config;
 
 pub mod connection;
 
+pub mod desync;
+
 pub mod events;
 
 mod input;
 
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "inspector", not(target_family = "wasm"))))
+)]
+#[cfg(all(feature = "inspector", not(target_family = "wasm")))]
+pub mod inspector;
+
+pub mod lag_compensation;
+
 pub mod plugin;
 
 pub mod resource;
 
 pub mod room;
 
+pub mod tick_event;
+
 #[cfg_attr(docsrs, doc(cfg(feature = "leafwing")))]
 #[cfg(feature = "leafwing")]
 pub mod input_leafwing;
@@ -4,9 +4,11 @@ use bevy::prelude::*;
 use tracing::{debug, error, trace, trace_span};
 
 use crate::_reexport::ComponentProtocol;
+use crate::connection::netcode::ClientId;
 use crate::connection::server::{NetServer, ServerConnection};
 use crate::prelude::{MainSet, TickManager, TimeManager};
 use crate::protocol::message::MessageProtocol;
+use crate::protocol::message_registry::{receive_registered_messages, MessageRegistry};
 use crate::protocol::Protocol;
 use crate::server::connection::ConnectionManager;
 use crate::server::events::{ConnectEvent, DisconnectEvent, EntityDespawnEvent, EntitySpawnEvent};
@@ -44,6 +46,10 @@ impl<P: Protocol> Plugin for ServerNetworkingPlugin<P> {
                 PreUpdate,
                 (
                     receive::<P>.in_set(MainSet::Receive),
+                    receive_registered_messages::<P, ClientId>
+                        .after(receive::<P>)
+                        .in_set(MainSet::Receive)
+                        .run_if(resource_exists::<MessageRegistry>),
                     apply_deferred.in_set(MainSet::ReceiveFlush),
                 ),
             )
@@ -85,8 +91,8 @@ pub(crate) fn receive<P: Protocol>(world: &mut World) {
                                             }
 
                                             // handle disconnections
-                                            for client_id in netcode.new_disconnections().iter().copied() {
-                                                connection_manager.remove(client_id);
+                                            for (client_id, reason) in netcode.new_disconnections_with_reason() {
+                                                connection_manager.remove(client_id, reason);
                                                 room_manager.client_disconnect(client_id);
                                             };
 
@@ -103,6 +109,17 @@ pub(crate) fn receive<P: Protocol>(world: &mut World) {
                                                     .recv_packet(&mut reader, tick_manager.as_ref())
                                                     .expect("could not recv packet");
                                             }
+                                            // the packets received on the reliable stream of the transport
+                                            while let Some((mut reader, client_id)) =
+                                                netcode.recv_reliable()
+                                            {
+                                                connection_manager
+                                                    .connection_mut(client_id)
+                                                    .expect("connection not found")
+                                                    .message_manager
+                                                    .recv_stream_packet(&mut reader)
+                                                    .expect("could not recv packet");
+                                            }
 
                                             // RECEIVE: read messages and parse them into events
                                             connection_manager
@@ -133,9 +150,9 @@ pub(crate) fn receive<P: Protocol>(world: &mut World) {
                                                 if connection_manager.events.has_disconnections() {
                                                     let mut connect_event_writer =
                                                         world.get_resource_mut::<Events<DisconnectEvent>>().unwrap();
-                                                    for client_id in connection_manager.events.iter_disconnections() {
+                                                    for (client_id, reason) in connection_manager.events.iter_disconnections() {
                                                         debug!("Client disconnected event: {}", client_id);
-                                                        connect_event_writer.send(DisconnectEvent::new(client_id));
+                                                        connect_event_writer.send(DisconnectEvent::with_reason(client_id, reason));
                                                     }
                                                 }
 
@@ -195,9 +212,18 @@ pub(crate) fn send<P: Protocol>(
         .try_for_each(|(client_id, connection)| {
             let client_span =
                 trace_span!("send_packets_to_client", client_id = ?client_id).entered();
+            connection
+                .message_manager
+                .set_reliable_stream(netserver.has_reliable_stream(*client_id));
             for packet_byte in connection.send_packets(&time_manager, &tick_manager)? {
                 netserver.send(packet_byte.as_slice(), *client_id)?;
             }
+            for packet_byte in connection
+                .message_manager
+                .send_stream_packets(tick_manager.tick())?
+            {
+                netserver.send_reliable(packet_byte.as_slice(), *client_id)?;
+            }
             Ok(())
         })
         .unwrap_or_else(|e: anyhow::Error| {
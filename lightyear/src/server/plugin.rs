@@ -4,9 +4,11 @@ use std::sync::Mutex;
 
 use bevy::prelude::{default, App, Plugin as PluginType};
 
-use crate::connection::server::NetServer;
+use crate::connection::server::{NetServer, ServerConnection};
 use crate::protocol::component::ComponentProtocol;
+use crate::protocol::fingerprint::ProtocolFingerprint;
 use crate::protocol::message::MessageProtocol;
+use crate::protocol::message_registry::MessageRegistry;
 use crate::protocol::Protocol;
 use crate::server::connection::ConnectionManager;
 use crate::server::events::ServerEventsPlugin;
@@ -52,7 +54,8 @@ impl<P: Protocol> ServerPlugin<P> {
 impl<P: Protocol> PluginType for ServerPlugin<P> {
     fn build(&self, app: &mut App) {
         let config = self.config.lock().unwrap().deref_mut().take().unwrap();
-        let mut netserver = config.server_config.net.clone().build_server();
+        let fingerprint = ProtocolFingerprint::new(&config.protocol, &config.server_config.shared);
+        let mut netserver = config.server_config.net.clone().build_server(fingerprint.0);
         // TODO: maybe also don't start the io/server right away, but only here?
         // start the server
         netserver.start();
@@ -62,12 +65,14 @@ impl<P: Protocol> PluginType for ServerPlugin<P> {
         app
             // RESOURCES //
             .insert_resource(config.server_config.clone())
+            .insert_resource(fingerprint)
             // TODO: move these into the Networking/Replication plugins
             .insert_resource(netserver)
             .insert_resource(ConnectionManager::<P>::new(
                 config.protocol.channel_registry().clone(),
                 config.server_config.packet,
                 config.server_config.ping,
+                config.server_config.replication,
             ))
             // PLUGINS
             .add_plugins(SharedPlugin::<P> {
@@ -84,4 +89,23 @@ impl<P: Protocol> PluginType for ServerPlugin<P> {
                 send_interval: config.server_config.shared.server_send_interval,
             });
     }
+
+    fn finish(&self, app: &mut App) {
+        // all plugins are built: assign the net ids of the messages registered at runtime
+        if let Some(mut registry) = app.world.get_resource_mut::<MessageRegistry>() {
+            registry.build();
+            let registry = registry.clone();
+            let fingerprint = app
+                .world
+                .resource::<ProtocolFingerprint>()
+                .with_registered_messages(&registry);
+            app.world
+                .resource_mut::<ServerConnection>()
+                .set_protocol_hash(fingerprint.0);
+            app.world.insert_resource(fingerprint);
+            app.world
+                .resource_mut::<ConnectionManager<P>>()
+                .message_registry = registry;
+        }
+    }
 }
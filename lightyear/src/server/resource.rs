@@ -94,8 +94,8 @@ impl<'w, 's, P: Protocol> ServerMut<'w, 's, P> {
         }
 
         // handle disconnections
-        for client_id in self.netcode.new_disconnections().iter().copied() {
-            self.connection_manager.remove(client_id);
+        for (client_id, reason) in self.netcode.new_disconnections_with_reason() {
+            self.connection_manager.remove(client_id, reason);
             self.room_manager.client_disconnect(client_id);
         }
         Ok(())
@@ -243,7 +243,24 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
             //     "Send entity spawn for tick {:?}",
             //     self.tick_manager.tick()
             // );
-            let replication_sender = &mut self.connection_mut(client_id)?.replication_sender;
+            let connection = self.connection_mut(client_id)?;
+            if let Some(snapshot_sender) = &mut connection.snapshot_sender {
+                snapshot_sender.prepare_entity_spawn(entity);
+                if replicate.prediction_target.should_send_to(&client_id) {
+                    snapshot_sender.prepare_component_insert(
+                        entity,
+                        P::Components::from(ShouldBePredicted::default()),
+                    );
+                }
+                if replicate.interpolation_target.should_send_to(&client_id) {
+                    snapshot_sender.prepare_component_insert(
+                        entity,
+                        P::Components::from(ShouldBeInterpolated),
+                    );
+                }
+                return Ok(());
+            }
+            let replication_sender = &mut connection.replication_sender;
             // update the collect changes tick
             // replication_sender
             //     .group_channels
@@ -288,7 +305,12 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
             //     "Send entity despawn for tick {:?}",
             //     self.tick_manager.tick()
             // );
-            let replication_sender = &mut self.connection_mut(client_id)?.replication_sender;
+            let connection = self.connection_mut(client_id)?;
+            if let Some(snapshot_sender) = &mut connection.snapshot_sender {
+                snapshot_sender.prepare_entity_despawn(entity);
+                return Ok(());
+            }
+            let replication_sender = &mut connection.replication_sender;
             // update the collect changes tick
             // replication_sender
             //     .group_channels
@@ -300,6 +322,27 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
         })
     }
 
+    fn prepare_entity_detach(
+        &mut self,
+        entity: Entity,
+        replicate: &Replicate<P>,
+        target: NetworkTarget,
+        system_current_tick: BevyTick,
+    ) -> Result<()> {
+        let group_id = replicate.replication_group.group_id(Some(entity));
+        self.apply_replication(target).try_for_each(|client_id| {
+            let connection = self.connection_mut(client_id)?;
+            // snapshots cannot detach entities: the entity stays in the client's snapshot, frozen
+            if connection.snapshot_sender.is_some() {
+                return Ok(());
+            }
+            connection
+                .replication_sender
+                .prepare_entity_detach(entity, group_id);
+            Ok(())
+        })
+    }
+
     // TODO: perf gain if we batch this? (send vec of components) (same for update/removes)
     fn prepare_component_insert(
         &mut self,
@@ -341,7 +384,12 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
                 //     tick = ?self.tick_manager.tick(),
                 //     "Inserting single component"
                 // );
-                let replication_sender = &mut self.connection_mut(client_id)?.replication_sender;
+                let connection = self.connection_mut(client_id)?;
+                if let Some(snapshot_sender) = &mut connection.snapshot_sender {
+                    snapshot_sender.prepare_component_insert(entity, component.clone());
+                    return Ok(());
+                }
+                let replication_sender = &mut connection.replication_sender;
                 // update the collect changes tick
                 // replication_sender
                 //     .group_channels
@@ -364,7 +412,12 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
         let group_id = replicate.replication_group.group_id(Some(entity));
         debug!(?entity, ?component_kind, "Sending RemoveComponent");
         self.apply_replication(target).try_for_each(|client_id| {
-            let replication_sender = &mut self.connection_mut(client_id)?.replication_sender;
+            let connection = self.connection_mut(client_id)?;
+            if let Some(snapshot_sender) = &mut connection.snapshot_sender {
+                snapshot_sender.prepare_component_remove(entity, component_kind);
+                return Ok(());
+            }
+            let replication_sender = &mut connection.replication_sender;
             // TODO: I don't think it's actually correct to only correct the changes since that action.
             // what if we do:
             // - Frame 1: update is ACKED
@@ -382,6 +435,31 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
         })
     }
 
+    fn prepare_component_detach(
+        &mut self,
+        entity: Entity,
+        component_kind: P::ComponentKinds,
+        replicate: &Replicate<P>,
+        target: NetworkTarget,
+        system_current_tick: BevyTick,
+    ) -> Result<()> {
+        let group_id = replicate.replication_group.group_id(Some(entity));
+        debug!(?entity, ?component_kind, "Sending DetachComponent");
+        self.apply_replication(target).try_for_each(|client_id| {
+            let connection = self.connection_mut(client_id)?;
+            // snapshots cannot detach components: the component stays in the client's snapshot, frozen
+            if connection.snapshot_sender.is_some() {
+                return Ok(());
+            }
+            connection.replication_sender.prepare_component_detach(
+                entity,
+                group_id,
+                component_kind,
+            );
+            Ok(())
+        })
+    }
+
     fn prepare_entity_update(
         &mut self,
         entity: Entity,
@@ -403,7 +481,17 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
         let group_id = replicate.group_id(Some(entity));
         self.apply_replication(target).try_for_each(|client_id| {
             // TODO: should we have additional state tracking so that we know we are in the process of sending this entity to clients?
-            let replication_sender = &mut self.connection_mut(client_id)?.replication_sender;
+            let connection = self.connection_mut(client_id)?;
+            if let Some(snapshot_sender) = &mut connection.snapshot_sender {
+                // the snapshot keeps track of the full state, so we only need the changes since the last snapshot
+                if snapshot_sender.last_build_bevy_tick.map_or(true, |tick| {
+                    component_change_tick.is_newer_than(tick, system_current_tick)
+                }) {
+                    snapshot_sender.prepare_entity_update(entity, component.clone());
+                }
+                return Ok(());
+            }
+            let replication_sender = &mut connection.replication_sender;
             let collect_changes_since_this_tick = replication_sender
                 .group_channels
                 .entry(group_id)
@@ -450,35 +538,8 @@ impl<P: Protocol> ReplicationSend<P> for ConnectionManager<P> {
     fn cleanup(&mut self, tick: Tick) {
         debug!("Running replication clean");
         for connection in self.connections.values_mut() {
-            // if it's been enough time since we last any action for the group, we can set the last_action_tick to None
-            // (meaning that there's no need when we receive the update to check if we have already received a previous action)
-            for group_channel in connection.replication_sender.group_channels.values_mut() {
-                debug!("Checking group channel: {:?}", group_channel);
-                if let Some(last_action_tick) = group_channel.last_action_tick {
-                    if tick - last_action_tick > (i16::MAX / 2) {
-                        debug!(
-                    ?tick,
-                    ?last_action_tick,
-                    ?group_channel,
-                    "Setting the last_action tick to None because there hasn't been any new actions in a while");
-                        group_channel.last_action_tick = None;
-                    }
-                }
-            }
-            // if it's been enough time since we last had any update for the group, we update the latest_tick for the group
-            for group_channel in connection.replication_receiver.group_channels.values_mut() {
-                debug!("Checking group channel: {:?}", group_channel);
-                if let Some(latest_tick) = group_channel.latest_tick {
-                    if tick - latest_tick > (i16::MAX / 2) {
-                        debug!(
-                    ?tick,
-                    ?latest_tick,
-                    ?group_channel,
-                    "Setting the latest_tick tick to tick because there hasn't been any new updates in a while");
-                        group_channel.latest_tick = Some(tick);
-                    }
-                }
-            }
+            connection.replication_sender.cleanup(tick);
+            connection.replication_receiver.cleanup(tick);
         }
     }
 }
@@ -0,0 +1,122 @@
+//! Server side of the tick events: release the events sent by the clients when the server tick reaches their tick.
+//!
+//! See [`crate::shared::tick_event`] for more details.
+//!
+//! Events that are too far ahead of the server tick are dropped, see [`TickEventPlugin::with_max_ticks_ahead`].
+//!
+//! ```rust,ignore
+//! use lightyear::prelude::server::*;
+//!
+//! fn setup(app: &mut App) {
+//!     app.add_plugins(TickEventPlugin::<MyProtocol, Shoot>::default());
+//! }
+//!
+//! fn handle_shots(mut events: EventReader<TickEvent<Shoot>>) {
+//!     for event in events.read() {
+//!         info!(client = ?event.context(), tick = ?event.tick(), "shot fired");
+//!     }
+//! }
+//! ```
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    App, EventReader, EventWriter, FixedPreUpdate, IntoSystemConfigs, Plugin, PreUpdate, Res,
+    ResMut, Resource,
+};
+use tracing::warn;
+
+use crate::connection::netcode::ClientId;
+use crate::prelude::MainSet;
+use crate::protocol::Protocol;
+use crate::server::events::MessageEvent;
+use crate::shared::tick_event::{
+    register_tick_event, NetworkedEvent, TickEventBuffer, TickEventMessage,
+};
+use crate::shared::tick_manager::TickManager;
+
+/// Bevy [`Event`](bevy::prelude::Event) emitted on the server when the tick of an event sent by a client is reached
+pub type TickEvent<E> = crate::shared::tick_event::TickEvent<E, ClientId>;
+
+/// Default value of [`TickEventPlugin::with_max_ticks_ahead`]
+const DEFAULT_MAX_TICKS_AHEAD: u16 = 128;
+
+/// Plugin that emits the events `E` sent by the clients as [`TickEvent`]s when the server tick reaches their tick.
+///
+/// The events are emitted in `FixedPreUpdate`, so that the `FixedUpdate` systems of that tick can handle them.
+pub struct TickEventPlugin<P, E> {
+    max_ticks_ahead: u16,
+    _marker: PhantomData<(P, E)>,
+}
+
+impl<P, E> Default for TickEventPlugin<P, E> {
+    fn default() -> Self {
+        Self {
+            max_ticks_ahead: DEFAULT_MAX_TICKS_AHEAD,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, E> TickEventPlugin<P, E> {
+    /// Events that are more than `max_ticks_ahead` ticks ahead of the server tick when they are received are dropped,
+    /// so that a client cannot make the server buffer events indefinitely (default: 128 ticks)
+    pub fn with_max_ticks_ahead(mut self, max_ticks_ahead: u16) -> Self {
+        self.max_ticks_ahead = max_ticks_ahead;
+        self
+    }
+}
+
+#[derive(Resource)]
+struct TickEventSettings<E> {
+    max_ticks_ahead: u16,
+    _marker: PhantomData<E>,
+}
+
+impl<P: Protocol, E: NetworkedEvent> Plugin for TickEventPlugin<P, E> {
+    fn build(&self, app: &mut App) {
+        register_tick_event::<E>(app);
+        // RESOURCES
+        app.init_resource::<TickEventBuffer<E, ClientId>>();
+        app.insert_resource(TickEventSettings::<E> {
+            max_ticks_ahead: self.max_ticks_ahead,
+            _marker: PhantomData,
+        });
+        // EVENTS
+        app.add_event::<TickEvent<E>>();
+        // SYSTEMS
+        app.add_systems(PreUpdate, buffer_tick_events::<E>.after(MainSet::Receive));
+        app.add_systems(FixedPreUpdate, release_tick_events::<E>);
+    }
+}
+
+/// Buffer the received events until their tick is reached
+fn buffer_tick_events<E: NetworkedEvent>(
+    tick_manager: Res<TickManager>,
+    settings: Res<TickEventSettings<E>>,
+    mut messages: EventReader<MessageEvent<TickEventMessage<E>>>,
+    mut buffer: ResMut<TickEventBuffer<E, ClientId>>,
+) {
+    for message in messages.read() {
+        let tick = message.message().tick;
+        let ticks_ahead = tick - tick_manager.tick();
+        if ticks_ahead > 0 && ticks_ahead as u16 > settings.max_ticks_ahead {
+            warn!(
+                client = ?message.context(),
+                ?tick,
+                server_tick = ?tick_manager.tick(),
+                "Dropping a tick event that is too far in the future"
+            );
+            continue;
+        }
+        buffer.add(message.message().clone(), *message.context());
+    }
+}
+
+/// Emit the events whose tick is reached
+fn release_tick_events<E: NetworkedEvent>(
+    tick_manager: Res<TickManager>,
+    mut buffer: ResMut<TickEventBuffer<E, ClientId>>,
+    mut events: EventWriter<TickEvent<E>>,
+) {
+    events.send_batch(buffer.release(tick_manager.tick()));
+}
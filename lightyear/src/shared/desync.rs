@@ -0,0 +1,108 @@
+//! Detect when the client's prediction diverges from the server state.
+//!
+//! This is opt-in: add the server [`DesyncDetectionPlugin`](crate::server::desync::DesyncDetectionPlugin)
+//! and the client [`DesyncDetectionPlugin`](crate::client::prediction::desync::DesyncDetectionPlugin),
+//! with the same [`DesyncConfig`] and the same components.
+//!
+//! Every [`DesyncConfig::interval`], the server computes a checksum of the selected components of every entity that
+//! is predicted by a client, and sends them to that client in a [`ChecksumMessage`].
+//! The client computes the checksum of the value stored in the [`PredictionHistory`](crate::client::prediction::PredictionHistory)
+//! for the same tick, and emits a [`DesyncEvent`](crate::client::prediction::desync::DesyncEvent) if they differ.
+//!
+//! The checksums are computed on the serialized component (after quantization), so the prediction has to
+//! match the server state exactly, like for rollback checks.
+use bevy::prelude::Entity;
+use bevy::utils::Duration;
+use serde::{Deserialize, Serialize};
+
+use lightyear_macros::MessageInternal;
+
+use crate::_reexport::{WriteBuffer, WriteWordBuffer};
+use crate::prelude::{Named, Tick};
+use crate::protocol::fingerprint::FnvHasher;
+
+/// Configuration of the desync detection. Should be the same on the client and the server
+#[derive(Clone, Debug)]
+pub struct DesyncConfig {
+    /// How often the server sends the checksums
+    pub interval: Duration,
+    /// If true, the server also sends the debug representation of the components so that the client
+    /// can log both the server and the predicted values when it detects a desync
+    pub log_values: bool,
+}
+
+impl Default for DesyncConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            log_values: false,
+        }
+    }
+}
+
+impl DesyncConfig {
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn with_log_values(mut self, log_values: bool) -> Self {
+        self.log_values = log_values;
+        self
+    }
+}
+
+/// Checksum of a component of an entity
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ComponentChecksum {
+    /// The entity in the server world
+    pub entity: Entity,
+    /// Id of the component type, see [`component_id`]
+    pub component: u64,
+    pub checksum: u64,
+    /// Debug representation of the component, only sent if [`DesyncConfig::log_values`] is true
+    pub value: Option<String>,
+}
+
+/// Checksums of the predicted components on the server at a given tick
+#[derive(MessageInternal, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChecksumMessage {
+    pub tick: Tick,
+    pub checksums: Vec<ComponentChecksum>,
+}
+
+/// Id of the component type that is the same on the client and the server
+pub(crate) fn component_id<C: Named>() -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write_str(C::NAME);
+    hasher.finish()
+}
+
+/// Checksum of the serialized component
+pub(crate) fn checksum<C: Serialize>(component: &C) -> anyhow::Result<u64> {
+    let mut writer = WriteWordBuffer::with_capacity(64);
+    writer.serialize(component)?;
+    let mut hasher = FnvHasher::default();
+    hasher.write(writer.finish_write());
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::protocol::{Component1, Component2};
+
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(
+            checksum(&Component1(1.0)).unwrap(),
+            checksum(&Component1(1.0)).unwrap()
+        );
+        assert_ne!(
+            checksum(&Component1(1.0)).unwrap(),
+            checksum(&Component1(2.0)).unwrap()
+        );
+        assert_ne!(component_id::<Component1>(), component_id::<Component2>());
+    }
+}
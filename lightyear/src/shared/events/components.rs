@@ -4,6 +4,7 @@ use std::marker::PhantomData;
 
 use bevy::prelude::{Component, Entity, Event};
 
+use crate::connection::netcode::DisconnectReason;
 #[cfg(feature = "leafwing")]
 use crate::inputs::leafwing::InputMessage;
 use crate::packet::message::Message;
@@ -21,14 +22,26 @@ impl<Ctx> ConnectEvent<Ctx> {
 }
 
 #[derive(Event)]
-pub struct DisconnectEvent<Ctx = ()>(Ctx);
+pub struct DisconnectEvent<Ctx = ()> {
+    context: Ctx,
+    reason: DisconnectReason,
+}
 
 impl<Ctx> DisconnectEvent<Ctx> {
+    /// Create a disconnect event whose reason is not known,
+    /// it is reported as [`DisconnectReason::ClientDisconnected`]
     pub fn new(context: Ctx) -> Self {
-        Self(context)
+        Self::with_reason(context, DisconnectReason::ClientDisconnected)
+    }
+    pub fn with_reason(context: Ctx, reason: DisconnectReason) -> Self {
+        Self { context, reason }
     }
     pub fn context(&self) -> &Ctx {
-        &self.0
+        &self.context
+    }
+    /// Why the connection was closed
+    pub fn reason(&self) -> &DisconnectReason {
+        &self.reason
     }
 }
 
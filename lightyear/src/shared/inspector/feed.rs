@@ -0,0 +1,130 @@
+//! Websocket server that publishes the inspector snapshots to the connected frontends
+use std::net::SocketAddr;
+
+use async_compat::Compat;
+use bevy::prelude::Resource;
+use bevy::tasks::IoTaskPool;
+use bevy::utils::Duration;
+use futures_util::SinkExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{error, info};
+
+use super::{InspectorConfig, InspectorSnapshot};
+
+/// Number of snapshots that can be buffered for a frontend before the oldest ones are dropped
+const FEED_BUFFER_SIZE: usize = 16;
+
+#[derive(Resource)]
+pub(crate) struct InspectorFeed {
+    config: InspectorConfig,
+    sender: broadcast::Sender<String>,
+    /// Time at which we last published a snapshot
+    last_snapshot: Option<Duration>,
+}
+
+impl InspectorFeed {
+    /// Start listening for frontend connections
+    pub(crate) fn start(config: InspectorConfig) -> Self {
+        let (sender, _) = broadcast::channel(FEED_BUFFER_SIZE);
+        let addr = config.addr;
+        let feed_sender = sender.clone();
+        IoTaskPool::get()
+            .spawn(Compat::new(async move {
+                let listener = match TcpListener::bind(addr).await {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        error!(
+                            "Could not start the network inspector feed on {}: {:?}",
+                            addr, e
+                        );
+                        return;
+                    }
+                };
+                info!("Network inspector feed listening on ws://{}", addr);
+                while let Ok((stream, frontend_addr)) = listener.accept().await {
+                    let receiver = feed_sender.subscribe();
+                    IoTaskPool::get()
+                        .spawn(Compat::new(serve_frontend(stream, frontend_addr, receiver)))
+                        .detach();
+                }
+            }))
+            .detach();
+        Self {
+            config,
+            sender,
+            last_snapshot: None,
+        }
+    }
+
+    /// Returns true if at least one frontend is connected to the feed
+    pub(crate) fn has_frontends(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Returns true if it's time to publish a new snapshot.
+    /// We don't build snapshots if no frontend is connected.
+    pub(crate) fn should_publish(&mut self, now: Duration) -> bool {
+        if !self.has_frontends() {
+            return false;
+        }
+        if self
+            .last_snapshot
+            .is_some_and(|last| now.saturating_sub(last) < self.config.interval)
+        {
+            return false;
+        }
+        self.last_snapshot = Some(now);
+        true
+    }
+
+    /// Subscribe to the published snapshots, like a frontend would
+    #[cfg(test)]
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+
+    pub(crate) fn publish(&self, snapshot: &InspectorSnapshot) {
+        match serde_json::to_string(snapshot) {
+            Ok(json) => {
+                // an error only means that all the frontends disconnected in the meantime
+                let _ = self.sender.send(json);
+            }
+            Err(e) => error!("Could not serialize the inspector snapshot: {:?}", e),
+        }
+    }
+}
+
+/// Forward the snapshots to a frontend until it disconnects
+async fn serve_frontend(
+    stream: TcpStream,
+    addr: SocketAddr,
+    mut receiver: broadcast::Receiver<String>,
+) {
+    let mut ws_stream = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws_stream) => ws_stream,
+        Err(e) => {
+            error!(
+                "Error during the websocket handshake with the inspector frontend {}: {:?}",
+                addr, e
+            );
+            return;
+        }
+    };
+    info!("Inspector frontend connected from {}", addr);
+    loop {
+        match receiver.recv().await {
+            Ok(json) => {
+                if ws_stream.send(Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
+            // the frontend is too slow: skip the snapshots it missed
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        }
+    }
+    info!("Inspector frontend {} disconnected", addr);
+}
@@ -0,0 +1,374 @@
+//! Live network inspector.
+//!
+//! Add the client [`InspectorPlugin`](crate::client::inspector::InspectorPlugin) or the server
+//! [`InspectorPlugin`](crate::server::inspector::InspectorPlugin) to a game built with the `inspector` feature:
+//! the app then serves a local websocket feed (on [`InspectorConfig::addr`]) that publishes an
+//! [`InspectorSnapshot`] in JSON every [`InspectorConfig::interval`].
+//!
+//! By default the server's feed is on port 9102 and the client's feed is on port 9103, so that a server and a client
+//! can run on the same machine.
+//!
+//! Each snapshot contains the state of every connection (RTT, jitter, packet loss), the number of messages and bytes
+//! sent and received on each channel, the replication traffic of each replication group, and the rollbacks that
+//! happened since the previous snapshot. The counters are cumulative since the start of the connection, so that a
+//! frontend that misses some snapshots can still compute the rates.
+//!
+//! The `lightyear_frontend` crate is a dashboard that connects to this feed and renders the snapshots as
+//! timelines and tables.
+use std::net::{Ipv4Addr, SocketAddr};
+
+use bevy::utils::Duration;
+use serde::{Deserialize, Serialize};
+
+pub use crate::channel::builder::ChannelStats;
+pub use crate::shared::replication::send::ReplicationGroupStats;
+
+use crate::connection::netcode::ClientId;
+use crate::packet::message_manager::MessageManager;
+use crate::protocol::Protocol;
+use crate::shared::ping::manager::PingManager;
+use crate::shared::replication::send::ReplicationSender;
+use crate::shared::tick_manager::Tick;
+
+pub(crate) mod feed;
+
+/// Configuration of the network inspector
+#[derive(Clone, Debug)]
+pub struct InspectorConfig {
+    /// Address of the websocket feed. It should be a local address, since the feed is not authenticated
+    pub addr: SocketAddr,
+    /// How often a snapshot is published
+    pub interval: Duration,
+}
+
+impl InspectorConfig {
+    /// Default port of the server's feed
+    pub const DEFAULT_SERVER_PORT: u16 = 9102;
+    /// Default port of the client's feed
+    pub const DEFAULT_CLIENT_PORT: u16 = 9103;
+
+    fn with_port(port: u16) -> Self {
+        Self {
+            addr: SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port),
+            interval: Duration::from_millis(250),
+        }
+    }
+
+    /// Default configuration for the server's feed
+    pub fn server() -> Self {
+        Self::with_port(Self::DEFAULT_SERVER_PORT)
+    }
+
+    /// Default configuration for the client's feed
+    pub fn client() -> Self {
+        Self::with_port(Self::DEFAULT_CLIENT_PORT)
+    }
+
+    pub fn with_addr(mut self, addr: SocketAddr) -> Self {
+        self.addr = addr;
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// Whether the snapshot was published by a server or a client
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InspectorSource {
+    Server,
+    Client,
+}
+
+/// State of the networking of the app at a given time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InspectorSnapshot {
+    pub source: InspectorSource,
+    pub tick: Tick,
+    /// Time elapsed since the app started, in seconds
+    pub time: f32,
+    /// On the server, one entry per connected client; on the client, the connection to the server
+    pub connections: Vec<ConnectionSnapshot>,
+    /// Rollbacks that happened since the previous snapshot (only on the client)
+    pub rollbacks: Vec<RollbackSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConnectionSnapshot {
+    pub client_id: ClientId,
+    pub rtt_ms: f32,
+    pub jitter_ms: f32,
+    /// Fraction of the sent packets that were lost recently
+    pub packet_loss: f32,
+    pub channels: Vec<ChannelSnapshot>,
+    pub groups: Vec<GroupSnapshot>,
+}
+
+impl ConnectionSnapshot {
+    pub(crate) fn new<P: Protocol>(
+        client_id: ClientId,
+        ping_manager: &PingManager,
+        message_manager: &MessageManager,
+        replication_sender: &ReplicationSender<P>,
+    ) -> Self {
+        let mut channels: Vec<ChannelSnapshot> = message_manager
+            .channel_stats()
+            .map(|(kind, stats)| ChannelSnapshot {
+                name: message_manager
+                    .channel_registry
+                    .name(kind)
+                    .unwrap_or("unknown")
+                    .to_string(),
+                stats: *stats,
+            })
+            .collect();
+        channels.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut groups: Vec<GroupSnapshot> = replication_sender
+            .group_channels
+            .iter()
+            .map(|(group_id, channel)| GroupSnapshot {
+                group_id: group_id.0,
+                stats: channel.stats,
+            })
+            .collect();
+        groups.sort_by_key(|group| group.group_id);
+        Self {
+            client_id,
+            rtt_ms: ping_manager.rtt().as_secs_f32() * 1000.0,
+            jitter_ms: ping_manager.jitter().as_secs_f32() * 1000.0,
+            packet_loss: message_manager.packet_loss(),
+            channels,
+            groups,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChannelSnapshot {
+    pub name: String,
+    #[serde(flatten)]
+    pub stats: ChannelStats,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GroupSnapshot {
+    pub group_id: u64,
+    #[serde(flatten)]
+    pub stats: ReplicationGroupStats,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RollbackSnapshot {
+    /// Tick at which the rollback happened
+    pub tick: Tick,
+    /// First tick that was resimulated
+    pub start_tick: Tick,
+    /// Number of entities that were rolled back
+    pub num_entities: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, Entity};
+    use serde_json::json;
+    use tokio::sync::broadcast::Receiver;
+
+    use crate::client::inspector::InspectorPlugin as ClientInspectorPlugin;
+    use crate::connection::client::{ClientConnection, NetClient};
+    use crate::prelude::client::{InterpolationConfig, PredictionConfig, SyncConfig};
+    use crate::prelude::*;
+    use crate::server::inspector::InspectorPlugin as ServerInspectorPlugin;
+    use crate::shared::inspector::feed::InspectorFeed;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    use super::*;
+
+    /// The `lightyear_frontend` dashboard deserializes the snapshots with its own copy of these types,
+    /// so the JSON layout must not change without updating the frontend.
+    #[test]
+    fn test_snapshot_json_layout() {
+        let snapshot = InspectorSnapshot {
+            source: InspectorSource::Client,
+            tick: Tick(10),
+            time: 1.5,
+            connections: vec![ConnectionSnapshot {
+                client_id: 1,
+                rtt_ms: 20.0,
+                jitter_ms: 2.0,
+                packet_loss: 0.25,
+                channels: vec![ChannelSnapshot {
+                    name: "Channel1".to_string(),
+                    stats: ChannelStats {
+                        messages_sent: 1,
+                        bytes_sent: 2,
+                        messages_received: 3,
+                        bytes_received: 4,
+                    },
+                }],
+                groups: vec![GroupSnapshot {
+                    group_id: 5,
+                    stats: ReplicationGroupStats {
+                        actions_sent: 6,
+                        updates_sent: 7,
+                        bytes_sent: 8,
+                    },
+                }],
+            }],
+            rollbacks: vec![RollbackSnapshot {
+                tick: Tick(10),
+                start_tick: Tick(8),
+                num_entities: 3,
+            }],
+        };
+        assert_eq!(
+            serde_json::to_value(&snapshot).unwrap(),
+            json!({
+                "source": "Client",
+                "tick": 10,
+                "time": 1.5,
+                "connections": [{
+                    "client_id": 1,
+                    "rtt_ms": 20.0,
+                    "jitter_ms": 2.0,
+                    "packet_loss": 0.25,
+                    "channels": [{
+                        "name": "Channel1",
+                        "messages_sent": 1,
+                        "bytes_sent": 2,
+                        "messages_received": 3,
+                        "bytes_received": 4,
+                    }],
+                    "groups": [{
+                        "group_id": 5,
+                        "actions_sent": 6,
+                        "updates_sent": 7,
+                        "bytes_sent": 8,
+                    }],
+                }],
+                "rollbacks": [{
+                    "tick": 10,
+                    "start_tick": 8,
+                    "num_entities": 3,
+                }],
+            })
+        );
+    }
+
+    /// Read the last snapshot published on the feed since the previous call
+    fn last_snapshot(receiver: &mut Receiver<String>) -> Option<InspectorSnapshot> {
+        let mut last = None;
+        while let Ok(json) = receiver.try_recv() {
+            last = Some(serde_json::from_str(&json).unwrap());
+        }
+        last
+    }
+
+    #[test]
+    fn test_plugins_publish_snapshots() {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            SyncConfig::default().speedup_factor(1.0),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            link_conditioner,
+            frame_duration,
+        );
+        // let the OS pick the ports, we read the snapshots directly from the feed
+        let local_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+        stepper
+            .server_app
+            .add_plugins(ServerInspectorPlugin::<MyProtocol>::new(
+                InspectorConfig::server()
+                    .with_addr(local_addr)
+                    .with_interval(Duration::ZERO),
+            ));
+        stepper
+            .client_app
+            .add_plugins(ClientInspectorPlugin::<MyProtocol>::new(
+                InspectorConfig::client()
+                    .with_addr(local_addr)
+                    .with_interval(Duration::ZERO),
+            ));
+        stepper.init();
+        let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+        let mut server_feed = stepper
+            .server_app
+            .world
+            .resource::<InspectorFeed>()
+            .subscribe();
+        let mut client_feed = stepper
+            .client_app
+            .world
+            .resource::<InspectorFeed>()
+            .subscribe();
+
+        stepper
+            .client_app
+            .world
+            .resource_mut::<ClientConnectionManager>()
+            .send_message::<Channel1, _>(Message1("1".to_string()))
+            .unwrap();
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(0.0),
+                Replicate {
+                    replication_target: NetworkTarget::All,
+                    ..default()
+                },
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let server_snapshot = last_snapshot(&mut server_feed).unwrap();
+        assert_eq!(server_snapshot.source, InspectorSource::Server);
+        assert_eq!(
+            server_snapshot.tick,
+            stepper.server_app.world.resource::<TickManager>().tick()
+        );
+        let [connection] = server_snapshot.connections.as_slice() else {
+            panic!("expected one connection");
+        };
+        assert_eq!(connection.client_id, client_id);
+        let channel1 = connection
+            .channels
+            .iter()
+            .find(|channel| channel.name == "Channel1")
+            .unwrap();
+        assert_eq!(channel1.stats.messages_received, 1);
+        let group = connection
+            .groups
+            .iter()
+            .find(|group| Entity::from_bits(group.group_id) == server_entity)
+            .unwrap();
+        assert_eq!(group.stats.actions_sent, 1);
+
+        let client_snapshot = last_snapshot(&mut client_feed).unwrap();
+        assert_eq!(client_snapshot.source, InspectorSource::Client);
+        let [connection] = client_snapshot.connections.as_slice() else {
+            panic!("expected one connection");
+        };
+        let channel1 = connection
+            .channels
+            .iter()
+            .find(|channel| channel.name == "Channel1")
+            .unwrap();
+        assert_eq!(channel1.stats.messages_sent, 1);
+    }
+}
@@ -2,8 +2,17 @@
 
 pub mod config;
 
+pub mod desync;
+
 pub mod events;
 
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "inspector", not(target_family = "wasm"))))
+)]
+#[cfg(all(feature = "inspector", not(target_family = "wasm")))]
+pub mod inspector;
+
 pub mod log;
 
 pub mod ping;
@@ -14,6 +23,8 @@ pub mod replication;
 
 pub mod sets;
 
+pub mod tick_event;
+
 pub mod tick_manager;
 
 pub mod time_manager;
@@ -36,8 +36,8 @@ pub struct Replicate<P: Protocol> {
     #[doc(hidden)]
     pub replication_clients_cache: HashMap<ClientId, ClientVisibility>,
     pub replication_mode: ReplicationMode,
-    // TODO: currently, if the host removes Replicate, then the entity is not removed in the remote
-    //  it just keeps living but doesn't receive any updates. Should we make this configurable?
+    /// What happens to the entity on the remote if the `Replicate` component is removed but the entity is not despawned
+    pub stop_policy: ReplicationStopPolicy,
     pub replication_group: ReplicationGroup,
     /// If true, recursively add `Replicate` and `ParentSync` components to all children to make sure they are replicated
     /// If false, you can still replicate hierarchies, but in a more fine-grained manner. You will have to add the `Replicate`
@@ -61,6 +61,8 @@ pub struct PerComponentReplicationMetadata {
     /// Custom replication target for this component. We will replicate to the intersection of
     /// the entity's replication target and this target
     target: NetworkTarget,
+    /// What happens to the component on the remote when its replication gets disabled
+    stop_policy: ReplicationStopPolicy,
 }
 impl Default for PerComponentReplicationMetadata {
     fn default() -> Self {
@@ -68,10 +70,67 @@ impl Default for PerComponentReplicationMetadata {
             disabled: false,
             replicate_once: false,
             target: NetworkTarget::All,
+            stop_policy: ReplicationStopPolicy::default(),
         }
     }
 }
 
+/// What happens on the remote when we stop replicating an entity (by removing the [`Replicate`] component
+/// without despawning the entity), or a component (with [`Replicate::disable_component`])
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub enum ReplicationStopPolicy {
+    /// The entity (or component) keeps living on the remote, but doesn't receive any updates anymore
+    #[default]
+    Freeze,
+    /// The entity is despawned (or the component is removed) on the remote
+    Despawn,
+    /// The entity becomes a local-only entity on the remote: it is removed from the entity mapping
+    /// and gets the [`Detached`] marker component. This is useful to hand over an entity to the remote
+    /// (for example a ragdoll that is simulated only on the client).
+    /// On the client, the predicted and interpolated entities of a detached entity are detached as well:
+    /// the predicted entity keeps being simulated but is excluded from rollbacks, and the interpolated
+    /// entity stops being interpolated.
+    ///
+    /// For components, the component is listed in the [`DetachedComponents`] of the entity on the remote.
+    /// The client then stops correcting the predicted component with the server state (it is only rolled
+    /// back to its own predicted history), and stops interpolating the interpolated component.
+    /// For entities that are neither predicted nor interpolated, this is the same as [`ReplicationStopPolicy::Freeze`].
+    ///
+    /// With snapshot replication, entities cannot be detached and this is the same as [`ReplicationStopPolicy::Freeze`].
+    Detach,
+}
+
+/// Marker component added on the receiving side to entities that the remote stopped replicating with
+/// [`ReplicationStopPolicy::Detach`]. The entity is now fully owned by the local world.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Detached;
+
+/// Component added on the receiving side to entities for which the remote stopped replicating some components
+/// with [`ReplicationStopPolicy::Detach`]. The components are now fully owned by the local world.
+#[derive(Component, Debug)]
+pub struct DetachedComponents<P: Protocol> {
+    pub(crate) kinds: HashSet<P::ComponentKinds>,
+}
+
+impl<P: Protocol> Default for DetachedComponents<P> {
+    fn default() -> Self {
+        Self {
+            kinds: HashSet::default(),
+        }
+    }
+}
+
+impl<P: Protocol> DetachedComponents<P> {
+    /// Returns true if the remote stopped replicating the component `C` with [`ReplicationStopPolicy::Detach`]
+    pub fn contains<C>(&self) -> bool
+    where
+        P::ComponentKinds: FromType<C>,
+    {
+        self.kinds
+            .contains(&<P::ComponentKinds as FromType<C>>::from_type())
+    }
+}
+
 impl<P: Protocol> Replicate<P> {
     pub(crate) fn group_id(&self, entity: Option<Entity>) -> ReplicationGroupId {
         self.replication_group.group_id(entity)
@@ -103,11 +162,20 @@ impl<P: Protocol> Replicate<P> {
     /// Replication target for this specific component
     /// This will be the intersection of the provided `entity_target`, and the `target` of the component
     /// if it exists
-    pub fn target<C>(&self, mut entity_target: NetworkTarget) -> NetworkTarget
+    pub fn target<C>(&self, entity_target: NetworkTarget) -> NetworkTarget
     where
         P::ComponentKinds: FromType<C>,
     {
         let kind = <P::ComponentKinds as FromType<C>>::from_type();
+        self.component_target(kind, entity_target)
+    }
+
+    /// Replication target for the component of the provided kind (see [`Replicate::target`])
+    pub(crate) fn component_target(
+        &self,
+        kind: P::ComponentKinds,
+        mut entity_target: NetworkTarget,
+    ) -> NetworkTarget {
         match self.per_component_metadata.get(&kind) {
             None => entity_target,
             Some(metadata) => {
@@ -148,6 +216,43 @@ impl<P: Protocol> Replicate<P> {
         }
     }
 
+    /// Set what happens to the component on the remote when its replication gets disabled
+    /// with [`Replicate::disable_component`]
+    pub fn set_component_stop_policy<C>(&mut self, stop_policy: ReplicationStopPolicy)
+    where
+        P::ComponentKinds: FromType<C>,
+    {
+        let kind = <P::ComponentKinds as FromType<C>>::from_type();
+        self.per_component_metadata
+            .entry(kind)
+            .or_default()
+            .stop_policy = stop_policy;
+        // if we are back at the default, remove the entry
+        if self.per_component_metadata.get(&kind).unwrap()
+            == &PerComponentReplicationMetadata::default()
+        {
+            self.per_component_metadata.remove(&kind);
+        }
+    }
+
+    /// Returns the list of components whose replication is disabled in `self`, but was not disabled in `previous`,
+    /// along with what should happen to them on the remote
+    pub(crate) fn newly_disabled_components<'a>(
+        &'a self,
+        previous: &'a Replicate<P>,
+    ) -> impl Iterator<Item = (P::ComponentKinds, ReplicationStopPolicy)> + 'a {
+        self.per_component_metadata
+            .iter()
+            .filter(|(kind, metadata)| {
+                metadata.disabled
+                    && !previous
+                        .per_component_metadata
+                        .get(*kind)
+                        .is_some_and(|previous| previous.disabled)
+            })
+            .map(|(kind, metadata)| (*kind, metadata.stop_policy))
+    }
+
     pub fn enable_replicate_once<C>(&mut self)
     where
         P::ComponentKinds: FromType<C>,
@@ -280,6 +385,7 @@ impl<P: Protocol> Default for Replicate<P> {
             interpolation_target: NetworkTarget::None,
             replication_clients_cache: HashMap::new(),
             replication_mode: ReplicationMode::default(),
+            stop_policy: ReplicationStopPolicy::default(),
             replication_group: Default::default(),
             replicate_hierarchy: true,
             per_component_metadata: HashMap::default(),
@@ -138,8 +138,46 @@ impl EntityMapper for RemoteEntityMap {
     }
 }
 
+/// Entity mapper that leaves the entities unchanged, and only records whether some of them
+/// are remote entities that are not present in the [`RemoteEntityMap`] yet
+pub(super) struct UnmappedEntityFinder<'a> {
+    entity_map: &'a RemoteEntityMap,
+    pub(super) found: bool,
+}
+
+impl<'a> UnmappedEntityFinder<'a> {
+    pub(super) fn new(entity_map: &'a RemoteEntityMap) -> Self {
+        Self {
+            entity_map,
+            found: false,
+        }
+    }
+}
+
+impl EntityMapper for UnmappedEntityFinder<'_> {
+    fn map_entity(&mut self, entity: Entity) -> Entity {
+        if entity != Entity::PLACEHOLDER && self.entity_map.get_local(entity).is_none() {
+            self.found = true;
+        }
+        entity
+    }
+}
+
+/// Entity mapper that maps the remote entities that could not be resolved to [`Entity::PLACEHOLDER`]
+pub(super) struct PlaceholderEntityMapper<'a>(pub(super) &'a RemoteEntityMap);
+
+impl EntityMapper for PlaceholderEntityMapper<'_> {
+    fn map_entity(&mut self, entity: Entity) -> Entity {
+        self.0
+            .get_local(entity)
+            .copied()
+            .unwrap_or(Entity::PLACEHOLDER)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use bevy::prelude::Entity;
     use bevy::utils::Duration;
 
     use crate::prelude::client::*;
@@ -235,4 +273,121 @@ mod tests {
         );
         Ok(())
     }
+
+    // A component references an entity of another replication group that has not been replicated yet:
+    // the component is held back until the referenced entity is replicated.
+    #[test]
+    fn test_deferred_entity_reference() -> anyhow::Result<()> {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+
+        // the target is not replicated yet
+        let server_target = stepper.server_app.world.spawn(Component1(0.0)).id();
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((Component4(server_target), Replicate::default()))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // the entity is spawned, but the component is held back
+        let client_entity = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .unwrap();
+        assert!(stepper
+            .client_app
+            .world
+            .entity(client_entity)
+            .get::<Component4>()
+            .is_none());
+
+        // replicate the target, in a different replication group
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_target)
+            .insert(Replicate::default());
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let client_target = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_target)
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .entity(client_entity)
+                .get::<Component4>(),
+            Some(&Component4(client_target))
+        );
+
+        // a reference to an entity that is never replicated resolves to a placeholder after the timeout
+        let server_missing = stepper.server_app.world.spawn_empty().id();
+        let server_entity_2 = stepper
+            .server_app
+            .world
+            .spawn((Component4(server_missing), Replicate::default()))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_entity_2 = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity_2)
+            .unwrap();
+        assert!(stepper
+            .client_app
+            .world
+            .entity(client_entity_2)
+            .get::<Component4>()
+            .is_none());
+        for _ in 0..ReplicationConfig::default().entity_reference_timeout_ticks {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .entity(client_entity_2)
+                .get::<Component4>(),
+            Some(&Component4(Entity::PLACEHOLDER))
+        );
+        Ok(())
+    }
 }
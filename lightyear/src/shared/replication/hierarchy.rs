@@ -90,6 +90,14 @@ impl<P: Protocol> HierarchySyncPlugin<P> {
                 parent_sync,
                 parent
             );
+            // the parent could not be mapped to a local entity (see `ReplicationConfig::entity_reference_timeout_ticks`)
+            if parent_sync.0 == Some(Entity::PLACEHOLDER) {
+                warn!(
+                    ?entity,
+                    "the replicated parent of the entity does not exist"
+                );
+                continue;
+            }
             if let Some(new_parent) = parent_sync.0 {
                 if parent.filter(|&parent| **parent == new_parent).is_none() {
                     commands.entity(entity).set_parent(new_parent);
@@ -24,7 +24,9 @@ pub mod entity_map;
 pub(crate) mod hierarchy;
 pub(crate) mod plugin;
 pub(crate) mod receive;
+pub mod resources;
 pub(crate) mod send;
+pub(crate) mod snapshot;
 pub mod systems;
 
 // // NOTE: cannot add trait bounds on C: ComponentProtocol and K: ComponentProtocolKind because of https://github.com/serde-rs/serde/issues/1296
@@ -49,6 +51,10 @@ pub mod systems;
 pub struct EntityActions<C, K: Hash + Eq> {
     pub(crate) spawn: bool,
     pub(crate) despawn: bool,
+    /// The entity is not replicated anymore, and becomes a local-only entity on the remote
+    pub(crate) detach: bool,
+    /// The components are not replicated anymore, and become local-only components on the remote
+    pub(crate) detach_components: HashSet<K>,
     // Cannot use HashSet because we would need ComponentProtocol to implement Hash + Eq
     pub(crate) insert: Vec<C>,
     pub(crate) remove: HashSet<K>,
@@ -61,6 +67,8 @@ impl<C, K: Hash + Eq> Default for EntityActions<C, K> {
         Self {
             spawn: false,
             despawn: false,
+            detach: false,
+            detach_components: HashSet::new(),
             insert: Vec::new(),
             remove: HashSet::new(),
             updates: Vec::new(),
@@ -86,12 +94,24 @@ pub struct EntityUpdatesMessage<C> {
     pub(crate) updates: Vec<(Entity, Vec<C>)>,
 }
 
+/// Snapshot of all the entities visible to a client, delta-encoded against a previous snapshot
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SnapshotMessage<C, K: Hash + Eq> {
+    /// The tick of the snapshot that this message is a delta against.
+    /// If None, the message contains the full world state
+    baseline_tick: Option<Tick>,
+    // we use vec but the order of entities should not matter
+    pub(crate) actions: Vec<(Entity, EntityActions<C, K>)>,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub enum ReplicationMessageData<C, K: Hash + Eq> {
     /// All the entity actions (Spawn/despawn/inserts/removals) for a given group
     Actions(EntityActionMessage<C, K>),
     /// All the entity updates for a given group
     Updates(EntityUpdatesMessage<C>),
+    /// Delta-encoded snapshot of all the replicated entities (when using [`ReplicationSendMode::Snapshot`](crate::server::config::ReplicationSendMode))
+    Snapshot(SnapshotMessage<C, K>),
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
@@ -137,6 +157,15 @@ pub trait ReplicationSend<P: Protocol>: Resource {
         system_current_tick: BevyTick,
     ) -> Result<()>;
 
+    /// Stop replicating the entity, and let the remote keep it as a local-only entity
+    fn prepare_entity_detach(
+        &mut self,
+        entity: Entity,
+        replicate: &Replicate<P>,
+        target: NetworkTarget,
+        system_current_tick: BevyTick,
+    ) -> Result<()>;
+
     fn prepare_component_insert(
         &mut self,
         entity: Entity,
@@ -157,6 +186,16 @@ pub trait ReplicationSend<P: Protocol>: Resource {
         system_current_tick: BevyTick,
     ) -> Result<()>;
 
+    /// Stop replicating the component, and let the remote keep it as a local-only component
+    fn prepare_component_detach(
+        &mut self,
+        entity: Entity,
+        component_kind: P::ComponentKinds,
+        replicate: &Replicate<P>,
+        target: NetworkTarget,
+        system_current_tick: BevyTick,
+    ) -> Result<()>;
+
     fn prepare_entity_update(
         &mut self,
         entity: Entity,
@@ -190,11 +229,19 @@ pub trait ReplicationSend<P: Protocol>: Resource {
 mod tests {
     use bevy::utils::Duration;
 
+    use crate::client::prediction::{Rollback, RollbackState};
+    use crate::packet::message::MessageId;
     use crate::prelude::client::*;
+    use crate::prelude::server::ReplicationSendMode;
     use crate::prelude::*;
     use crate::tests::protocol::*;
     use crate::tests::stepper::{BevyStepper, Step};
 
+    use super::{
+        EntityActionMessage, EntityActions, EntityUpdatesMessage, ReplicationMessage,
+        ReplicationMessageData,
+    };
+
     // An entity gets replicated from server to client,
     // then a component gets removed from that entity on server,
     // that component should also removed on client as well.
@@ -271,4 +318,541 @@ mod tests {
             .is_none());
         Ok(())
     }
+
+    // With snapshot replication, spawns, updates, removals and despawns are all
+    // replicated via the snapshots, with a single confirmed tick
+    #[test]
+    fn test_snapshot_replication() -> anyhow::Result<()> {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper
+            .server_app
+            .world
+            .resource_mut::<ServerConnectionManager>()
+            .replication_config
+            .send_mode = ReplicationSendMode::Snapshot;
+        stepper.init();
+
+        // Create entities on server
+        let server_entity_1 = stepper
+            .server_app
+            .world
+            .spawn((Component1(0.0), Replicate::default()))
+            .id();
+        let server_entity_2 = stepper
+            .server_app
+            .world
+            .spawn((Component2(0.0), Replicate::default()))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        // Check that the entities are replicated to client
+        let client_entity_1 = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity_1)
+            .unwrap();
+        let client_entity_2 = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity_2)
+            .unwrap();
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .entity(client_entity_1)
+                .get::<Component1>()
+                .unwrap(),
+            &Component1(0.0)
+        );
+
+        // Update a component and remove another one on the server
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_entity_1)
+            .get_mut::<Component1>()
+            .unwrap()
+            .0 = 1.0;
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_entity_2)
+            .remove::<Component2>();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .entity(client_entity_1)
+                .get::<Component1>()
+                .unwrap(),
+            &Component1(1.0)
+        );
+        assert!(stepper
+            .client_app
+            .world
+            .entity(client_entity_2)
+            .get::<Component2>()
+            .is_none());
+
+        // Despawn the entity on the server
+        stepper.server_app.world.despawn(server_entity_1);
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world
+            .get_entity(client_entity_1)
+            .is_none());
+        Ok(())
+    }
+    // The remote applies the `ReplicationStopPolicy` when an entity or a component stops being replicated
+    #[test]
+    fn test_replication_stop_policy() -> anyhow::Result<()> {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+
+        let mut replicate = Replicate::default();
+        replicate.set_component_stop_policy::<Component2>(ReplicationStopPolicy::Despawn);
+        let server_despawned = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(0.0),
+                Component2(0.0),
+                Replicate {
+                    stop_policy: ReplicationStopPolicy::Despawn,
+                    ..replicate
+                },
+            ))
+            .id();
+        let server_detached = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(0.0),
+                Replicate {
+                    stop_policy: ReplicationStopPolicy::Detach,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        let client_despawned = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_despawned)
+            .unwrap();
+        let client_detached = *stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_detached)
+            .unwrap();
+
+        // disabling the replication of a component with the Despawn policy removes it on the client
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_despawned)
+            .get_mut::<Replicate>()
+            .unwrap()
+            .disable_component::<Component2>();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world
+            .entity(client_despawned)
+            .get::<Component2>()
+            .is_none());
+
+        // removing Replicate despawns or detaches the entity on the client
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_despawned)
+            .remove::<Replicate>();
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_detached)
+            .remove::<Replicate>();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world
+            .get_entity(client_despawned)
+            .is_none());
+        assert!(stepper
+            .client_app
+            .world
+            .entity(client_detached)
+            .contains::<Detached>());
+        assert!(stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_detached)
+            .is_none());
+
+        // the detached entity is not affected by the server anymore
+        stepper.server_app.world.despawn(server_detached);
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world
+            .get_entity(client_detached)
+            .is_some());
+        Ok(())
+    }
+
+    // With the Detach policy, the client hands over the predicted and interpolated entities/components
+    // to the local world
+    #[test]
+    fn test_replication_stop_policy_detach() -> anyhow::Result<()> {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+
+        let mut replicate = Replicate {
+            prediction_target: NetworkTarget::All,
+            ..Default::default()
+        };
+        replicate.set_component_stop_policy::<Component1>(ReplicationStopPolicy::Detach);
+        let server_component_detached = stepper
+            .server_app
+            .world
+            .spawn((Component1(0.0), replicate))
+            .id();
+        let server_predicted = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(0.0),
+                Replicate {
+                    prediction_target: NetworkTarget::All,
+                    stop_policy: ReplicationStopPolicy::Detach,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        let server_interpolated = stepper
+            .server_app
+            .world
+            .spawn((
+                Component1(0.0),
+                Replicate {
+                    interpolation_target: NetworkTarget::All,
+                    stop_policy: ReplicationStopPolicy::Detach,
+                    ..Default::default()
+                },
+            ))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let get_confirmed = |stepper: &BevyStepper, server_entity| {
+            let confirmed = *stepper
+                .client_app
+                .world
+                .resource::<ClientConnectionManager>()
+                .replication_receiver
+                .remote_entity_map
+                .get_local(server_entity)
+                .unwrap();
+            let links = stepper
+                .client_app
+                .world
+                .get::<Confirmed>(confirmed)
+                .unwrap();
+            (confirmed, links.predicted, links.interpolated)
+        };
+        let (_, predicted_component_detached, _) =
+            get_confirmed(&stepper, server_component_detached);
+        let predicted_component_detached = predicted_component_detached.unwrap();
+        let (confirmed_predicted, predicted, _) = get_confirmed(&stepper, server_predicted);
+        let predicted = predicted.unwrap();
+        let (confirmed_interpolated, _, interpolated) =
+            get_confirmed(&stepper, server_interpolated);
+        let interpolated = interpolated.unwrap();
+        assert!(stepper
+            .client_app
+            .world
+            .entity(interpolated)
+            .contains::<InterpolateStatus<Component1>>());
+
+        // detaching a predicted component: the client's prediction is not corrected by the server anymore
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_component_detached)
+            .get_mut::<Replicate>()
+            .unwrap()
+            .disable_component::<Component1>();
+        stepper.frame_step();
+        stepper.frame_step();
+        stepper
+            .client_app
+            .world
+            .get_mut::<Component1>(predicted_component_detached)
+            .unwrap()
+            .0 = 5.0;
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        let current_tick = stepper.client_app.world.resource::<TickManager>().tick();
+        stepper.client_app.world.resource_mut::<Rollback>().state = RollbackState::ShouldRollback {
+            current_tick: current_tick - 2,
+        };
+        stepper.frame_step();
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .get::<Component1>(predicted_component_detached)
+                .unwrap(),
+            &Component1(5.0)
+        );
+
+        // detaching the entity: the predicted and interpolated entities are handed over to the client
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_predicted)
+            .remove::<Replicate>();
+        stepper
+            .server_app
+            .world
+            .entity_mut(server_interpolated)
+            .remove::<Replicate>();
+        stepper.frame_step();
+        stepper.frame_step();
+        let predicted_entity = stepper.client_app.world.entity(predicted);
+        assert!(predicted_entity.contains::<Detached>());
+        assert_eq!(
+            predicted_entity.get::<RollbackEligibility>(),
+            Some(&RollbackEligibility::Excluded)
+        );
+        assert!(predicted_entity
+            .get::<Predicted>()
+            .unwrap()
+            .confirmed_entity
+            .is_none());
+        let interpolated_entity = stepper.client_app.world.entity(interpolated);
+        assert!(interpolated_entity.contains::<Detached>());
+        assert!(!interpolated_entity.contains::<InterpolateStatus<Component1>>());
+
+        // despawning the confirmed entities doesn't despawn the detached entities
+        stepper.client_app.world.despawn(confirmed_predicted);
+        stepper.client_app.world.despawn(confirmed_interpolated);
+        stepper.frame_step();
+        assert!(stepper.client_app.world.get_entity(predicted).is_some());
+        assert!(stepper.client_app.world.get_entity(interpolated).is_some());
+        Ok(())
+    }
+
+    /// A group that is missing an action message holds back the messages of the other groups,
+    /// so that the confirmed entities never get ahead of each other
+    #[test]
+    fn test_replication_groups_wait_for_missing_actions() -> anyhow::Result<()> {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        let mut stepper = BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        );
+        stepper.init();
+
+        // each entity is in its own replication group
+        let server_a = stepper
+            .server_app
+            .world
+            .spawn((Component1(0.0), Replicate::default()))
+            .id();
+        let server_b = stepper
+            .server_app
+            .world
+            .spawn((Component1(0.0), Replicate::default()))
+            .id();
+        for _ in 0..10 {
+            stepper.frame_step();
+        }
+        let receiver = &stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver;
+        let client_a = *receiver.remote_entity_map.get_local(server_a).unwrap();
+        let client_b = *receiver.remote_entity_map.get_local(server_b).unwrap();
+        let group_a = *receiver.remote_entity_to_group.get(&server_a).unwrap();
+        let group_b = *receiver.remote_entity_to_group.get(&server_b).unwrap();
+        let channel_a = receiver.group_channels.get(&group_a).unwrap();
+        let next_action_id = channel_a.actions_pending_recv_message_id;
+        let tick = channel_a.latest_tick.unwrap();
+        let action = |sequence_id: MessageId, value: f32| ReplicationMessage {
+            group_id: group_a,
+            data: ReplicationMessageData::Actions(EntityActionMessage {
+                sequence_id,
+                actions: vec![(
+                    server_a,
+                    EntityActions {
+                        updates: vec![Component1(value).into()],
+                        ..Default::default()
+                    },
+                )],
+            }),
+        };
+
+        // the packet with the action message for tick + 1 is delayed, and arrives after
+        // the packets for tick + 2
+        let mut manager = stepper
+            .client_app
+            .world
+            .resource_mut::<ClientConnectionManager>();
+        manager
+            .replication_receiver
+            .recv_message(action(next_action_id + 1, 2.0), tick + 2);
+        manager.replication_receiver.recv_message(
+            ReplicationMessage {
+                group_id: group_b,
+                data: ReplicationMessageData::Updates(EntityUpdatesMessage {
+                    last_action_tick: None,
+                    updates: vec![(server_b, vec![Component1(2.0).into()])],
+                }),
+            },
+            tick + 2,
+        );
+        stepper.frame_step();
+        // the update of group B is held back until group A can catch up
+        assert_eq!(
+            stepper.client_app.world.get::<Component1>(client_a),
+            Some(&Component1(0.0))
+        );
+        assert_eq!(
+            stepper.client_app.world.get::<Component1>(client_b),
+            Some(&Component1(0.0))
+        );
+
+        stepper
+            .client_app
+            .world
+            .resource_mut::<ClientConnectionManager>()
+            .replication_receiver
+            .recv_message(action(next_action_id, 1.0), tick + 1);
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world.get::<Component1>(client_a),
+            Some(&Component1(2.0))
+        );
+        assert_eq!(
+            stepper.client_app.world.get::<Component1>(client_b),
+            Some(&Component1(2.0))
+        );
+        let receiver = &stepper
+            .client_app
+            .world
+            .resource::<ClientConnectionManager>()
+            .replication_receiver;
+        assert_eq!(receiver.get_confirmed_tick(client_a), Some(tick + 2));
+        assert_eq!(receiver.get_confirmed_tick(client_b), Some(tick + 2));
+        Ok(())
+    }
 }
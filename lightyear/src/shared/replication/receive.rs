@@ -4,9 +4,10 @@ use std::iter::Extend;
 
 use anyhow::Context;
 use bevy::ecs::entity::EntityHash;
-use bevy::prelude::{DespawnRecursiveExt, Entity, World};
+use bevy::prelude::{DespawnRecursiveExt, Entity, EntityWorldMut, World};
 use bevy::reflect::Reflect;
-use bevy::utils::HashSet;
+use bevy::utils::hashbrown::hash_map::Entry;
+use bevy::utils::{HashMap, HashSet};
 use tracing::{debug, error, trace, trace_span, warn};
 
 use crate::packet::message::MessageId;
@@ -16,17 +17,35 @@ use crate::protocol::component::ComponentProtocol;
 use crate::protocol::component::{ComponentBehaviour, ComponentKindBehaviour};
 use crate::protocol::Protocol;
 use crate::shared::events::connection::ConnectionEvents;
-use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::replication::components::{Detached, DetachedComponents, ReplicationGroupId};
 
-use super::entity_map::RemoteEntityMap;
+use super::entity_map::{PlaceholderEntityMapper, RemoteEntityMap, UnmappedEntityFinder};
+use super::snapshot::{SnapshotReceiver, SNAPSHOT_GROUP_ID};
 use super::{
-    EntityActionMessage, EntityUpdatesMessage, ReplicationMessage, ReplicationMessageData,
+    EntityActionMessage, EntityActions, EntityUpdatesMessage, ReplicationMessage,
+    ReplicationMessageData,
 };
 
 type EntityHashMap<K, V> = hashbrown::HashMap<K, V, EntityHash>;
 
 type EntityHashSet<K> = hashbrown::HashSet<K, EntityHash>;
 
+/// Default number of ticks that a component can wait for the entities it references to be replicated
+pub(crate) const DEFAULT_ENTITY_REFERENCE_TIMEOUT_TICKS: u16 = 64;
+
+/// A received component that references remote entities that have not been replicated yet.
+///
+/// It is held back until all the entities it references have been spawned, so that
+/// the entity mapping doesn't silently produce the wrong entity.
+#[derive(Debug)]
+struct PendingComponent<C> {
+    component: C,
+    /// If true, the component must be inserted on the entity (instead of updated)
+    insert: bool,
+    /// Local tick at which the component was first held back
+    tick: Tick,
+}
+
 pub(crate) struct ReplicationReceiver<P: Protocol> {
     /// Map between local and remote entities. (used mostly on client because it's when we receive entity updates)
     pub remote_entity_map: RemoteEntityMap,
@@ -37,6 +56,23 @@ pub(crate) struct ReplicationReceiver<P: Protocol> {
     // BOTH
     /// Buffer to so that we have an ordered receiver per group
     pub group_channels: EntityHashMap<ReplicationGroupId, GroupChannel<P>>,
+
+    /// Reconstructs the world state from the snapshot messages, if the remote uses snapshot replication
+    snapshots: SnapshotReceiver<P>,
+
+    /// Number of ticks that we wait before applying replication messages, so that messages that arrive
+    /// out of order because of network jitter can still be applied in order of tick
+    playout_delay_ticks: u16,
+
+    /// Components that are waiting for the remote entities they reference to be replicated,
+    /// indexed by remote entity
+    pending_components:
+        EntityHashMap<Entity, HashMap<P::ComponentKinds, PendingComponent<P::Components>>>,
+    /// Number of ticks after which a pending component is applied even if some of the entities
+    /// it references are still missing
+    entity_reference_timeout_ticks: u16,
+    /// Local tick at which we last read replication messages
+    current_tick: Tick,
 }
 
 impl<P: Protocol> ReplicationReceiver<P> {
@@ -47,9 +83,27 @@ impl<P: Protocol> ReplicationReceiver<P> {
             remote_entity_to_group: Default::default(),
             // BOTH
             group_channels: Default::default(),
+            snapshots: SnapshotReceiver::default(),
+            playout_delay_ticks: 0,
+            pending_components: Default::default(),
+            entity_reference_timeout_ticks: DEFAULT_ENTITY_REFERENCE_TIMEOUT_TICKS,
+            current_tick: Tick(0),
         }
     }
 
+    pub(crate) fn with_playout_delay_ticks(mut self, playout_delay_ticks: u16) -> Self {
+        self.playout_delay_ticks = playout_delay_ticks;
+        self
+    }
+
+    pub(crate) fn with_entity_reference_timeout_ticks(
+        mut self,
+        entity_reference_timeout_ticks: u16,
+    ) -> Self {
+        self.entity_reference_timeout_ticks = entity_reference_timeout_ticks;
+        self
+    }
+
     /// Recv a new replication message and buffer it
     pub(crate) fn recv_message(
         &mut self,
@@ -89,23 +143,11 @@ impl<P: Protocol> ReplicationReceiver<P> {
 
                 // TODO: include somewhere in the update message the m.last_ack_tick since when we compute changes?
                 //  (if we want to do diff compression?
-                // otherwise buffer the update
-                match m.last_action_tick {
-                    None => {
-                        channel
-                            .buffered_updates_without_last_action_tick
-                            .entry(remote_tick)
-                            .or_insert(m);
-                    }
-                    Some(last_action_tick) => {
-                        channel
-                            .buffered_updates_with_last_action_tick
-                            .entry(last_action_tick)
-                            .or_default()
-                            .entry(remote_tick)
-                            .or_insert(m);
-                    }
-                };
+                // otherwise buffer the update; they will be read in order of tick
+                channel.buffered_updates.entry(remote_tick).or_insert(m);
+            }
+            ReplicationMessageData::Snapshot(m) => {
+                self.snapshots.recv_message(m, remote_tick);
             }
         }
         trace!(?channel, "group channel after buffering");
@@ -116,14 +158,23 @@ impl<P: Protocol> ReplicationReceiver<P> {
     ///
     /// A message is ready if:
     /// - actions are read in order
-    /// - updates are read in order, and only if the actions that preceded them have already been read
+    /// - updates are read in order of tick, and only if the actions that preceded them have already been read
     /// - the remote tick is <= the current tick (i.e. we do not read messages from the future)
+    /// - the remote tick is older than `remote_tick_estimate` by at least the playout delay.
+    ///   `remote_tick_estimate` is our estimate of the most recent tick that the remote has sent.
+    /// - the remote tick is not more recent than the latest tick applied by any group that is still waiting
+    ///   for a missing action message, so that the confirmed ticks stay consistent across groups.
     ///
+    /// Actions and updates of a group are returned in order of tick; stale updates are discarded.
+    ///
+    /// For snapshots, all the snapshots that are not from the future are returned in order of tick
+    /// (each as a delta against the previous snapshot)
     ///
     /// Updates the `latest_tick` for this group
     pub(crate) fn read_messages(
         &mut self,
         current_tick: Tick,
+        remote_tick_estimate: Tick,
     ) -> Vec<(
         ReplicationGroupId,
         Vec<(
@@ -131,15 +182,49 @@ impl<P: Protocol> ReplicationReceiver<P> {
             ReplicationMessageData<P::Components, P::ComponentKinds>,
         )>,
     )> {
-        trace!(?current_tick, ?self.group_channels, "reading replication messages");
-        self.group_channels
+        self.current_tick = current_tick;
+        // with no playout delay, we apply the messages as soon as they are not from the future
+        let release_tick = if self.playout_delay_ticks == 0 {
+            current_tick
+        } else {
+            std::cmp::min(
+                current_tick,
+                remote_tick_estimate - self.playout_delay_ticks,
+            )
+        };
+        // a group that is missing an action message cannot apply anything past its latest tick until that
+        // message arrives; the other groups wait for it so that they don't get ahead
+        let release_tick = self
+            .group_channels
+            .values()
+            .filter(|channel| channel.is_waiting_for_actions())
+            .filter_map(|channel| channel.latest_tick)
+            .fold(release_tick, std::cmp::min);
+        trace!(?current_tick, ?release_tick, ?self.group_channels, "reading replication messages");
+        let mut res: Vec<_> = self
+            .group_channels
             .iter_mut()
             .filter_map(|(group_id, channel)| {
                 channel
-                    .read_messages(current_tick)
+                    .read_messages(release_tick)
                     .map(|messages| (*group_id, messages))
             })
-            .collect()
+            .collect();
+        let snapshots = self.snapshots.read_messages(release_tick);
+        if let Some((tick, _)) = snapshots.last() {
+            self.group_channels
+                .entry(SNAPSHOT_GROUP_ID)
+                .or_default()
+                .latest_tick = Some(*tick);
+            res.push((
+                SNAPSHOT_GROUP_ID,
+                snapshots
+                    .into_iter()
+                    .map(|(tick, snapshot)| (tick, ReplicationMessageData::Snapshot(snapshot)))
+                    .collect(),
+            ));
+        }
+        res
     }
 
     /// Gets the tick at which the provided confirmed entity currently is
@@ -175,6 +260,27 @@ impl<P: Protocol> ReplicationReceiver<P> {
             .get(&remote_entity)
             .and_then(|group_id| self.group_channels.get(group_id))
     }
+
+    /// Account for tick wrapping: ticks that are too far apart cannot be compared anymore.
+    ///
+    /// If it's been enough time since we last had any update for a group, we move the latest_tick of the group
+    /// forward so that it stays comparable with the ticks of the new messages.
+    /// `tick` is the local tick, which can be a bit ahead of the remote tick, so we keep the latest_tick well
+    /// behind it to avoid discarding new updates.
+    pub(crate) fn cleanup(&mut self, tick: Tick) {
+        for group_channel in self.group_channels.values_mut() {
+            if let Some(latest_tick) = group_channel.latest_tick {
+                if tick.is_too_far_from(latest_tick) {
+                    debug!(
+                        ?tick,
+                        ?latest_tick,
+                        ?group_channel,
+                        "Moving the latest_tick forward because there hasn't been any new updates in a while");
+                    group_channel.latest_tick = Some(tick - Tick::MAX_COMPARABLE_DISTANCE);
+                }
+            }
+        }
+    }
 }
 
 /// We want:
@@ -202,119 +308,7 @@ impl<P: Protocol> ReplicationReceiver<P> {
         match replication {
             ReplicationMessageData::Actions(m) => {
                 debug!(?tick, ?m, "Received replication actions");
-                // NOTE: order matters here, because some components can depend on other entities.
-                // These components could even form a cycle, for example A.HasWeapon(B) and B.HasHolder(A)
-                // Our solution is to first handle spawn for all entities separately.
-                for (entity, actions) in m.actions.iter() {
-                    debug!(remote_entity = ?entity, "Received entity actions");
-                    assert!(!(actions.spawn && actions.despawn));
-                    // spawn
-                    if actions.spawn {
-                        self.remote_entity_to_group.insert(*entity, group_id);
-                        if let Some(local_entity) = self.remote_entity_map.get_local(*entity) {
-                            if world.get_entity(*local_entity).is_some() {
-                                warn!("Received spawn for an entity that already exists");
-                                continue;
-                            }
-                            warn!("Received spawn for an entity that is already in our entity mapping! Not spawning");
-                            continue;
-                        }
-                        // TODO: optimization: spawn the bundle of insert components
-                        let local_entity = world.spawn_empty();
-                        self.remote_entity_map.insert(*entity, local_entity.id());
-                        trace!("Updated remote entity map: {:?}", self.remote_entity_map);
-
-                        debug!(remote_entity = ?entity, "Received entity spawn");
-                        events.push_spawn(local_entity.id());
-                    }
-                }
-
-                for (entity, actions) in m.actions.into_iter() {
-                    debug!(remote_entity = ?entity, "Received entity actions");
-
-                    // despawn
-                    if actions.despawn {
-                        debug!(remote_entity = ?entity, "Received entity despawn");
-                        if let Some(local_entity) = self.remote_entity_map.remove_by_remote(entity)
-                        {
-                            if let Some(group) = self.group_channels.get_mut(&group_id) {
-                                group.remote_entities.remove(&entity);
-                            }
-                            // TODO: we despawn all children as well right now, but that might not be what we want?
-                            if let Some(entity_mut) = world.get_entity_mut(local_entity) {
-                                entity_mut.despawn_recursive();
-                            }
-                            events.push_despawn(local_entity);
-                            self.remote_entity_to_group.remove(&entity);
-                        } else {
-                            error!("Received despawn for an entity that does not exist")
-                        }
-                        continue;
-                    }
-
-                    // safety: we know by this point that the entity exists
-                    let Ok(mut local_entity_mut) =
-                        self.remote_entity_map.get_by_remote(world, entity)
-                    else {
-                        error!("cannot find entity");
-                        continue;
-                    };
-
-                    // inserts
-                    let kinds = actions
-                        .insert
-                        .iter()
-                        .map(|c| c.into())
-                        .collect::<HashSet<P::ComponentKinds>>();
-                    debug!(remote_entity = ?entity, ?kinds, "Received InsertComponent");
-                    for mut component in actions.insert {
-                        // map any entities inside the component
-                        component.map_entities(&mut self.remote_entity_map);
-                        // TODO: figure out what to do with tick here
-                        events.push_insert_component(
-                            local_entity_mut.id(),
-                            (&component).into(),
-                            Tick(0),
-                        );
-                        component.insert(&mut local_entity_mut);
-
-                        // TODO: special-case for pre-spawned entities: we receive them from a client, but then we
-                        //  we should immediately take ownership of it, so we won't receive a despawn for it
-                        //  thus, we should remove it from the entity map right after receiving it!
-                        //  Actually, we should figure out a way to cleanup every received entity where the sender
-                        //  stopped replicating or didn't replicate the Despawn, as this could just cause memory to accumulate
-
-                        // TODO: maybe if is-server, attach the client-id to the ShouldBePredicted entity
-                        //  to know for which client we should do the pre-prediction
-                    }
-
-                    // removals
-                    trace!(remote_entity = ?entity, ?actions.remove, "Received RemoveComponent");
-                    for kind in actions.remove {
-                        events.push_remove_component(local_entity_mut.id(), kind, Tick(0));
-                        kind.remove(&mut local_entity_mut);
-                    }
-
-                    // (no need to run apply_deferred after applying actions, that is only for Commands)
-
-                    // updates
-                    let kinds = actions
-                        .updates
-                        .iter()
-                        .map(|c| c.into())
-                        .collect::<Vec<P::ComponentKinds>>();
-                    debug!(remote_entity = ?entity, ?kinds, "Received UpdateComponent");
-                    for mut component in actions.updates {
-                        // map any entities inside the component
-                        component.map_entities(&mut self.remote_entity_map);
-                        events.push_update_component(
-                            local_entity_mut.id(),
-                            (&component).into(),
-                            Tick(0),
-                        );
-                        component.update(&mut local_entity_mut);
-                    }
-                }
+                self.apply_actions(world, group_id, m.actions, events);
             }
             ReplicationMessageData::Updates(m) => {
                 debug!(?tick, ?m, "Received replication updates");
@@ -324,15 +318,14 @@ impl<P: Protocol> ReplicationReceiver<P> {
                     if let Ok(mut local_entity) =
                         self.remote_entity_map.get_by_remote(world, entity)
                     {
-                        for mut component in components {
-                            // map any entities inside the component
-                            component.map_entities(&mut self.remote_entity_map);
-                            events.push_update_component(
-                                local_entity.id(),
-                                (&component).into(),
-                                Tick(0),
+                        for component in components {
+                            self.apply_component(
+                                &mut local_entity,
+                                entity,
+                                component,
+                                false,
+                                events,
                             );
-                            component.update(&mut local_entity);
                         }
                     } else {
                         // we can get a few buffered updates after the entity has been despawned
@@ -342,6 +335,18 @@ impl<P: Protocol> ReplicationReceiver<P> {
                     }
                 }
             }
+            ReplicationMessageData::Snapshot(m) => {
+                debug!(?tick, ?m, "Received replication snapshot");
+                // update the list of entities in the group
+                let channel = self.group_channels.entry(group_id).or_default();
+                m.actions
+                    .iter()
+                    .filter(|(_, actions)| actions.spawn)
+                    .for_each(|(entity, _)| {
+                        channel.remote_entities.insert(*entity);
+                    });
+                self.apply_actions(world, group_id, m.actions, events);
+            }
         }
 
         // update the Confirmed tick for all entities in the replication group
@@ -363,6 +368,276 @@ impl<P: Protocol> ReplicationReceiver<P> {
                 }
             });
     }
+
+    /// Apply entity actions (spawns, despawns, inserts, removals, updates) to the world
+    fn apply_actions(
+        &mut self,
+        world: &mut World,
+        group_id: ReplicationGroupId,
+        actions: Vec<(Entity, EntityActions<P::Components, P::ComponentKinds>)>,
+        events: &mut ConnectionEvents<P>,
+    ) {
+        // NOTE: order matters here, because some components can depend on other entities.
+        // These components could even form a cycle, for example A.HasWeapon(B) and B.HasHolder(A)
+        // Our solution is to first handle spawn for all entities separately.
+        for (entity, actions) in actions.iter() {
+            debug!(remote_entity = ?entity, "Received entity actions");
+            assert!(!(actions.spawn && actions.despawn));
+            // spawn
+            if actions.spawn {
+                self.remote_entity_to_group.insert(*entity, group_id);
+                if let Some(local_entity) = self.remote_entity_map.get_local(*entity) {
+                    if world.get_entity(*local_entity).is_some() {
+                        warn!("Received spawn for an entity that already exists");
+                        continue;
+                    }
+                    warn!("Received spawn for an entity that is already in our entity mapping! Not spawning");
+                    continue;
+                }
+                // TODO: optimization: spawn the bundle of insert components
+                let local_entity = world.spawn_empty();
+                self.remote_entity_map.insert(*entity, local_entity.id());
+                trace!("Updated remote entity map: {:?}", self.remote_entity_map);
+
+                debug!(remote_entity = ?entity, "Received entity spawn");
+                events.push_spawn(local_entity.id());
+            }
+        }
+
+        for (entity, actions) in actions.into_iter() {
+            debug!(remote_entity = ?entity, "Received entity actions");
+
+            // despawn
+            if actions.despawn {
+                debug!(remote_entity = ?entity, "Received entity despawn");
+                if let Some(local_entity) = self.remote_entity_map.remove_by_remote(entity) {
+                    if let Some(group) = self.group_channels.get_mut(&group_id) {
+                        group.remote_entities.remove(&entity);
+                    }
+                    // TODO: we despawn all children as well right now, but that might not be what we want?
+                    if let Some(entity_mut) = world.get_entity_mut(local_entity) {
+                        entity_mut.despawn_recursive();
+                    }
+                    events.push_despawn(local_entity);
+                    self.remote_entity_to_group.remove(&entity);
+                    self.pending_components.remove(&entity);
+                } else {
+                    error!("Received despawn for an entity that does not exist")
+                }
+                continue;
+            }
+
+            // detach: the entity stays in the world, but is not replicated anymore
+            if actions.detach {
+                debug!(remote_entity = ?entity, "Received entity detach");
+                if let Some(local_entity) = self.remote_entity_map.remove_by_remote(entity) {
+                    if let Some(group) = self.group_channels.get_mut(&group_id) {
+                        group.remote_entities.remove(&entity);
+                    }
+                    self.remote_entity_to_group.remove(&entity);
+                    self.pending_components.remove(&entity);
+                    if let Some(mut entity_mut) = world.get_entity_mut(local_entity) {
+                        entity_mut.insert(Detached);
+                    }
+                } else {
+                    error!("Received detach for an entity that does not exist")
+                }
+                continue;
+            }
+
+            // safety: we know by this point that the entity exists
+            let Ok(mut local_entity_mut) = self.remote_entity_map.get_by_remote(world, entity)
+            else {
+                error!("cannot find entity");
+                continue;
+            };
+
+            // inserts
+            let kinds = actions
+                .insert
+                .iter()
+                .map(|c| c.into())
+                .collect::<HashSet<P::ComponentKinds>>();
+            debug!(remote_entity = ?entity, ?kinds, "Received InsertComponent");
+            for component in actions.insert {
+                self.apply_component(&mut local_entity_mut, entity, component, true, events);
+
+                // TODO: special-case for pre-spawned entities: we receive them from a client, but then we
+                //  we should immediately take ownership of it, so we won't receive a despawn for it
+                //  thus, we should remove it from the entity map right after receiving it!
+                //  Actually, we should figure out a way to cleanup every received entity where the sender
+                //  stopped replicating or didn't replicate the Despawn, as this could just cause memory to accumulate
+
+                // TODO: maybe if is-server, attach the client-id to the ShouldBePredicted entity
+                //  to know for which client we should do the pre-prediction
+            }
+
+            // removals
+            trace!(remote_entity = ?entity, ?actions.remove, "Received RemoveComponent");
+            for kind in actions.remove {
+                // a component that was waiting for its entity references is not needed anymore
+                if let Some(pending) = self.pending_components.get_mut(&entity) {
+                    pending.remove(&kind);
+                }
+                events.push_remove_component(local_entity_mut.id(), kind, Tick(0));
+                kind.remove(&mut local_entity_mut);
+            }
+
+            // detached components: the components stay on the entity, but are not replicated anymore
+            if !actions.detach_components.is_empty() {
+                trace!(remote_entity = ?entity, ?actions.detach_components, "Received DetachComponent");
+                match local_entity_mut.get_mut::<DetachedComponents<P>>() {
+                    Some(mut detached) => detached.kinds.extend(actions.detach_components),
+                    None => {
+                        local_entity_mut.insert(DetachedComponents::<P> {
+                            kinds: actions.detach_components,
+                        });
+                    }
+                }
+            }
+
+            // (no need to run apply_deferred after applying actions, that is only for Commands)
+
+            // updates
+            let kinds = actions
+                .updates
+                .iter()
+                .map(|c| c.into())
+                .collect::<Vec<P::ComponentKinds>>();
+            debug!(remote_entity = ?entity, ?kinds, "Received UpdateComponent");
+            for component in actions.updates {
+                self.apply_component(&mut local_entity_mut, entity, component, false, events);
+            }
+        }
+    }
+
+    /// Map the entities inside the component, and insert or update it on the local entity.
+    ///
+    /// If the component references remote entities that have not been replicated yet (for example
+    /// because they belong to a different replication group), the component is held back until
+    /// these entities are spawned. A more recent value of the component replaces the one that was held back.
+    fn apply_component(
+        &mut self,
+        local_entity_mut: &mut EntityWorldMut,
+        remote_entity: Entity,
+        mut component: P::Components,
+        insert: bool,
+        events: &mut ConnectionEvents<P>,
+    ) {
+        let kind: P::ComponentKinds = (&component).into();
+        let mut finder = UnmappedEntityFinder::new(&self.remote_entity_map);
+        component.map_entities(&mut finder);
+        if finder.found {
+            trace!(
+                ?remote_entity,
+                ?kind,
+                "component references entities that are not replicated yet, holding it back"
+            );
+            match self
+                .pending_components
+                .entry(remote_entity)
+                .or_default()
+                .entry(kind)
+            {
+                Entry::Occupied(mut entry) => {
+                    let pending = entry.get_mut();
+                    pending.component = component;
+                    pending.insert |= insert;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(PendingComponent {
+                        component,
+                        insert,
+                        tick: self.current_tick,
+                    });
+                }
+            }
+            return;
+        }
+        // the component supersedes any older value that was still waiting for its entity references
+        let pending_insert = self
+            .pending_components
+            .get_mut(&remote_entity)
+            .and_then(|pending| pending.remove(&kind))
+            .is_some_and(|pending| pending.insert);
+        component.map_entities(&mut self.remote_entity_map);
+        Self::write_component(
+            local_entity_mut,
+            component,
+            insert || pending_insert,
+            events,
+        );
+    }
+
+    fn write_component(
+        local_entity_mut: &mut EntityWorldMut,
+        component: P::Components,
+        insert: bool,
+        events: &mut ConnectionEvents<P>,
+    ) {
+        // TODO: figure out what to do with tick here
+        if insert {
+            events.push_insert_component(local_entity_mut.id(), (&component).into(), Tick(0));
+            component.insert(local_entity_mut);
+        } else {
+            events.push_update_component(local_entity_mut.id(), (&component).into(), Tick(0));
+            component.update(local_entity_mut);
+        }
+    }
+
+    /// Apply the pending components whose entity references can now be mapped.
+    ///
+    /// This should be called after all the replication messages of the current tick have been applied,
+    /// so that references to entities of other replication groups can be resolved.
+    /// Components that have waited for more than `entity_reference_timeout_ticks` are applied anyway,
+    /// with the references that could not be resolved mapped to [`Entity::PLACEHOLDER`].
+    pub(crate) fn resolve_pending_components(
+        &mut self,
+        world: &mut World,
+        events: &mut ConnectionEvents<P>,
+    ) {
+        if self.pending_components.is_empty() {
+            return;
+        }
+        for (remote_entity, components) in std::mem::take(&mut self.pending_components) {
+            // the entity could have been despawned locally
+            let Ok(mut local_entity_mut) =
+                self.remote_entity_map.get_by_remote(world, remote_entity)
+            else {
+                continue;
+            };
+            for (kind, mut pending) in components {
+                let mut finder = UnmappedEntityFinder::new(&self.remote_entity_map);
+                pending.component.map_entities(&mut finder);
+                if !finder.found {
+                    pending.component.map_entities(&mut self.remote_entity_map);
+                } else if i32::from(self.current_tick - pending.tick)
+                    >= i32::from(self.entity_reference_timeout_ticks)
+                {
+                    warn!(
+                        ?remote_entity,
+                        ?kind,
+                        "timed out waiting for the entities referenced by a replicated component"
+                    );
+                    pending
+                        .component
+                        .map_entities(&mut PlaceholderEntityMapper(&self.remote_entity_map));
+                } else {
+                    self.pending_components
+                        .entry(remote_entity)
+                        .or_default()
+                        .insert(kind, pending);
+                    continue;
+                }
+                Self::write_component(
+                    &mut local_entity_mut,
+                    pending.component,
+                    pending.insert,
+                    events,
+                );
+            }
+        }
+    }
 }
 
 /// Channel to keep track of receiving/sending replication messages for a given Group
@@ -376,14 +651,8 @@ pub struct GroupChannel<P: Protocol> {
     pub actions_recv_message_buffer:
         BTreeMap<MessageId, (Tick, EntityActionMessage<P::Components, P::ComponentKinds>)>,
     // updates
-    // map from necessary_last_action_tick to the buffered message
-    // the first tick is the last_action_tick (we can only apply the update if the last action tick has been reached)
-    // the second tick is the update's server tick when it was sent
-    pub buffered_updates_with_last_action_tick:
-        BTreeMap<Tick, BTreeMap<Tick, EntityUpdatesMessage<P::Components>>>,
-    // updates for which there is no condition on the last_action_tick: we can apply them immediately
-    pub buffered_updates_without_last_action_tick:
-        BTreeMap<Tick, EntityUpdatesMessage<P::Components>>,
+    /// Updates that have been received but not applied yet, ordered by the remote tick at which they were sent
+    pub buffered_updates: BTreeMap<Tick, EntityUpdatesMessage<P::Components>>,
     /// remote tick of the latest update/action that we applied to the local group
     pub latest_tick: Option<Tick>,
 }
@@ -394,88 +663,75 @@ impl<P: Protocol> Default for GroupChannel<P> {
             remote_entities: HashSet::default(),
             actions_pending_recv_message_id: MessageId(0),
             actions_recv_message_buffer: BTreeMap::new(),
-            buffered_updates_with_last_action_tick: Default::default(),
-            buffered_updates_without_last_action_tick: Default::default(),
+            buffered_updates: BTreeMap::new(),
             latest_tick: None,
         }
     }
 }
 
 impl<P: Protocol> GroupChannel<P> {
-    /// Reads a message from the internal buffer to get its content
+    /// Returns true if the group has received messages that cannot be read because the action message
+    /// they come after has not been received yet.
+    fn is_waiting_for_actions(&self) -> bool {
+        if self
+            .actions_recv_message_buffer
+            .contains_key(&self.actions_pending_recv_message_id)
+        {
+            return false;
+        }
+        !self.actions_recv_message_buffer.is_empty()
+            || self.buffered_updates.values().any(|update| {
+                update.last_action_tick.is_some_and(|last_action_tick| {
+                    self.latest_tick
+                        .map_or(true, |latest_tick| last_action_tick > latest_tick)
+                })
+            })
+    }
+
+    /// Returns the tick of the next action message, if it can be read.
+    ///
     /// Since we are receiving messages in order, we don't return from the buffer
     /// until we have received the message we are waiting for (the next expected MessageId)
     /// This assumes that the sender sends all message ids sequentially.
-    ///
-    /// If had received updates that were waiting on a given action, we also return them
-    fn read_action(
-        &mut self,
-        current_tick: Tick,
-    ) -> Option<(Tick, EntityActionMessage<P::Components, P::ComponentKinds>)> {
-        // TODO: maybe only get the message if our local client tick is >= to it? (so that we don't apply an update from the future)
-        let Some(message) = self
+    fn next_action_tick(&self, release_tick: Tick) -> Option<Tick> {
+        let (tick, _) = self
             .actions_recv_message_buffer
-            .get(&self.actions_pending_recv_message_id)
-        else {
-            return None;
-        };
-        // if the message is from the future, keep it there
-        if message.0 > current_tick {
-            trace!("message tick is from the future compared to our tick");
+            .get(&self.actions_pending_recv_message_id)?;
+        // if the message is from the future (or still in the playout delay), keep it there
+        if *tick > release_tick {
+            trace!("action message tick is after the release tick");
             return None;
         }
-
-        // We have received the message we are waiting for
-        let message = self
-            .actions_recv_message_buffer
-            .remove(&self.actions_pending_recv_message_id)
-            .unwrap();
-
-        self.actions_pending_recv_message_id += 1;
-        // Update the latest server tick that we have processed
-        self.latest_tick = Some(message.0);
-        Some(message)
+        Some(*tick)
     }
 
-    fn read_buffered_updates(&mut self) -> Vec<(Tick, EntityUpdatesMessage<P::Components>)> {
-        // if we haven't applied any actions (latest_tick is None) we cannot apply any updates
-        let Some(latest_tick) = self.latest_tick else {
-            return vec![];
-        };
-        // go through all the buffered updates whose last_action_tick has been reached
-        // (the update's last_action_tick <= latest_tick)
-        let not_ready = self
-            .buffered_updates_with_last_action_tick
-            .split_off(&(latest_tick + 1));
-
-        let mut res = vec![];
-
-        let buffered_updates_to_consider =
-            std::mem::take(&mut self.buffered_updates_with_last_action_tick);
-        for (_, mut updates) in buffered_updates_to_consider.into_iter() {
-            // remove all updates whose tick is <= latest_tick
-            for (tick, update) in updates.split_off(&self.latest_tick.unwrap()) {
-                self.latest_tick = Some(tick);
-                res.push((tick, update));
-            }
+    /// Returns the tick of the oldest buffered update, if it can be read.
+    ///
+    /// An update can only be read once the action message that preceded it has been read
+    fn next_update_tick(&self, release_tick: Tick) -> Option<Tick> {
+        let (tick, update) = self.buffered_updates.first_key_value()?;
+        if *tick > release_tick {
+            trace!("update message tick is after the release tick");
+            return None;
         }
-        // keep updates for which we are waiting for the action_tick
-        self.buffered_updates_with_last_action_tick = not_ready;
-
-        // apply all the updates for the buffered updates that have no last_action_tick
-        // remove all updates whose tick is <= latest_tick
-        let mut buffered_updates_to_consider =
-            std::mem::take(&mut self.buffered_updates_without_last_action_tick);
-        for (tick, update) in buffered_updates_to_consider.split_off(&self.latest_tick.unwrap()) {
-            self.latest_tick = Some(tick);
-            res.push((tick, update));
+        match update.last_action_tick {
+            // there is no ordering constraint with the actions
+            None => Some(*tick),
+            Some(last_action_tick) => self
+                .latest_tick
+                .is_some_and(|latest_tick| last_action_tick <= latest_tick)
+                .then_some(*tick),
         }
-        res
     }
 
+    /// Reads the actions and updates that are ready to be applied, in order of their remote tick.
+    ///
+    /// A message is ready if its tick is older than `release_tick`. We stop at the first update
+    /// that is still waiting for its action message, so that the entities of the group never see
+    /// out-of-order states.
     fn read_messages(
         &mut self,
-        current_tick: Tick,
+        release_tick: Tick,
     ) -> Option<
         Vec<(
             Tick,
@@ -483,21 +739,43 @@ impl<P: Protocol> GroupChannel<P> {
         )>,
     > {
         let mut res = Vec::new();
-
-        // check for any actions that are ready to be applied
-        while let Some((tick, actions)) = self.read_action(current_tick) {
-            res.push((tick, ReplicationMessageData::Actions(actions)));
+        loop {
+            let read_update = match (
+                self.next_action_tick(release_tick),
+                self.next_update_tick(release_tick),
+            ) {
+                (None, None) => break,
+                (Some(action_tick), Some(update_tick)) => update_tick < action_tick,
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+            };
+            if read_update {
+                let (tick, updates) = self.buffered_updates.pop_first().unwrap();
+                // we have already applied a more recent message for this group
+                if self
+                    .latest_tick
+                    .is_some_and(|latest_tick| tick <= latest_tick)
+                {
+                    trace!(?tick, "discarding stale update message");
+                    continue;
+                }
+                self.latest_tick = Some(tick);
+                res.push((tick, ReplicationMessageData::Updates(updates)));
+            } else {
+                let (tick, actions) = self
+                    .actions_recv_message_buffer
+                    .remove(&self.actions_pending_recv_message_id)
+                    .unwrap();
+                self.actions_pending_recv_message_id += 1;
+                // Update the latest server tick that we have processed
+                self.latest_tick = Some(tick);
+                res.push((tick, ReplicationMessageData::Actions(actions)));
+            }
+        }
+        // drop any updates that are older than the latest applied message; they would be stale
+        if let Some(latest_tick) = self.latest_tick {
+            self.buffered_updates = self.buffered_updates.split_off(&(latest_tick + 1));
         }
-
-        // TODO: (IMPORTANT): should we try to get the updates in order of tick?
-
-        // check for any buffered updates that are ready to be applied now that we have applied more actions/updates
-        res.extend(
-            self.read_buffered_updates()
-                .into_iter()
-                .map(|(tick, updates)| (tick, ReplicationMessageData::Updates(updates))),
-        );
-
         (!res.is_empty()).then_some(res)
     }
 }
@@ -574,9 +852,7 @@ mod tests {
             .group_channels
             .get(&group_id)
             .unwrap()
-            .buffered_updates_with_last_action_tick
-            .get(&Tick(0))
-            .unwrap()
+            .buffered_updates
             .get(&Tick(1))
             .is_some());
 
@@ -595,14 +871,12 @@ mod tests {
             .group_channels
             .get(&group_id)
             .unwrap()
-            .buffered_updates_with_last_action_tick
-            .get(&Tick(2))
-            .unwrap()
+            .buffered_updates
             .get(&Tick(4))
             .is_some());
 
         // read messages: only read the first action and update
-        let read_messages = manager.read_messages(Tick(10));
+        let read_messages = manager.read_messages(Tick(10), Tick(10));
         let replication_data = &read_messages.first().unwrap().1;
         assert_eq!(replication_data.get(0).unwrap().0, Tick(0));
         assert_eq!(replication_data.get(1).unwrap().0, Tick(1));
@@ -618,7 +892,7 @@ mod tests {
             },
             Tick(3),
         );
-        assert!(manager.read_messages(Tick(10)).is_empty());
+        assert!(manager.read_messages(Tick(10), Tick(10)).is_empty());
 
         // recv actions-2: we should now be able to read actions-2, actions-3, updates-4
         manager.recv_message(
@@ -631,11 +905,52 @@ mod tests {
             },
             Tick(2),
         );
-        let read_messages = manager.read_messages(Tick(10));
+        let read_messages = manager.read_messages(Tick(10), Tick(10));
         let replication_data = &read_messages.first().unwrap().1;
         assert_eq!(replication_data.len(), 3);
         assert_eq!(replication_data.get(0).unwrap().0, Tick(2));
         assert_eq!(replication_data.get(1).unwrap().0, Tick(3));
         assert_eq!(replication_data.get(2).unwrap().0, Tick(4));
     }
+
+    #[allow(clippy::get_first)]
+    #[test]
+    fn test_read_updates_in_tick_order() {
+        let mut manager = ReplicationReceiver::<MyProtocol>::new().with_playout_delay_ticks(2);
+        let group_id = ReplicationGroupId(0);
+        let update = |tick: u16| {
+            (
+                ReplicationMessage {
+                    group_id,
+                    data: ReplicationMessageData::Updates(EntityUpdatesMessage {
+                        last_action_tick: None,
+                        updates: Default::default(),
+                    }),
+                },
+                Tick(tick),
+            )
+        };
+
+        // updates arrive out of order because of jitter
+        for tick in [3, 1, 2] {
+            let (message, remote_tick) = update(tick);
+            manager.recv_message(message, remote_tick);
+        }
+
+        // the playout delay holds back the messages that are too recent
+        let read_messages = manager.read_messages(Tick(10), Tick(4));
+        let replication_data = &read_messages.first().unwrap().1;
+        assert_eq!(replication_data.len(), 2);
+        assert_eq!(replication_data.get(0).unwrap().0, Tick(1));
+        assert_eq!(replication_data.get(1).unwrap().0, Tick(2));
+
+        // an update that is older than the latest applied update is discarded
+        let (message, remote_tick) = update(1);
+        manager.recv_message(message, remote_tick);
+        let read_messages = manager.read_messages(Tick(10), Tick(5));
+        let replication_data = &read_messages.first().unwrap().1;
+        assert_eq!(replication_data.len(), 1);
+        assert_eq!(replication_data.get(0).unwrap().0, Tick(3));
+        assert!(manager.read_messages(Tick(10), Tick(10)).is_empty());
+    }
 }
@@ -0,0 +1,593 @@
+//! Replicate bevy [`Resource`]s from the server to the clients
+//!
+//! The resource needs to be a [`Message`], and [`ReplicateResourceMessage<R>`] needs to be added to the message protocol.
+//! Then add the [`ResourceReplicationPlugin<P, R>`] to both the client and server apps, and insert the
+//! [`ReplicateResource<R>`] resource on the server to start replicating the resource.
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use bevy::prelude::{
+    resource_exists, App, Commands, Condition, DetectChanges, EventReader, FixedPostUpdate,
+    IntoSystemConfigs, Local, Plugin, PostUpdate, PreUpdate, Res, ResMut, Resource,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, trace};
+
+use crate::channel::builder::Channel;
+use crate::client::events::MessageEvent;
+use crate::client::prediction::plugin::PredictionSet;
+use crate::client::prediction::predicted_history::{ComponentState, PredictionHistory};
+use crate::client::prediction::{Rollback, RollbackState};
+use crate::packet::message::Message;
+use crate::prelude::{
+    ChannelKind, LightyearMapEntities, MainSet, Named, NetworkTarget, Protocol, ReplicationSet,
+    Tick, TickManager,
+};
+
+/// Message used to replicate the resource `R` from the server to the clients.
+///
+/// This message must be added to the message protocol for the resource to be replicated.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReplicateResourceMessage<R> {
+    /// Server tick at which the resource was sent
+    pub(crate) tick: Tick,
+    /// The new value of the resource; `None` if the resource was removed
+    pub(crate) resource: Option<R>,
+}
+
+impl<R> Named for ReplicateResourceMessage<R> {
+    // the wrapper must not use the name of `R`, which can also be a message of the protocol
+    const NAME: &'static str = "ReplicateResource";
+}
+
+impl<R: LightyearMapEntities> LightyearMapEntities for ReplicateResourceMessage<R> {
+    fn map_entities<M: bevy::ecs::entity::EntityMapper>(&mut self, entity_mapper: &mut M) {
+        if let Some(resource) = self.resource.as_mut() {
+            resource.map_entities(entity_mapper);
+        }
+    }
+}
+
+/// Resource that can be replicated
+pub trait ReplicableResource:
+    Resource + Message + Clone + PartialEq + Debug + Serialize + DeserializeOwned
+{
+}
+
+impl<R: Resource + Message + Clone + PartialEq + Debug + Serialize + DeserializeOwned>
+    ReplicableResource for R
+{
+}
+
+/// Insert this resource on the server to replicate the resource `R` to the clients.
+///
+/// Every time the resource `R` is inserted, updated or removed, the change is sent to the clients
+/// in `target` on the channel `channel`. Newly connected clients receive the current value of the resource.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ReplicateResource<R> {
+    /// The clients that receive the resource
+    pub target: NetworkTarget,
+    /// The channel used to send the resource updates
+    pub channel: ChannelKind,
+    _marker: PhantomData<R>,
+}
+
+impl<R> ReplicateResource<R> {
+    pub fn new<C: Channel>(target: NetworkTarget) -> Self {
+        Self {
+            target,
+            channel: ChannelKind::of::<C>(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// The latest value of the resource `R` that was received from the server, for predicted resources
+#[derive(Resource, Debug)]
+pub struct ConfirmedResource<R> {
+    /// Server tick of the latest update
+    pub tick: Tick,
+    /// `None` if the resource was removed on the server
+    pub resource: Option<R>,
+}
+
+/// Plugin that handles the replication of the resource `R`.
+///
+/// It must be added on both the server and the client apps.
+/// If `predicted` is true, the resource is predicted on the client: it is modified by the client's
+/// systems, and a rollback is triggered when the server's value doesn't match the predicted history.
+pub struct ResourceReplicationPlugin<P, R> {
+    predicted: bool,
+    _marker: PhantomData<(P, R)>,
+}
+
+impl<P, R> Default for ResourceReplicationPlugin<P, R> {
+    fn default() -> Self {
+        Self {
+            predicted: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, R> ResourceReplicationPlugin<P, R> {
+    /// Predict the resource on the client
+    pub fn predicted(mut self) -> Self {
+        self.predicted = true;
+        self
+    }
+}
+
+impl<P: Protocol, R: ReplicableResource> Plugin for ResourceReplicationPlugin<P, R>
+where
+    P::Message: From<ReplicateResourceMessage<R>>,
+{
+    fn build(&self, app: &mut App) {
+        // SERVER
+        app.add_systems(
+            PostUpdate,
+            send_resource_updates::<P, R>
+                .in_set(ReplicationSet::SendComponentUpdates)
+                .run_if(resource_exists::<crate::server::connection::ConnectionManager<P>>),
+        );
+        // CLIENT
+        let is_client = resource_exists::<crate::client::connection::ConnectionManager<P>>;
+        if self.predicted {
+            app.add_systems(
+                PreUpdate,
+                (
+                    check_resource_rollback::<R>.in_set(PredictionSet::CheckRollback),
+                    prepare_resource_rollback::<R>.in_set(PredictionSet::PrepareRollback),
+                )
+                    // the prediction plugin could be disabled
+                    .run_if(is_client.and_then(resource_exists::<Rollback>)),
+            );
+            app.add_systems(
+                FixedPostUpdate,
+                update_resource_history::<R>
+                    .in_set(PredictionSet::UpdateHistory)
+                    .run_if(is_client.and_then(resource_exists::<Rollback>)),
+            );
+        } else {
+            app.add_systems(
+                PreUpdate,
+                receive_resource_updates::<R>
+                    .after(MainSet::ReceiveFlush)
+                    .run_if(is_client),
+            );
+        }
+    }
+}
+
+/// Send the resource to the clients when it is inserted, updated or removed.
+/// Also send the resource to newly connected clients.
+fn send_resource_updates<P: Protocol, R: ReplicableResource>(
+    mut connection_manager: ResMut<crate::server::connection::ConnectionManager<P>>,
+    tick_manager: Res<TickManager>,
+    replicate: Option<Res<ReplicateResource<R>>>,
+    resource: Option<Res<R>>,
+    // whether the resource existed the last time this system ran
+    mut existed: Local<bool>,
+) where
+    P::Message: From<ReplicateResourceMessage<R>>,
+{
+    let resource_exists = resource.is_some();
+    let Some(replicate) = replicate else {
+        *existed = resource_exists;
+        return;
+    };
+    let tick = tick_manager.tick();
+    let (message, target) = match resource {
+        Some(resource) => {
+            let target = if resource.is_changed() || replicate.is_changed() {
+                replicate.target.clone()
+            } else {
+                // the resource didn't change, only send it to the clients that just connected
+                let new_clients: Vec<_> = connection_manager
+                    .new_clients
+                    .iter()
+                    .filter(|client_id| replicate.target.should_send_to(client_id))
+                    .copied()
+                    .collect();
+                if new_clients.is_empty() {
+                    *existed = resource_exists;
+                    return;
+                }
+                NetworkTarget::Only(new_clients)
+            };
+            (
+                ReplicateResourceMessage {
+                    tick,
+                    resource: Some(resource.clone()),
+                },
+                target,
+            )
+        }
+        None => {
+            if !*existed {
+                return;
+            }
+            (
+                ReplicateResourceMessage {
+                    tick,
+                    resource: None,
+                },
+                replicate.target.clone(),
+            )
+        }
+    };
+    *existed = resource_exists;
+    trace!(?message, ?target, "Sending resource update");
+    let _ = connection_manager
+        .buffer_message(message.into(), replicate.channel, target)
+        .map_err(|e| error!("Error sending resource update for {}: {:?}", R::NAME, e));
+}
+
+/// Apply the resource updates received from the server
+fn receive_resource_updates<R: ReplicableResource>(
+    mut commands: Commands,
+    mut events: EventReader<MessageEvent<ReplicateResourceMessage<R>>>,
+) {
+    // only the most recent update matters
+    let Some(message) = events
+        .read()
+        .map(|event| event.message())
+        .max_by_key(|m| m.tick)
+    else {
+        return;
+    };
+    debug!(tick = ?message.tick, "Received resource update for {}", R::NAME);
+    match &message.resource {
+        Some(resource) => commands.insert_resource(resource.clone()),
+        None => commands.remove_resource::<R>(),
+    }
+}
+
+/// For predicted resources, compare the resource received from the server with the predicted history,
+/// and trigger a rollback if there is a mismatch
+fn check_resource_rollback<R: ReplicableResource>(
+    mut commands: Commands,
+    mut events: EventReader<MessageEvent<ReplicateResourceMessage<R>>>,
+    tick_manager: Res<TickManager>,
+    mut rollback: ResMut<Rollback>,
+    resource: Option<Res<R>>,
+    history: Option<ResMut<PredictionHistory<R>>>,
+    // update received for a tick that the client hasn't reached yet
+    mut pending: Local<Option<ReplicateResourceMessage<R>>>,
+) {
+    // only the most recent update matters
+    let Some(message) = events
+        .read()
+        .map(|event| event.message().clone())
+        .chain(pending.take())
+        .max_by_key(|m| m.tick)
+    else {
+        return;
+    };
+    let tick = message.tick;
+    if tick > tick_manager.tick() {
+        debug!(
+            ?tick,
+            "Resource update is at a tick in the future compared to the client timeline, buffering it"
+        );
+        *pending = Some(message);
+        return;
+    }
+    commands.insert_resource(ConfirmedResource {
+        tick,
+        resource: message.resource.clone(),
+    });
+    let Some(mut history) = history else {
+        // first time that we receive the resource: start predicting it right away
+        let mut history = PredictionHistory::<R>::default();
+        match &message.resource {
+            Some(r) => {
+                history
+                    .buffer
+                    .add_item(tick, ComponentState::Updated(r.clone()));
+                commands.insert_resource(r.clone());
+            }
+            None => {
+                history.buffer.add_item(tick, ComponentState::Removed);
+            }
+        }
+        commands.insert_resource(history);
+        return;
+    };
+    let history_value = history.pop_until_tick(tick);
+    let should_rollback = match &message.resource {
+        None => history_value.map_or(resource.is_some(), |history_value| {
+            history_value != ComponentState::Removed
+        }),
+        Some(r) => history_value.map_or(true, |history_value| match history_value {
+            ComponentState::Updated(history_value) => history_value != *r,
+            ComponentState::Removed => true,
+        }),
+    };
+    if should_rollback {
+        debug!(?tick, "Rollback check: mismatch for resource {}", R::NAME);
+        // if the components already triggered a rollback, start from the oldest of the two ticks
+        let current_tick = match rollback.state {
+            RollbackState::ShouldRollback { current_tick } => current_tick.min(tick + 1),
+            RollbackState::Default => tick + 1,
+        };
+        rollback.state = RollbackState::ShouldRollback { current_tick };
+    }
+}
+
+/// Restore the predicted resource to its state at the rollback tick.
+///
+/// That is the latest value received from the server, unless the rollback was triggered by components
+/// at a more recent tick: then the resource was predicted correctly and we use the predicted value at that tick.
+fn prepare_resource_rollback<R: ReplicableResource>(
+    mut commands: Commands,
+    rollback: Res<Rollback>,
+    confirmed: Option<Res<ConfirmedResource<R>>>,
+    resource: Option<ResMut<R>>,
+    history: Option<ResMut<PredictionHistory<R>>>,
+) {
+    let RollbackState::ShouldRollback { current_tick } = rollback.state else {
+        return;
+    };
+    let (Some(confirmed), Some(mut history)) = (confirmed, history) else {
+        return;
+    };
+    let rollback_tick = current_tick - 1;
+    let (tick, state) = match history.get(rollback_tick) {
+        Some(state) if rollback_tick > confirmed.tick => (rollback_tick, state.clone()),
+        _ => (
+            confirmed.tick,
+            confirmed
+                .resource
+                .clone()
+                .map_or(ComponentState::Removed, ComponentState::Updated),
+        ),
+    };
+    history.clear();
+    match state {
+        ComponentState::Removed => {
+            history.buffer.add_item(tick, ComponentState::Removed);
+            if resource.is_some() {
+                commands.remove_resource::<R>();
+            }
+        }
+        ComponentState::Updated(c) => {
+            history
+                .buffer
+                .add_item(tick, ComponentState::Updated(c.clone()));
+            match resource {
+                Some(mut resource) => *resource = c.clone(),
+                None => commands.insert_resource(c.clone()),
+            }
+        }
+    }
+}
+
+/// Record the history of the predicted resource after each fixed-update tick
+fn update_resource_history<R: ReplicableResource>(
+    tick_manager: Res<TickManager>,
+    rollback: Res<Rollback>,
+    resource: Option<Res<R>>,
+    history: Option<ResMut<PredictionHistory<R>>>,
+    // whether the resource existed the last time this system ran
+    mut existed: Local<bool>,
+) {
+    let Some(mut history) = history else {
+        return;
+    };
+    let tick = match rollback.state {
+        RollbackState::Default => tick_manager.tick(),
+        RollbackState::ShouldRollback { current_tick } => current_tick,
+    };
+    match resource {
+        Some(resource) => {
+            if resource.is_changed() {
+                history
+                    .buffer
+                    .add_item(tick, ComponentState::Updated(resource.clone()));
+            }
+            *existed = true;
+        }
+        None => {
+            if *existed {
+                history.buffer.add_item(tick, ComponentState::Removed);
+            }
+            *existed = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::FixedUpdate;
+    use bevy::utils::Duration;
+
+    use crate::prelude::client::*;
+    use crate::prelude::*;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    use super::*;
+
+    fn stepper() -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(0),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        let sync_config = SyncConfig::default().speedup_factor(1.0);
+        let prediction_config = PredictionConfig::default().disable(false);
+        let interpolation_config = InterpolationConfig::default();
+        BevyStepper::new(
+            shared_config,
+            sync_config,
+            prediction_config,
+            interpolation_config,
+            link_conditioner,
+            frame_duration,
+        )
+    }
+
+    /// Client-only system, so that the client mispredicts the resource
+    fn increment_resource(resource: Option<ResMut<Resource1>>) {
+        if let Some(mut resource) = resource {
+            resource.0 += 1.0;
+        }
+    }
+
+    #[test]
+    fn test_resource_replication() {
+        let mut stepper = stepper();
+        stepper
+            .server_app
+            .add_plugins(ResourceReplicationPlugin::<MyProtocol, Resource1>::default());
+        stepper
+            .client_app
+            .add_plugins(ResourceReplicationPlugin::<MyProtocol, Resource1>::default());
+        stepper.init();
+
+        // insert the resource on the server
+        stepper.server_app.world.insert_resource(Resource1(1.0));
+        stepper
+            .server_app
+            .world
+            .insert_resource(ReplicateResource::<Resource1>::new::<Channel1>(
+                NetworkTarget::All,
+            ));
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world.get_resource::<Resource1>(),
+            Some(&Resource1(1.0))
+        );
+
+        // update the resource
+        stepper.server_app.world.resource_mut::<Resource1>().0 = 2.0;
+        stepper.frame_step();
+        stepper.frame_step();
+        assert_eq!(
+            stepper.client_app.world.get_resource::<Resource1>(),
+            Some(&Resource1(2.0))
+        );
+
+        // remove the resource
+        stepper.server_app.world.remove_resource::<Resource1>();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world
+            .get_resource::<Resource1>()
+            .is_none());
+    }
+
+    /// The predicted resource is rolled back to the server value when the prediction was wrong,
+    /// and the ticks since the server update are resimulated
+    #[test]
+    fn test_mispredicted_resource_rollback() {
+        let mut stepper = stepper();
+        stepper
+            .server_app
+            .add_plugins(ResourceReplicationPlugin::<MyProtocol, Resource1>::default());
+        stepper
+            .client_app
+            .add_plugins(ResourceReplicationPlugin::<MyProtocol, Resource1>::default().predicted());
+        stepper
+            .client_app
+            .add_systems(FixedUpdate, increment_resource);
+        stepper.init();
+
+        stepper.server_app.world.insert_resource(Resource1(1.0));
+        stepper
+            .server_app
+            .world
+            .insert_resource(ReplicateResource::<Resource1>::new::<Channel1>(
+                NetworkTarget::All,
+            ));
+        stepper.frame_step();
+        stepper.frame_step();
+        // the client started predicting the resource
+        let predicted = stepper.client_app.world.resource::<Resource1>().0;
+        assert!(predicted > 1.0);
+
+        // the server value doesn't match the prediction
+        stepper.server_app.world.resource_mut::<Resource1>().0 = 100.0;
+        stepper.frame_step();
+        stepper.frame_step();
+        let confirmed = stepper
+            .client_app
+            .world
+            .resource::<ConfirmedResource<Resource1>>();
+        assert_eq!(confirmed.resource, Some(Resource1(100.0)));
+        let resimulated_ticks = (stepper.client_tick() - confirmed.tick) as f32;
+        assert_eq!(
+            stepper.client_app.world.resource::<Resource1>(),
+            &Resource1(100.0 + resimulated_ticks)
+        );
+        assert!(matches!(
+            stepper.client_app.world.resource::<Rollback>().state,
+            RollbackState::Default
+        ));
+
+        // the server removes the resource
+        stepper.server_app.world.remove_resource::<Resource1>();
+        stepper.frame_step();
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world
+            .get_resource::<Resource1>()
+            .is_none());
+    }
+
+    /// Updates for a tick that the client hasn't reached yet are applied once the client reaches that tick
+    #[test]
+    fn test_predicted_resource_future_tick() {
+        let mut stepper = stepper();
+        stepper
+            .client_app
+            .add_plugins(ResourceReplicationPlugin::<MyProtocol, Resource1>::default().predicted());
+        stepper.init();
+
+        let tick = stepper.client_tick() + 3;
+        stepper
+            .client_app
+            .world
+            .send_event(crate::client::events::MessageEvent::new(
+                ReplicateResourceMessage {
+                    tick,
+                    resource: Some(Resource1(1.0)),
+                },
+                (),
+            ));
+        stepper.frame_step();
+        assert!(stepper
+            .client_app
+            .world
+            .get_resource::<Resource1>()
+            .is_none());
+
+        for _ in 0..3 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.client_app.world.get_resource::<Resource1>(),
+            Some(&Resource1(1.0))
+        );
+        assert_eq!(
+            stepper
+                .client_app
+                .world
+                .resource::<ConfirmedResource<Resource1>>()
+                .tick,
+            tick
+        );
+    }
+}
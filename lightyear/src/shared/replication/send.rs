@@ -8,6 +8,7 @@ use bevy::prelude::{Entity, Reflect};
 use bevy::utils::petgraph::data::ElementIterator;
 use bevy::utils::{hashbrown, HashMap, HashSet};
 use crossbeam_channel::Receiver;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, error, trace, warn};
 
 use crate::_reexport::{EntityActionsChannel, EntityUpdatesChannel, FromType};
@@ -177,6 +178,15 @@ impl<P: Protocol> ReplicationSender<P> {
             .despawn = true;
     }
 
+    pub(crate) fn prepare_entity_detach(&mut self, entity: Entity, group_id: ReplicationGroupId) {
+        self.pending_actions
+            .entry(group_id)
+            .or_default()
+            .entry(entity)
+            .or_default()
+            .detach = true;
+    }
+
     // we want to send all component inserts that happen together for the same entity in a single message
     // (because otherwise the inserts might be received at different packets/ticks by the remote, and
     // the remote might expect the components insert to be received at the same time)
@@ -284,6 +294,21 @@ impl<P: Protocol> ReplicationSender<P> {
             .insert(kind);
     }
 
+    pub(crate) fn prepare_component_detach(
+        &mut self,
+        entity: Entity,
+        group_id: ReplicationGroupId,
+        kind: P::ComponentKinds,
+    ) {
+        self.pending_actions
+            .entry(group_id)
+            .or_default()
+            .entry(entity)
+            .or_default()
+            .detach_components
+            .insert(kind);
+    }
+
     pub(crate) fn prepare_entity_update(
         &mut self,
         entity: Entity,
@@ -394,6 +419,25 @@ impl<P: Protocol> ReplicationSender<P> {
         self.pending_unique_components.clear();
         messages
     }
+
+    /// Account for tick wrapping: ticks that are too far apart cannot be compared anymore.
+    ///
+    /// If it's been enough time since we sent any action for a group, we can set the last_action_tick to None
+    /// (meaning that there's no need when we receive the update to check if we have already received a previous action)
+    pub(crate) fn cleanup(&mut self, tick: Tick) {
+        for group_channel in self.group_channels.values_mut() {
+            if let Some(last_action_tick) = group_channel.last_action_tick {
+                if tick.is_too_far_from(last_action_tick) {
+                    debug!(
+                        ?tick,
+                        ?last_action_tick,
+                        ?group_channel,
+                        "Setting the last_action tick to None because there hasn't been any new actions in a while");
+                    group_channel.last_action_tick = None;
+                }
+            }
+        }
+    }
 }
 
 /// Channel to keep track of sending replication messages for a given Group
@@ -412,6 +456,17 @@ pub struct GroupChannel {
     /// for this group because of the bandwidth cap, in which case it will be accumulated.
     pub accumulated_priority: Option<f32>,
     pub base_priority: f32,
+    pub stats: ReplicationGroupStats,
+}
+
+/// Replication traffic of a group since the start of the connection
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+pub struct ReplicationGroupStats {
+    /// Number of entity actions messages (spawns, despawns, component inserts and removals) sent for the group
+    pub actions_sent: u64,
+    /// Number of entity updates messages sent for the group
+    pub updates_sent: u64,
+    pub bytes_sent: u64,
 }
 
 impl Default for GroupChannel {
@@ -422,11 +477,22 @@ impl Default for GroupChannel {
             accumulated_priority: None,
             collect_changes_since_this_tick: None,
             base_priority: 1.0,
+            stats: ReplicationGroupStats::default(),
         }
     }
 }
 
 impl GroupChannel {
+    /// Keep track of a replication message that was buffered for this group
+    pub(crate) fn record_sent_message(&mut self, is_update: bool, bytes: u64) {
+        if is_update {
+            self.stats.updates_sent += 1;
+        } else {
+            self.stats.actions_sent += 1;
+        }
+        self.stats.bytes_sent += bytes;
+    }
+
     pub(crate) fn update_collect_changes_since_this_tick(&mut self, bevy_tick: BevyTick) {
         // the bevy_tick passed is either at receive or send, and is always more recent
         // than the previous bevy_tick
@@ -519,6 +585,8 @@ mod tests {
                     EntityActions {
                         spawn: true,
                         despawn: false,
+                        detach: false,
+                        detach_components: HashSet::default(),
                         insert: vec![MyComponentsProtocol::Component1(Component1(1.0))],
                         remove: HashSet::from_iter(vec![MyComponentsProtocolKind::Component2]),
                         updates: vec![MyComponentsProtocol::Component3(Component3(3.0))],
@@ -529,6 +597,8 @@ mod tests {
                     EntityActions {
                         spawn: false,
                         despawn: false,
+                        detach: false,
+                        detach_components: HashSet::default(),
                         insert: vec![],
                         remove: HashSet::default(),
                         updates: vec![MyComponentsProtocol::Component2(Component2(4.0))],
@@ -0,0 +1,511 @@
+//! Snapshot-based replication
+//!
+//! Instead of replicating each replication group independently, the server keeps track (for each client)
+//! of the full state of all the entities that are visible to that client. Every send interval, that state is
+//! delta-encoded against the last snapshot that the client acknowledged (the baseline), and sent on an unreliable channel.
+//! If the message is lost, the next snapshot will still be encoded against the same baseline, so the client
+//! will eventually converge to the server's state.
+//!
+//! The client reconstructs the full world state from each snapshot and applies it atomically, so that all the
+//! replicated entities share the same confirmed tick.
+//!
+//! Components are compared using the checksum of their serialized value, so that they don't need to implement `PartialEq`.
+use std::collections::BTreeMap;
+
+use bevy::ecs::component::Tick as BevyTick;
+use bevy::ecs::entity::EntityHash;
+use bevy::prelude::Entity;
+use bevy::utils::HashMap;
+use crossbeam_channel::Receiver;
+use tracing::{debug, trace};
+
+use crate::packet::message::MessageId;
+use crate::prelude::Tick;
+use crate::protocol::Protocol;
+use crate::shared::desync::checksum;
+use crate::shared::replication::components::ReplicationGroupId;
+
+use super::{EntityActions, SnapshotMessage};
+
+type EntityHashMap<K, V> = hashbrown::HashMap<K, V, EntityHash>;
+
+/// Replication group that contains all the entities replicated via snapshots.
+///
+/// Group ids are usually derived from an entity, and `u64::MAX` does not correspond to any valid entity.
+pub(crate) const SNAPSHOT_GROUP_ID: ReplicationGroupId = ReplicationGroupId(u64::MAX);
+
+/// Maximum age (in ticks) of a baseline. If the client hasn't acked a snapshot for longer than this,
+/// we send the full world state instead of a delta.
+///
+/// This also bounds how many snapshots are kept in memory on both sides.
+pub(crate) const MAX_BASELINE_AGE: u16 = 128;
+
+/// A replicated component, along with the checksum of its serialized value
+#[derive(Clone)]
+pub(crate) struct SnapshotComponent<C> {
+    component: C,
+    /// None if the component could not be serialized, in which case it is always considered changed
+    checksum: Option<u64>,
+}
+
+impl<C: serde::Serialize> SnapshotComponent<C> {
+    fn new(component: C) -> Self {
+        let checksum = checksum(&component).ok();
+        Self {
+            component,
+            checksum,
+        }
+    }
+
+    fn has_same_value(&self, other: &Self) -> bool {
+        self.checksum.is_some() && self.checksum == other.checksum
+    }
+}
+
+/// State of all the replicated entities visible to a client at a given tick
+pub(crate) struct WorldSnapshot<P: Protocol> {
+    pub(crate) entities:
+        EntityHashMap<Entity, HashMap<P::ComponentKinds, SnapshotComponent<P::Components>>>,
+}
+
+impl<P: Protocol> Default for WorldSnapshot<P> {
+    fn default() -> Self {
+        Self {
+            entities: EntityHashMap::default(),
+        }
+    }
+}
+
+impl<P: Protocol> Clone for WorldSnapshot<P> {
+    fn clone(&self) -> Self {
+        Self {
+            entities: self.entities.clone(),
+        }
+    }
+}
+
+impl<P: Protocol> WorldSnapshot<P> {
+    /// Compute the list of actions needed to go from the `baseline` state to the current state
+    pub(crate) fn delta(
+        &self,
+        baseline: &WorldSnapshot<P>,
+    ) -> Vec<(Entity, EntityActions<P::Components, P::ComponentKinds>)> {
+        let mut res = Vec::new();
+        for (entity, components) in self.entities.iter() {
+            let mut actions = EntityActions::default();
+            match baseline.entities.get(entity) {
+                None => {
+                    actions.spawn = true;
+                    actions.insert = components.values().map(|c| c.component.clone()).collect();
+                }
+                Some(baseline_components) => {
+                    for (kind, component) in components.iter() {
+                        match baseline_components.get(kind) {
+                            None => actions.insert.push(component.component.clone()),
+                            Some(baseline_component)
+                                if !baseline_component.has_same_value(component) =>
+                            {
+                                actions.updates.push(component.component.clone())
+                            }
+                            _ => {}
+                        }
+                    }
+                    actions.remove = baseline_components
+                        .keys()
+                        .filter(|kind| !components.contains_key(*kind))
+                        .copied()
+                        .collect();
+                }
+            }
+            if actions.spawn
+                || !actions.insert.is_empty()
+                || !actions.updates.is_empty()
+                || !actions.remove.is_empty()
+            {
+                res.push((*entity, actions));
+            }
+        }
+        for entity in baseline.entities.keys() {
+            if !self.entities.contains_key(entity) {
+                res.push((
+                    *entity,
+                    EntityActions {
+                        despawn: true,
+                        ..Default::default()
+                    },
+                ));
+            }
+        }
+        res
+    }
+
+    /// Apply a list of actions (computed with [`WorldSnapshot::delta`]) to the state
+    pub(crate) fn apply_delta(
+        &mut self,
+        actions: &[(Entity, EntityActions<P::Components, P::ComponentKinds>)],
+    ) {
+        for (entity, actions) in actions {
+            if actions.despawn {
+                self.entities.remove(entity);
+                continue;
+            }
+            let components = self.entities.entry(*entity).or_default();
+            for kind in actions.remove.iter() {
+                components.remove(kind);
+            }
+            for component in actions.insert.iter().chain(actions.updates.iter()) {
+                components.insert(component.into(), SnapshotComponent::new(component.clone()));
+            }
+        }
+    }
+}
+
+/// Builds the snapshots for a single client
+pub(crate) struct SnapshotSender<P: Protocol> {
+    /// Get notified whenever a snapshot message has been received by the remote
+    ack_tracker: Receiver<MessageId>,
+    /// Map from the message-id to the tick of the snapshot that was sent in this message
+    message_id_to_tick: HashMap<MessageId, Tick>,
+    /// Current state of all the entities visible to the client
+    current: WorldSnapshot<P>,
+    /// Snapshots that were sent but not acked yet
+    sent: BTreeMap<Tick, WorldSnapshot<P>>,
+    /// Most recent snapshot that was acked by the client
+    baseline: Option<(Tick, WorldSnapshot<P>)>,
+    /// Bevy tick at which we built the last snapshot; we only need to collect component updates
+    /// that happened since then
+    pub(crate) last_build_bevy_tick: Option<BevyTick>,
+}
+
+impl<P: Protocol> SnapshotSender<P> {
+    pub(crate) fn new(ack_tracker: Receiver<MessageId>) -> Self {
+        Self {
+            ack_tracker,
+            message_id_to_tick: HashMap::default(),
+            current: WorldSnapshot::default(),
+            sent: BTreeMap::new(),
+            baseline: None,
+            last_build_bevy_tick: None,
+        }
+    }
+
+    pub(crate) fn prepare_entity_spawn(&mut self, entity: Entity) {
+        self.current.entities.entry(entity).or_default();
+    }
+
+    pub(crate) fn prepare_entity_despawn(&mut self, entity: Entity) {
+        self.current.entities.remove(&entity);
+    }
+
+    pub(crate) fn prepare_component_insert(&mut self, entity: Entity, component: P::Components) {
+        // ignore components for entities that are not visible to the client
+        if let Some(components) = self.current.entities.get_mut(&entity) {
+            components.insert((&component).into(), SnapshotComponent::new(component));
+        }
+    }
+
+    pub(crate) fn prepare_component_remove(&mut self, entity: Entity, kind: P::ComponentKinds) {
+        if let Some(components) = self.current.entities.get_mut(&entity) {
+            components.remove(&kind);
+        }
+    }
+
+    pub(crate) fn prepare_entity_update(&mut self, entity: Entity, component: P::Components) {
+        if let Some(components) = self.current.entities.get_mut(&entity) {
+            let kind: P::ComponentKinds = (&component).into();
+            // we only update components that were already replicated (this matters for `replicate_once` components,
+            // or components whose insert was not sent to this client)
+            if let Some(existing) = components.get_mut(&kind) {
+                *existing = SnapshotComponent::new(component);
+            }
+        }
+    }
+
+    /// Build the snapshot message for the current tick, delta-encoded against the last acked snapshot.
+    ///
+    /// The message is sent even if nothing changed since the baseline, so that the client can
+    /// still advance the confirmed tick of the replicated entities.
+    pub(crate) fn finalize(
+        &mut self,
+        tick: Tick,
+        bevy_tick: BevyTick,
+    ) -> SnapshotMessage<P::Components, P::ComponentKinds> {
+        self.last_build_bevy_tick = Some(bevy_tick);
+        // if the baseline is too old, send the full state instead
+        if self
+            .baseline
+            .as_ref()
+            .is_some_and(|(baseline_tick, _)| tick - *baseline_tick > MAX_BASELINE_AGE as i16)
+        {
+            debug!(?tick, "snapshot baseline is too old, sending full snapshot");
+            self.baseline = None;
+        }
+        let empty = WorldSnapshot::default();
+        let (baseline_tick, baseline) = match &self.baseline {
+            Some((baseline_tick, baseline)) => (Some(*baseline_tick), baseline),
+            None => (None, &empty),
+        };
+        let actions = self.current.delta(baseline);
+        self.sent.insert(tick, self.current.clone());
+        // we won't use those snapshots as baselines anymore
+        self.sent
+            .retain(|sent_tick, _| tick - *sent_tick <= MAX_BASELINE_AGE as i16);
+        self.message_id_to_tick
+            .retain(|_, sent_tick| tick - *sent_tick <= MAX_BASELINE_AGE as i16);
+        trace!(?tick, ?baseline_tick, "built snapshot message");
+        SnapshotMessage {
+            baseline_tick,
+            actions,
+        }
+    }
+
+    /// Keep track of the message-id that the snapshot for this tick was sent with
+    pub(crate) fn track_message(&mut self, message_id: MessageId, tick: Tick) {
+        self.message_id_to_tick.insert(message_id, tick);
+    }
+
+    /// Update the baseline with the most recent snapshot that the client acked
+    pub(crate) fn recv_acks(&mut self) {
+        while let Ok(message_id) = self.ack_tracker.try_recv() {
+            let Some(tick) = self.message_id_to_tick.remove(&message_id) else {
+                continue;
+            };
+            if self
+                .baseline
+                .as_ref()
+                .is_some_and(|(baseline_tick, _)| *baseline_tick >= tick)
+            {
+                continue;
+            }
+            if let Some(snapshot) = self.sent.remove(&tick) {
+                trace!(?tick, "snapshot acked, updating baseline");
+                self.baseline = Some((tick, snapshot));
+                self.sent.retain(|sent_tick, _| *sent_tick > tick);
+                self.message_id_to_tick
+                    .retain(|_, sent_tick| *sent_tick > tick);
+            }
+        }
+    }
+}
+
+/// Reconstructs the world state from the snapshot messages received from the server
+pub(crate) struct SnapshotReceiver<P: Protocol> {
+    /// World states reconstructed from the received snapshots, by server tick
+    received: BTreeMap<Tick, WorldSnapshot<P>>,
+    /// The world state that is currently applied to the local World
+    applied: Option<(Tick, WorldSnapshot<P>)>,
+}
+
+impl<P: Protocol> Default for SnapshotReceiver<P> {
+    fn default() -> Self {
+        Self {
+            received: BTreeMap::new(),
+            applied: None,
+        }
+    }
+}
+
+impl<P: Protocol> SnapshotReceiver<P> {
+    /// Reconstruct the world state contained in the snapshot message and buffer it
+    pub(crate) fn recv_message(
+        &mut self,
+        message: SnapshotMessage<P::Components, P::ComponentKinds>,
+        remote_tick: Tick,
+    ) {
+        // we have already applied a more recent snapshot
+        if self
+            .applied
+            .as_ref()
+            .is_some_and(|(applied_tick, _)| remote_tick <= *applied_tick)
+        {
+            trace!(?remote_tick, "snapshot is too old, ignored");
+            return;
+        }
+        let mut state = match message.baseline_tick {
+            None => WorldSnapshot::default(),
+            Some(baseline_tick) => {
+                let baseline = self.received.get(&baseline_tick).or(self
+                    .applied
+                    .as_ref()
+                    .filter(|(applied_tick, _)| *applied_tick == baseline_tick)
+                    .map(|(_, state)| state));
+                let Some(baseline) = baseline.cloned() else {
+                    debug!(
+                        ?remote_tick,
+                        ?baseline_tick,
+                        "missing baseline for snapshot, ignored"
+                    );
+                    return;
+                };
+                // the server won't send any more deltas against snapshots older than the baseline
+                self.received = self.received.split_off(&baseline_tick);
+                baseline
+            }
+        };
+        state.apply_delta(&message.actions);
+        self.received
+            .retain(|tick, _| remote_tick - *tick <= MAX_BASELINE_AGE as i16);
+        self.received.insert(remote_tick, state);
+    }
+
+    /// Return all the snapshots that were not applied yet and are not from the future, in order of tick.
+    ///
+    /// Each snapshot is returned as the list of actions to apply to the World to go from the previous
+    /// snapshot to that snapshot, so that every snapshot is applied (and added to the interpolation history).
+    pub(crate) fn read_messages(
+        &mut self,
+        current_tick: Tick,
+    ) -> Vec<(Tick, SnapshotMessage<P::Components, P::ComponentKinds>)> {
+        let mut res = Vec::new();
+        let empty = WorldSnapshot::default();
+        for (tick, state) in self.received.range(..=current_tick) {
+            if self
+                .applied
+                .as_ref()
+                .is_some_and(|(applied_tick, _)| tick <= applied_tick)
+            {
+                continue;
+            }
+            let (baseline_tick, baseline) = match &self.applied {
+                Some((applied_tick, applied)) => (Some(*applied_tick), applied),
+                None => (None, &empty),
+            };
+            let message = SnapshotMessage {
+                baseline_tick,
+                actions: state.delta(baseline),
+            };
+            self.applied = Some((*tick, state.clone()));
+            res.push((*tick, message));
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::protocol::*;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_delta() {
+        let (ack_sender, ack_receiver) = crossbeam_channel::unbounded();
+        let mut sender = SnapshotSender::<MyProtocol>::new(ack_receiver);
+        let mut receiver = SnapshotReceiver::<MyProtocol>::default();
+        let entity = Entity::from_raw(0);
+
+        // spawn: full snapshot
+        sender.prepare_entity_spawn(entity);
+        sender.prepare_component_insert(entity, MyComponentsProtocol::Component1(Component1(1.0)));
+        let message = sender.finalize(Tick(1), BevyTick::new(1));
+        assert_eq!(message.baseline_tick, None);
+        assert_eq!(
+            message.actions,
+            vec![(
+                entity,
+                EntityActions {
+                    spawn: true,
+                    insert: vec![MyComponentsProtocol::Component1(Component1(1.0))],
+                    ..Default::default()
+                }
+            )]
+        );
+        sender.track_message(MessageId(0), Tick(1));
+        receiver.recv_message(message, Tick(1));
+        // we do not read snapshots from the future
+        assert!(receiver.read_messages(Tick(0)).is_empty());
+        let mut messages = receiver.read_messages(Tick(1));
+        assert_eq!(messages.len(), 1);
+        let (tick, message) = messages.remove(0);
+        assert_eq!(tick, Tick(1));
+        assert!(message.actions[0].1.spawn);
+
+        // nothing changed and the snapshot was not acked: still send the full state
+        let message = sender.finalize(Tick(2), BevyTick::new(2));
+        assert_eq!(message.baseline_tick, None);
+        assert_eq!(message.actions.len(), 1);
+
+        // ack the snapshot: the snapshot is empty, but still sent so that the client's confirmed tick advances
+        ack_sender.send(MessageId(0)).unwrap();
+        sender.recv_acks();
+        let message = sender.finalize(Tick(3), BevyTick::new(3));
+        assert_eq!(message.baseline_tick, Some(Tick(1)));
+        assert!(message.actions.is_empty());
+        receiver.recv_message(message, Tick(3));
+        let mut messages = receiver.read_messages(Tick(3));
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages.remove(0).0, Tick(3));
+
+        // update: delta against the acked snapshot
+        sender.prepare_entity_update(entity, MyComponentsProtocol::Component1(Component1(2.0)));
+        sender.prepare_component_insert(entity, MyComponentsProtocol::Component2(Component2(3.0)));
+        let message = sender.finalize(Tick(4), BevyTick::new(4));
+        assert_eq!(message.baseline_tick, Some(Tick(1)));
+        assert_eq!(
+            message.actions,
+            vec![(
+                entity,
+                EntityActions {
+                    insert: vec![MyComponentsProtocol::Component2(Component2(3.0))],
+                    updates: vec![MyComponentsProtocol::Component1(Component1(2.0))],
+                    ..Default::default()
+                }
+            )]
+        );
+
+        // the update message is lost; the next snapshot is still encoded against the same baseline
+        sender.prepare_component_remove(entity, MyComponentsProtocolKind::Component2);
+        let message = sender.finalize(Tick(5), BevyTick::new(5));
+        assert_eq!(message.baseline_tick, Some(Tick(1)));
+        receiver.recv_message(message, Tick(5));
+        let mut messages = receiver.read_messages(Tick(10));
+        assert_eq!(messages.len(), 1);
+        let (tick, message) = messages.remove(0);
+        assert_eq!(tick, Tick(5));
+        assert_eq!(message.baseline_tick, Some(Tick(3)));
+        assert_eq!(
+            message.actions,
+            vec![(
+                entity,
+                EntityActions {
+                    updates: vec![MyComponentsProtocol::Component1(Component1(2.0))],
+                    ..Default::default()
+                }
+            )]
+        );
+
+        // update then despawn: both snapshots are received before being read, and are both applied in order
+        sender.prepare_entity_update(entity, MyComponentsProtocol::Component1(Component1(4.0)));
+        let message = sender.finalize(Tick(6), BevyTick::new(6));
+        receiver.recv_message(message, Tick(6));
+        sender.prepare_entity_despawn(entity);
+        let message = sender.finalize(Tick(7), BevyTick::new(7));
+        receiver.recv_message(message, Tick(7));
+        let mut messages = receiver.read_messages(Tick(10));
+        assert_eq!(messages.len(), 2);
+        let (tick, message) = messages.remove(0);
+        assert_eq!(tick, Tick(6));
+        assert_eq!(
+            message.actions,
+            vec![(
+                entity,
+                EntityActions {
+                    updates: vec![MyComponentsProtocol::Component1(Component1(4.0))],
+                    ..Default::default()
+                }
+            )]
+        );
+        let (tick, message) = messages.remove(0);
+        assert_eq!(tick, Tick(7));
+        assert_eq!(
+            message.actions,
+            vec![(
+                entity,
+                EntityActions {
+                    despawn: true,
+                    ..Default::default()
+                }
+            )]
+        );
+    }
+}
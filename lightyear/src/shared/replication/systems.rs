@@ -4,7 +4,7 @@ use std::ops::Deref;
 use bevy::ecs::entity::Entities;
 use bevy::ecs::system::SystemChangeTick;
 use bevy::prelude::{
-    Added, App, Commands, Component, DetectChanges, Entity, IntoSystemConfigs, PostUpdate,
+    Added, App, Changed, Commands, Component, DetectChanges, Entity, IntoSystemConfigs, PostUpdate,
     PreUpdate, Query, Ref, RemovedComponents, Res, ResMut, Without,
 };
 use tracing::{debug, error, info, trace};
@@ -13,23 +13,132 @@ use crate::_reexport::FromType;
 use crate::prelude::{MainSet, NetworkTarget, TickManager};
 use crate::protocol::Protocol;
 use crate::server::room::ClientVisibility;
-use crate::shared::replication::components::{DespawnTracker, Replicate, ReplicationMode};
+use crate::shared::replication::components::{
+    DespawnTracker, Replicate, ReplicationMode, ReplicationStopPolicy,
+};
 use crate::shared::replication::ReplicationSend;
 use crate::shared::sets::ReplicationSet;
 
 // TODO: run these systems only if there is at least 1 remote connected!!! (so we don't burn CPU when there are no connections)
 
 /// For every entity that removes their Replicate component but are not despawned, remove the component
-/// from our replicate cache (so that the entity's despawns are no longer replicated)
+/// from our replicate cache (so that the entity's despawns are no longer replicated),
+/// and apply the entity's [`ReplicationStopPolicy`] on the remote
 fn handle_replicate_remove<P: Protocol, R: ReplicationSend<P>>(
     mut sender: ResMut<R>,
     mut query: RemovedComponents<Replicate<P>>,
     entity_check: &Entities,
+    system_bevy_ticks: SystemChangeTick,
 ) {
     for entity in query.read() {
         if entity_check.contains(entity) {
             debug!("handling replicate component remove (delete from cache)");
-            sender.get_mut_replicate_component_cache().remove(&entity);
+            let Some(replicate) = sender.get_mut_replicate_component_cache().remove(&entity) else {
+                continue;
+            };
+            // same as for despawns, we use the cached replication target
+            let target = replicate.replication_target.clone();
+            let _ = match replicate.stop_policy {
+                ReplicationStopPolicy::Freeze => Ok(()),
+                ReplicationStopPolicy::Despawn => sender.prepare_entity_despawn(
+                    entity,
+                    &replicate,
+                    target,
+                    system_bevy_ticks.this_run(),
+                ),
+                ReplicationStopPolicy::Detach => sender.prepare_entity_detach(
+                    entity,
+                    &replicate,
+                    target,
+                    system_bevy_ticks.this_run(),
+                ),
+            }
+            .map_err(|e| {
+                error!("error stopping the replication of the entity: {:?}", e);
+            });
+        }
+    }
+}
+
+/// The remotes that the entity is currently replicated to
+fn replicated_to<P: Protocol>(replicate: &Replicate<P>) -> NetworkTarget {
+    match replicate.replication_mode {
+        // only the clients that currently see the entity
+        ReplicationMode::Room => NetworkTarget::Only(
+            replicate
+                .replication_clients_cache
+                .iter()
+                .filter(|(client_id, visibility)| {
+                    replicate.replication_target.should_send_to(client_id)
+                        && matches!(visibility, ClientVisibility::Maintained)
+                })
+                .map(|(client_id, _)| *client_id)
+                .collect(),
+        ),
+        ReplicationMode::NetworkTarget => replicate.replication_target.clone(),
+    }
+}
+
+/// When the replication of a component gets disabled at runtime, apply the component's
+/// [`ReplicationStopPolicy`] on the remote.
+///
+/// The replicate cache only keeps up-to-date what we need to stop the replication of the entity
+/// (the replication target, the stop policies and which components are disabled), so that we can
+/// detect which components were just disabled.
+fn handle_replicate_change<P: Protocol, R: ReplicationSend<P>>(
+    mut sender: ResMut<R>,
+    query: Query<(Entity, &Replicate<P>), Changed<Replicate<P>>>,
+    system_bevy_ticks: SystemChangeTick,
+) {
+    for (entity, replicate) in query.iter() {
+        // the entity has not been replicated yet
+        let Some(cached) = sender.get_mut_replicate_component_cache().get_mut(&entity) else {
+            continue;
+        };
+        let stopped_kinds = replicate
+            .newly_disabled_components(cached)
+            .filter(|(_, policy)| !matches!(policy, ReplicationStopPolicy::Freeze))
+            .collect::<Vec<_>>();
+        if cached.replication_target != replicate.replication_target {
+            cached.replication_target = replicate.replication_target.clone();
+        }
+        if cached.per_component_metadata != replicate.per_component_metadata {
+            cached.per_component_metadata = replicate.per_component_metadata.clone();
+        }
+        cached.stop_policy = replicate.stop_policy;
+        cached.replication_group = replicate.replication_group;
+        if stopped_kinds.is_empty() {
+            continue;
+        }
+        let entity_target = replicated_to(replicate);
+        for (kind, policy) in stopped_kinds {
+            trace!(
+                ?entity,
+                ?kind,
+                ?policy,
+                "component replication disabled, applying its stop policy on the remote"
+            );
+            let target = replicate.component_target(kind, entity_target.clone());
+            let _ = match policy {
+                ReplicationStopPolicy::Despawn => sender.prepare_component_remove(
+                    entity,
+                    kind,
+                    replicate,
+                    target,
+                    system_bevy_ticks.this_run(),
+                ),
+                ReplicationStopPolicy::Detach => sender.prepare_component_detach(
+                    entity,
+                    kind,
+                    replicate,
+                    target,
+                    system_bevy_ticks.this_run(),
+                ),
+                ReplicationStopPolicy::Freeze => Ok(()),
+            }
+            .map_err(|e| {
+                error!("error stopping the replication of the component: {:?}", e);
+            });
         }
     }
 }
@@ -446,7 +555,11 @@ pub fn add_replication_send_systems<P: Protocol, R: ReplicationSend<P>>(app: &mu
             //  It is ok to run it every frame because it creates at most one message per despawn
             // NOTE: we make sure to update the replicate_cache before we make use of it in `send_entity_despawn`
             (
-                (add_despawn_tracker::<P, R>, handle_replicate_remove::<P, R>),
+                (
+                    add_despawn_tracker::<P, R>,
+                    handle_replicate_change::<P, R>,
+                    handle_replicate_remove::<P, R>,
+                ),
                 send_entity_despawn::<P, R>,
             )
                 .chain()
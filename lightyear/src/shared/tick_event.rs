@@ -0,0 +1,381 @@
+//! Networked events that are stamped with the tick at which they happened.
+//!
+//! A regular message is emitted as a [`MessageEvent`](crate::shared::events::components::MessageEvent) as soon as it
+//! is received, without the tick of the sender. A tick event is sent along with a [`Tick`], and the receiver buffers it
+//! until its own timeline reaches that tick, then emits it as a [`TickEvent`].
+//!
+//! This is useful for effects (explosions, sounds, etc.) that should line up with the entities they belong to:
+//! - on the client, the events can be released on the interpolation timeline (to match the interpolated entities)
+//!   or on the prediction timeline, see [`TickEventPlugin`](crate::client::tick_event::TickEventPlugin)
+//! - on the server, the events are released in `FixedPreUpdate` when the server tick reaches the tick of the event,
+//!   so that the `FixedUpdate` systems of that tick can handle them, see [`TickEventPlugin`](crate::server::tick_event::TickEventPlugin)
+//!
+//! The event type must be added with the `TickEventPlugin` on both the client and the server apps, and is sent
+//! with the `send_tick_event` methods of the `ConnectionManager`s.
+//! Events whose tick is already reached when they are received are released immediately.
+use bevy::ecs::entity::EntityMapper;
+use bevy::prelude::{App, Event, Resource};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::connection::netcode::ClientId;
+use crate::packet::message::Message;
+use crate::prelude::Named;
+use crate::protocol::message_registry::MessageRegistry;
+use crate::shared::events::components::MessageEvent;
+use crate::shared::replication::entity_map::LightyearMapEntities;
+use crate::shared::tick_manager::Tick;
+use crate::utils::ready_buffer::ReadyBuffer;
+
+/// Event that can be sent with a tick, see [`crate::shared::tick_event`]
+pub trait NetworkedEvent: Message + Serialize + DeserializeOwned + Clone + PartialEq {}
+impl<E: Message + Serialize + DeserializeOwned + Clone + PartialEq> NetworkedEvent for E {}
+
+/// The message that is sent on the wire: the event along with the tick at which it happened
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct TickEventMessage<E> {
+    pub(crate) tick: Tick,
+    pub(crate) event: E,
+}
+
+impl<E> Named for TickEventMessage<E> {
+    const NAME: &'static str = "TickEvent";
+}
+
+impl<E: LightyearMapEntities> LightyearMapEntities for TickEventMessage<E> {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.event.map_entities(entity_mapper);
+    }
+}
+
+/// Register the message used to send the tick events of type `E`
+pub(crate) fn register_tick_event<E: NetworkedEvent>(app: &mut App) {
+    // the wrapper is generic, so its name in the registry includes the name of the event
+    let name = format!("{}<{}>", TickEventMessage::<E>::NAME, E::NAME);
+    app.world
+        .get_resource_or_insert_with(MessageRegistry::default)
+        .add::<TickEventMessage<E>>(name);
+    app.add_event::<MessageEvent<TickEventMessage<E>>>();
+    app.add_event::<MessageEvent<TickEventMessage<E>, ClientId>>();
+}
+
+/// Bevy [`Event`] emitted when the timeline of the receiver reaches the tick of a networked event
+#[derive(Event, Debug)]
+pub struct TickEvent<E: NetworkedEvent, Ctx = ()> {
+    event: E,
+    tick: Tick,
+    context: Ctx,
+}
+
+impl<E: NetworkedEvent, Ctx> TickEvent<E, Ctx> {
+    pub fn new(event: E, tick: Tick, context: Ctx) -> Self {
+        Self {
+            event,
+            tick,
+            context,
+        }
+    }
+
+    pub fn event(&self) -> &E {
+        &self.event
+    }
+
+    /// Tick at which the event happened on the sender
+    pub fn tick(&self) -> Tick {
+        self.tick
+    }
+
+    pub fn context(&self) -> &Ctx {
+        &self.context
+    }
+}
+
+/// Tick events that were received but whose tick is not reached yet
+#[derive(Resource)]
+pub(crate) struct TickEventBuffer<E: NetworkedEvent, Ctx: PartialEq> {
+    buffer: ReadyBuffer<Tick, (E, Ctx)>,
+}
+
+impl<E: NetworkedEvent, Ctx: PartialEq> Default for TickEventBuffer<E, Ctx> {
+    fn default() -> Self {
+        Self {
+            buffer: ReadyBuffer::new(),
+        }
+    }
+}
+
+impl<E: NetworkedEvent, Ctx: PartialEq> TickEventBuffer<E, Ctx> {
+    pub(crate) fn add(&mut self, message: TickEventMessage<E>, context: Ctx) {
+        self.buffer.add_item(message.tick, (message.event, context));
+    }
+
+    /// Remove the events whose tick is older or equal than `tick`, in tick order
+    pub(crate) fn release(&mut self, tick: Tick) -> impl Iterator<Item = TickEvent<E, Ctx>> {
+        self.buffer
+            .drain_until(&tick)
+            .into_iter()
+            .map(|(tick, (event, context))| TickEvent::new(event, tick, context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{EventReader, FixedUpdate, IntoSystemConfigs, PreUpdate, Res, ResMut};
+    use bevy::utils::Duration;
+
+    use crate::prelude::client::{
+        ClientConnection, InterpolationConfig, NetClient, PredictionConfig, SyncConfig,
+    };
+    use crate::prelude::*;
+    use crate::shared::tick_manager::TickManager;
+    use crate::tests::protocol::*;
+    use crate::tests::stepper::{BevyStepper, Step};
+
+    use super::*;
+
+    fn message(tick: u16, value: &str) -> TickEventMessage<Message1> {
+        TickEventMessage {
+            tick: Tick(tick),
+            event: Message1(value.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_release() {
+        let mut buffer = TickEventBuffer::<Message1, ()>::default();
+        buffer.add(message(5, "b"), ());
+        buffer.add(message(3, "a"), ());
+        buffer.add(message(8, "c"), ());
+
+        assert!(buffer.release(Tick(2)).next().is_none());
+        let released: Vec<_> = buffer
+            .release(Tick(5))
+            .map(|event| (event.tick(), event.event().clone()))
+            .collect();
+        assert_eq!(
+            released,
+            vec![
+                (Tick(3), Message1("a".to_string())),
+                (Tick(5), Message1("b".to_string()))
+            ]
+        );
+        assert_eq!(buffer.release(Tick(10)).count(), 1);
+    }
+
+    /// For each released event: (tick of the event, tick at which it was released)
+    #[derive(Resource, Default)]
+    struct Released(Vec<(Tick, Tick)>);
+
+    fn record_client_events(
+        tick_manager: Res<TickManager>,
+        mut events: EventReader<client::TickEvent<Message1>>,
+        mut released: ResMut<Released>,
+    ) {
+        for event in events.read() {
+            released.0.push((event.tick(), tick_manager.tick()));
+        }
+    }
+
+    fn record_server_events(
+        tick_manager: Res<TickManager>,
+        mut events: EventReader<server::TickEvent<Message1>>,
+        mut released: ResMut<Released>,
+    ) {
+        for event in events.read() {
+            released.0.push((event.tick(), tick_manager.tick()));
+        }
+    }
+
+    fn stepper() -> BevyStepper {
+        let frame_duration = Duration::from_millis(10);
+        let tick_duration = Duration::from_millis(10);
+        let shared_config = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..Default::default()
+        };
+        let link_conditioner = LinkConditionerConfig {
+            incoming_latency: Duration::from_millis(20),
+            incoming_jitter: Duration::from_millis(0),
+            incoming_loss: 0.0,
+        };
+        BevyStepper::new(
+            shared_config,
+            SyncConfig::default(),
+            PredictionConfig::default(),
+            InterpolationConfig::default(),
+            link_conditioner,
+            frame_duration,
+        )
+    }
+
+    /// Events sent for a future tick are released when the timeline of the receiver reaches that tick,
+    /// in time for the `FixedUpdate` systems of that tick
+    #[test]
+    fn test_release_at_tick() {
+        let mut stepper = stepper();
+        stepper
+            .client_app
+            .add_plugins(client::TickEventPlugin::<MyProtocol, Message1>::new(
+                client::TickEventTimeline::Prediction,
+            ))
+            .init_resource::<Released>()
+            .add_systems(FixedUpdate, record_client_events);
+        stepper
+            .server_app
+            .add_plugins(server::TickEventPlugin::<MyProtocol, Message1>::default())
+            .init_resource::<Released>()
+            .add_systems(FixedUpdate, record_server_events);
+        stepper.init();
+        let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+
+        let server_event_tick = stepper.server_tick() + 20;
+        stepper
+            .server_app
+            .world
+            .resource_mut::<ServerConnectionManager>()
+            .send_tick_event::<Channel1, _>(
+                client_id,
+                Message1("server".to_string()),
+                server_event_tick,
+            )
+            .unwrap();
+        let client_event_tick = stepper.client_tick() + 20;
+        stepper
+            .client_app
+            .world
+            .resource_mut::<ClientConnectionManager>()
+            .send_tick_event::<Channel1, _>(Message1("client".to_string()), client_event_tick)
+            .unwrap();
+
+        // the events are received before their tick is reached
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        assert!(stepper.client_app.world.resource::<Released>().0.is_empty());
+        assert!(stepper.server_app.world.resource::<Released>().0.is_empty());
+
+        for _ in 0..30 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.client_app.world.resource::<Released>().0,
+            vec![(server_event_tick, server_event_tick)]
+        );
+        assert_eq!(
+            stepper.server_app.world.resource::<Released>().0,
+            vec![(client_event_tick, client_event_tick)]
+        );
+    }
+
+    /// The server drops the events that are too far ahead of its tick
+    #[test]
+    fn test_drop_events_too_far_ahead() {
+        let mut stepper = stepper();
+        stepper
+            .client_app
+            .add_plugins(client::TickEventPlugin::<MyProtocol, Message1>::new(
+                client::TickEventTimeline::Prediction,
+            ));
+        stepper
+            .server_app
+            .add_plugins(
+                server::TickEventPlugin::<MyProtocol, Message1>::default().with_max_ticks_ahead(50),
+            )
+            .init_resource::<Released>()
+            .add_systems(FixedUpdate, record_server_events);
+        stepper.init();
+
+        let event_tick = stepper.client_tick() + 20;
+        let mut connection = stepper
+            .client_app
+            .world
+            .resource_mut::<ClientConnectionManager>();
+        connection
+            .send_tick_event::<Channel1, _>(Message1("near".to_string()), event_tick)
+            .unwrap();
+        connection
+            .send_tick_event::<Channel1, _>(Message1("far".to_string()), event_tick + 1000)
+            .unwrap();
+        for _ in 0..5 {
+            stepper.frame_step();
+        }
+        // only the event within the horizon is buffered
+        assert_eq!(
+            stepper
+                .server_app
+                .world
+                .resource::<TickEventBuffer<Message1, ClientId>>()
+                .buffer
+                .len(),
+            1
+        );
+        for _ in 0..30 {
+            stepper.frame_step();
+        }
+        assert_eq!(
+            stepper.server_app.world.resource::<Released>().0,
+            vec![(event_tick, event_tick)]
+        );
+    }
+
+    /// For each released event: (tick of the event, interpolation tick at which it was released)
+    fn record_interpolation_events(
+        tick_manager: Res<TickManager>,
+        connection: Res<ClientConnectionManager>,
+        mut events: EventReader<client::TickEvent<Message1>>,
+        mut released: ResMut<Released>,
+    ) {
+        for event in events.read() {
+            released.0.push((
+                event.tick(),
+                connection.sync_manager.interpolation_tick(&tick_manager),
+            ));
+        }
+    }
+
+    /// On the interpolation timeline, the events are released when the interpolation tick reaches their tick,
+    /// which is well after the client tick reached it
+    #[test]
+    fn test_interpolation_timeline() {
+        let mut stepper = stepper();
+        stepper
+            .client_app
+            .add_plugins(client::TickEventPlugin::<MyProtocol, Message1>::new(
+                client::TickEventTimeline::Interpolation,
+            ))
+            .init_resource::<Released>()
+            .add_systems(
+                PreUpdate,
+                record_interpolation_events.after(MainSet::Receive),
+            );
+        stepper
+            .server_app
+            .add_plugins(server::TickEventPlugin::<MyProtocol, Message1>::default());
+        stepper.init();
+        let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+
+        let event_tick = stepper.server_tick() + 5;
+        stepper
+            .server_app
+            .world
+            .resource_mut::<ServerConnectionManager>()
+            .send_tick_event::<Channel1, _>(client_id, Message1("server".to_string()), event_tick)
+            .unwrap();
+
+        // the client tick reaches the tick of the event, but not the interpolation tick
+        while stepper.client_tick() < event_tick + 2 {
+            stepper.frame_step();
+        }
+        assert!(stepper.client_app.world.resource::<Released>().0.is_empty());
+
+        for _ in 0..50 {
+            stepper.frame_step();
+        }
+        let released = &stepper.client_app.world.resource::<Released>().0;
+        assert_eq!(released.len(), 1);
+        let (tick, interpolation_tick) = released[0];
+        assert_eq!(tick, event_tick);
+        // released on the first frame where the interpolation tick reached the tick of the event
+        assert!(interpolation_tick >= event_tick && interpolation_tick - event_tick <= 1);
+    }
+}
@@ -11,6 +11,33 @@ use crate::utils::wrapping_id::wrapping_id;
 // Internal id that tracks the Tick value for the server and the client
 wrapping_id!(Tick);
 
+impl Tick {
+    /// Ticks that are further apart than this cannot be compared reliably: because the tick wraps around,
+    /// an old tick could look like it's in the future.
+    pub(crate) const MAX_COMPARABLE_DISTANCE: u16 = (i16::MAX / 2) as u16;
+
+    /// Returns true if the two ticks are too far apart to be compared reliably
+    /// (for example if one of them was stored a long time ago and hasn't been updated since)
+    pub(crate) fn is_too_far_from(self, other: Tick) -> bool {
+        (self - other).unsigned_abs() > Self::MAX_COMPARABLE_DISTANCE
+    }
+
+    /// Generation (number of wraps around u16::MAX) of `self`, given the generation of `reference`:
+    /// `self` is one generation ahead if it is after `reference` but wrapped around, and one generation
+    /// behind if it is before `reference` and `reference` wrapped around.
+    ///
+    /// The two ticks need to be less than i16::MAX ticks apart.
+    pub(crate) fn generation(self, reference: Tick, reference_generation: u16) -> u16 {
+        if self >= reference && self.0 < reference.0 {
+            reference_generation.wrapping_add(1)
+        } else if self < reference && self.0 > reference.0 {
+            reference_generation.saturating_sub(1)
+        } else {
+            reference_generation
+        }
+    }
+}
+
 pub struct TickManagerPlugin {
     pub(crate) config: TickConfig,
 }
@@ -96,3 +123,25 @@ impl TickManager {
         self.tick
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Tick;
+
+    #[test]
+    fn test_is_too_far_from() {
+        assert!(!Tick(1).is_too_far_from(Tick(u16::MAX)));
+        assert!(!Tick(1).is_too_far_from(Tick(1) + Tick::MAX_COMPARABLE_DISTANCE as i16));
+        assert!(Tick(1).is_too_far_from(Tick(1) + Tick(40000)));
+        assert!(Tick(1).is_too_far_from(Tick(1) - 20000));
+    }
+
+    #[test]
+    fn test_generation() {
+        assert_eq!(Tick(5).generation(Tick(u16::MAX - 10), 2), 3);
+        assert_eq!(Tick(u16::MAX - 10).generation(Tick(5), 2), 1);
+        assert_eq!(Tick(u16::MAX - 10).generation(Tick(5), 0), 0);
+        assert_eq!(Tick(990).generation(Tick(1000), 2), 2);
+        assert_eq!(Tick(1000).generation(Tick(990), 2), 2);
+    }
+}
@@ -0,0 +1,20 @@
+//! Utilities to write deterministic integration tests for multiplayer games.
+//!
+//! Enable the `test_utils` feature to use them. The stepper applies the
+//! [`LinkConditionerConfig`](crate::prelude::LinkConditionerConfig)s itself with the time it advances,
+//! so it doesn't need the `mock_time` feature.
+//!
+//! ```rust,ignore
+//! let mut stepper = MultiClientStepper::new(
+//!     StepperConfig::new(server_config, client_config)
+//!         .with_client(1, None)
+//!         .with_client(2, Some(LinkConditionerConfig::average_condition())),
+//!     MyProtocol::default(),
+//! );
+//! stepper.init();
+//! stepper.server_app.world.spawn((Position::default(), Replicate::default()));
+//! stepper.frame_steps(10);
+//! ```
+pub mod stepper;
+
+pub use stepper::{MultiClientStepper, StepperClientConfig, StepperConfig};
@@ -0,0 +1,488 @@
+//! Step a server app and multiple client apps in lockstep, using mock time and local channels instead of a real network.
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use bevy::ecs::entity::Entity;
+use bevy::prelude::{App, Mut, PluginGroup, Real, Resource, Time, World};
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::{Duration, HashMap, Instant};
+use bevy::MinimalPlugins;
+use crossbeam_channel::{Receiver, Sender};
+
+use crate::client::config::ClientConfig;
+use crate::client::connection::ConnectionManager as ClientConnectionManager;
+use crate::client::plugin::{ClientPlugin, PluginConfig as ClientPluginConfig};
+use crate::connection::client::{ClientConnection, NetClient, NetConfig as ClientNetConfig};
+use crate::connection::netcode::{generate_key, ClientId};
+use crate::connection::server::{NetConfig as ServerNetConfig, NetServer, ServerConnection};
+use crate::prelude::client::Authentication;
+use crate::prelude::{IoConfig, LinkConditionerConfig, Tick, TickManager, TransportConfig};
+use crate::protocol::Protocol;
+use crate::server::config::ServerConfig;
+use crate::server::connection::ConnectionManager as ServerConnectionManager;
+use crate::server::plugin::{PluginConfig as ServerPluginConfig, ServerPlugin};
+use crate::transport::conditioner::condition_packet;
+use crate::transport::LOCAL_SOCKET;
+use crate::utils::ready_buffer::ReadyBuffer;
+
+/// Configuration of one of the clients of the [`MultiClientStepper`]
+#[derive(Clone, Debug)]
+pub struct StepperClientConfig {
+    pub client_id: ClientId,
+    /// Network conditions between this client and the server, applied in both directions
+    pub conditioner: Option<LinkConditionerConfig>,
+}
+
+/// Configuration of the [`MultiClientStepper`]
+#[derive(Clone)]
+pub struct StepperConfig {
+    /// Duration of a frame, i.e. how much the mock time advances on every [`MultiClientStepper::frame_step`]
+    pub frame_duration: Duration,
+    /// Configuration of the server. The `net` config is replaced by local channels
+    pub server: ServerConfig,
+    /// Configuration shared by all the clients. The `shared` config is replaced by the server's
+    /// and the `net` config is replaced by local channels
+    pub client: ClientConfig,
+    pub clients: Vec<StepperClientConfig>,
+}
+
+impl StepperConfig {
+    pub fn new(server: ServerConfig, client: ClientConfig) -> Self {
+        Self {
+            frame_duration: server.shared.tick.tick_duration,
+            server,
+            client,
+            clients: vec![],
+        }
+    }
+
+    pub fn with_frame_duration(mut self, frame_duration: Duration) -> Self {
+        self.frame_duration = frame_duration;
+        self
+    }
+
+    /// Add a client with the given id, and optional network conditions between the client and the server
+    pub fn with_client(
+        mut self,
+        client_id: ClientId,
+        conditioner: Option<LinkConditionerConfig>,
+    ) -> Self {
+        self.clients.push(StepperClientConfig {
+            client_id,
+            conditioner,
+        });
+        self
+    }
+}
+
+/// One direction of the local link between a client and the server.
+///
+/// The packets are conditioned here with the stepper's clock (instead of the io's conditioner, which uses
+/// the wall clock), so that the network conditions follow the time advanced by the stepper
+struct LinkDirection {
+    recv: Receiver<Vec<u8>>,
+    send: Sender<Vec<u8>>,
+    conditioner: Option<LinkConditionerConfig>,
+    time_queue: ReadyBuffer<Instant, Vec<u8>>,
+}
+
+impl LinkDirection {
+    fn new(
+        recv: Receiver<Vec<u8>>,
+        send: Sender<Vec<u8>>,
+        conditioner: Option<LinkConditionerConfig>,
+    ) -> Self {
+        Self {
+            recv,
+            send,
+            conditioner,
+            time_queue: ReadyBuffer::new(),
+        }
+    }
+
+    /// Forward the packets that are ready to be received at time `now`
+    fn relay(&mut self, now: Instant) {
+        while let Ok(packet) = self.recv.try_recv() {
+            match &self.conditioner {
+                Some(conditioner) => {
+                    condition_packet(conditioner, &mut self.time_queue, packet, now)
+                }
+                None => {
+                    let _ = self.send.send(packet);
+                }
+            }
+        }
+        while let Some((_, packet)) = self.time_queue.pop_item(&now) {
+            let _ = self.send.send(packet);
+        }
+    }
+}
+
+/// Local link between a client and the server
+struct ClientLink {
+    to_server: LinkDirection,
+    to_client: LinkDirection,
+}
+
+/// Runs a server app and multiple client apps connected via local channels, with mock time.
+///
+/// Every app is updated once per step, so the tests are deterministic (apart from the randomness of the
+/// link conditioners).
+pub struct MultiClientStepper<P: Protocol> {
+    pub server_app: App,
+    pub client_apps: HashMap<ClientId, App>,
+    pub frame_duration: Duration,
+    /// fixed timestep duration
+    pub tick_duration: Duration,
+    pub current_time: Instant,
+    links: HashMap<ClientId, ClientLink>,
+    _marker: std::marker::PhantomData<P>,
+}
+
+impl<P: Protocol> MultiClientStepper<P> {
+    pub fn new(config: StepperConfig, protocol: P) -> Self {
+        let now = Instant::now();
+        let protocol_id = 0;
+        let private_key = generate_key();
+        let shared_config = config.server.shared.clone();
+
+        let mut server_channels = vec![];
+        let mut client_apps = HashMap::new();
+        let mut links = HashMap::new();
+        for (i, client) in config.clients.iter().enumerate() {
+            // each client needs a different address so that the server can tell them apart
+            let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 1000 + i as u16);
+            let (from_server_send, from_server_recv) = crossbeam_channel::unbounded();
+            let (to_client_send, to_client_recv) = crossbeam_channel::unbounded();
+            let (from_client_send, from_client_recv) = crossbeam_channel::unbounded();
+            let (to_server_send, to_server_recv) = crossbeam_channel::unbounded();
+            server_channels.push((addr, to_server_recv, from_server_send));
+            links.insert(
+                client.client_id,
+                ClientLink {
+                    to_server: LinkDirection::new(
+                        from_client_recv,
+                        to_server_send,
+                        client.conditioner.clone(),
+                    ),
+                    to_client: LinkDirection::new(
+                        from_server_recv,
+                        to_client_send,
+                        client.conditioner.clone(),
+                    ),
+                },
+            );
+            let client_io = IoConfig::from_transport(TransportConfig::LocalChannel {
+                recv: to_client_recv,
+                send: from_client_send,
+            });
+
+            let netcode_config = match &config.client.net {
+                ClientNetConfig::Netcode { config, .. } => config.clone(),
+                _ => Default::default(),
+            };
+            let client_config = ClientConfig {
+                shared: shared_config.clone(),
+                net: ClientNetConfig::Netcode {
+                    auth: Authentication::Manual {
+                        server_addr: LOCAL_SOCKET,
+                        protocol_id,
+                        private_key,
+                        client_id: client.client_id,
+                    },
+                    config: netcode_config,
+                    io: client_io,
+                },
+                ..config.client.clone()
+            };
+            let mut client_app = App::new();
+            client_app.add_plugins(MinimalPlugins.build());
+            client_app.add_plugins(ClientPlugin::new(ClientPluginConfig::new(
+                client_config,
+                protocol.clone(),
+            )));
+            // Initialize Real time (needed only for the first TimeSystem run)
+            client_app
+                .world
+                .resource_mut::<Time<Real>>()
+                .update_with_instant(now);
+            client_apps.insert(client.client_id, client_app);
+        }
+
+        let netcode_config = match &config.server.net {
+            ServerNetConfig::Netcode { config, .. } => config.clone(),
+            _ => Default::default(),
+        };
+        let server_config = ServerConfig {
+            net: ServerNetConfig::Netcode {
+                config: netcode_config
+                    .with_protocol_id(protocol_id)
+                    .with_key(private_key),
+                io: IoConfig::from_transport(TransportConfig::Channels {
+                    channels: server_channels,
+                }),
+            },
+            ..config.server
+        };
+        let mut server_app = App::new();
+        server_app.add_plugins(MinimalPlugins.build());
+        server_app.add_plugins(ServerPlugin::new(ServerPluginConfig::new(
+            server_config,
+            protocol,
+        )));
+        server_app
+            .world
+            .resource_mut::<Time<Real>>()
+            .update_with_instant(now);
+
+        Self {
+            server_app,
+            client_apps,
+            frame_duration: config.frame_duration,
+            tick_duration: shared_config.tick.tick_duration,
+            current_time: now,
+            links,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Finish building the apps, connect all the clients and step until they are all synced with the server
+    ///
+    /// Systems and plugins should be added to the apps before calling this.
+    ///
+    /// Panics if the clients are not synced after 100 frames.
+    pub fn init(&mut self) {
+        // finish building the plugins, like `App::run` would do
+        self.server_app.finish();
+        self.server_app.cleanup();
+        for client_app in self.client_apps.values_mut() {
+            client_app.finish();
+            client_app.cleanup();
+        }
+        let client_ids: Vec<ClientId> = self.client_apps.keys().copied().collect();
+        for client_id in client_ids {
+            self.connect_client(client_id);
+        }
+        let synced = self.step_until(100, |stepper| {
+            stepper
+                .client_apps
+                .keys()
+                .all(|client_id| stepper.is_client_synced(*client_id))
+        });
+        assert!(synced, "some clients are still not synced after 100 frames");
+    }
+
+    /// Step frames until the condition is true, for at most `max_frames` frames.
+    ///
+    /// Returns true if the condition was met.
+    pub fn step_until(&mut self, max_frames: usize, condition: impl Fn(&Self) -> bool) -> bool {
+        for _ in 0..max_frames {
+            if condition(self) {
+                return true;
+            }
+            self.frame_step();
+        }
+        condition(self)
+    }
+
+    /// Start connecting the client to the server. The connection is established during the next steps.
+    pub fn connect_client(&mut self, client_id: ClientId) {
+        self.client_app_mut(client_id)
+            .world
+            .resource_mut::<ClientConnection>()
+            .connect()
+            .expect("could not connect");
+    }
+
+    /// Disconnect the client from the server. The server receives the disconnection during the next steps.
+    pub fn disconnect_client(&mut self, client_id: ClientId) {
+        self.client_app_mut(client_id)
+            .world
+            .resource_mut::<ClientConnection>()
+            .disconnect()
+            .expect("could not disconnect");
+    }
+
+    /// Returns true if the server considers the client connected
+    pub fn is_client_connected(&self, client_id: ClientId) -> bool {
+        self.server_app
+            .world
+            .resource::<ServerConnection>()
+            .connected_client_ids()
+            .contains(&client_id)
+    }
+
+    /// Returns true if the client is connected and has been time-synced with the server
+    pub fn is_client_synced(&self, client_id: ClientId) -> bool {
+        let world = self.client_world(client_id);
+        world.resource::<ClientConnection>().is_connected()
+            && world.resource::<ClientConnectionManager<P>>().is_synced()
+    }
+
+    pub fn client_app(&self, client_id: ClientId) -> &App {
+        self.client_apps
+            .get(&client_id)
+            .unwrap_or_else(|| panic!("unknown client {client_id}"))
+    }
+
+    pub fn client_app_mut(&mut self, client_id: ClientId) -> &mut App {
+        self.client_apps
+            .get_mut(&client_id)
+            .unwrap_or_else(|| panic!("unknown client {client_id}"))
+    }
+
+    pub fn client_world(&self, client_id: ClientId) -> &World {
+        &self.client_app(client_id).world
+    }
+
+    pub fn client_world_mut(&mut self, client_id: ClientId) -> &mut World {
+        &mut self.client_app_mut(client_id).world
+    }
+
+    pub fn client_resource<R: Resource>(&self, client_id: ClientId) -> &R {
+        self.client_world(client_id).resource::<R>()
+    }
+
+    pub fn client_resource_mut<R: Resource>(&mut self, client_id: ClientId) -> Mut<'_, R> {
+        self.client_world_mut(client_id).resource_mut::<R>()
+    }
+
+    pub fn server_tick(&self) -> Tick {
+        self.server_app.world.resource::<TickManager>().tick()
+    }
+
+    pub fn client_tick(&self, client_id: ClientId) -> Tick {
+        self.client_resource::<TickManager>(client_id).tick()
+    }
+
+    /// Returns the entity in the client world that was replicated from `server_entity`
+    pub fn client_entity(&self, client_id: ClientId, server_entity: Entity) -> Option<Entity> {
+        self.client_resource::<ClientConnectionManager<P>>(client_id)
+            .replication_receiver
+            .remote_entity_map
+            .get_local(server_entity)
+            .copied()
+    }
+
+    /// Returns the entity in the server world that was replicated from `client_entity`, owned by the client
+    pub fn server_entity(&self, client_id: ClientId, client_entity: Entity) -> Option<Entity> {
+        self.server_app
+            .world
+            .resource::<ServerConnectionManager<P>>()
+            .connection(client_id)
+            .ok()?
+            .replication_receiver
+            .remote_entity_map
+            .get_local(client_entity)
+            .copied()
+    }
+
+    pub fn advance_time(&mut self, duration: Duration) {
+        self.current_time += duration;
+        self.server_app
+            .insert_resource(TimeUpdateStrategy::ManualInstant(self.current_time));
+        for client_app in self.client_apps.values_mut() {
+            client_app.insert_resource(TimeUpdateStrategy::ManualInstant(self.current_time));
+        }
+    }
+
+    /// Update every app once
+    fn update(&mut self) {
+        for link in self.links.values_mut() {
+            link.to_client.relay(self.current_time);
+        }
+        for client_app in self.client_apps.values_mut() {
+            client_app.update();
+        }
+        for link in self.links.values_mut() {
+            link.to_server.relay(self.current_time);
+        }
+        self.server_app.update();
+    }
+
+    /// Advance all the apps by one frame duration
+    pub fn frame_step(&mut self) {
+        self.advance_time(self.frame_duration);
+        self.update();
+    }
+
+    /// Advance all the apps by `n` frames
+    pub fn frame_steps(&mut self, n: usize) {
+        for _ in 0..n {
+            self.frame_step();
+        }
+    }
+
+    /// Advance all the apps by one fixed timestep duration
+    pub fn tick_step(&mut self) {
+        self.advance_time(self.tick_duration);
+        self.update();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+
+    use crate::prelude::client::ClientConfig;
+    use crate::prelude::server::ServerConfig;
+    use crate::prelude::*;
+    use crate::tests::protocol::*;
+
+    use super::*;
+
+    #[test]
+    fn test_multi_client_stepper() {
+        let tick_duration = Duration::from_millis(10);
+        let shared = SharedConfig {
+            tick: TickConfig::new(tick_duration),
+            ..default()
+        };
+        let config = StepperConfig::new(
+            ServerConfig {
+                shared,
+                ..default()
+            },
+            ClientConfig::default(),
+        )
+        .with_client(1, None)
+        .with_client(
+            2,
+            Some(LinkConditionerConfig::new(
+                Duration::from_millis(50),
+                Duration::default(),
+                0.0,
+            )),
+        );
+        let mut stepper = MultiClientStepper::new(config, protocol());
+        stepper.init();
+        assert!(stepper.is_client_connected(1));
+        assert!(stepper.is_client_connected(2));
+
+        let server_entity = stepper
+            .server_app
+            .world
+            .spawn((Component1(1.0), Replicate::default()))
+            .id();
+        stepper.frame_step();
+        stepper.frame_step();
+        // the second client has some latency
+        let client_entity = stepper.client_entity(1, server_entity).unwrap();
+        assert_eq!(
+            stepper.client_world(1).get::<Component1>(client_entity),
+            Some(&Component1(1.0))
+        );
+        assert!(stepper.client_entity(2, server_entity).is_none());
+        stepper.frame_steps(10);
+        assert!(stepper.client_entity(2, server_entity).is_some());
+
+        // disconnect a client mid-test
+        stepper.disconnect_client(2);
+        stepper.frame_steps(10);
+        assert!(stepper.is_client_connected(1));
+        assert!(!stepper.is_client_connected(2));
+
+        // and connect it again
+        stepper.connect_client(2);
+        assert!(stepper.step_until(100, |stepper| stepper.is_client_connected(2)));
+    }
+}
@@ -28,6 +28,7 @@ pub fn bevy_setup(app: &mut App, auth: Authentication) {
         prediction: PredictionConfig::default(),
         interpolation: InterpolationConfig::default(),
         packet: Default::default(),
+        replication: Default::default(),
     };
     let plugin_config = PluginConfig::new(config, protocol());
     let plugin = ClientPlugin::new(plugin_config);
@@ -16,6 +16,7 @@ use crate::shared::events::connection::IterMessageEvent;
 use crate::tests::protocol::*;
 
 // TODO: rework connection_soak, we need to create bevy plugins now to be able to use the rest of the library
+//  (to test many clients against a real server, use the `load_test` binary of the `stepper` example)
 #[test]
 #[ignore]
 fn test_connection_soak() -> anyhow::Result<()> {
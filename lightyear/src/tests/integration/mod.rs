@@ -1 +1,3 @@
+mod stats;
 mod tick_wrapping;
+mod transport_stream;
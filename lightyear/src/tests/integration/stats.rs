@@ -0,0 +1,90 @@
+use crate::connection::client::{ClientConnection, NetClient};
+use crate::prelude::client::SyncConfig;
+use crate::prelude::*;
+use crate::shared::replication::components::ReplicationGroupId;
+use crate::shared::replication::send::ReplicationGroupStats;
+use crate::tests::protocol::*;
+use crate::tests::stepper::{BevyStepper, Step};
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+/// This test checks that the replication traffic of each group is counted by the server
+#[test]
+fn test_replication_group_stats() {
+    let frame_duration = Duration::from_millis(10);
+    let tick_duration = Duration::from_millis(10);
+    let shared_config = SharedConfig {
+        tick: TickConfig::new(tick_duration),
+        ..Default::default()
+    };
+    let link_conditioner = LinkConditionerConfig {
+        incoming_latency: Duration::from_millis(0),
+        incoming_jitter: Duration::from_millis(0),
+        incoming_loss: 0.0,
+    };
+    let mut stepper = BevyStepper::new(
+        shared_config,
+        SyncConfig::default().speedup_factor(1.0),
+        client::PredictionConfig::default(),
+        client::InterpolationConfig::default(),
+        link_conditioner,
+        frame_duration,
+    );
+    stepper.init();
+    let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+    let group_stats = |stepper: &BevyStepper, entity: Entity| {
+        stepper
+            .server_app
+            .world
+            .resource::<ServerConnectionManager>()
+            .connection(client_id)
+            .unwrap()
+            .replication_sender
+            .group_channels
+            .get(&ReplicationGroupId(entity.to_bits()))
+            .map(|channel| channel.stats)
+            .unwrap_or_default()
+    };
+
+    let server_entity = stepper
+        .server_app
+        .world
+        .spawn((
+            Component1(0.0),
+            Replicate {
+                replication_target: NetworkTarget::All,
+                ..default()
+            },
+        ))
+        .id();
+    stepper.frame_step();
+    let spawn_stats = group_stats(&stepper, server_entity);
+    assert_eq!(spawn_stats.actions_sent, 1);
+    assert_eq!(spawn_stats.updates_sent, 0);
+    assert!(spawn_stats.bytes_sent > 0);
+
+    // let the client ack the spawn
+    for _ in 0..5 {
+        stepper.frame_step();
+    }
+    let idle_stats = group_stats(&stepper, server_entity);
+    assert_eq!(idle_stats.actions_sent, 1);
+
+    stepper
+        .server_app
+        .world
+        .get_mut::<Component1>(server_entity)
+        .unwrap()
+        .0 = 1.0;
+    stepper.frame_step();
+    let update_stats = group_stats(&stepper, server_entity);
+    assert_eq!(
+        update_stats,
+        ReplicationGroupStats {
+            actions_sent: 1,
+            updates_sent: idle_stats.updates_sent + 1,
+            bytes_sent: update_stats.bytes_sent,
+        }
+    );
+    assert!(update_stats.bytes_sent > idle_stats.bytes_sent);
+}
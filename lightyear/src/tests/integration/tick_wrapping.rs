@@ -74,6 +74,7 @@ fn test_sync_after_tick_wrap() {
         ))
         .id();
 
+    stepper.build();
     for i in 0..200 {
         stepper.frame_step();
     }
@@ -165,6 +166,7 @@ fn test_sync_after_tick_half_wrap() {
         ))
         .id();
 
+    stepper.build();
     for i in 0..200 {
         stepper.frame_step();
     }
@@ -199,3 +201,85 @@ fn test_sync_after_tick_half_wrap() {
         &Component1(47.0)
     );
 }
+
+/// This test checks that replication still works after a long session where an entity has not been updated
+/// for more than i16::MAX ticks, i.e. when the ticks stored for the replication group cannot be compared
+/// with the current tick anymore
+///
+/// This test runs a full tick generation (u16::MAX ticks) so it is slow; run it with `cargo test -- --ignored`
+#[test]
+#[ignore]
+fn test_replication_after_long_idle() {
+    let tick_duration = Duration::from_millis(10);
+    // use long frames so that we can run a lot of ticks quickly
+    let frame_duration = tick_duration * 4;
+    let shared_config = SharedConfig {
+        tick: TickConfig::new(tick_duration),
+        ..Default::default()
+    };
+    let link_conditioner = LinkConditionerConfig {
+        incoming_latency: Duration::from_millis(20),
+        incoming_jitter: Duration::from_millis(0),
+        incoming_loss: 0.0,
+    };
+    let mut stepper = BevyStepper::new(
+        shared_config,
+        SyncConfig::default(),
+        client::PredictionConfig::default(),
+        client::InterpolationConfig::default(),
+        link_conditioner,
+        frame_duration,
+    );
+    stepper.init();
+
+    let server_entity = stepper
+        .server_app
+        .world
+        .spawn((
+            Component1(0.0),
+            Replicate {
+                replication_target: NetworkTarget::All,
+                ..default()
+            },
+        ))
+        .id();
+    for _ in 0..10 {
+        stepper.frame_step();
+    }
+    let client_entity = *stepper
+        .client_app
+        .world
+        .resource::<ClientConnectionManager>()
+        .replication_receiver
+        .remote_entity_map
+        .get_local(server_entity)
+        .unwrap();
+
+    // stay idle for more than i16::MAX ticks
+    let start_tick = stepper.server_tick();
+    while stepper.server_tick() - start_tick >= 0 {
+        stepper.frame_step();
+    }
+    for _ in 0..100 {
+        stepper.frame_step();
+    }
+
+    // an update to the component is still received by the client
+    stepper
+        .server_app
+        .world
+        .get_mut::<Component1>(server_entity)
+        .unwrap()
+        .0 = 1.0;
+    for _ in 0..10 {
+        stepper.frame_step();
+    }
+    assert_eq!(
+        stepper
+            .client_app
+            .world
+            .get::<Component1>(client_entity)
+            .unwrap(),
+        &Component1(1.0)
+    );
+}
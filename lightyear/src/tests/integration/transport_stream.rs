@@ -0,0 +1,92 @@
+use crate::connection::client::{ClientConnection, NetClient};
+use crate::connection::server::{NetServer, ServerConnection};
+use crate::prelude::client::SyncConfig;
+use crate::prelude::*;
+use crate::tests::protocol::*;
+use crate::tests::stepper::{BevyStepper, Step};
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+/// This test checks that the channels that use the reliable stream of the transport are delivered,
+/// and that the datagrams are still accepted once packets were sent on the stream
+#[test]
+fn test_send_on_reliable_stream() {
+    let frame_duration = Duration::from_millis(10);
+    let tick_duration = Duration::from_millis(10);
+    let shared_config = SharedConfig {
+        tick: TickConfig::new(tick_duration),
+        ..Default::default()
+    };
+    let link_conditioner = LinkConditionerConfig {
+        incoming_latency: Duration::from_millis(0),
+        incoming_jitter: Duration::from_millis(0),
+        incoming_loss: 0.0,
+    };
+    let mut stepper = BevyStepper::new(
+        shared_config,
+        SyncConfig::default().speedup_factor(1.0),
+        client::PredictionConfig::default(),
+        client::InterpolationConfig::default(),
+        link_conditioner,
+        frame_duration,
+    );
+    stepper.init();
+    let client_id = stepper.client_app.world.resource::<ClientConnection>().id();
+    // the local channels are reliable and ordered, so they provide a reliable stream
+    assert!(stepper
+        .client_app
+        .world
+        .resource::<ClientConnection>()
+        .has_reliable_stream());
+    assert!(stepper
+        .server_app
+        .world
+        .resource::<ServerConnection>()
+        .has_reliable_stream(client_id));
+
+    for i in 0..3 {
+        let mut client_connection = stepper
+            .client_app
+            .world
+            .resource_mut::<ClientConnectionManager>();
+        client_connection
+            .send_message::<Channel3, _>(Message2(i))
+            .unwrap();
+        client_connection
+            .send_message::<Channel1, _>(Message1(i.to_string()))
+            .unwrap();
+        stepper
+            .server_app
+            .world
+            .resource_mut::<ServerConnectionManager>()
+            .send_message::<Channel3, _>(client_id, Message2(i))
+            .unwrap();
+        stepper.frame_step();
+        stepper.frame_step();
+
+        let server_stream_events: Vec<_> = stepper
+            .server_app
+            .world
+            .resource_mut::<Events<server::MessageEvent<Message2>>>()
+            .drain()
+            .map(|event| (event.message().clone(), *event.context()))
+            .collect();
+        assert_eq!(server_stream_events, vec![(Message2(i), client_id)]);
+        let server_datagram_events: Vec<_> = stepper
+            .server_app
+            .world
+            .resource_mut::<Events<server::MessageEvent<Message1>>>()
+            .drain()
+            .map(|event| event.message().clone())
+            .collect();
+        assert_eq!(server_datagram_events, vec![Message1(i.to_string())]);
+        let client_stream_events: Vec<_> = stepper
+            .client_app
+            .world
+            .resource_mut::<Events<client::MessageEvent<Message2>>>()
+            .drain()
+            .map(|event| event.message().clone())
+            .collect();
+        assert_eq!(client_stream_events, vec![Message2(i)]);
+    }
+}
@@ -1,5 +1,5 @@
 use bevy::ecs::entity::MapEntities;
-use bevy::prelude::{default, Component, Entity, EntityMapper, Reflect};
+use bevy::prelude::{default, Component, Entity, EntityMapper, Reflect, Resource};
 use cfg_if::cfg_if;
 use derive_more::{Add, Mul};
 use std::ops::Mul;
@@ -16,10 +16,14 @@ pub struct Message1(pub String);
 #[derive(MessageInternal, Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Message2(pub u32);
 
+#[derive(Resource, MessageInternal, Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Resource1(pub f32);
+
 #[message_protocol_internal(protocol = "MyProtocol")]
 pub enum MyMessageProtocol {
     Message1(Message1),
     Message2(Message2),
+    ReplicateResource1(ReplicateResourceMessage<Resource1>),
 }
 
 // Components
@@ -49,6 +53,40 @@ impl LightyearMapEntities for Component4 {
     }
 }
 
+/// Component that is sent with a lower precision
+#[quantized_internal]
+#[derive(Component, MessageInternal, Serialize, Deserialize, Clone, Debug, PartialEq, Add, Mul)]
+pub struct Component5(#[quantize(min = -10.0, max = 10.0, precision = 0.01)] pub f32);
+
+impl Mul<f32> for &Component5 {
+    type Output = Component5;
+    fn mul(self, rhs: f32) -> Self::Output {
+        Component5(self.0 * rhs)
+    }
+}
+
+/// Component that is extrapolated when the client runs out of server updates
+#[derive(Component, MessageInternal, Serialize, Deserialize, Clone, Debug, PartialEq, Add, Mul)]
+pub struct Component6(pub f32);
+
+impl Mul<f32> for &Component6 {
+    type Output = Component6;
+    fn mul(self, rhs: f32) -> Self::Output {
+        Component6(self.0 * rhs)
+    }
+}
+
+/// Component that is extrapolated but not interpolated
+#[derive(Component, MessageInternal, Serialize, Deserialize, Clone, Debug, PartialEq, Add, Mul)]
+pub struct Component7(pub f32);
+
+impl Mul<f32> for &Component7 {
+    type Output = Component7;
+    fn mul(self, rhs: f32) -> Self::Output {
+        Component7(self.0 * rhs)
+    }
+}
+
 #[component_protocol_internal(protocol = "MyProtocol")]
 pub enum MyComponentsProtocol {
     #[sync(full)]
@@ -59,6 +97,12 @@ pub enum MyComponentsProtocol {
     Component3(Component3),
     #[sync(simple)]
     Component4(Component4),
+    #[sync(full, quantized)]
+    Component5(Component5),
+    #[sync(full, extrapolate = "LinearExtrapolator")]
+    Component6(Component6),
+    #[sync(full, lerp = "NullInterpolator", extrapolate = "LinearExtrapolator")]
+    Component7(Component7),
 }
 
 // Inputs
@@ -111,6 +155,9 @@ pub struct Channel1;
 #[derive(ChannelInternal)]
 pub struct Channel2;
 
+#[derive(ChannelInternal)]
+pub struct Channel3;
+
 pub fn protocol() -> MyProtocol {
     let mut p = MyProtocol::default();
     p.add_channel::<Channel1>(ChannelSettings {
@@ -121,5 +168,12 @@ pub fn protocol() -> MyProtocol {
         mode: ChannelMode::UnorderedUnreliableWithAcks,
         ..default()
     });
+    p.add_channel::<Channel3>(ChannelSettings {
+        mode: ChannelMode::OrderedReliable(ReliableSettings {
+            use_transport_stream: true,
+            ..default()
+        }),
+        ..default()
+    });
     p
 }
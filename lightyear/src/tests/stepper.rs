@@ -4,6 +4,7 @@ use std::str::FromStr;
 
 use crate::connection::client::{ClientConnection, NetClient};
 use bevy::ecs::system::SystemState;
+use bevy::app::PluginsState;
 use bevy::prelude::{App, Mut, PluginGroup, Real, Time, World};
 use bevy::time::TimeUpdateStrategy;
 use bevy::{DefaultPlugins, MinimalPlugins};
@@ -84,6 +85,7 @@ impl BevyStepper {
             net: net_config,
             ping: PingConfig::default(),
             packet: Default::default(),
+            replication: Default::default(),
         };
         let plugin_config = server::PluginConfig::new(config, protocol());
         let plugin = server::ServerPlugin::new(plugin_config);
@@ -111,6 +113,7 @@ impl BevyStepper {
             prediction: prediction_config,
             interpolation: interpolation_config,
             packet: Default::default(),
+            replication: Default::default(),
         };
         let plugin_config = client::PluginConfig::new(config, protocol());
         let plugin = client::ClientPlugin::new(plugin_config);
@@ -154,7 +157,20 @@ impl BevyStepper {
     pub(crate) fn server_tick(&self) -> Tick {
         self.server_app.world.resource::<TickManager>().tick()
     }
+    /// Finish building the plugins, like `App::run` would do.
+    ///
+    /// Called by [`BevyStepper::init`]; only needed for tests that step the apps before connecting.
+    pub(crate) fn build(&mut self) {
+        for app in [&mut self.client_app, &mut self.server_app] {
+            if app.plugins_state() != PluginsState::Cleaned {
+                app.finish();
+                app.cleanup();
+            }
+        }
+    }
+
     pub(crate) fn init(&mut self) {
+        self.build();
         self.client_app
             .world
             .resource_mut::<ClientConnection>()
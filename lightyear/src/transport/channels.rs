@@ -127,4 +127,9 @@ impl PacketSender for ChannelsSender {
             .try_send(payload.to_vec())
             .map_err(|_| std::io::Error::other("error sending packet"))
     }
+
+    // the channels are reliable and ordered, so they can also be used as the reliable stream
+    fn has_reliable_stream(&self, address: &SocketAddr) -> bool {
+        self.send.contains_key(address)
+    }
 }
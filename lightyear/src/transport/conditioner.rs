@@ -1,3 +1,5 @@
+use std::ops::AddAssign;
+
 use bevy::utils::Duration;
 /**
 Contains the [`LinkConditionerConfig`] struct which can be used to simulate network conditions
@@ -13,10 +15,10 @@ use crate::transport::PacketReceiver;
 use crate::utils::ready_buffer::ReadyBuffer;
 
 cfg_if! {
-    if #[cfg(any(test))] {
-        use mock_instant::Instant;
+    if #[cfg(any(test, feature = "mock_time"))] {
+        pub(crate) use mock_instant::Instant;
     } else {
-        use bevy::utils::Instant;
+        pub(crate) use bevy::utils::Instant;
     }
 }
 
@@ -54,18 +56,19 @@ impl<T: PacketReceiver, P: Eq> ConditionedPacketReceiver<T, P> {
 }
 
 // Condition a packet by potentially adding latency/jitter/loss to it
-fn condition_packet<P: Eq>(
+// `now` is the time at which the packet is received, according to the clock used by the `time_queue`
+pub(crate) fn condition_packet<I: Ord + AddAssign<Duration>, P: Eq>(
     config: &LinkConditionerConfig,
-    time_queue: &mut ReadyBuffer<Instant, P>,
+    time_queue: &mut ReadyBuffer<I, P>,
     packet: P,
+    now: I,
 ) {
     let mut rng = thread_rng();
     if rng.gen_range(0.0..1.0) <= config.incoming_loss {
         return;
     }
     let mut latency: i32 = config.incoming_latency.as_millis() as i32;
-    // TODO: how can i use the virtual time here?
-    let mut packet_timestamp = Instant::now();
+    let mut packet_timestamp = now;
     if config.incoming_jitter > Duration::default() {
         let jitter: i32 = config.incoming_jitter.as_millis() as i32;
         latency += rng.gen_range(-jitter..jitter);
@@ -84,10 +87,12 @@ impl<T: PacketReceiver> PacketReceiver for ConditionedPacketReceiver<T, (SocketA
                 Ok(option) => match option {
                     None => break,
                     // add conditioning (put the packets in the time queue)
+                    // TODO: how can i use the virtual time here?
                     Some((data, addr)) => condition_packet(
                         &self.config,
                         &mut self.time_queue,
                         (addr, data.to_vec().into_boxed_slice()),
+                        Instant::now(),
                     ),
                 },
                 Err(err) => {
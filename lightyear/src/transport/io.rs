@@ -17,6 +17,7 @@ use super::LOCAL_SOCKET;
 use crate::transport::channels::Channels;
 use crate::transport::conditioner::{ConditionedPacketReceiver, LinkConditionerConfig};
 use crate::transport::local::LocalChannel;
+use crate::transport::multiplexed::MultiplexedTransport;
 use crate::transport::{PacketReceiver, PacketSender, Transport};
 
 #[cfg(not(target_family = "wasm"))]
@@ -32,10 +33,14 @@ cfg_if::cfg_if! {
 #[cfg(feature = "webtransport")]
 use crate::transport::webtransport::client::WebTransportClientSocket;
 
+#[cfg(all(feature = "websocket", not(target_family = "wasm")))]
+use crate::transport::certificate::TlsCertificate;
 #[cfg(feature = "websocket")]
 use crate::transport::websocket::client::WebSocketClientSocket;
 #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
 use crate::transport::websocket::server::WebSocketServerSocket;
+#[cfg(feature = "websocket")]
+use crate::transport::websocket::WebSocketClientTls;
 
 #[derive(Clone)]
 pub enum TransportConfig {
@@ -55,9 +60,17 @@ pub enum TransportConfig {
         certificate: Certificate,
     },
     #[cfg(feature = "websocket")]
-    WebSocketClient { server_addr: SocketAddr },
+    WebSocketClient {
+        server_addr: SocketAddr,
+        /// If set, connect to the server with `wss://`
+        tls: Option<WebSocketClientTls>,
+    },
     #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
-    WebSocketServer { server_addr: SocketAddr },
+    WebSocketServer {
+        server_addr: SocketAddr,
+        /// If set, the server only accepts secure connections (`wss://`) using this certificate
+        certificate: Option<TlsCertificate>,
+    },
     Channels {
         channels: Vec<(SocketAddr, Receiver<Vec<u8>>, Sender<Vec<u8>>)>,
     },
@@ -65,6 +78,12 @@ pub enum TransportConfig {
         recv: Receiver<Vec<u8>>,
         send: Sender<Vec<u8>>,
     },
+    /// Listen on multiple transports at the same time (for example UDP for native clients and
+    /// WebTransport for browser clients). Packets to a remote are sent using the transport that the
+    /// remote is using.
+    ///
+    /// The local address is the local address of the first transport.
+    Multiplexed(Vec<TransportConfig>),
 }
 
 // TODO: derive Debug directly on TransportConfig once the new version of wtransport is out
@@ -75,17 +94,20 @@ impl std::fmt::Debug for TransportConfig {
 }
 
 impl TransportConfig {
-    pub fn get_io(self) -> Io {
+    /// Build the [`Io`] for this transport.
+    ///
+    /// Returns an error if the transport could not be created.
+    pub fn get_io(self) -> Result<Io> {
         // we don't use `dyn Transport` and instead repeat the code for `transport.listen()` because that function is not
         // object-safe (we would get "the size of `dyn Transport` cannot be statically determined")
         match self {
             #[cfg_attr(docsrs, doc(cfg(not(target_family = "wasm"))))]
             #[cfg(not(target_family = "wasm"))]
             TransportConfig::UdpSocket(addr) => {
-                let transport = UdpSocket::new(addr).unwrap();
+                let transport = UdpSocket::new(addr)?;
                 let addr = transport.local_addr();
                 let (sender, receiver) = transport.listen();
-                Io::new(addr, sender, receiver)
+                Ok(Io::new(addr, sender, receiver))
             }
             #[cfg_attr(
                 docsrs,
@@ -99,7 +121,7 @@ impl TransportConfig {
                 let transport = WebTransportClientSocket::new(client_addr, server_addr);
                 let addr = transport.local_addr();
                 let (sender, receiver) = transport.listen();
-                Io::new(addr, sender, receiver)
+                Ok(Io::new(addr, sender, receiver))
             }
             #[cfg_attr(
                 docsrs,
@@ -115,7 +137,7 @@ impl TransportConfig {
                     WebTransportClientSocket::new(client_addr, server_addr, certificate_digest);
                 let addr = transport.local_addr();
                 let (sender, receiver) = transport.listen();
-                Io::new(addr, sender, receiver)
+                Ok(Io::new(addr, sender, receiver))
             }
             #[cfg_attr(
                 docsrs,
@@ -129,26 +151,31 @@ impl TransportConfig {
                 let transport = WebTransportServerSocket::new(server_addr, certificate);
                 let addr = transport.local_addr();
                 let (sender, receiver) = transport.listen();
-                Io::new(addr, sender, receiver)
+                Ok(Io::new(addr, sender, receiver))
             }
             #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
             #[cfg(feature = "websocket")]
-            TransportConfig::WebSocketClient { server_addr } => {
-                let transport = WebSocketClientSocket::new(server_addr);
+            TransportConfig::WebSocketClient { server_addr, tls } => {
+                let transport = WebSocketClientSocket::new(server_addr, tls)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
                 let addr = transport.local_addr();
                 let (sender, receiver) = transport.listen();
-                Io::new(addr, sender, receiver)
+                Ok(Io::new(addr, sender, receiver))
             }
             #[cfg_attr(
                 docsrs,
                 doc(cfg(all(feature = "websocket", not(target_family = "wasm"))))
             )]
             #[cfg(all(feature = "websocket", not(target_family = "wasm")))]
-            TransportConfig::WebSocketServer { server_addr } => {
-                let transport = WebSocketServerSocket::new(server_addr);
+            TransportConfig::WebSocketServer {
+                server_addr,
+                certificate,
+            } => {
+                let transport = WebSocketServerSocket::new(server_addr, certificate)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
                 let addr = transport.local_addr();
                 let (sender, receiver) = transport.listen();
-                Io::new(addr, sender, receiver)
+                Ok(Io::new(addr, sender, receiver))
             }
             TransportConfig::Channels { channels } => {
                 let mut transport = Channels::new();
@@ -157,13 +184,36 @@ impl TransportConfig {
                 }
                 let addr = transport.local_addr();
                 let (sender, receiver) = transport.listen();
-                Io::new(addr, sender, receiver)
+                Ok(Io::new(addr, sender, receiver))
             }
             TransportConfig::LocalChannel { recv, send } => {
                 let transport = LocalChannel::new(recv, send);
                 let addr = transport.local_addr();
                 let (sender, receiver) = transport.listen();
-                Io::new(addr, sender, receiver)
+                Ok(Io::new(addr, sender, receiver))
+            }
+            TransportConfig::Multiplexed(configs) => {
+                if configs.is_empty() {
+                    return Err(std::io::Error::other(
+                        "a multiplexed transport needs at least one transport",
+                    ));
+                }
+                let ios = configs
+                    .into_iter()
+                    .map(|config| config.get_io())
+                    .collect::<Result<Vec<Io>>>()?;
+                let addr = ios[0].local_addr();
+                let transport = MultiplexedTransport::new(
+                    addr,
+                    ios.into_iter()
+                        .map(|io| {
+                            let (receiver, sender) = io.to_parts();
+                            (sender, receiver)
+                        })
+                        .collect(),
+                );
+                let (sender, receiver) = transport.listen();
+                Ok(Io::new(addr, sender, receiver))
             }
         }
     }
@@ -206,8 +256,8 @@ impl IoConfig {
         self
     }
 
-    pub fn get_io(self) -> Io {
-        let mut io = self.transport.get_io();
+    pub fn get_io(self) -> Result<Io> {
+        let mut io = self.transport.get_io()?;
         if let Some(conditioner) = self.conditioner {
             io = Io::new(
                 io.local_addr,
@@ -215,7 +265,7 @@ impl IoConfig {
                 Box::new(ConditionedPacketReceiver::new(io.receiver, conditioner)),
             );
         }
-        io
+        Ok(io)
     }
 }
 
@@ -242,7 +292,7 @@ pub struct IoStats {
 }
 
 impl Io {
-    pub fn from_config(config: IoConfig) -> Self {
+    pub fn from_config(config: IoConfig) -> Result<Self> {
         config.get_io()
     }
 
@@ -313,6 +363,29 @@ impl PacketSender for Io {
         self.stats.packets_sent += 1;
         self.sender.send(payload, address)
     }
+
+    fn send_reliable(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("transport.packets_sent").increment(1);
+            metrics::gauge!("transport.bytes_sent").increment(payload.len() as f64);
+        }
+        self.stats.bytes_sent += payload.len();
+        self.stats.packets_sent += 1;
+        self.sender.send_reliable(payload, address)
+    }
+
+    fn has_reliable_stream(&self, address: &SocketAddr) -> bool {
+        self.sender.has_reliable_stream(address)
+    }
+
+    fn connected(&mut self, address: &SocketAddr) {
+        self.sender.connected(address)
+    }
+
+    fn disconnected(&mut self, address: &SocketAddr) {
+        self.sender.disconnected(address)
+    }
 }
 
 pub struct IoDiagnosticsPlugin;
@@ -381,6 +454,22 @@ impl PacketSender for Box<dyn PacketSender + Send + Sync> {
     fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
         (**self).send(payload, address)
     }
+
+    fn send_reliable(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        (**self).send_reliable(payload, address)
+    }
+
+    fn has_reliable_stream(&self, address: &SocketAddr) -> bool {
+        (**self).has_reliable_stream(address)
+    }
+
+    fn connected(&mut self, address: &SocketAddr) {
+        (**self).connected(address)
+    }
+
+    fn disconnected(&mut self, address: &SocketAddr) {
+        (**self).disconnected(address)
+    }
 }
 
 impl PacketReceiver for Box<dyn PacketReceiver + Send + Sync> {
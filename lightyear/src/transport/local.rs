@@ -67,4 +67,9 @@ impl PacketSender for LocalChannelSender {
             .try_send(payload.to_vec())
             .map_err(|_| std::io::Error::other("error sending packet"))
     }
+
+    // the channel is reliable and ordered, so it can also be used as the reliable stream
+    fn has_reliable_stream(&self, _: &SocketAddr) -> bool {
+        true
+    }
 }
@@ -14,12 +14,20 @@ pub(crate) mod local;
 #[cfg(not(target_family = "wasm"))]
 pub(crate) mod udp;
 
-#[cfg(target_family = "wasm")]
-mod certificate;
+/// TLS certificates for the secure WebSocket transport
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "websocket", not(target_family = "wasm"))))
+)]
+#[cfg(all(feature = "websocket", not(target_family = "wasm")))]
+pub(crate) mod certificate;
 
 /// The transport is a map of channels (used for server, during testing)
 pub(crate) mod channels;
 
+/// The transport aggregates multiple transports
+pub(crate) mod multiplexed;
+
 /// The transport is using WebTransport
 #[cfg_attr(docsrs, doc(cfg(feature = "webtransport")))]
 #[cfg(feature = "webtransport")]
@@ -55,12 +63,47 @@ pub trait Transport {
 pub trait PacketSender: Send + Sync {
     /// Send data on the socket to the remote address
     fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()>;
+
+    /// Send data to the remote address on a reliable ordered stream.
+    ///
+    /// Should only be used if [`PacketSender::has_reliable_stream`] returns true for that address;
+    /// by default the data is sent like any other packet.
+    fn send_reliable(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        self.send(payload, address)
+    }
+
+    /// Returns true if the transport has a reliable ordered stream to the remote address
+    fn has_reliable_stream(&self, _address: &SocketAddr) -> bool {
+        false
+    }
+
+    /// Called when the connection with the remote address has been authenticated
+    fn connected(&mut self, _address: &SocketAddr) {}
+
+    /// Called when the connection with the remote address has been closed
+    fn disconnected(&mut self, _address: &SocketAddr) {}
 }
 
 impl PacketSender for Box<dyn PacketSender> {
     fn send(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
         (**self).send(payload, address)
     }
+
+    fn send_reliable(&mut self, payload: &[u8], address: &SocketAddr) -> Result<()> {
+        (**self).send_reliable(payload, address)
+    }
+
+    fn has_reliable_stream(&self, address: &SocketAddr) -> bool {
+        (**self).has_reliable_stream(address)
+    }
+
+    fn connected(&mut self, address: &SocketAddr) {
+        (**self).connected(address)
+    }
+
+    fn disconnected(&mut self, address: &SocketAddr) {
+        (**self).disconnected(address)
+    }
 }
 
 /// Receive data from a remote address
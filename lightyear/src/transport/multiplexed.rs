@@ -0,0 +1,269 @@
+//! Aggregate multiple transports behind a single [`Io`](crate::transport::io::Io),
+//! so that a server can accept clients using different transports (UDP, WebTransport, WebSocket) at the same time.
+//!
+//! Outgoing packets are sent through the transport from which the remote address last sent a packet.
+//! Once the connection with the remote address is authenticated, its transport is pinned until the
+//! connection is closed, so that packets from the same address on another transport cannot hijack the route.
+//!
+//! NOTE: remotes are identified by their [`SocketAddr`] only, so two clients using different
+//! transports but the same address (for example the same port with TCP and UDP) cannot be distinguished.
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+use bevy::utils::HashMap;
+use tracing::error;
+
+use crate::transport::{PacketReceiver, PacketSender, Transport};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Route {
+    /// Index of the transport used by the remote address
+    index: usize,
+    /// The connection with the remote address is authenticated, the route cannot change anymore
+    pinned: bool,
+}
+
+/// Maps each remote address to the transport it is using
+type RouteMap = Arc<RwLock<HashMap<SocketAddr, Route>>>;
+
+pub(crate) struct MultiplexedTransport {
+    local_addr: SocketAddr,
+    senders: Vec<Box<dyn PacketSender>>,
+    receivers: Vec<Box<dyn PacketReceiver>>,
+}
+
+impl MultiplexedTransport {
+    /// Create a transport from the listening halves of the inner transports.
+    ///
+    /// The local address is the local address of the first transport.
+    pub(crate) fn new(
+        local_addr: SocketAddr,
+        transports: Vec<(Box<dyn PacketSender>, Box<dyn PacketReceiver>)>,
+    ) -> Self {
+        let (senders, receivers) = transports.into_iter().unzip();
+        Self {
+            local_addr,
+            senders,
+            receivers,
+        }
+    }
+}
+
+impl Transport for MultiplexedTransport {
+    fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn listen(self) -> (Box<dyn PacketSender>, Box<dyn PacketReceiver>) {
+        let routes = RouteMap::default();
+        let sender = MultiplexedSender {
+            senders: self.senders,
+            routes: routes.clone(),
+        };
+        let receiver = MultiplexedReceiver {
+            receivers: self.receivers,
+            next: 0,
+            routes,
+        };
+        (Box::new(sender), Box::new(receiver))
+    }
+}
+
+struct MultiplexedSender {
+    senders: Vec<Box<dyn PacketSender>>,
+    routes: RouteMap,
+}
+
+impl MultiplexedSender {
+    fn route(&mut self, address: &SocketAddr) -> std::io::Result<&mut Box<dyn PacketSender>> {
+        let route = self.routes.read().unwrap().get(address).copied();
+        route
+            .and_then(|route| self.senders.get_mut(route.index))
+            .ok_or_else(|| {
+                std::io::Error::other(format!(
+                    "no transport has received packets from {}",
+                    address
+                ))
+            })
+    }
+}
+
+impl PacketSender for MultiplexedSender {
+    fn send(&mut self, payload: &[u8], address: &SocketAddr) -> std::io::Result<()> {
+        self.route(address)?.send(payload, address)
+    }
+
+    fn send_reliable(&mut self, payload: &[u8], address: &SocketAddr) -> std::io::Result<()> {
+        self.route(address)?.send_reliable(payload, address)
+    }
+
+    fn has_reliable_stream(&self, address: &SocketAddr) -> bool {
+        let route = self.routes.read().unwrap().get(address).copied();
+        route
+            .and_then(|route| self.senders.get(route.index))
+            .is_some_and(|sender| sender.has_reliable_stream(address))
+    }
+
+    fn connected(&mut self, address: &SocketAddr) {
+        let index = {
+            let mut routes = self.routes.write().unwrap();
+            let Some(route) = routes.get_mut(address) else {
+                return;
+            };
+            route.pinned = true;
+            route.index
+        };
+        if let Some(sender) = self.senders.get_mut(index) {
+            sender.connected(address);
+        }
+    }
+
+    fn disconnected(&mut self, address: &SocketAddr) {
+        let Some(route) = self.routes.write().unwrap().remove(address) else {
+            return;
+        };
+        if let Some(sender) = self.senders.get_mut(route.index) {
+            sender.disconnected(address);
+        }
+    }
+}
+
+struct MultiplexedReceiver {
+    receivers: Vec<Box<dyn PacketReceiver>>,
+    /// Index of the transport that is polled first, so that a busy transport cannot starve the others
+    next: usize,
+    routes: RouteMap,
+}
+
+impl PacketReceiver for MultiplexedReceiver {
+    fn recv(&mut self) -> std::io::Result<Option<(&mut [u8], SocketAddr)>> {
+        let num_receivers = self.receivers.len();
+        if num_receivers == 0 {
+            return Ok(None);
+        }
+        let start = self.next % num_receivers;
+        self.next = start + 1;
+        let (before, after) = self.receivers.split_at_mut(start);
+        let receivers = after
+            .iter_mut()
+            .enumerate()
+            .map(|(i, receiver)| (start + i, receiver))
+            .chain(before.iter_mut().enumerate());
+        for (index, receiver) in receivers {
+            // an error on one transport should not prevent receiving packets from the others
+            let (buffer, address) = match receiver.recv() {
+                Ok(Some(packet)) => packet,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("error receiving packets from transport {index}: {e}");
+                    continue;
+                }
+            };
+            let route = self.routes.read().unwrap().get(&address).copied();
+            // until the connection is authenticated, answer through the transport that was used last
+            if route.map_or(true, |route| !route.pinned && route.index != index) {
+                self.routes.write().unwrap().insert(
+                    address,
+                    Route {
+                        index,
+                        pinned: false,
+                    },
+                );
+            }
+            return Ok(Some((buffer, address)));
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::io::TransportConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_multiplexed_transport() -> anyhow::Result<()> {
+        let addr_a: SocketAddr = "127.0.0.1:1000".parse()?;
+        let addr_b: SocketAddr = "127.0.0.1:2000".parse()?;
+        let (remote_a_send, recv_a) = crossbeam_channel::unbounded();
+        let (send_a, remote_a_recv) = crossbeam_channel::unbounded();
+        let (remote_b_send, recv_b) = crossbeam_channel::unbounded();
+        let (send_b, remote_b_recv) = crossbeam_channel::unbounded();
+        let mut io = TransportConfig::Multiplexed(vec![
+            TransportConfig::Channels {
+                channels: vec![(addr_a, recv_a, send_a)],
+            },
+            TransportConfig::Channels {
+                channels: vec![(addr_b, recv_b, send_b)],
+            },
+        ])
+        .get_io()?;
+
+        // we don't know the remotes yet
+        assert!(io.send(b"hello", &addr_a).is_err());
+
+        remote_a_send.send(b"from a".to_vec())?;
+        remote_b_send.send(b"from b".to_vec())?;
+        let mut received = vec![];
+        while let Some((buffer, address)) = io.recv()? {
+            received.push((buffer.to_vec(), address));
+        }
+        received.sort();
+        assert_eq!(
+            received,
+            vec![(b"from a".to_vec(), addr_a), (b"from b".to_vec(), addr_b)]
+        );
+
+        // each packet is sent through the transport of the remote
+        io.send(b"to a", &addr_a)?;
+        io.send(b"to b", &addr_b)?;
+        assert_eq!(remote_a_recv.try_recv()?, b"to a".to_vec());
+        assert_eq!(remote_b_recv.try_recv()?, b"to b".to_vec());
+        assert!(remote_a_recv.try_recv().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiplexed_transport_pinned_route() -> anyhow::Result<()> {
+        let addr: SocketAddr = "127.0.0.1:1000".parse()?;
+        let (remote_a_send, recv_a) = crossbeam_channel::unbounded();
+        let (send_a, remote_a_recv) = crossbeam_channel::unbounded();
+        let (remote_b_send, recv_b) = crossbeam_channel::unbounded();
+        let (send_b, remote_b_recv) = crossbeam_channel::unbounded();
+        let mut io = TransportConfig::Multiplexed(vec![
+            TransportConfig::Channels {
+                channels: vec![(addr, recv_a, send_a)],
+            },
+            TransportConfig::Channels {
+                channels: vec![(addr, recv_b, send_b)],
+            },
+        ])
+        .get_io()?;
+
+        remote_a_send.send(b"from a".to_vec())?;
+        while io.recv()?.is_some() {}
+        io.connected(&addr);
+
+        // a packet from the same address on another transport does not change the route
+        // of the authenticated connection
+        remote_b_send.send(b"from b".to_vec())?;
+        while io.recv()?.is_some() {}
+        io.send(b"to a", &addr)?;
+        assert_eq!(remote_a_recv.try_recv()?, b"to a".to_vec());
+        assert!(remote_b_recv.try_recv().is_err());
+
+        // once the connection is closed, the address can use another transport
+        io.disconnected(&addr);
+        remote_b_send.send(b"from b".to_vec())?;
+        while io.recv()?.is_some() {}
+        io.send(b"to b", &addr)?;
+        assert_eq!(remote_b_recv.try_recv()?, b"to b".to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiplexed_transport_empty() {
+        assert!(TransportConfig::Multiplexed(vec![]).get_io().is_err());
+    }
+}
@@ -27,35 +27,39 @@ use futures_util::{
 };
 
 use tokio_tungstenite::{
-    connect_async, connect_async_with_config, tungstenite::Message, MaybeTlsStream,
+    connect_async, connect_async_tls_with_config, connect_async_with_config, tungstenite::Message,
+    Connector, MaybeTlsStream,
 };
 use tracing::{debug, info, trace};
 use tracing_log::log::error;
 
+use crate::transport::certificate::client_config;
 use crate::transport::{PacketReceiver, PacketSender, Transport, LOCAL_SOCKET};
 
-use super::MTU;
+use super::{WebSocketClientTls, MTU};
 
 pub struct WebSocketClientSocket {
     server_addr: SocketAddr,
+    url: String,
+    tls_connector: Option<Connector>,
 }
 
 impl WebSocketClientSocket {
-    pub(crate) fn new(server_addr: SocketAddr) -> Self {
-        Self { server_addr }
+    /// Returns an error if the trusted certificates are invalid
+    pub(crate) fn new(server_addr: SocketAddr, tls: Option<WebSocketClientTls>) -> Result<Self> {
+        let tls_connector = tls
+            .as_ref()
+            .map(|tls| {
+                client_config(&tls.trusted_certificates)
+                    .map(|config| Connector::Rustls(Arc::new(config)))
+            })
+            .transpose()?;
+        Ok(Self {
+            server_addr,
+            url: WebSocketClientTls::url(tls.as_ref(), server_addr),
+            tls_connector,
+        })
     }
-
-    /*fn get_tls_connector(&self) -> TlsConnector {
-        let root_store = RootCertStore {
-            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
-        };
-
-        let config = ClientConfig::builder()
-            .with_root_certificates(root_store)
-            .with_no_client_auth();
-
-        TlsConnector::from(Arc::new(config))
-    }*/
 }
 
 impl Transport for WebSocketClientSocket {
@@ -82,13 +86,15 @@ impl Transport for WebSocketClientSocket {
             clientbound_rx,
         };
 
+        let url = self.url;
+        let tls_connector = self.tls_connector;
+
         IoTaskPool::get()
             .spawn(Compat::new(async move {
                 info!("Starting client websocket task");
-                let (ws_stream, _) =
-                    connect_async_with_config(format!("ws://{}/", self.server_addr), None, true)
-                        .await
-                        .expect("Unable to connect to websocket server");
+                let (ws_stream, _) = connect_async_tls_with_config(url, None, true, tls_connector)
+                    .await
+                    .expect("Unable to connect to websocket server");
                 info!("WebSocket handshake has been successfully completed");
 
                 let (mut write, mut read) = ws_stream.split();
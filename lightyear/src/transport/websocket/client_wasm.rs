@@ -23,15 +23,17 @@ use web_sys::{
 
 use crate::transport::{PacketReceiver, PacketSender, Transport, LOCAL_SOCKET};
 
-use super::MTU;
+use super::{WebSocketClientTls, MTU};
 
 pub struct WebSocketClientSocket {
     server_addr: SocketAddr,
+    tls: Option<WebSocketClientTls>,
 }
 
 impl WebSocketClientSocket {
-    pub(crate) fn new(server_addr: SocketAddr) -> Self {
-        Self { server_addr }
+    /// The browser handles the TLS configuration, so this never fails
+    pub(crate) fn new(server_addr: SocketAddr, tls: Option<WebSocketClientTls>) -> Result<Self> {
+        Ok(Self { server_addr, tls })
     }
 }
 
@@ -54,7 +56,12 @@ impl Transport for WebSocketClientSocket {
 
         info!("Starting client websocket task");
 
-        let ws = WebSocket::new(&format!("ws://{}/", self.server_addr)).unwrap();
+        // the browser decides which certificates are trusted
+        let ws = WebSocket::new(&WebSocketClientTls::url(
+            self.tls.as_ref(),
+            self.server_addr,
+        ))
+        .unwrap();
 
         ws.set_binary_type(BinaryType::Arraybuffer);
 
@@ -12,10 +12,54 @@ cfg_if::cfg_if! {
 
 const MTU: usize = 1472;
 
+/// TLS settings of a WebSocket client: the client connects to the server with `wss://` instead of `ws://`
+#[derive(Clone, Debug, Default)]
+pub struct WebSocketClientTls {
+    /// Domain name used to connect to the server and to verify its certificate.
+    /// If `None`, the ip address of the server is used.
+    pub server_name: Option<String>,
+    /// DER-encoded certificates to trust in addition to the web PKI roots,
+    /// for example a self-signed development certificate or a custom CA.
+    ///
+    /// The web PKI roots are only trusted with the `websocket-tls` feature: without it, the client
+    /// only trusts these certificates.
+    ///
+    /// Ignored in the browser, which uses its own certificate store.
+    pub trusted_certificates: Vec<Vec<u8>>,
+}
+
+impl WebSocketClientTls {
+    /// Trust the DER-encoded `certificate`
+    pub fn with_trusted_certificate(mut self, certificate: Vec<u8>) -> Self {
+        self.trusted_certificates.push(certificate);
+        self
+    }
+
+    /// Connect to the server using the domain name `server_name`
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    /// Url of the WebSocket server
+    pub(crate) fn url(tls: Option<&Self>, server_addr: std::net::SocketAddr) -> String {
+        match tls {
+            None => format!("ws://{}/", server_addr),
+            Some(Self {
+                server_name: Some(server_name),
+                ..
+            }) => format!("wss://{}:{}/", server_name, server_addr.port()),
+            Some(_) => format!("wss://{}/", server_addr),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::client::*;
     use super::server::*;
+    use super::WebSocketClientTls;
+    use crate::transport::certificate::TlsCertificate;
     use crate::transport::{PacketReceiver, PacketSender, Transport};
     use bevy::tasks::{IoTaskPool, TaskPoolBuilder};
     use bevy::utils::Duration;
@@ -26,8 +70,8 @@ mod tests {
     async fn test_websocket_native() -> anyhow::Result<()> {
         let server_addr = "127.0.0.1:7000".parse().unwrap();
 
-        let client_socket = WebSocketClientSocket::new(server_addr);
-        let server_socket = WebSocketServerSocket::new(server_addr);
+        let client_socket = WebSocketClientSocket::new(server_addr, None)?;
+        let server_socket = WebSocketServerSocket::new(server_addr, None)?;
 
         let (mut server_send, mut server_recv) = server_socket.listen();
         let (mut client_send, mut client_recv) = client_socket.listen();
@@ -60,4 +104,43 @@ mod tests {
         dbg!(recv_msg);
         Ok(())
     }
+
+    #[cfg(all(feature = "websocket-tls", not(target_family = "wasm")))]
+    #[tokio::test]
+    async fn test_websocket_native_tls() -> anyhow::Result<()> {
+        let server_addr = "127.0.0.1:7001".parse().unwrap();
+        let certificate = TlsCertificate::self_signed(vec!["127.0.0.1".to_string()])?;
+        let tls =
+            WebSocketClientTls::default().with_trusted_certificate(certificate.certificate_der());
+
+        let client_socket = WebSocketClientSocket::new(server_addr, Some(tls))?;
+        let server_socket = WebSocketServerSocket::new(server_addr, Some(certificate))?;
+
+        let (mut server_send, mut server_recv) = server_socket.listen();
+        let (mut client_send, mut client_recv) = client_socket.listen();
+
+        let msg = b"hello world";
+
+        // client to server
+        client_send.send(msg, &server_addr)?;
+
+        // the TLS handshake takes a bit longer
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let Some((recv_msg, address)) = server_recv.recv()? else {
+            panic!("server expected to receive a packet from client");
+        };
+        assert_eq!(recv_msg, msg);
+
+        // server to client
+        server_send.send(msg, &address)?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let Some((recv_msg, address)) = client_recv.recv()? else {
+            panic!("client expected to receive a packet from server");
+        };
+        assert_eq!(address, server_addr);
+        assert_eq!(recv_msg, msg);
+        Ok(())
+    }
 }
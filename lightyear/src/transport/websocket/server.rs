@@ -17,43 +17,42 @@ use futures_util::{
     SinkExt, StreamExt, TryFutureExt,
 };
 use tokio::{
+    io::{AsyncRead, AsyncWrite},
     net::{TcpListener, TcpStream},
     sync::mpsc::{error::TryRecvError, unbounded_channel, UnboundedReceiver, UnboundedSender},
 };
+use tokio_rustls::TlsAcceptor;
 use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
 
+use crate::transport::certificate::TlsCertificate;
 use crate::transport::{PacketReceiver, PacketSender, Transport};
 
 use super::MTU;
 
 pub struct WebSocketServerSocket {
     server_addr: SocketAddr,
+    /// If set, the server only accepts secure WebSocket connections (`wss://`)
+    tls_acceptor: Option<TlsAcceptor>,
 }
 
 impl WebSocketServerSocket {
-    pub(crate) fn new(server_addr: SocketAddr) -> Self {
-        Self { server_addr }
+    /// Returns an error if the certificate cannot be used by the TLS server
+    pub(crate) fn new(
+        server_addr: SocketAddr,
+        certificate: Option<TlsCertificate>,
+    ) -> Result<Self> {
+        let tls_acceptor = certificate
+            .map(|certificate| {
+                certificate
+                    .server_config()
+                    .map(|config| TlsAcceptor::from(Arc::new(config)))
+            })
+            .transpose()?;
+        Ok(Self {
+            server_addr,
+            tls_acceptor,
+        })
     }
-
-    /*fn get_tls_acceptor(&self) -> Option<TlsAcceptor> {
-        if let Some(config) = &self.tls_config {
-            let server_config = ServerConfig::builder()
-                .with_no_client_auth()
-                .with_single_cert(
-                    certs(&mut BufReader::new(&*config.certs))
-                        .map(|e| e.unwrap())
-                        .collect(),
-                    rsa_private_keys(&mut BufReader::new(&*config.keys))
-                        .map(|e| e.unwrap().into())
-                        .next()
-                        .unwrap(),
-                )
-                .unwrap();
-            Some(TlsAcceptor::from(Arc::new(server_config)))
-        } else {
-            None
-        }
-    }*/
 }
 
 type ClientBoundTxMap = Arc<Mutex<HashMap<SocketAddr, UnboundedSender<Message>>>>;
@@ -79,6 +78,8 @@ impl Transport for WebSocketServerSocket {
             serverbound_rx,
         };
 
+        let tls_acceptor = self.tls_acceptor;
+
         IoTaskPool::get()
             .spawn(Compat::new(async move {
                 info!("Starting server websocket task");
@@ -86,67 +87,34 @@ impl Transport for WebSocketServerSocket {
                 while let Ok((stream, addr)) = listener.accept().await {
                     let clientbound_tx_map = clientbound_tx_map.clone();
                     let serverbound_tx = serverbound_tx.clone();
+                    let tls_acceptor = tls_acceptor.clone();
                     IoTaskPool::get()
                         .spawn(Compat::new(async move {
-                            let ws_stream = tokio_tungstenite::accept_async(stream)
-                                .await
-                                .expect("Error during the websocket handshake occurred");
-
-                            info!("New WebSocket connection: {}", addr);
-
-                            let (clientbound_tx, mut clientbound_rx) =
-                                unbounded_channel::<Message>();
-                            let (mut write, mut read) = ws_stream.split();
-
-                            clientbound_tx_map
-                                .lock()
-                                .unwrap()
-                                .insert(addr, clientbound_tx);
-
-                            let serverbound_tx = serverbound_tx.clone();
-
-                            let (close_tx, mut close_rx) = tokio::sync::mpsc::channel(1);
-                            let close_tx_clone = close_tx.clone();
-
-                            let clientbound_handle = IoTaskPool::get().spawn(async move {
-                                while let Some(msg) = clientbound_rx.recv().await {
-                                    write
-                                        .send(msg)
+                            match tls_acceptor {
+                                Some(tls_acceptor) => match tls_acceptor.accept(stream).await {
+                                    Ok(stream) => {
+                                        handle_connection(
+                                            stream,
+                                            addr,
+                                            clientbound_tx_map,
+                                            serverbound_tx,
+                                        )
                                         .await
-                                        .map_err(|e| {
-                                            error!(
-                                                "Encountered error while sending websocket msg: {}",
-                                                e
-                                            );
-                                        })
-                                        .unwrap();
-                                }
-                                write.close().await.unwrap_or_else(|e| {
-                                    error!("Error closing websocket: {:?}", e);
-                                });
-                                let _ = close_tx_clone.send(());
-                            });
-                            let serverbound_handle = IoTaskPool::get().spawn(async move {
-                                while let Some(msg) = read.next().await {
-                                    match msg {
-                                        Ok(msg) => {
-                                            serverbound_tx.send((addr, msg)).unwrap_or_else(|e| {
-                                                error!("receive websocket error: {:?}", e)
-                                            });
-                                        }
-                                        Err(e) => {
-                                            error!("receive websocket error: {:?}", e);
-                                        }
                                     }
+                                    Err(e) => {
+                                        error!("TLS handshake with {} failed: {:?}", addr, e)
+                                    }
+                                },
+                                None => {
+                                    handle_connection(
+                                        stream,
+                                        addr,
+                                        clientbound_tx_map,
+                                        serverbound_tx,
+                                    )
+                                    .await
                                 }
-                                // TODO: how to cancel the clientbound_handle?
-                                let _ = close_tx.clone().send(());
-                            });
-                            // wait until the websocket is done
-                            let _ = close_rx.recv().await;
-                            info!("Connection with {} closed", addr);
-                            clientbound_tx_map.lock().unwrap().remove(&addr);
-                            // dropping the task handles cancels them
+                            }
                         }))
                         .detach();
                 }
@@ -157,6 +125,75 @@ impl Transport for WebSocketServerSocket {
     }
 }
 
+/// Run the websocket connection with a client, over a plain TCP stream or a TLS stream
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
+    stream: S,
+    addr: SocketAddr,
+    clientbound_tx_map: ClientBoundTxMap,
+    serverbound_tx: UnboundedSender<(SocketAddr, Message)>,
+) {
+    let ws_stream = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws_stream) => ws_stream,
+        Err(e) => {
+            error!(
+                "Error during the websocket handshake with {}: {:?}",
+                addr, e
+            );
+            return;
+        }
+    };
+
+    info!("New WebSocket connection: {}", addr);
+
+    let (clientbound_tx, mut clientbound_rx) = unbounded_channel::<Message>();
+    let (mut write, mut read) = ws_stream.split();
+
+    clientbound_tx_map
+        .lock()
+        .unwrap()
+        .insert(addr, clientbound_tx);
+
+    let (close_tx, mut close_rx) = tokio::sync::mpsc::channel(1);
+    let close_tx_clone = close_tx.clone();
+
+    let clientbound_handle = IoTaskPool::get().spawn(async move {
+        while let Some(msg) = clientbound_rx.recv().await {
+            write
+                .send(msg)
+                .await
+                .map_err(|e| {
+                    error!("Encountered error while sending websocket msg: {}", e);
+                })
+                .unwrap();
+        }
+        write.close().await.unwrap_or_else(|e| {
+            error!("Error closing websocket: {:?}", e);
+        });
+        let _ = close_tx_clone.send(());
+    });
+    let serverbound_handle = IoTaskPool::get().spawn(async move {
+        while let Some(msg) = read.next().await {
+            match msg {
+                Ok(msg) => {
+                    serverbound_tx
+                        .send((addr, msg))
+                        .unwrap_or_else(|e| error!("receive websocket error: {:?}", e));
+                }
+                Err(e) => {
+                    error!("receive websocket error: {:?}", e);
+                }
+            }
+        }
+        // TODO: how to cancel the clientbound_handle?
+        let _ = close_tx.clone().send(());
+    });
+    // wait until the websocket is done
+    let _ = close_rx.recv().await;
+    info!("Connection with {} closed", addr);
+    clientbound_tx_map.lock().unwrap().remove(&addr);
+    // dropping the task handles cancels them
+}
+
 struct WebSocketServerSocketSender {
     server_addr: SocketAddr,
     addr_to_clientbound_tx: ClientBoundTxMap,
@@ -1,10 +1,11 @@
 #![cfg(not(target_family = "wasm"))]
 //! WebTransport client implementation.
-use super::MTU;
+use super::{stream, MTU};
 use crate::transport::{PacketReceiver, PacketSender, Transport};
 use async_compat::Compat;
 use bevy::tasks::{IoTaskPool, TaskPool};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::error::TryRecvError;
@@ -41,6 +42,11 @@ impl Transport for WebTransportClientSocket {
         let server_addr = self.server_addr;
         let (to_server_sender, mut to_server_receiver) = mpsc::unbounded_channel();
         let (from_server_sender, from_server_receiver) = mpsc::unbounded_channel();
+        let (to_server_stream_sender, mut to_server_stream_receiver) =
+            mpsc::unbounded_channel::<Box<[u8]>>();
+        let (from_server_stream_sender, from_server_stream_receiver) = mpsc::unbounded_channel();
+        let stream_open = Arc::new(AtomicBool::new(false));
+        let stream_open_clone = stream_open.clone();
 
         let config = ClientConfig::builder()
             .with_bind_address(client_addr)
@@ -102,10 +108,67 @@ impl Transport for WebTransportClientSocket {
                         }
                     }
                 });
+                // open the bidirectional stream used for the reliable packets
+                let connection_stream = connection.clone();
+                let stream_handle = IoTaskPool::get().spawn(Compat::new(async move {
+                    let (mut send_stream, mut recv_stream) = match connection_stream.open_bi().await
+                    {
+                        Ok(opening) => match opening.await {
+                            Ok(streams) => streams,
+                            Err(e) => {
+                                error!("could not open stream: {:?}", e);
+                                return;
+                            }
+                        },
+                        Err(e) => {
+                            error!("could not open stream: {:?}", e);
+                            return;
+                        }
+                    };
+                    // the server can only accept the stream once some data has been written on it
+                    if let Err(e) = stream::write_frame(&mut send_stream, &[]).await {
+                        error!("could not open stream: {:?}", e);
+                        return;
+                    }
+                    stream_open_clone.store(true, Ordering::Relaxed);
+                    let send_stream_open = stream_open_clone.clone();
+                    let send_stream_handle = IoTaskPool::get().spawn(Compat::new(async move {
+                        while let Some(msg) = to_server_stream_receiver.recv().await {
+                            trace!("send stream packet to server: {:?}", &msg);
+                            if let Err(e) =
+                                stream::write_frame(&mut send_stream, msg.as_ref()).await
+                            {
+                                error!("send stream error: {:?}", e);
+                                break;
+                            }
+                        }
+                        send_stream_open.store(false, Ordering::Relaxed);
+                    }));
+                    loop {
+                        match stream::read_frame(&mut recv_stream).await {
+                            Ok(data) if data.is_empty() => {}
+                            Ok(data) => {
+                                trace!("receive stream packet from server: {:?}", &data);
+                                if let Err(e) = from_server_stream_sender.send(data) {
+                                    error!("could not forward stream packet from server: {:?}", e);
+                                    break;
+                                }
+                            }
+                            Err(e) => {
+                                error!("receive stream error: {:?}", e);
+                                break;
+                            }
+                        }
+                    }
+                    // drop the stream: the reliable packets are sent as datagrams from now on
+                    stream_open_clone.store(false, Ordering::Relaxed);
+                    send_stream_handle.cancel().await;
+                }));
                 connection.closed().await;
                 info!("WebTransport connection closed.");
                 recv_handle.cancel().await;
                 send_handle.cancel().await;
+                stream_handle.cancel().await;
                 // tokio
                 // recv_handle.abort();
                 // send_handle.abort();
@@ -113,10 +176,15 @@ impl Transport for WebTransportClientSocket {
             .detach();
         // TODO: maybe wait for the connection to be ready before returning here?
 
-        let packet_sender = WebTransportClientPacketSender { to_server_sender };
+        let packet_sender = WebTransportClientPacketSender {
+            to_server_sender,
+            to_server_stream_sender,
+            stream_open,
+        };
         let packet_receiver = WebTransportClientPacketReceiver {
             server_addr,
             from_server_receiver,
+            from_server_stream_receiver,
             buffer: [0; MTU],
         };
         (Box::new(packet_sender), Box::new(packet_receiver))
@@ -125,6 +193,9 @@ impl Transport for WebTransportClientSocket {
 
 struct WebTransportClientPacketSender {
     to_server_sender: mpsc::UnboundedSender<Box<[u8]>>,
+    to_server_stream_sender: mpsc::UnboundedSender<Box<[u8]>>,
+    /// True once the reliable stream to the server is open
+    stream_open: Arc<AtomicBool>,
 }
 
 impl PacketSender for WebTransportClientPacketSender {
@@ -134,16 +205,35 @@ impl PacketSender for WebTransportClientPacketSender {
             .send(data)
             .map_err(|e| std::io::Error::other(format!("send_datagram error: {:?}", e)))
     }
+
+    fn send_reliable(&mut self, payload: &[u8], address: &SocketAddr) -> std::io::Result<()> {
+        if !self.stream_open.load(Ordering::Relaxed) {
+            return self.send(payload, address);
+        }
+        let data = payload.to_vec().into_boxed_slice();
+        self.to_server_stream_sender
+            .send(data)
+            .map_err(|e| std::io::Error::other(format!("send stream error: {:?}", e)))
+    }
+
+    fn has_reliable_stream(&self, _address: &SocketAddr) -> bool {
+        self.stream_open.load(Ordering::Relaxed)
+    }
 }
 
 struct WebTransportClientPacketReceiver {
     server_addr: SocketAddr,
     from_server_receiver: mpsc::UnboundedReceiver<Datagram>,
+    from_server_stream_receiver: mpsc::UnboundedReceiver<Vec<u8>>,
     buffer: [u8; MTU],
 }
 
 impl PacketReceiver for WebTransportClientPacketReceiver {
     fn recv(&mut self) -> std::io::Result<Option<(&mut [u8], SocketAddr)>> {
+        if let Ok(data) = self.from_server_stream_receiver.try_recv() {
+            self.buffer[..data.len()].copy_from_slice(&data);
+            return Ok(Some((&mut self.buffer[..data.len()], self.server_addr)));
+        }
         match self.from_server_receiver.try_recv() {
             Ok(data) => {
                 // convert from datagram to payload via xwt
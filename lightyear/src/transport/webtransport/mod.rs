@@ -14,10 +14,53 @@ cfg_if::cfg_if! {
 // See: https://gafferongames.com/post/packet_fragmentation_and_reassembly/
 const MTU: usize = 1472;
 
+/// Helpers to send packets on the reliable bidirectional stream opened by the client.
+///
+/// A stream has no message boundaries, so each packet is prefixed with its length.
+/// Empty frames are ignored; the client writes one when it opens the stream so that the server can accept it.
+///
+/// NOTE: only the native client opens a stream. The wasm client does not support streams yet, so the packets of
+/// channels with `use_transport_stream` are sent as datagrams to and from wasm clients.
+#[cfg(all(feature = "webtransport", not(target_family = "wasm")))]
+pub(crate) mod stream {
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    use super::MTU;
+
+    pub(crate) async fn write_frame(
+        stream: &mut (impl AsyncWrite + Unpin),
+        payload: &[u8],
+    ) -> anyhow::Result<()> {
+        let len = u16::try_from(payload.len())?;
+        AsyncWriteExt::write_all(stream, &len.to_be_bytes()).await?;
+        AsyncWriteExt::write_all(stream, payload).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn read_frame(
+        stream: &mut (impl AsyncRead + Unpin),
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut len = [0u8; 2];
+        AsyncReadExt::read_exact(stream, &mut len).await?;
+        let len = u16::from_be_bytes(len) as usize;
+        // the packets are copied into a buffer of size MTU, a bigger frame can only come from a misbehaving peer
+        if len > MTU {
+            anyhow::bail!(
+                "received a stream frame of {} bytes, larger than the MTU",
+                len
+            );
+        }
+        let mut payload = vec![0; len];
+        AsyncReadExt::read_exact(stream, &mut payload).await?;
+        Ok(payload)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::client::*;
     use super::server::*;
+    use super::{stream, MTU};
     use crate::transport::{PacketReceiver, PacketSender, Transport};
     use bevy::tasks::{IoTaskPool, TaskPoolBuilder};
     use bevy::utils::Duration;
@@ -69,6 +112,24 @@ mod tests {
         dbg!(recv_msg);
         Ok(())
     }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[tokio::test]
+    async fn test_stream_frames() -> anyhow::Result<()> {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        // the frames keep their boundaries even though the stream doesn't
+        stream::write_frame(&mut client, b"hello").await?;
+        stream::write_frame(&mut client, &[]).await?;
+        stream::write_frame(&mut client, &[7; 100]).await?;
+        assert_eq!(stream::read_frame(&mut server).await?, b"hello");
+        assert!(stream::read_frame(&mut server).await?.is_empty());
+        assert_eq!(stream::read_frame(&mut server).await?, vec![7; 100]);
+
+        // frames larger than the MTU are rejected
+        stream::write_frame(&mut client, &[0; MTU + 1]).await?;
+        assert!(stream::read_frame(&mut server).await.is_err());
+        Ok(())
+    }
 }
 
 #[cfg(target_family = "wasm")]
@@ -16,7 +16,7 @@ use wtransport::tls::Certificate;
 use wtransport::ServerConfig;
 use wtransport::{Connection, Endpoint};
 
-use crate::transport::webtransport::MTU;
+use crate::transport::webtransport::{stream, MTU};
 use crate::transport::{PacketReceiver, PacketSender, Transport};
 
 /// WebTransport client socket
@@ -37,6 +37,8 @@ impl WebTransportServerSocket {
         incoming_session: IncomingSession,
         from_client_sender: UnboundedSender<(Datagram, SocketAddr)>,
         to_client_channels: Arc<Mutex<HashMap<SocketAddr, UnboundedSender<Box<[u8]>>>>>,
+        from_client_stream_sender: UnboundedSender<(Vec<u8>, SocketAddr)>,
+        to_client_stream_channels: Arc<Mutex<HashMap<SocketAddr, UnboundedSender<Box<[u8]>>>>>,
     ) {
         let session_request = incoming_session
             .await
@@ -103,10 +105,62 @@ impl WebTransportServerSocket {
             }
         });
 
+        // reliable packets are sent on the bidirectional stream opened by the client
+        let connection_stream = connection.clone();
+        let stream_channels = to_client_stream_channels.clone();
+        let stream_handle = IoTaskPool::get().spawn(Compat::new(async move {
+            let (mut send_stream, mut recv_stream) = match connection_stream.accept_bi().await {
+                Ok(streams) => streams,
+                Err(e) => {
+                    debug!("client {} did not open a stream: {:?}", client_addr, e);
+                    return;
+                }
+            };
+            let (to_client_stream_sender, mut to_client_stream_receiver) =
+                mpsc::unbounded_channel::<Box<[u8]>>();
+            stream_channels
+                .lock()
+                .unwrap()
+                .insert(client_addr, to_client_stream_sender);
+            let reader_stream_channels = stream_channels.clone();
+            let from_client_stream_handle = IoTaskPool::get().spawn(Compat::new(async move {
+                loop {
+                    match stream::read_frame(&mut recv_stream).await {
+                        Ok(data) if data.is_empty() => {}
+                        Ok(data) => {
+                            trace!("received stream packet from client: {:?}", &data);
+                            if let Err(e) = from_client_stream_sender.send((data, client_addr)) {
+                                error!("could not forward stream packet from client: {:?}", e);
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            error!("receive stream error: {:?}", e);
+                            break;
+                        }
+                    }
+                }
+                // drop the stream: the reliable packets are sent as datagrams from now on
+                reader_stream_channels.lock().unwrap().remove(&client_addr);
+            }));
+            while let Some(msg) = to_client_stream_receiver.recv().await {
+                trace!("sending stream packet to client!: {:?}", &msg);
+                if let Err(e) = stream::write_frame(&mut send_stream, msg.as_ref()).await {
+                    error!("send stream error: {:?}", e);
+                    break;
+                }
+            }
+            from_client_stream_handle.cancel().await;
+        }));
+
         // await for the quic connection to be closed for any reason
         connection.closed().await;
         info!("Connection with {} closed", client_addr);
         to_client_channels.lock().unwrap().remove(&client_addr);
+        to_client_stream_channels
+            .lock()
+            .unwrap()
+            .remove(&client_addr);
         debug!("Dropping tasks");
 
         // TODO: need to disconnect the client in netcode
@@ -126,15 +180,19 @@ impl Transport for WebTransportServerSocket {
             mpsc::unbounded_channel::<(Box<[u8]>, SocketAddr)>();
         let (from_client_sender, from_client_receiver) = mpsc::unbounded_channel();
         let to_client_senders = Arc::new(Mutex::new(HashMap::new()));
+        let (from_client_stream_sender, from_client_stream_receiver) = mpsc::unbounded_channel();
+        let to_client_stream_senders = Arc::new(Mutex::new(HashMap::new()));
 
         let packet_sender = WebTransportServerSocketSender {
             server_addr,
             to_client_senders: to_client_senders.clone(),
+            to_client_stream_senders: to_client_stream_senders.clone(),
         };
         let packet_receiver = WebTransportServerSocketReceiver {
             buffer: [0; MTU],
             server_addr,
             from_client_receiver,
+            from_client_stream_receiver,
         };
 
         let config = ServerConfig::builder()
@@ -151,6 +209,8 @@ impl Transport for WebTransportServerSocket {
                     // clone the channel for each client
                     let from_client_sender = from_client_sender.clone();
                     let to_client_senders = to_client_senders.clone();
+                    let from_client_stream_sender = from_client_stream_sender.clone();
+                    let to_client_stream_senders = to_client_stream_senders.clone();
 
                     // new client connecting
                     let incoming_session = endpoint.accept().await;
@@ -160,6 +220,8 @@ impl Transport for WebTransportServerSocket {
                             incoming_session,
                             from_client_sender,
                             to_client_senders,
+                            from_client_stream_sender,
+                            to_client_stream_senders,
                         )))
                         .detach();
                 }
@@ -172,6 +234,8 @@ impl Transport for WebTransportServerSocket {
 struct WebTransportServerSocketSender {
     server_addr: SocketAddr,
     to_client_senders: Arc<Mutex<HashMap<SocketAddr, UnboundedSender<Box<[u8]>>>>>,
+    /// Only contains the clients that opened a reliable stream
+    to_client_stream_senders: Arc<Mutex<HashMap<SocketAddr, UnboundedSender<Box<[u8]>>>>>,
 }
 
 impl PacketSender for WebTransportServerSocketSender {
@@ -189,15 +253,43 @@ impl PacketSender for WebTransportServerSocketSender {
             // )))
         }
     }
+
+    fn send_reliable(&mut self, payload: &[u8], address: &SocketAddr) -> std::io::Result<()> {
+        let to_client_stream_sender = self
+            .to_client_stream_senders
+            .lock()
+            .unwrap()
+            .get(address)
+            .cloned();
+        if let Some(to_client_stream_sender) = to_client_stream_sender {
+            to_client_stream_sender.send(payload.into()).map_err(|e| {
+                std::io::Error::other(format!("unable to send stream message to client: {}", e))
+            })
+        } else {
+            self.send(payload, address)
+        }
+    }
+
+    fn has_reliable_stream(&self, address: &SocketAddr) -> bool {
+        self.to_client_stream_senders
+            .lock()
+            .unwrap()
+            .contains_key(address)
+    }
 }
 
 struct WebTransportServerSocketReceiver {
     buffer: [u8; MTU],
     server_addr: SocketAddr,
     from_client_receiver: UnboundedReceiver<(Datagram, SocketAddr)>,
+    from_client_stream_receiver: UnboundedReceiver<(Vec<u8>, SocketAddr)>,
 }
 impl PacketReceiver for WebTransportServerSocketReceiver {
     fn recv(&mut self) -> std::io::Result<Option<(&mut [u8], SocketAddr)>> {
+        if let Ok((data, addr)) = self.from_client_stream_receiver.try_recv() {
+            self.buffer[..data.len()].copy_from_slice(&data);
+            return Ok(Some((&mut self.buffer[..data.len()], addr)));
+        }
         match self.from_client_receiver.try_recv() {
             Ok((data, addr)) => {
                 self.buffer[..data.len()].copy_from_slice(data.payload().as_ref());
@@ -1,9 +1,10 @@
 //! Implement lightyear traits for some common bevy types
 use crate::_reexport::LinearInterpolator;
-use crate::client::components::{ComponentSyncMode, LerpFn, SyncComponent};
+use crate::client::components::{ComponentSyncMode, InterpolationSamples, LerpFn, SyncComponent};
+use crate::client::interpolation::spline::hermite;
 use bevy::ecs::entity::{EntityHashSet, MapEntities};
 use bevy::hierarchy::Parent;
-use bevy::prelude::{Children, Entity, EntityMapper, Transform};
+use bevy::prelude::{Children, Entity, EntityMapper, Transform, Vec3};
 use std::ops::Mul;
 use tracing::{info, trace};
 
@@ -38,6 +39,74 @@ impl LerpFn<Transform> for TransformLinearInterpolation {
     }
 }
 
+/// Interpolates the translation and scale of a [`Transform`] along a Catmull-Rom spline going through
+/// the server states (see [`CatmullRomInterpolator`](crate::client::interpolation::CatmullRomInterpolator)).
+///
+/// NOTE: the rotation does not follow the spline: it uses a plain slerp between the `start` and `end` states,
+/// like [`TransformLinearInterpolation`], so the angular velocity still changes abruptly at every server state.
+pub struct TransformCatmullRomInterpolation;
+
+impl TransformCatmullRomInterpolation {
+    /// Catmull-Rom spline between `p1` and `p2`, where the tangents are expressed relative to the [t1, t2] interval
+    fn catmull_rom(
+        p0: Option<(f32, Vec3)>,
+        (t1, p1): (f32, Vec3),
+        (t2, p2): (f32, Vec3),
+        p3: Option<(f32, Vec3)>,
+        t: f32,
+    ) -> Vec3 {
+        let interval = t2 - t1;
+        let m1 = p0.map_or(p2 - p1, |(t0, p0)| (p2 - p0) * (interval / (t2 - t0)));
+        let m2 = p3.map_or(p2 - p1, |(t3, p3)| (p3 - p1) * (interval / (t3 - t1)));
+        hermite(p1, m1, p2, m2, t)
+    }
+}
+
+impl LerpFn<Transform> for TransformCatmullRomInterpolation {
+    fn lerp(start: &Transform, other: &Transform, t: f32) -> Transform {
+        TransformLinearInterpolation::lerp(start, other, t)
+    }
+
+    fn interpolate(samples: &InterpolationSamples<Transform>, t: f32) -> Transform {
+        let (start_tick, start) = samples.start;
+        let (end_tick, end) = samples.end;
+        let end_time = (end_tick - start_tick) as f32;
+        if end_time <= 0.0 {
+            return Self::lerp(start, end, t);
+        }
+        // express the ticks relative to the start tick
+        let previous = samples
+            .previous
+            .filter(|(tick, _)| *tick < start_tick)
+            .map(|(tick, value)| ((tick - start_tick) as f32, value));
+        let next = samples
+            .next
+            .filter(|(tick, _)| *tick > end_tick)
+            .map(|(tick, value)| ((tick - start_tick) as f32, value));
+        let translation = Self::catmull_rom(
+            previous.map(|(time, value)| (time, value.translation)),
+            (0.0, start.translation),
+            (end_time, end.translation),
+            next.map(|(time, value)| (time, value.translation)),
+            t,
+        );
+        let scale = Self::catmull_rom(
+            previous.map(|(time, value)| (time, value.scale)),
+            (0.0, start.scale),
+            (end_time, end.scale),
+            next.map(|(time, value)| (time, value.scale)),
+            t,
+        );
+        // slerp takes the shortest path between the two rotations
+        let rotation = start.rotation.slerp(end.rotation, t).normalize();
+        Transform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+}
+
 impl LightyearMapEntities for Transform {
     fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {}
 }
@@ -62,3 +131,56 @@ cfg_if::cfg_if! {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Quat;
+
+    use crate::prelude::Tick;
+
+    use super::*;
+
+    fn transform(x: f32, angle: f32) -> Transform {
+        Transform::from_xyz(x, 0.0, 0.0).with_rotation(Quat::from_rotation_z(angle))
+    }
+
+    #[test]
+    fn test_transform_catmull_rom_interpolation() {
+        // samples of x = tick^2
+        let previous = transform(0.0, 0.0);
+        let start = transform(1.0, 0.0);
+        let end = transform(4.0, 1.0);
+        let next = transform(9.0, 1.0);
+        let samples = InterpolationSamples {
+            previous: Some((Tick(0), &previous)),
+            start: (Tick(1), &start),
+            end: (Tick(2), &end),
+            next: Some((Tick(3), &next)),
+        };
+        let res = TransformCatmullRomInterpolation::interpolate(&samples, 0.5);
+        assert_eq!(res.translation, Vec3::new(2.25, 0.0, 0.0));
+        assert_eq!(res.scale, Vec3::ONE);
+        assert!(res.rotation.abs_diff_eq(Quat::from_rotation_z(0.5), 1e-5));
+
+        // the spline goes through the server states
+        assert_eq!(
+            TransformCatmullRomInterpolation::interpolate(&samples, 0.0).translation,
+            start.translation
+        );
+        assert_eq!(
+            TransformCatmullRomInterpolation::interpolate(&samples, 1.0).translation,
+            end.translation
+        );
+
+        // without the neighbouring states, we fall back to linear interpolation
+        let samples = InterpolationSamples {
+            previous: None,
+            next: None,
+            ..samples
+        };
+        assert_eq!(
+            TransformCatmullRomInterpolation::interpolate(&samples, 0.5).translation,
+            Vec3::new(2.5, 0.0, 0.0)
+        );
+    }
+}
@@ -10,6 +10,8 @@ use syn::{
     PathArguments, Token, Type, TypeParam,
 };
 
+use crate::shared::type_name;
+
 #[derive(Debug, FromMeta)]
 /// Struct that will hold the value of attributes passed to the macro
 struct MacroAttrs {
@@ -37,11 +39,16 @@ struct SyncField {
     once: bool,
     #[darling(default)]
     external: bool,
+    /// compare the predicted value with the quantized confirmed value using the `Quantize` implementation
+    #[darling(default)]
+    quantized: bool,
 
     #[darling(default)]
     lerp: Option<Ident>,
     #[darling(default)]
     corrector: Option<Ident>,
+    #[darling(default)]
+    extrapolate: Option<Ident>,
 }
 
 impl SyncField {
@@ -189,6 +196,7 @@ pub fn component_protocol_impl(
     let insert_method = insert_method(&input, &fields);
     let update_method = update_method(&input, &fields);
     let type_ids_method = type_ids_method(&fields, &enum_kind_name);
+    let type_names_method = type_names_method(&fields);
 
     // EnumKind methods
     let enum_kind = get_enum_kind(&input, &enum_kind_name);
@@ -220,6 +228,7 @@ pub fn component_protocol_impl(
                 type Protocol = #protocol;
 
                 #type_ids_method
+                #type_names_method
                 #insert_method
                 #update_method
                 #add_systems_method
@@ -418,6 +427,11 @@ fn sync_metadata_impl(fields: &Vec<SyncField>, enum_name: &Ident) -> TokenStream
                 Ident::new("NullInterpolator", Span::call_site())
             }
         });
+        // extrapolation
+        let extrapolator = &field
+            .extrapolate
+            .clone()
+            .unwrap_or(Ident::new("NullExtrapolator", Span::call_site()));
         // prediction
         let mut corrector = field
             .corrector
@@ -426,14 +440,26 @@ fn sync_metadata_impl(fields: &Vec<SyncField>, enum_name: &Ident) -> TokenStream
         if corrector == "InterpolatedCorrector" {
             corrector = interpolator.clone();
         }
+        // compare the quantized values, using the `Quantize` implementation generated by `#[quantized]`
+        let quantize_method = if field.quantized {
+            quote! {
+                fn quantize(component: #component_type) -> #component_type {
+                    Quantize::quantized(&component)
+                }
+            }
+        } else {
+            quote! {}
+        };
         body = quote! {
             #body
             impl SyncMetadata<#component_type> for #enum_name {
                 type Interpolator = #interpolator;
+                type Extrapolator = #extrapolator;
                 type Corrector = #corrector;
                 fn mode() -> ComponentSyncMode {
                     #mode
                 }
+                #quantize_method
             }
         }
     }
@@ -717,6 +743,15 @@ fn update_method(input: &ItemEnum, fields: &Vec<Field>) -> TokenStream {
     }
 }
 
+fn type_names_method(fields: &[Field]) -> TokenStream {
+    let names = fields.iter().map(|field| type_name(&field.ty));
+    quote! {
+        fn type_names() -> Vec<&'static str> {
+            vec![#(#names),*]
+        }
+    }
+}
+
 fn type_ids_method(fields: &Vec<Field>, enum_kind_name: &Ident) -> TokenStream {
     let mut body = quote! {
         let mut res = HashMap::default();
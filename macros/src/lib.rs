@@ -11,10 +11,12 @@ use syn::{parse_macro_input, ItemEnum};
 use channel::channel_impl;
 use component::component_protocol_impl;
 use message::{message_impl, message_protocol_impl};
+use quantize::quantized_impl;
 
 mod channel;
 mod component;
 mod message;
+mod quantize;
 mod shared;
 
 // Channel
@@ -90,3 +92,27 @@ pub fn component_protocol(
     let shared_crate_name = quote! { lightyear };
     component_protocol_impl(args, input, shared_crate_name)
 }
+
+// Quantization
+
+#[doc(hidden)]
+#[proc_macro_attribute]
+pub fn quantized_internal(
+    _args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let shared_crate_name = quote! { crate };
+    quantized_impl(input, shared_crate_name)
+}
+
+/// Attribute macro applied to a struct to quantize the fields marked with `#[quantize(...)]`.
+///
+/// Must be placed above the serde derives.
+#[proc_macro_attribute]
+pub fn quantized(
+    _args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let shared_crate_name = quote! { lightyear };
+    quantized_impl(input, shared_crate_name)
+}
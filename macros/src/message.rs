@@ -1,4 +1,4 @@
-use crate::shared::generate_unique_ident;
+use crate::shared::{generate_unique_ident, type_name};
 use darling::ast::NestedMeta;
 use darling::util::PathList;
 use darling::{Error, FromDeriveInput, FromMeta};
@@ -111,6 +111,10 @@ pub fn message_protocol_impl(
     input.variants.push(parse_quote! {
         InputMessage(#shared_crate_name::inputs::native::InputMessage<<#protocol as Protocol>::Input>)
     });
+    // messages registered at runtime are sent wrapped in this variant
+    input.variants.push(parse_quote! {
+        DynamicMessage(#shared_crate_name::_reexport::DynamicMessage)
+    });
 
     #[cfg(feature = "leafwing")]
     for i in 1..3 {
@@ -139,6 +143,7 @@ pub fn message_protocol_impl(
     let add_events_method = add_events_method(&fields);
     let push_message_events_method = push_message_events_method(&fields, protocol);
     let name_method = name_method(&input, &fields);
+    let type_names_method = type_names_method(&fields);
     let map_entities_impl = map_entities_impl(&input);
     let encode_method = encode_method();
     let decode_method = decode_method();
@@ -162,6 +167,7 @@ pub fn message_protocol_impl(
                 type Protocol = #protocol;
 
                 #name_method
+                #type_names_method
                 #message_kind_method
                 #input_message_kind_method
                 #add_events_method
@@ -348,6 +354,15 @@ fn name_method(input: &ItemEnum, fields: &Vec<Field>) -> TokenStream {
     }
 }
 
+fn type_names_method(fields: &[Field]) -> TokenStream {
+    let names = fields.iter().map(|field| type_name(&field.ty));
+    quote! {
+        fn type_names() -> Vec<&'static str> {
+            vec![#(#names),*]
+        }
+    }
+}
+
 fn map_entities_impl(input: &ItemEnum) -> TokenStream {
     let enum_name = &input.ident;
     let variants = input.variants.iter().map(|v| v.ident.clone());
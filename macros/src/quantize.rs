@@ -0,0 +1,289 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, parse_quote, Expr, Index, ItemStruct, Member, MetaNameValue, Token, Type,
+};
+
+use crate::shared::generate_unique_ident;
+
+enum FieldKind {
+    Float,
+    Int,
+    Quat,
+}
+
+impl FieldKind {
+    fn from_type(ty: &Type) -> Option<Self> {
+        let Type::Path(path) = ty else {
+            return None;
+        };
+        let ident = path.path.segments.last()?.ident.to_string();
+        match ident.as_str() {
+            "f32" | "f64" | "Vec2" | "Vec3" => Some(FieldKind::Float),
+            "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+                Some(FieldKind::Int)
+            }
+            "Quat" => Some(FieldKind::Quat),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct QuantizeArgs {
+    min: Option<Expr>,
+    max: Option<Expr>,
+    precision: Option<Expr>,
+    bits: Option<Expr>,
+}
+
+impl QuantizeArgs {
+    fn parse(args: Punctuated<MetaNameValue, Token![,]>) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+        for arg in args {
+            let slot = if arg.path.is_ident("min") {
+                &mut parsed.min
+            } else if arg.path.is_ident("max") {
+                &mut parsed.max
+            } else if arg.path.is_ident("precision") {
+                &mut parsed.precision
+            } else if arg.path.is_ident("bits") {
+                &mut parsed.bits
+            } else {
+                return Err(syn::Error::new_spanned(
+                    arg.path,
+                    "expected one of `min`, `max`, `precision`, `bits`",
+                ));
+            };
+            *slot = Some(arg.value);
+        }
+        Ok(parsed)
+    }
+
+    /// Returns the expression that builds the quantization used for the field, and the constant assertions
+    /// that validate the arguments at compile time
+    fn quantization(
+        self,
+        kind: &FieldKind,
+        ty: &Type,
+        shared_crate_name: &TokenStream,
+    ) -> syn::Result<(TokenStream, TokenStream)> {
+        let module = quote! { #shared_crate_name::serialize::quantize };
+        let check_range = |min: &Expr, max: &Expr| {
+            quote! {
+                assert!(
+                    ((#min) as f64) < ((#max) as f64),
+                    "the quantization range must not be empty"
+                );
+            }
+        };
+        let check_bits = |bits: &Expr| {
+            quote! {
+                assert!(
+                    (#bits) >= 1 && (#bits) <= 32,
+                    "floats can be quantized to 1 to 32 bits"
+                );
+            }
+        };
+        match (kind, self) {
+            (
+                FieldKind::Float,
+                QuantizeArgs {
+                    min: Some(min),
+                    max: Some(max),
+                    precision: Some(precision),
+                    bits: None,
+                },
+            ) => {
+                let check_range = check_range(&min, &max);
+                Ok((
+                    quote! {
+                        #module::FloatQuantization::new((#min) as f64, (#max) as f64, (#precision) as f64)
+                    },
+                    quote! {
+                        #check_range
+                        assert!(
+                            ((#precision) as f64) > 0.0,
+                            "the quantization precision must be positive"
+                        );
+                        assert!(
+                            ((#max) as f64 - (#min) as f64) / ((#precision) as f64) < u32::MAX as f64,
+                            "floats can be quantized to at most 32 bits, use a coarser precision"
+                        );
+                    },
+                ))
+            }
+            (
+                FieldKind::Float,
+                QuantizeArgs {
+                    min: Some(min),
+                    max: Some(max),
+                    precision: None,
+                    bits: Some(bits),
+                },
+            ) => {
+                let check_range = check_range(&min, &max);
+                let check_bits = check_bits(&bits);
+                Ok((
+                    quote! {
+                        #module::FloatQuantization::with_bits((#min) as f64, (#max) as f64, #bits)
+                    },
+                    quote! {
+                        #check_range
+                        #check_bits
+                    },
+                ))
+            }
+            (
+                FieldKind::Int,
+                QuantizeArgs {
+                    min: Some(min),
+                    max: Some(max),
+                    precision: None,
+                    bits: None,
+                },
+            ) => Ok((
+                quote! {
+                    #module::IntQuantization::new((#min) as i64, (#max) as i64)
+                },
+                quote! {
+                    assert!(
+                        ((#min) as i64) < ((#max) as i64),
+                        "the quantization range must not be empty"
+                    );
+                },
+            )),
+            (
+                FieldKind::Quat,
+                QuantizeArgs {
+                    min: None,
+                    max: None,
+                    precision: None,
+                    bits: Some(bits),
+                },
+            ) => Ok((
+                quote! {
+                    #module::QuatQuantization::new(#bits)
+                },
+                check_bits(&bits),
+            )),
+            (FieldKind::Float, _) => Err(syn::Error::new_spanned(
+                ty,
+                "quantized floats need `min`, `max` and either `precision` or `bits`",
+            )),
+            (FieldKind::Int, _) => Err(syn::Error::new_spanned(
+                ty,
+                "quantized integers need `min` and `max`",
+            )),
+            (FieldKind::Quat, _) => Err(syn::Error::new_spanned(
+                ty,
+                "quantized quaternions need `bits`",
+            )),
+        }
+    }
+}
+
+pub fn quantized_impl(
+    input: proc_macro::TokenStream,
+    shared_crate_name: TokenStream,
+) -> proc_macro::TokenStream {
+    let mut input = parse_macro_input!(input as ItemStruct);
+    match quantize_fields(&mut input, &shared_crate_name) {
+        Ok(output) => proc_macro::TokenStream::from(output),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn quantize_fields(
+    input: &mut ItemStruct,
+    shared_crate_name: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let struct_name = input.ident.clone();
+    let mut modules = quote! {};
+    let mut quantized_body = quote! {};
+    for (i, field) in input.fields.iter_mut().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        };
+        let Some(position) = field
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("quantize"))
+        else {
+            quantized_body = quote! {
+                #quantized_body
+                #member: self.#member.clone(),
+            };
+            continue;
+        };
+        let attr = field.attrs.remove(position);
+        let ty = field.ty.clone();
+        let kind = FieldKind::from_type(&ty).ok_or_else(|| {
+            syn::Error::new_spanned(
+                &ty,
+                "only f32, f64, Vec2, Vec3, Quat and integer fields can be quantized",
+            )
+        })?;
+        let args = QuantizeArgs::parse(
+            attr.parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated)?,
+        )?;
+        let (quantization, checks) = args.quantization(&kind, &ty, shared_crate_name)?;
+
+        // serde (de)serializes the field with the functions of this module
+        let module_name = generate_unique_ident(&format!(
+            "quantize_{}_{}",
+            struct_name.to_string().to_lowercase(),
+            i
+        ));
+        let module_path = module_name.to_string();
+        field
+            .attrs
+            .push(parse_quote! { #[serde(with = #module_path)] });
+        modules = quote! {
+            #modules
+            #[doc(hidden)]
+            mod #module_name {
+                use super::*;
+                use #shared_crate_name::serialize::quantize::Quantization;
+
+                // invalid arguments are compile errors
+                const _: () = {
+                    #checks
+                };
+
+                pub fn serialize<S: serde::Serializer>(value: &#ty, serializer: S) -> ::core::result::Result<S::Ok, S::Error> {
+                    #quantization.serialize(value, serializer)
+                }
+
+                pub fn deserialize<'de, D: serde::Deserializer<'de>>(deserializer: D) -> ::core::result::Result<#ty, D::Error> {
+                    #quantization.deserialize(deserializer)
+                }
+
+                pub fn quantize(value: &#ty) -> #ty {
+                    #quantization.quantize(value)
+                }
+            }
+        };
+        quantized_body = quote! {
+            #quantized_body
+            #member: #module_name::quantize(&self.#member),
+        };
+    }
+
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        #input
+
+        #modules
+
+        impl #impl_generics #shared_crate_name::serialize::quantize::Quantize for #struct_name #type_generics #where_clause {
+            fn quantized(&self) -> Self {
+                Self {
+                    #quantized_body
+                }
+            }
+        }
+    })
+}
@@ -1,5 +1,6 @@
-use proc_macro2::{Ident, Span};
-use syn::{Data, DeriveInput, Fields};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::{Data, DeriveInput, Fields, Type};
 
 pub enum StructType {
     Struct,
@@ -25,3 +26,38 @@ pub(crate) fn generate_unique_ident(prefix: &str) -> Ident {
 
     Ident::new(&ident, Span::call_site())
 }
+
+/// Canonical name of a type, used in the protocol fingerprint.
+///
+/// We don't use `quote!{#ty}.to_string()` because its spacing is an implementation detail of `proc_macro2`:
+/// here the tokens are only separated by a space when two identifiers or literals are next to each other.
+pub(crate) fn type_name(ty: &Type) -> String {
+    let mut name = String::new();
+    write_tokens(&mut name, ty.to_token_stream());
+    name
+}
+
+fn write_tokens(name: &mut String, tokens: TokenStream) {
+    let mut previous_is_word = false;
+    for token in tokens {
+        let is_word = matches!(token, TokenTree::Ident(_) | TokenTree::Literal(_));
+        if is_word && previous_is_word {
+            name.push(' ');
+        }
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                name.push_str(open);
+                write_tokens(name, group.stream());
+                name.push_str(close);
+            }
+            token => name.push_str(&token.to_string()),
+        }
+        previous_is_word = is_word;
+    }
+}
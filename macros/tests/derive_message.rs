@@ -11,10 +11,20 @@ pub mod some_message {
     #[derive(Message, Serialize, Deserialize, Debug, PartialEq, Clone)]
     pub struct Message2(pub u32);
 
+    pub mod nested {
+        use serde::{Deserialize, Serialize};
+
+        use lightyear::prelude::*;
+
+        #[derive(Message, Serialize, Deserialize, Debug, PartialEq, Clone)]
+        pub struct Message3(pub u32);
+    }
+
     #[message_protocol(protocol = "MyProtocol")]
     pub enum MyMessageProtocol {
         Message1(Message1),
         Message2(Message2),
+        Message3(nested::Message3),
     }
 
     #[derive(Component, Message, Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -36,7 +46,7 @@ pub mod some_message {
 mod tests {
     use super::some_message::*;
     use lightyear::_reexport::{
-        BitSerializable, ReadBuffer, ReadWordBuffer, WriteBuffer, WriteWordBuffer,
+        BitSerializable, MessageProtocol, ReadBuffer, ReadWordBuffer, WriteBuffer, WriteWordBuffer,
     };
     use lightyear::prelude::*;
 
@@ -54,4 +64,20 @@ mod tests {
 
         Ok(())
     }
+
+    /// The type names are part of the protocol fingerprint, they must not depend on the token spacing
+    #[test]
+    fn test_message_type_names() {
+        assert_eq!(
+            <MyMessageProtocol as MessageProtocol>::type_names(),
+            vec![
+                "Message1",
+                "Message2",
+                "nested::Message3",
+                // messages added by the macro
+                "lightyear::inputs::native::InputMessage<<MyProtocol as Protocol>::Input>",
+                "lightyear::_reexport::DynamicMessage",
+            ]
+        );
+    }
 }